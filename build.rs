@@ -18,12 +18,14 @@ fn main() {
 const COMMON: &[&str] = &[
     "src/game/common/fullscreen.wgsl",
     "src/game/render/world/shaders/camera_env.wgsl",
+    "src/game/render/world/shaders/lighting.wgsl",
     "src/game/render/world/shaders/geometry_buffer.wgsl",
 ];
 
 const SHADERS: &[&str] = &[
     "src/engine/gizmos.wgsl",
     "src/game/render/world/shaders/compositor.wgsl",
+    "src/game/render/world/shaders/linear_depth.wgsl",
     "src/game/render/world/shaders/models.wgsl",
     "src/game/render/world/shaders/terrain.wgsl",
     "src/game/render/world/shaders/ui.wgsl",
@@ -49,6 +51,9 @@ fn compile_shaders() {
 
     let mut validator = Validator::new(ValidationFlags::all(), Capabilities::all());
 
+    // Reflection of each shader, in the order of SHADERS.
+    let mut reflections = Vec::with_capacity(SHADERS.len());
+
     for path in SHADERS {
         let module = create_shader_module(&mut composer, path);
 
@@ -56,6 +61,8 @@ fn compile_shaders() {
             .validate(&module)
             .unwrap_or_else(|e| panic!("Validation failed for {path}:\n{e:#?}"));
 
+        reflections.push(reflect(&module, &info));
+
         let out_path = shaders_dir.join(
             PathBuf::from(path)
                 .with_extension("wgsl")
@@ -69,7 +76,48 @@ fn compile_shaders() {
         std::fs::write(&out_path, wgsl_text).expect("write .wgsl");
     }
 
-    write_shaders_module();
+    write_shaders_module(&reflections);
+}
+
+/// What the engine needs to know about a shader's entry points.
+struct Reflection {
+    /// Names of the compute entry points.
+    compute_entry_points: Vec<String>,
+    /// For each entry point, the `(group, binding)` pairs of the resources it
+    /// uses, sorted.
+    entry_point_bindings: Vec<(String, Vec<(u32, u32)>)>,
+}
+
+fn reflect(module: &Module, info: &naga::valid::ModuleInfo) -> Reflection {
+    let compute_entry_points = module
+        .entry_points
+        .iter()
+        .filter(|entry_point| entry_point.stage == naga::ShaderStage::Compute)
+        .map(|entry_point| entry_point.name.clone())
+        .collect();
+
+    let entry_point_bindings = module
+        .entry_points
+        .iter()
+        .enumerate()
+        .map(|(index, entry_point)| {
+            let function_info = info.get_entry_point(index);
+            let mut bindings: Vec<(u32, u32)> = module
+                .global_variables
+                .iter()
+                .filter(|(handle, _)| !function_info[*handle].is_empty())
+                .filter_map(|(_, variable)| variable.binding.as_ref())
+                .map(|binding| (binding.group, binding.binding))
+                .collect();
+            bindings.sort();
+            (entry_point.name.clone(), bindings)
+        })
+        .collect();
+
+    Reflection {
+        compute_entry_points,
+        entry_point_bindings,
+    }
 }
 
 fn add_support_shader(composer: &mut Composer, path: impl AsRef<Path>) {
@@ -114,7 +162,7 @@ fn create_shader_module(composer: &mut Composer, path: impl AsRef<Path>) -> Modu
     }
 }
 
-fn write_shaders_module() {
+fn write_shaders_module(reflections: &[Reflection]) {
     println!("cargo:rerun-if-changed=build.rs");
 
     let variants: Vec<_> = SHADERS
@@ -188,6 +236,67 @@ fn write_shaders_module() {
         })
         .collect();
 
+    let support_paths = COMMON;
+
+    let path_arms: Vec<_> = SHADERS
+        .iter()
+        .map(|path| {
+            let stem = Path::new(path)
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .to_upper_camel_case();
+            let variant = format_ident!("{stem}");
+
+            quote! {
+                ShaderSource::#variant => #path,
+            }
+        })
+        .collect();
+
+    let compute_entry_point_arms: Vec<_> = SHADERS
+        .iter()
+        .zip(reflections)
+        .map(|(path, reflection)| {
+            let stem = Path::new(path)
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .to_upper_camel_case();
+            let variant = format_ident!("{stem}");
+            let names = &reflection.compute_entry_points;
+
+            quote! {
+                ShaderSource::#variant => &[#( #names ),*],
+            }
+        })
+        .collect();
+
+    let entry_point_binding_arms: Vec<_> = SHADERS
+        .iter()
+        .zip(reflections)
+        .flat_map(|(path, reflection)| {
+            let stem = Path::new(path)
+                .file_stem()
+                .unwrap()
+                .to_string_lossy()
+                .to_upper_camel_case();
+            let variant = format_ident!("{stem}");
+
+            reflection
+                .entry_point_bindings
+                .iter()
+                .map(move |(name, bindings)| {
+                    let pairs = bindings
+                        .iter()
+                        .map(|(group, binding)| quote!((#group, #binding)));
+                    quote! {
+                        (ShaderSource::#variant, #name) => Some(&[#( #pairs ),*]),
+                    }
+                })
+        })
+        .collect();
+
     let tokens = quote! {
         #[allow(dead_code)]
         #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -208,12 +317,42 @@ fn write_shaders_module() {
             }
         }
 
+        /// Modules imported by the shaders, relative to the crate root.
+        #[allow(dead_code)]
+        pub const SUPPORT_SHADER_PATHS: &[&str] = &[#( #support_paths ),*];
+
+        /// Path of the shader's source, relative to the crate root.
+        #[allow(dead_code)]
+        pub fn shader_path(source: ShaderSource) -> &'static str {
+            match source {
+                #( #path_arms )*
+            }
+        }
+
         #[allow(dead_code)]
         pub fn shader_source(source: ShaderSource) -> &'static str {
             match source {
                 #( #source_arms )*
             }
         }
+
+        #[allow(dead_code)]
+        pub fn compute_entry_points(source: ShaderSource) -> &'static [&'static str] {
+            match source {
+                #( #compute_entry_point_arms )*
+            }
+        }
+
+        #[allow(dead_code)]
+        pub fn entry_point_bindings(
+            source: ShaderSource,
+            entry_point: &str,
+        ) -> Option<&'static [(u32, u32)]> {
+            match (source, entry_point) {
+                #( #entry_point_binding_arms )*
+                _ => None,
+            }
+        }
     };
 
     let out_file =
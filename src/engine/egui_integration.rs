@@ -53,6 +53,31 @@ impl EguiIntegration {
         }
     }
 
+    /// Make `view` available to `egui::Image`. When `id` is given, the existing texture is
+    /// pointed at the new view instead of registering a new one, e.g. after a resize.
+    pub fn register_native_texture(
+        &mut self,
+        view: &wgpu::TextureView,
+        id: Option<egui::TextureId>,
+    ) -> egui::TextureId {
+        match id {
+            Some(id) => {
+                self.egui_renderer.update_egui_texture_from_wgpu_texture(
+                    &self.device,
+                    view,
+                    wgpu::FilterMode::Linear,
+                    id,
+                );
+                id
+            }
+            None => self.egui_renderer.register_native_texture(
+                &self.device,
+                view,
+                wgpu::FilterMode::Linear,
+            ),
+        }
+    }
+
     pub fn window_event(
         &mut self,
         window: &winit::window::Window,
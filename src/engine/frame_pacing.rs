@@ -0,0 +1,243 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use winit::event_loop::ControlFlow;
+
+/// What the main loop does between the end of one frame and the start of the
+/// next. Only matters when presenting does not block on vsync.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PacingStrategy {
+    /// Give up the rest of the time slice, then start the next frame.
+    Yield,
+    /// Start frames no more often than once per duration, sleeping in the
+    /// event loop in between. Keeps the CPU idle on mostly static screens.
+    Sleep(Duration),
+    /// Start the next frame immediately and keep the event loop polling.
+    /// Lowest latency, but keeps a core busy.
+    Spin,
+}
+
+impl PacingStrategy {
+    /// Sleep pacing for screens that render almost nothing, about 30 frames per second.
+    pub const IDLE: Self = Self::Sleep(Duration::from_micros(33_333));
+
+    /// The control flow for the event loop after a frame that started at
+    /// `frame_start`.
+    pub fn control_flow(self, frame_start: Instant) -> ControlFlow {
+        match self {
+            Self::Yield => ControlFlow::Wait,
+            Self::Sleep(duration) => ControlFlow::WaitUntil(frame_start + duration),
+            Self::Spin => ControlFlow::Poll,
+        }
+    }
+
+    /// Whether the next frame should be requested right away. If not, it is
+    /// requested when the event loop wakes up at the deadline of
+    /// [Self::control_flow].
+    pub fn redraw_immediately(self) -> bool {
+        match self {
+            Self::Yield | Self::Spin => true,
+            Self::Sleep(_) => false,
+        }
+    }
+}
+
+/// Where the time of a single frame went.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FrameTimings {
+    /// Time from the start of the previous frame to the start of this one.
+    pub frame: Duration,
+    /// Time spent in `Queue::submit`.
+    pub submit: Duration,
+    /// Time spent in `SurfaceTexture::present`, which includes waiting for vsync.
+    pub present: Duration,
+}
+
+/// Summary of the frames in the [FramePacing] window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FramePacingStats {
+    pub min: Duration,
+    pub avg: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    pub avg_submit: Duration,
+    pub avg_present: Duration,
+}
+
+/// Rolling window of recent frame timings.
+pub struct FramePacing {
+    frames: VecDeque<FrameTimings>,
+    capacity: usize,
+    /// Pacing chosen in the debug UI, used instead of the game's preference.
+    pub strategy_override: Option<PacingStrategy>,
+}
+
+impl Default for FramePacing {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl FramePacing {
+    /// Number of frames kept by default, a few seconds at common refresh rates.
+    pub const DEFAULT_CAPACITY: usize = 240;
+
+    /// Create a window holding up to `capacity` frames.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+            strategy_override: None,
+        }
+    }
+
+    /// Add the timings of a finished frame, dropping the oldest one if the
+    /// window is full.
+    pub fn record(&mut self, timings: FrameTimings) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(timings);
+    }
+
+    /// Summarize the frames in the window, or `None` if nothing was recorded.
+    pub fn stats(&self) -> Option<FramePacingStats> {
+        let count = self.frames.len() as u32;
+        if count == 0 {
+            return None;
+        }
+
+        let mut frame_times: Vec<Duration> = self.frames.iter().map(|f| f.frame).collect();
+        frame_times.sort_unstable();
+
+        let p99_index = ((frame_times.len() - 1) as f32 * 0.99).round() as usize;
+
+        Some(FramePacingStats {
+            min: frame_times[0],
+            avg: frame_times.iter().sum::<Duration>() / count,
+            p99: frame_times[p99_index],
+            max: frame_times[frame_times.len() - 1],
+            avg_submit: self.frames.iter().map(|f| f.submit).sum::<Duration>() / count,
+            avg_present: self.frames.iter().map(|f| f.present).sum::<Duration>() / count,
+        })
+    }
+
+    /// Frame times in milliseconds, oldest first, for plotting.
+    pub fn frame_times_ms(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frames.iter().map(|f| f.frame.as_secs_f32() * 1000.0)
+    }
+
+    /// Draw the stats, a frame time histogram and the pacing override.
+    #[cfg(feature = "egui")]
+    pub fn debug_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_label("Pacing")
+            .selected_text(match self.strategy_override {
+                None => String::from("Automatic"),
+                Some(strategy) => format!("{strategy:?}"),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.strategy_override, None, "Automatic");
+                for strategy in [
+                    PacingStrategy::Yield,
+                    PacingStrategy::IDLE,
+                    PacingStrategy::Spin,
+                ] {
+                    ui.selectable_value(
+                        &mut self.strategy_override,
+                        Some(strategy),
+                        format!("{strategy:?}"),
+                    );
+                }
+            });
+
+        let Some(stats) = self.stats() else {
+            ui.label("No frames recorded.");
+            return;
+        };
+
+        let ms = |duration: Duration| format!("{:.2} ms", duration.as_secs_f64() * 1000.0);
+
+        egui::Grid::new("frame_pacing").show(ui, |ui| {
+            for (label, value) in [
+                ("Min", stats.min),
+                ("Avg", stats.avg),
+                ("P99", stats.p99),
+                ("Max", stats.max),
+                ("Avg submit", stats.avg_submit),
+                ("Avg present", stats.avg_present),
+            ] {
+                ui.label(label);
+                ui.label(ms(value));
+                ui.end_row();
+            }
+        });
+
+        const BUCKETS: usize = 20;
+        const BUCKET_MS: f32 = 2.0;
+
+        let mut histogram = [0_u32; BUCKETS];
+        for frame_ms in self.frame_times_ms() {
+            let bucket = ((frame_ms / BUCKET_MS) as usize).min(BUCKETS - 1);
+            histogram[bucket] += 1;
+        }
+        let highest = histogram.iter().copied().max().unwrap_or(1).max(1);
+
+        let (rect, _) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width(), 60.0), egui::Sense::hover());
+        let painter = ui.painter_at(rect);
+        let bar_width = rect.width() / BUCKETS as f32;
+        for (index, count) in histogram.iter().enumerate() {
+            let height = rect.height() * *count as f32 / highest as f32;
+            let min = egui::pos2(
+                rect.left() + index as f32 * bar_width,
+                rect.bottom() - height,
+            );
+            let max = egui::pos2(min.x + bar_width - 1.0, rect.bottom());
+            painter.rect_filled(
+                egui::Rect::from_min_max(min, max),
+                0.0,
+                egui::Color32::LIGHT_BLUE,
+            );
+        }
+        ui.label(format!(
+            "{BUCKET_MS:.0} ms buckets, last bucket includes {:.0}+ ms",
+            BUCKET_MS * (BUCKETS - 1) as f32
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(ms: u64) -> FrameTimings {
+        FrameTimings {
+            frame: Duration::from_millis(ms),
+            submit: Duration::from_millis(1),
+            present: Duration::from_millis(ms / 2),
+        }
+    }
+
+    #[test]
+    fn stats_cover_the_rolling_window() {
+        let mut pacing = FramePacing::new(100);
+        assert_eq!(pacing.stats(), None);
+
+        // The first frame falls out of the window.
+        pacing.record(frame(500));
+        for _ in 0..98 {
+            pacing.record(frame(10));
+        }
+        pacing.record(frame(40));
+        pacing.record(frame(40));
+
+        let stats = pacing.stats().unwrap();
+        assert_eq!(stats.min, Duration::from_millis(10));
+        assert_eq!(stats.max, Duration::from_millis(40));
+        assert_eq!(stats.p99, Duration::from_millis(40));
+        assert_eq!(stats.avg, Duration::from_micros(10_600));
+        assert_eq!(stats.avg_submit, Duration::from_millis(1));
+    }
+}
@@ -2,22 +2,47 @@
 
 use glam::{Mat4, Vec3, Vec4};
 
-use crate::game::math::{BoundingBox, ViewProjection};
+use crate::{
+    engine::renderer::AsVertexLayout,
+    game::math::{BoundingBox, ViewProjection},
+};
 
 #[derive(Clone, Copy, Debug, bytemuck::NoUninit)]
 #[repr(C)]
 pub struct GizmoVertex {
     pub position: Vec3,
-    _padding: f32,
-    pub color: Vec4,
+    /// RGBA packed as [wgpu::VertexFormat::Unorm8x4].
+    pub color: [u8; 4],
 }
 
+impl AsVertexLayout for GizmoVertex {
+    const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Vertex;
+    /// The color is unpacked to a `vec4<f32>` by the GPU.
+    const ATTRIBUTES: &'static [wgpu::VertexAttribute] = &[
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Float32x3,
+            offset: std::mem::offset_of!(GizmoVertex, position) as wgpu::BufferAddress,
+            shader_location: 0,
+        },
+        wgpu::VertexAttribute {
+            format: wgpu::VertexFormat::Unorm8x4,
+            offset: std::mem::offset_of!(GizmoVertex, color) as wgpu::BufferAddress,
+            shader_location: 1,
+        },
+    ];
+}
+
+/// Identifies gizmo geometry that is uploaded once and drawn until it is
+/// removed. Handles are never reused.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct StaticGizmoHandle(pub(crate) u64);
+
 impl GizmoVertex {
     pub fn new(position: Vec3, color: Vec4) -> Self {
+        let color = (color.clamp(Vec4::ZERO, Vec4::ONE) * 255.0).round();
         Self {
             position,
-            _padding: 1.0,
-            color,
+            color: color.to_array().map(|channel| channel as u8),
         }
     }
 }
@@ -71,6 +96,46 @@ pub fn create_iso_sphere(transform: Mat4, radius: f32, resolution: i32) -> Vec<G
     vertices
 }
 
+/// Connect consecutive `points` with lines.
+pub fn create_line_strip(points: &[Vec3], color: Vec4) -> Vec<GizmoVertex> {
+    points
+        .windows(2)
+        .flat_map(|segment| segment.iter().map(|&point| GizmoVertex::new(point, color)))
+        .collect()
+}
+
+/// A circle around `center` in the plane facing `normal`, made of `segments`
+/// lines.
+pub fn create_circle(
+    center: Vec3,
+    normal: Vec3,
+    radius: f32,
+    segments: u32,
+    color: Vec4,
+) -> Vec<GizmoVertex> {
+    let segments = segments.max(3);
+    let (u, v) = normal.normalize_or(Vec3::Z).any_orthonormal_pair();
+
+    let points: Vec<Vec3> = (0..=segments)
+        .map(|i| {
+            let theta = i as f32 * std::f32::consts::TAU / segments as f32;
+            center + (u * theta.cos() + v * theta.sin()) * radius
+        })
+        .collect();
+
+    create_line_strip(&points, color)
+}
+
+/// A sphere drawn as three circles, one around each axis.
+pub fn create_wire_sphere(center: Vec3, radius: f32, color: Vec4) -> Vec<GizmoVertex> {
+    const SEGMENTS: u32 = 32;
+
+    [Vec3::X, Vec3::Y, Vec3::Z]
+        .into_iter()
+        .flat_map(|axis| create_circle(center, axis, radius, SEGMENTS, color))
+        .collect()
+}
+
 pub fn create_view_projection(view_projection: &ViewProjection, color: Vec4) -> Vec<GizmoVertex> {
     const EDGES: &[(usize, usize)] = &[
         // near ring
@@ -148,3 +213,106 @@ pub fn create_bounding_box(bounding_box: &BoundingBox, color: Vec4) -> Vec<Gizmo
 
     result
 }
+
+/// Every how many lines a grid draws a major line.
+pub const GRID_MAJOR_EVERY: i32 = 10;
+
+/// A grid of lines on the ground (XY) plane through `center`, reaching
+/// `extent` in each direction with lines every `spacing` units. Every
+/// [GRID_MAJOR_EVERY]th line, counted from `center`, is drawn in `color`, the
+/// rest in a darker shade.
+pub fn create_grid(center: Vec3, extent: f32, spacing: f32, color: Vec4) -> Vec<GizmoVertex> {
+    if spacing <= 0.0 || extent <= 0.0 {
+        return Vec::new();
+    }
+
+    let minor_color = (color.truncate() * 0.4).extend(color.w);
+    let half_lines = (extent / spacing).floor() as i32;
+
+    let mut vertices = Vec::with_capacity((half_lines as usize * 2 + 1) * 4);
+
+    for i in -half_lines..=half_lines {
+        let color = if i % GRID_MAJOR_EVERY == 0 {
+            color
+        } else {
+            minor_color
+        };
+        let offset = i as f32 * spacing;
+
+        vertices.push(GizmoVertex::new(
+            center + Vec3::new(offset, -extent, 0.0),
+            color,
+        ));
+        vertices.push(GizmoVertex::new(
+            center + Vec3::new(offset, extent, 0.0),
+            color,
+        ));
+        vertices.push(GizmoVertex::new(
+            center + Vec3::new(-extent, offset, 0.0),
+            color,
+        ));
+        vertices.push(GizmoVertex::new(
+            center + Vec3::new(extent, offset, 0.0),
+            color,
+        ));
+    }
+
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_are_packed_as_unorm8x4() {
+        let layout = GizmoVertex::layout();
+        assert_eq!(layout.array_stride, 16);
+
+        let color = layout.attributes[1];
+        assert_eq!(color.format, wgpu::VertexFormat::Unorm8x4);
+        assert_eq!(color.offset, 12);
+        assert_eq!(color.shader_location, 1);
+
+        let vertex = GizmoVertex::new(Vec3::ZERO, Vec4::new(1.0, 0.5, 0.0, 2.0));
+        assert_eq!(vertex.color, [255, 128, 0, 255]);
+    }
+
+    #[test]
+    fn strips_and_circles_are_line_lists() {
+        let color = Vec4::ONE;
+
+        let strip = create_line_strip(&[Vec3::ZERO, Vec3::X, Vec3::Y], color);
+        let positions: Vec<Vec3> = strip.iter().map(|v| v.position).collect();
+        assert_eq!(positions, [Vec3::ZERO, Vec3::X, Vec3::X, Vec3::Y]);
+        assert!(create_line_strip(&[Vec3::ZERO], color).is_empty());
+
+        let center = Vec3::new(1.0, 2.0, 3.0);
+        let circle = create_circle(center, Vec3::Z, 5.0, 16, color);
+        assert_eq!(circle.len(), 16 * 2);
+        assert!(circle.iter().all(|v| {
+            let offset = v.position - center;
+            (offset.length() - 5.0).abs() < 1e-4 && offset.z.abs() < 1e-4
+        }));
+        // The circle is closed.
+        assert!(circle[0].position.abs_diff_eq(circle[31].position, 1e-4));
+
+        assert_eq!(create_wire_sphere(center, 5.0, color).len(), 3 * 32 * 2);
+    }
+
+    #[test]
+    fn grid_has_major_lines_every_nth_line() {
+        let color = Vec4::ONE;
+        let vertices = create_grid(Vec3::ZERO, 100.0, 10.0, color);
+
+        // 21 lines along each axis, 2 vertices per line.
+        assert_eq!(vertices.len(), 21 * 2 * 2);
+
+        let major = GizmoVertex::new(Vec3::ZERO, color).color;
+        let major_vertices = vertices.iter().filter(|v| v.color == major).count();
+        // Lines at -100, 0 and 100 along each axis.
+        assert_eq!(major_vertices, 3 * 2 * 2);
+
+        assert!(create_grid(Vec3::ZERO, 100.0, 0.0, color).is_empty());
+    }
+}
@@ -4,24 +4,44 @@
 var<uniform> u_camera: CameraEnv;
 
 struct VertexInput {
-    @location(0) position: vec4<f32>,
+    @location(0) position: vec3<f32>,
     @location(1) color: vec4<f32>,
 }
 
 struct VertexOutput {
     @builtin(position) world_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) view_depth: f32,
 }
 
 @vertex
 fn vertex_main(vertex: VertexInput) -> VertexOutput {
+    let to_vertex = vertex.position - u_camera.position.xyz;
+
     return VertexOutput(
-        u_camera.proj_view * vertex.position,
-        vertex.color
+        u_camera.proj_view * vec4<f32>(vertex.position, 1.0),
+        vertex.color,
+        dot(to_vertex, u_camera.forward.xyz),
     );
 }
 
 @fragment
 fn fragment_main(vertex: VertexOutput) -> @location(0) vec4<f32> {
     return vertex.color;
-}
\ No newline at end of file
+}
+
+/// Darkest a line gets at the far plane when coloring by depth.
+const MIN_DEPTH_BRIGHTNESS: f32 = 0.25;
+
+@fragment
+fn fragment_color_by_depth(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    // The camera near and far planes are packed into the w components.
+    let near = u_camera.position.w;
+    let far = u_camera.forward.w;
+
+    let t = clamp((vertex.view_depth - near) / max(far - near, 1e-3), 0.0, 1.0);
+    // Square root spreads the falloff over the near range, where gizmos are inspected.
+    let brightness = mix(1.0, MIN_DEPTH_BRIGHTNESS, sqrt(t));
+
+    return vec4<f32>(vertex.color.rgb * brightness, vertex.color.a);
+}
@@ -1,4 +1,7 @@
-use std::collections::HashSet;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 use bevy_ecs::prelude::*;
 use glam::{IVec2, UVec2};
@@ -12,7 +15,7 @@ pub use winit::event::MouseButton;
 pub use winit::keyboard::KeyCode;
 
 /// A high-level input event derived from winit window events.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum InputEvent {
     MouseMove(UVec2),
     MouseDown(MouseButton),
@@ -21,10 +24,21 @@ pub enum InputEvent {
     MouseLeave,
     KeyDown(KeyCode),
     KeyUp(KeyCode),
+    /// The window gained (`true`) or lost (`false`) the keyboard focus.
+    Focused(bool),
 }
 
+/// Default amount of pixels a trackpad has to scroll to count as a single
+/// mouse wheel line.
+pub const DEFAULT_PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
 /// Converts a winit `WindowEvent` into an `InputEvent`, if applicable.
-pub fn translate_window_event(event: &WindowEvent) -> Option<InputEvent> {
+/// Scrolling is reported in lines, with pixel deltas from trackpads divided by
+/// `pixels_per_scroll_line`.
+pub fn translate_window_event(
+    event: &WindowEvent,
+    pixels_per_scroll_line: f32,
+) -> Option<InputEvent> {
     match event {
         WindowEvent::KeyboardInput { event, .. } if !event.repeat => {
             let PhysicalKey::Code(key) = event.physical_key else {
@@ -47,13 +61,12 @@ pub fn translate_window_event(event: &WindowEvent) -> Option<InputEvent> {
 
         WindowEvent::CursorLeft { .. } => Some(InputEvent::MouseLeave),
 
-        WindowEvent::MouseWheel { delta, .. } => {
-            let delta = match delta {
-                MouseScrollDelta::LineDelta(_, y) => *y,
-                MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => *y as f32,
-            };
-            Some(InputEvent::MouseWheel(delta))
-        }
+        WindowEvent::Focused(focused) => Some(InputEvent::Focused(*focused)),
+
+        WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::MouseWheel(scroll_lines(
+            *delta,
+            pixels_per_scroll_line,
+        ))),
 
         WindowEvent::MouseInput { state, button, .. } => {
             if state.is_pressed() {
@@ -67,7 +80,17 @@ pub fn translate_window_event(event: &WindowEvent) -> Option<InputEvent> {
     }
 }
 
-#[derive(Clone, Default, Resource)]
+/// Convert a scroll delta to lines.
+fn scroll_lines(delta: MouseScrollDelta, pixels_per_scroll_line: f32) -> f32 {
+    match delta {
+        MouseScrollDelta::LineDelta(_, y) => y,
+        MouseScrollDelta::PixelDelta(PhysicalPosition { y, .. }) => {
+            y as f32 / pixels_per_scroll_line.max(1.0)
+        }
+    }
+}
+
+#[derive(Clone, Resource)]
 pub struct InputState {
     /// The current position of the mouse inside the window client area in pixels. Set to `None` If
     /// the mouse is not over the client area.
@@ -79,15 +102,82 @@ pub struct InputState {
     mouse_just_pressed: HashSet<MouseButton>,
     mouse_just_released: HashSet<MouseButton>,
 
+    mouse_double_clicked: HashSet<MouseButton>,
+    /// When each currently held button was pressed.
+    mouse_pressed_at: HashMap<MouseButton, Duration>,
+    /// When each button was last pressed, if it can still start a double-click.
+    mouse_last_click_at: HashMap<MouseButton, Duration>,
+    /// Maximum time between two presses for them to count as a double-click.
+    double_click_window: Duration,
+
     key_pressed: HashSet<KeyCode>,
     key_just_pressed: HashSet<KeyCode>,
 
-    wheel_delta: f32,
+    /// Lines scrolled this frame.
+    scroll_delta: f32,
+
+    /// Whether the window has the keyboard focus. Input is ignored without it.
+    has_focus: bool,
+
+    /// Time the input state has been advanced by with [InputState::advance].
+    /// Events are stamped with it instead of the wall clock, so replayed
+    /// input produces the same clicks and hold durations.
+    now: Duration,
+}
+
+impl Default for InputState {
+    fn default() -> Self {
+        Self {
+            mouse_position: None,
+            last_mouse_position: None,
+            mouse_delta: None,
+            mouse_pressed: HashSet::default(),
+            mouse_just_pressed: HashSet::default(),
+            mouse_just_released: HashSet::default(),
+            mouse_double_clicked: HashSet::default(),
+            mouse_pressed_at: HashMap::default(),
+            mouse_last_click_at: HashMap::default(),
+            double_click_window: Self::DEFAULT_DOUBLE_CLICK_WINDOW,
+            key_pressed: HashSet::default(),
+            key_just_pressed: HashSet::default(),
+            scroll_delta: 0.0,
+            has_focus: true,
+            now: Duration::ZERO,
+        }
+    }
 }
 
 impl InputState {
+    /// Default maximum time between two presses of a double-click.
+    pub const DEFAULT_DOUBLE_CLICK_WINDOW: Duration = Duration::from_millis(400);
+
+    /// Set the maximum time between two presses for them to count as a double-click.
+    pub fn set_double_click_window(&mut self, window: Duration) {
+        self.double_click_window = window;
+    }
+
+    /// Advance the clock events are stamped with by `delta_time` seconds. Call
+    /// once per simulation update.
+    pub fn advance(&mut self, delta_time: f32) {
+        self.now += Duration::from_secs_f32(delta_time.max(0.0));
+    }
+
     /// Apply a single input event, updating the accumulated state.
     pub fn apply(&mut self, event: &InputEvent) {
+        self.apply_at(event, self.now);
+    }
+
+    /// Apply a single input event that happened at `now`.
+    fn apply_at(&mut self, event: &InputEvent, now: Duration) {
+        if let InputEvent::Focused(focused) = *event {
+            self.set_focus(focused);
+            return;
+        }
+
+        if !self.has_focus {
+            return;
+        }
+
         match *event {
             InputEvent::KeyDown(key) => {
                 self.key_pressed.insert(key);
@@ -107,31 +197,68 @@ impl InputState {
                 self.mouse_position = None;
             }
             InputEvent::MouseWheel(delta) => {
-                self.wheel_delta = delta;
+                // Trackpads send many small deltas per frame.
+                self.scroll_delta += delta;
             }
             InputEvent::MouseDown(button) => {
                 self.mouse_pressed.insert(button);
                 self.mouse_just_pressed.insert(button);
+                self.mouse_pressed_at.insert(button, now);
+
+                // The second press of a double-click can't also start the next one.
+                match self.mouse_last_click_at.remove(&button) {
+                    Some(last) if now.saturating_sub(last) <= self.double_click_window => {
+                        self.mouse_double_clicked.insert(button);
+                    }
+                    _ => {
+                        self.mouse_last_click_at.insert(button, now);
+                    }
+                }
             }
             InputEvent::MouseUp(button) => {
                 self.mouse_pressed.remove(&button);
                 self.mouse_just_released.insert(button);
+                self.mouse_pressed_at.remove(&button);
             }
+            InputEvent::Focused(_) => {}
         }
     }
 
+    /// Keys and buttons released while the window is not focused never send an
+    /// event, so everything held is dropped when the focus is lost. No release
+    /// is reported, so losing focus mid-drag doesn't count as a click.
+    fn set_focus(&mut self, focused: bool) {
+        self.has_focus = focused;
+        if focused {
+            return;
+        }
+
+        self.key_pressed.clear();
+        self.mouse_pressed.clear();
+        self.mouse_pressed_at.clear();
+        self.mouse_last_click_at.clear();
+        self.mouse_delta = None;
+        self.last_mouse_position = None;
+    }
+
     /// Reset per-frame transient state (e.g. "just pressed", "just released", deltas).
     /// Call once per frame after all systems have read the input.
     pub fn reset_per_frame(&mut self) {
         self.key_just_pressed.clear();
         self.mouse_just_pressed.clear();
         self.mouse_just_released.clear();
+        self.mouse_double_clicked.clear();
         self.mouse_delta = None;
-        self.wheel_delta = 0.0;
+        self.scroll_delta = 0.0;
     }
 }
 
 impl InputState {
+    /// Whether the window has the keyboard focus.
+    pub fn has_focus(&self) -> bool {
+        self.has_focus
+    }
+
     pub fn mouse_position(&self) -> Option<UVec2> {
         self.mouse_position
     }
@@ -156,11 +283,170 @@ impl InputState {
         self.mouse_just_released.contains(&button)
     }
 
+    /// Whether `button` was pressed twice within the double-click window this frame.
+    pub fn mouse_double_clicked(&self, button: MouseButton) -> bool {
+        self.mouse_double_clicked.contains(&button)
+    }
+
+    /// How long `button` has been held down, or `None` if it is not pressed.
+    pub fn mouse_held_for(&self, button: MouseButton) -> Option<Duration> {
+        self.mouse_held_for_at(button, self.now)
+    }
+
+    /// How long `button` has been held down as of `now`.
+    fn mouse_held_for_at(&self, button: MouseButton, now: Duration) -> Option<Duration> {
+        self.mouse_pressed_at
+            .get(&button)
+            .map(|pressed_at| now.saturating_sub(*pressed_at))
+    }
+
     pub fn mouse_delta(&self) -> Option<IVec2> {
         self.mouse_delta
     }
 
-    pub fn wheel_delta(&self) -> f32 {
-        self.wheel_delta
+    /// Lines scrolled this frame, positive when scrolling up or away from
+    /// the user. Mouse wheels and trackpads report in the same unit.
+    pub fn scroll_delta(&self) -> f32 {
+        self.scroll_delta
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: MouseButton = MouseButton::Left;
+
+    fn click(input: &mut InputState, at: Duration) {
+        input.apply_at(&InputEvent::MouseDown(LEFT), at);
+        input.apply_at(&InputEvent::MouseUp(LEFT), at + Duration::from_millis(50));
+    }
+
+    #[test]
+    fn two_quick_presses_are_a_double_click() {
+        let start = Duration::ZERO;
+        let mut input = InputState::default();
+
+        click(&mut input, start);
+        assert!(!input.mouse_double_clicked(LEFT));
+        input.reset_per_frame();
+
+        click(&mut input, start + Duration::from_millis(200));
+        assert!(input.mouse_double_clicked(LEFT));
+
+        input.reset_per_frame();
+        assert!(!input.mouse_double_clicked(LEFT));
+    }
+
+    #[test]
+    fn slow_presses_are_not_a_double_click() {
+        let start = Duration::ZERO;
+        let mut input = InputState::default();
+        input.set_double_click_window(Duration::from_millis(100));
+
+        click(&mut input, start);
+        click(&mut input, start + Duration::from_millis(300));
+        assert!(!input.mouse_double_clicked(LEFT));
+
+        // The slow second press starts a new double-click.
+        click(&mut input, start + Duration::from_millis(350));
+        assert!(input.mouse_double_clicked(LEFT));
+    }
+
+    #[test]
+    fn third_press_does_not_double_click_again() {
+        let start = Duration::ZERO;
+        let mut input = InputState::default();
+
+        click(&mut input, start);
+        click(&mut input, start + Duration::from_millis(100));
+        input.reset_per_frame();
+
+        click(&mut input, start + Duration::from_millis(200));
+        assert!(!input.mouse_double_clicked(LEFT));
+    }
+
+    #[test]
+    fn hold_duration_is_tracked_until_release() {
+        let start = Duration::ZERO;
+        let mut input = InputState::default();
+
+        assert_eq!(input.mouse_held_for_at(LEFT, start), None);
+
+        input.apply_at(&InputEvent::MouseDown(LEFT), start);
+        input.reset_per_frame();
+
+        let later = start + Duration::from_millis(750);
+        assert_eq!(
+            input.mouse_held_for_at(LEFT, later),
+            Some(Duration::from_millis(750))
+        );
+
+        input.apply_at(&InputEvent::MouseUp(LEFT), later);
+        assert_eq!(input.mouse_held_for_at(LEFT, later), None);
+    }
+
+    #[test]
+    fn hold_duration_follows_the_simulation_clock() {
+        let mut input = InputState::default();
+        input.advance(10.0);
+
+        input.apply(&InputEvent::MouseDown(LEFT));
+        assert_eq!(input.mouse_held_for(LEFT), Some(Duration::ZERO));
+
+        input.advance(0.5);
+        input.advance(0.25);
+        assert_eq!(input.mouse_held_for(LEFT), Some(Duration::from_millis(750)));
+    }
+
+    #[test]
+    fn losing_focus_mid_drag_is_not_a_release() {
+        let mut input = InputState::default();
+        input.apply(&InputEvent::MouseDown(LEFT));
+        input.reset_per_frame();
+
+        input.apply(&InputEvent::Focused(false));
+        assert!(!input.mouse_just_released(LEFT));
+        assert_eq!(input.mouse_held_for(LEFT), None);
+    }
+
+    #[test]
+    fn losing_focus_drops_everything_held() {
+        let mut input = InputState::default();
+        input.apply(&InputEvent::KeyDown(KeyCode::KeyW));
+        input.apply(&InputEvent::MouseDown(LEFT));
+        input.reset_per_frame();
+
+        input.apply(&InputEvent::Focused(false));
+        assert!(!input.has_focus());
+        assert!(!input.key_pressed(KeyCode::KeyW));
+        assert!(!input.mouse_pressed(LEFT));
+        assert!(!input.mouse_just_released(LEFT));
+
+        // Input is ignored until the focus returns.
+        input.apply(&InputEvent::KeyDown(KeyCode::KeyW));
+        assert!(!input.key_pressed(KeyCode::KeyW));
+
+        input.apply(&InputEvent::Focused(true));
+        input.apply(&InputEvent::KeyDown(KeyCode::KeyW));
+        assert!(input.key_pressed(KeyCode::KeyW));
+    }
+
+    #[test]
+    fn pixel_scrolling_is_normalized_to_lines() {
+        let pixels = MouseScrollDelta::PixelDelta(PhysicalPosition::new(0.0, 30.0));
+        assert_eq!(scroll_lines(pixels, 20.0), 1.5);
+        assert_eq!(
+            scroll_lines(MouseScrollDelta::LineDelta(0.0, -2.0), 20.0),
+            -2.0
+        );
+
+        let mut input = InputState::default();
+        input.apply(&InputEvent::MouseWheel(scroll_lines(pixels, 20.0)));
+        input.apply(&InputEvent::MouseWheel(scroll_lines(pixels, 20.0)));
+        assert_eq!(input.scroll_delta(), 3.0);
+
+        input.reset_per_frame();
+        assert_eq!(input.scroll_delta(), 0.0);
     }
 }
@@ -1,5 +1,26 @@
+use glam::Vec3;
+use thiserror::Error;
 use wgpu::util::DeviceExt;
 
+/// Problems found by [IndexedMesh::validate].
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum MeshError {
+    #[error("index count {0} is not a multiple of 3")]
+    IncompleteTriangle(usize),
+
+    #[error("index {index} at position {position} is out of range for {vertex_count} vertices")]
+    IndexOutOfRange {
+        position: usize,
+        index: u32,
+        vertex_count: usize,
+    },
+}
+
+/// Vertices that have a position, used for geometric checks on meshes.
+pub trait VertexPosition {
+    fn position(&self) -> Vec3;
+}
+
 #[derive(Clone)]
 pub struct IndexedMesh<V> {
     pub vertices: Vec<V>,
@@ -51,6 +72,71 @@ impl<V: Copy> IndexedMesh<V> {
     }
 }
 
+impl<V> IndexedMesh<V> {
+    /// Check that the indices form whole triangles and only reference
+    /// existing vertices.
+    pub fn validate(&self) -> Result<(), MeshError> {
+        if !self.indices.len().is_multiple_of(3) {
+            return Err(MeshError::IncompleteTriangle(self.indices.len()));
+        }
+
+        let vertex_count = self.vertices.len();
+        if let Some((position, &index)) = self
+            .indices
+            .iter()
+            .enumerate()
+            .find(|(_, index)| **index as usize >= vertex_count)
+        {
+            return Err(MeshError::IndexOutOfRange {
+                position,
+                index,
+                vertex_count,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl<V: VertexPosition> IndexedMesh<V> {
+    /// Remove triangles with zero area, either because they repeat a vertex or
+    /// because their corners are collinear. Vertices are left untouched.
+    /// Returns the number of triangles removed.
+    pub fn remove_degenerate_triangles(&mut self) -> usize {
+        let triangle_count = self.indices.len() / 3;
+
+        let vertices = &self.vertices;
+        let is_degenerate = |triangle: &[u32]| {
+            if triangle[0] == triangle[1]
+                || triangle[1] == triangle[2]
+                || triangle[0] == triangle[2]
+            {
+                return true;
+            }
+
+            // Out of range triangles are reported by `validate`, not removed here.
+            let [Some(a), Some(b), Some(c)] = [0, 1, 2].map(|i| vertices.get(triangle[i] as usize))
+            else {
+                return false;
+            };
+
+            let (a, b, c) = (a.position(), b.position(), c.position());
+            (b - a).cross(c - a).length_squared() <= f32::EPSILON * f32::EPSILON
+        };
+
+        let indices: Vec<u32> = self
+            .indices
+            .chunks_exact(3)
+            .filter(|triangle| !is_degenerate(triangle))
+            .flatten()
+            .copied()
+            .collect();
+
+        self.indices = indices;
+        triangle_count - self.indices.len() / 3
+    }
+}
+
 impl<V> Default for IndexedMesh<V> {
     fn default() -> Self {
         Self {
@@ -100,3 +186,52 @@ impl<V: bytemuck::NoUninit> IndexedMesh<V> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl VertexPosition for Vec3 {
+        fn position(&self) -> Vec3 {
+            *self
+        }
+    }
+
+    fn quad() -> IndexedMesh<Vec3> {
+        IndexedMesh {
+            vertices: vec![Vec3::ZERO, Vec3::X, Vec3::ONE, Vec3::Y],
+            indices: vec![0, 1, 2, 0, 2, 3],
+        }
+    }
+
+    #[test]
+    fn validate_reports_bad_indices() {
+        assert_eq!(quad().validate(), Ok(()));
+
+        let mut mesh = quad();
+        mesh.indices[4] = 7;
+        assert_eq!(
+            mesh.validate(),
+            Err(MeshError::IndexOutOfRange {
+                position: 4,
+                index: 7,
+                vertex_count: 4,
+            })
+        );
+
+        let mut mesh = quad();
+        mesh.indices.pop();
+        assert_eq!(mesh.validate(), Err(MeshError::IncompleteTriangle(5)));
+    }
+
+    #[test]
+    fn remove_degenerate_triangles_keeps_valid_faces() {
+        let mut mesh = quad();
+        mesh.vertices.push(Vec3::X * 2.0);
+        // Repeated vertex and collinear corners.
+        mesh.indices.extend([1, 1, 2, 0, 1, 4]);
+
+        assert_eq!(mesh.remove_degenerate_triangles(), 2);
+        assert_eq!(mesh.indices, quad().indices);
+    }
+}
@@ -1,8 +1,10 @@
 pub mod assets;
+pub mod frame_pacing;
 pub mod gizmos;
 pub mod growing_buffer;
 pub mod input;
 pub mod mesh;
+pub mod mouse_settings;
 pub mod renderer;
 pub mod scene;
 pub mod shader_cache;
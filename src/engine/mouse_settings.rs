@@ -0,0 +1,103 @@
+use std::{fmt::Write as _, path::Path};
+
+use bevy_ecs::prelude::*;
+use glam::Vec2;
+
+use crate::{
+    engine::assets::AssetError,
+    game::config::parser::{ConfigLines, ConfigSchema},
+};
+
+/// Player preferences for looking around with the mouse, read by camera
+/// controllers.
+#[derive(Clone, Copy, Debug, PartialEq, Resource)]
+pub struct MouseSettings {
+    /// Look rotation in degrees per pixel of mouse movement.
+    pub sensitivity: f32,
+    /// Moving the mouse up looks down.
+    pub invert_y: bool,
+}
+
+impl Default for MouseSettings {
+    fn default() -> Self {
+        Self {
+            sensitivity: 0.2,
+            invert_y: false,
+        }
+    }
+}
+
+impl MouseSettings {
+    /// File the settings are stored in, relative to the working directory.
+    pub const PATH: &str = "mouse_settings.txt";
+
+    /// Convert a mouse delta in pixels to a (yaw, pitch) delta in degrees.
+    pub fn look_delta(&self, mouse_delta: Vec2) -> Vec2 {
+        let pitch_sign = if self.invert_y { -1.0 } else { 1.0 };
+        Vec2::new(mouse_delta.x, mouse_delta.y * pitch_sign) * self.sensitivity
+    }
+
+    /// Read settings from `path`. Missing or invalid files fall back to the
+    /// defaults.
+    pub fn load_or_default(path: &Path) -> Self {
+        if !path.exists() {
+            return Self::default();
+        }
+
+        match Self::load(path) {
+            Ok(settings) => settings,
+            Err(err) => {
+                tracing::warn!("Could not load mouse settings: {err}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Describes how each line in the settings file maps to a field.
+    fn schema() -> ConfigSchema<Self> {
+        ConfigSchema::<Self>::new("MouseSettings")
+            .field("sensitivity", |s| &mut s.sensitivity)
+            .field("invert_y", |s| &mut s.invert_y)
+    }
+
+    /// Read settings written by [Self::save]. Keys that are not present keep
+    /// their default values.
+    pub fn load(path: &Path) -> Result<Self, AssetError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|err| AssetError::from_io_error(err, path))?;
+
+        Ok(Self::schema().parse(&ConfigLines::parse(&text)))
+    }
+
+    /// Write the settings to `path` as `key value` lines.
+    pub fn save(&self, path: &Path) -> Result<(), AssetError> {
+        let mut out = String::new();
+        let _ = writeln!(out, "sensitivity {}", self.sensitivity);
+        let _ = writeln!(out, "invert_y {}", self.invert_y);
+
+        std::fs::write(path, out).map_err(|err| AssetError::from_io_error(err, path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_survive_a_save_and_load() {
+        let path = std::env::temp_dir().join(format!(
+            "mouse_settings_round_trip_{}.txt",
+            std::process::id()
+        ));
+
+        let settings = MouseSettings {
+            sensitivity: 0.35,
+            invert_y: true,
+        };
+        settings.save(&path).unwrap();
+        let loaded = MouseSettings::load(&path);
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.unwrap(), settings);
+    }
+}
@@ -0,0 +1,215 @@
+/// Resource bound to a single binding of a bind group layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    /// A uniform buffer.
+    Uniform,
+    /// A storage buffer, only readable from shaders when `read_only` is set.
+    Storage { read_only: bool },
+    /// A single sampled texture.
+    Texture {
+        dimension: wgpu::TextureViewDimension,
+        sample_type: wgpu::TextureSampleType,
+    },
+    /// A sampler.
+    Sampler(wgpu::SamplerBindingType),
+}
+
+impl BindingKind {
+    /// The [wgpu::BindingType] for the binding.
+    pub fn binding_type(self) -> wgpu::BindingType {
+        let buffer = |ty| wgpu::BindingType::Buffer {
+            ty,
+            has_dynamic_offset: false,
+            min_binding_size: None,
+        };
+
+        match self {
+            Self::Uniform => buffer(wgpu::BufferBindingType::Uniform),
+            Self::Storage { read_only } => buffer(wgpu::BufferBindingType::Storage { read_only }),
+            Self::Texture {
+                dimension,
+                sample_type,
+            } => wgpu::BindingType::Texture {
+                sample_type,
+                view_dimension: dimension,
+                multisampled: false,
+            },
+            Self::Sampler(ty) => wgpu::BindingType::Sampler(ty),
+        }
+    }
+
+    /// Stages a binding of this kind is visible to unless overridden. Buffers
+    /// are often read by both stages, textures and samplers usually only while
+    /// shading.
+    pub fn default_visibility(self) -> wgpu::ShaderStages {
+        match self {
+            Self::Uniform | Self::Storage { .. } => wgpu::ShaderStages::VERTEX_FRAGMENT,
+            Self::Texture { .. } | Self::Sampler(_) => wgpu::ShaderStages::FRAGMENT,
+        }
+    }
+}
+
+/// Collects the entries of a bind group layout without spelling out every
+/// [wgpu::BindGroupLayoutEntry]. Entries are sorted by binding and each
+/// binding can only be used once.
+#[derive(Default)]
+pub struct BindGroupLayoutEntries {
+    entries: Vec<wgpu::BindGroupLayoutEntry>,
+}
+
+impl BindGroupLayoutEntries {
+    /// Start an empty layout.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a uniform buffer at `binding`.
+    pub fn uniform(self, binding: u32) -> Self {
+        self.with(binding, BindingKind::Uniform)
+    }
+
+    /// Add a read-only storage buffer at `binding`.
+    pub fn storage(self, binding: u32) -> Self {
+        self.with(binding, BindingKind::Storage { read_only: true })
+    }
+
+    /// Add a storage buffer at `binding` that shaders can write to.
+    pub fn storage_read_write(self, binding: u32) -> Self {
+        self.with(binding, BindingKind::Storage { read_only: false })
+    }
+
+    /// Add a texture at `binding`.
+    pub fn texture(
+        self,
+        binding: u32,
+        dimension: wgpu::TextureViewDimension,
+        sample_type: wgpu::TextureSampleType,
+    ) -> Self {
+        self.with(
+            binding,
+            BindingKind::Texture {
+                dimension,
+                sample_type,
+            },
+        )
+    }
+
+    /// Add a sampler at `binding`.
+    pub fn sampler(self, binding: u32, ty: wgpu::SamplerBindingType) -> Self {
+        self.with(binding, BindingKind::Sampler(ty))
+    }
+
+    /// Add a binding of any kind, visible to the kind's
+    /// [BindingKind::default_visibility].
+    ///
+    /// Panics if `binding` is already used.
+    pub fn with(mut self, binding: u32, kind: BindingKind) -> Self {
+        assert!(
+            self.entries.iter().all(|entry| entry.binding != binding),
+            "binding {binding} is used more than once"
+        );
+
+        self.entries.push(wgpu::BindGroupLayoutEntry {
+            binding,
+            visibility: kind.default_visibility(),
+            ty: kind.binding_type(),
+            count: None,
+        });
+        self
+    }
+
+    /// Override the stages the last added binding is visible to.
+    ///
+    /// Panics if no binding was added yet.
+    pub fn visibility(mut self, visibility: wgpu::ShaderStages) -> Self {
+        self.entries
+            .last_mut()
+            .expect("no binding to set the visibility of")
+            .visibility = visibility;
+        self
+    }
+
+    /// The entries sorted by binding.
+    pub fn build(mut self) -> Vec<wgpu::BindGroupLayoutEntry> {
+        self.entries.sort_by_key(|entry| entry.binding);
+        self.entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_are_typed_and_sorted() {
+        let entries = BindGroupLayoutEntries::new()
+            .storage(1)
+            .visibility(wgpu::ShaderStages::VERTEX)
+            .uniform(0)
+            .build();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].binding, 0);
+        assert_eq!(entries[0].ty, BindingKind::Uniform.binding_type());
+        assert_eq!(entries[0].visibility, wgpu::ShaderStages::VERTEX_FRAGMENT);
+        assert_eq!(entries[1].binding, 1);
+        assert_eq!(
+            entries[1].ty,
+            wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Storage { read_only: true },
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            }
+        );
+        assert_eq!(entries[1].visibility, wgpu::ShaderStages::VERTEX);
+    }
+
+    #[test]
+    fn textures_and_samplers_default_to_fragment() {
+        let entries = BindGroupLayoutEntries::new()
+            .texture(
+                0,
+                wgpu::TextureViewDimension::D2Array,
+                wgpu::TextureSampleType::Float { filterable: true },
+            )
+            .sampler(1, wgpu::SamplerBindingType::Filtering)
+            .build();
+
+        assert_eq!(
+            entries[0].ty,
+            wgpu::BindingType::Texture {
+                sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                view_dimension: wgpu::TextureViewDimension::D2Array,
+                multisampled: false,
+            }
+        );
+        assert_eq!(
+            entries[1].ty,
+            wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering)
+        );
+        assert!(
+            entries
+                .iter()
+                .all(|entry| entry.visibility == wgpu::ShaderStages::FRAGMENT)
+        );
+    }
+
+    #[test]
+    fn uniform_visibility_can_be_narrowed() {
+        let entries = BindGroupLayoutEntries::new()
+            .uniform(0)
+            .visibility(wgpu::ShaderStages::COMPUTE)
+            .uniform(1)
+            .visibility(wgpu::ShaderStages::FRAGMENT)
+            .build();
+
+        assert_eq!(entries[0].visibility, wgpu::ShaderStages::COMPUTE);
+        assert_eq!(entries[1].visibility, wgpu::ShaderStages::FRAGMENT);
+    }
+
+    #[test]
+    #[should_panic(expected = "binding 0 is used more than once")]
+    fn duplicate_bindings_panic() {
+        let _ = BindGroupLayoutEntries::new().uniform(0).storage(0);
+    }
+}
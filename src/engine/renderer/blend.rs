@@ -0,0 +1,99 @@
+/// Named blend states matching the material modes used by the game.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BlendPreset {
+    /// Source replaces the destination.
+    #[default]
+    Opaque,
+    /// Classic `src * α + dst * (1 - α)` blending.
+    AlphaBlend,
+    /// Source is added to the destination, used for emissive surfaces and OIT
+    /// accumulation.
+    Additive,
+    /// Like [BlendPreset::AlphaBlend], but the source color is already
+    /// multiplied by its alpha.
+    PremultipliedAlpha,
+}
+
+impl BlendPreset {
+    /// The [wgpu::BlendState] for the preset, or `None` when blending is
+    /// disabled.
+    pub const fn blend_state(self) -> Option<wgpu::BlendState> {
+        match self {
+            Self::Opaque => None,
+            Self::AlphaBlend => Some(wgpu::BlendState::ALPHA_BLENDING),
+            Self::Additive => {
+                let component = wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                };
+                Some(wgpu::BlendState {
+                    color: component,
+                    alpha: component,
+                })
+            }
+            Self::PremultipliedAlpha => Some(wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING),
+        }
+    }
+}
+
+/// A color target of a render pipeline and how fragments are blended into it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorTargetDescriptor {
+    pub format: wgpu::TextureFormat,
+    /// The blend state, or `None` to replace the destination.
+    pub blend: Option<wgpu::BlendState>,
+    pub write_mask: wgpu::ColorWrites,
+}
+
+impl ColorTargetDescriptor {
+    /// A target writing all channels of `format`, blended with `blend`.
+    pub const fn new(format: wgpu::TextureFormat, blend: BlendPreset) -> Self {
+        Self {
+            format,
+            blend: blend.blend_state(),
+            write_mask: wgpu::ColorWrites::ALL,
+        }
+    }
+
+    /// A target where fragments replace the destination.
+    pub const fn opaque(format: wgpu::TextureFormat) -> Self {
+        Self::new(format, BlendPreset::Opaque)
+    }
+
+    /// A target blended with [BlendPreset::AlphaBlend].
+    pub const fn alpha_blend(format: wgpu::TextureFormat) -> Self {
+        Self::new(format, BlendPreset::AlphaBlend)
+    }
+
+    /// A target blended with [BlendPreset::Additive].
+    pub const fn additive(format: wgpu::TextureFormat) -> Self {
+        Self::new(format, BlendPreset::Additive)
+    }
+
+    /// The same target with a blend state that has no preset.
+    pub const fn with_blend(self, blend: Option<wgpu::BlendState>) -> Self {
+        Self { blend, ..self }
+    }
+
+    /// The same target only writing the channels in `write_mask`.
+    pub const fn with_write_mask(self, write_mask: wgpu::ColorWrites) -> Self {
+        Self { write_mask, ..self }
+    }
+
+    /// Build the [wgpu::ColorTargetState]. Usable in constants, unlike the
+    /// [From] conversion.
+    pub const fn state(self) -> wgpu::ColorTargetState {
+        wgpu::ColorTargetState {
+            format: self.format,
+            blend: self.blend,
+            write_mask: self.write_mask,
+        }
+    }
+}
+
+impl From<ColorTargetDescriptor> for wgpu::ColorTargetState {
+    fn from(value: ColorTargetDescriptor) -> Self {
+        value.state()
+    }
+}
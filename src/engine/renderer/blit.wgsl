@@ -0,0 +1,25 @@
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) tex_coords: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var u_source: texture_2d<f32>;
+
+@group(0) @binding(1)
+var u_sampler: sampler;
+
+// Draws a single triangle covering the whole target with vertex indices 0, 1 and 2. The texture
+// coordinates are 0..1 over the visible part of the triangle.
+@vertex
+fn vertex(@builtin(vertex_index) vertex_index: u32) -> VertexOutput {
+    let tex_coords = vec2<f32>(f32(vertex_index >> 1u), f32(vertex_index & 1u)) * 2.0;
+    let position = vec4<f32>(tex_coords * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+
+    return VertexOutput(position, tex_coords);
+}
+
+@fragment
+fn fragment(vertex: VertexOutput) -> @location(0) vec4<f32> {
+    return textureSample(u_source, u_sampler, vertex.tex_coords);
+}
@@ -0,0 +1,65 @@
+use glam::Vec3;
+
+/// A color in linear space, the space wgpu expects clear values in.
+///
+/// wgpu does not encode clear values for sRGB targets: a clear of `0.5` on an
+/// `*Srgb` texture is treated as linear and encoded on store, which shows up
+/// as roughly `188` on screen instead of `128`. Colors picked in an image
+/// editor or read from the game data are sRGB and have to go through
+/// [Color::from_srgb] before being used for a clear.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+impl Color {
+    pub const BLACK: Self = Self::new(0.0, 0.0, 0.0, 1.0);
+
+    /// Create a color from linear components.
+    pub const fn new(r: f64, g: f64, b: f64, a: f64) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// Create an opaque color from 8-bit sRGB components.
+    pub fn from_srgb(r: u8, g: u8, b: u8) -> Self {
+        Self::from_srgb_vec3(Vec3::new(r as f32, g as f32, b as f32) / 255.0)
+    }
+
+    /// Create an opaque color from sRGB components in the `0..=1` range.
+    pub fn from_srgb_vec3(srgb: Vec3) -> Self {
+        Self::new(
+            srgb_to_linear(srgb.x) as f64,
+            srgb_to_linear(srgb.y) as f64,
+            srgb_to_linear(srgb.z) as f64,
+            1.0,
+        )
+    }
+
+    /// The linear RGB components.
+    pub fn to_vec3(self) -> Vec3 {
+        Vec3::new(self.r as f32, self.g as f32, self.b as f32)
+    }
+}
+
+impl From<Color> for wgpu::Color {
+    fn from(color: Color) -> Self {
+        wgpu::Color {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        }
+    }
+}
+
+/// Convert a single sRGB encoded component to linear.
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
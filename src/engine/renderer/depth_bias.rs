@@ -0,0 +1,43 @@
+/// Depth bias applied by a pipeline, in the units of [wgpu::DepthBiasState].
+///
+/// A positive bias pushes fragments away from the camera, so surfaces that are
+/// drawn on top of coplanar geometry use a negative bias.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct DepthBiasDescriptor {
+    /// Constant offset in the smallest resolvable depth units.
+    pub constant: i32,
+    /// Offset scaled by the slope of the polygon relative to the view.
+    pub slope_scale: f32,
+    /// Maximum absolute offset. `0.0` disables clamping.
+    pub clamp: f32,
+}
+
+impl DepthBiasDescriptor {
+    /// No depth bias.
+    pub const NONE: Self = Self {
+        constant: 0,
+        slope_scale: 0.0,
+        clamp: 0.0,
+    };
+
+    /// Bias for decals and ground overlays such as selection markers, pulling
+    /// them toward the camera far enough to win against the surface they lie
+    /// on without visibly floating above it.
+    pub fn decal() -> Self {
+        Self {
+            constant: -2,
+            slope_scale: -2.0,
+            clamp: 0.0,
+        }
+    }
+}
+
+impl From<DepthBiasDescriptor> for wgpu::DepthBiasState {
+    fn from(value: DepthBiasDescriptor) -> Self {
+        wgpu::DepthBiasState {
+            constant: value.constant,
+            slope_scale: value.slope_scale,
+            clamp: value.clamp,
+        }
+    }
+}
@@ -0,0 +1,72 @@
+/// A value built for a specific texture format, like a pipeline that renders
+/// into the surface. Users pass the format of the target they are about to
+/// render into to [FormatDependent::update], which rebuilds the value only if
+/// that format changed.
+pub struct FormatDependent<T> {
+    format: wgpu::TextureFormat,
+    value: T,
+}
+
+impl<T> FormatDependent<T> {
+    /// Build the value for `format`.
+    pub fn new(format: wgpu::TextureFormat, build: impl FnOnce(wgpu::TextureFormat) -> T) -> Self {
+        Self {
+            format,
+            value: build(format),
+        }
+    }
+
+    /// The format the value was built for.
+    #[inline]
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.format
+    }
+
+    #[inline]
+    pub fn get(&self) -> &T {
+        &self.value
+    }
+
+    /// Rebuild the value if `format` differs from the one it was built for.
+    /// Returns true if the value was rebuilt.
+    pub fn update(
+        &mut self,
+        format: wgpu::TextureFormat,
+        build: impl FnOnce(wgpu::TextureFormat) -> T,
+    ) -> bool {
+        if format == self.format {
+            return false;
+        }
+
+        tracing::info!(
+            "Render target format changed from {:?} to {format:?}, rebuilding.",
+            self.format
+        );
+        self.format = format;
+        self.value = build(format);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebuilds_only_when_the_format_changes() {
+        use wgpu::TextureFormat as F;
+
+        let mut builds = 0;
+        let mut build = |format: F| {
+            builds += 1;
+            format
+        };
+
+        let mut value = FormatDependent::new(F::Bgra8UnormSrgb, &mut build);
+        assert!(!value.update(F::Bgra8UnormSrgb, &mut build));
+        assert!(value.update(F::Rgba16Float, &mut build));
+        assert_eq!(*value.get(), F::Rgba16Float);
+        assert_eq!(value.format(), F::Rgba16Float);
+        assert_eq!(builds, 2);
+    }
+}
@@ -1,11 +1,152 @@
+/// Counts of live GPU resources, used to spot resources that are created
+/// without ever being freed.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ResourceReport {
+    pub buffers: usize,
+    pub textures: usize,
+    pub texture_views: usize,
+    pub samplers: usize,
+    pub shader_modules: usize,
+    pub render_pipelines: usize,
+    pub compute_pipelines: usize,
+    pub bind_groups: usize,
+    /// Bytes allocated on the GPU, if the backend reports it.
+    pub allocated_bytes: Option<u64>,
+}
+
+/// Optional device features that were available and enabled. Code that
+/// relies on one of these must check it and take a fallback path when it is
+/// missing, because the device is created with whatever subset is supported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RendererCapabilities {
+    /// [wgpu::Features::MULTI_DRAW_INDIRECT_COUNT].
+    pub multi_draw_indirect_count: bool,
+    /// [wgpu::Features::POLYGON_MODE_LINE], for wireframe pipelines.
+    pub polygon_mode_line: bool,
+    /// [wgpu::Features::TEXTURE_BINDING_ARRAY].
+    pub texture_binding_array: bool,
+    /// [wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING].
+    pub non_uniform_indexing: bool,
+    /// Samplers may use an anisotropy clamp above 1.
+    pub anisotropic_filtering: bool,
+}
+
+impl RendererCapabilities {
+    /// Capabilities of a device created with `features`.
+    /// `anisotropic_filtering` is passed separately, as it is not a
+    /// [wgpu::Features] flag.
+    pub fn new(features: wgpu::Features, anisotropic_filtering: bool) -> Self {
+        use wgpu::Features as F;
+
+        Self {
+            multi_draw_indirect_count: features.contains(F::MULTI_DRAW_INDIRECT_COUNT),
+            polygon_mode_line: features.contains(F::POLYGON_MODE_LINE),
+            texture_binding_array: features.contains(F::TEXTURE_BINDING_ARRAY),
+            non_uniform_indexing: features
+                .contains(F::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING),
+            anisotropic_filtering,
+        }
+    }
+
+    /// Name and state of each capability, for logging and debug UI.
+    pub fn list(&self) -> [(&'static str, bool); 5] {
+        [
+            ("Multi draw indirect count", self.multi_draw_indirect_count),
+            ("Polygon mode line", self.polygon_mode_line),
+            ("Texture binding array", self.texture_binding_array),
+            ("Non-uniform indexing", self.non_uniform_indexing),
+            ("Anisotropic filtering", self.anisotropic_filtering),
+        ]
+    }
+}
+
 #[derive(Clone)]
 pub struct Gpu {
+    pub instance: wgpu::Instance,
     pub device: wgpu::Device,
     pub queue: wgpu::Queue,
+    /// The highest anisotropy clamp samplers may use. 1 if anisotropic filtering is not supported.
+    pub max_sampler_anisotropy: u16,
+    /// Optional features the device was created with.
+    pub capabilities: RendererCapabilities,
+    /// The largest width or height textures are uploaded with. Larger images
+    /// are downscaled on load.
+    pub max_texture_size: u32,
 }
 
 impl Gpu {
-    pub fn new(device: wgpu::Device, queue: wgpu::Queue) -> Self {
-        Self { device, queue }
+    /// The highest anisotropy clamp wgpu accepts.
+    const MAX_ANISOTROPY: u16 = 16;
+
+    pub fn new(
+        instance: wgpu::Instance,
+        device: wgpu::Device,
+        queue: wgpu::Queue,
+        anisotropic_filtering: bool,
+        max_texture_size: u32,
+    ) -> Self {
+        let max_sampler_anisotropy = if anisotropic_filtering {
+            Self::MAX_ANISOTROPY
+        } else {
+            1
+        };
+
+        let capabilities = RendererCapabilities::new(device.features(), anisotropic_filtering);
+
+        let missing: Vec<&str> = capabilities
+            .list()
+            .into_iter()
+            .filter(|(_, enabled)| !enabled)
+            .map(|(name, _)| name)
+            .collect();
+        if !missing.is_empty() {
+            tracing::warn!(
+                "Optional GPU features not available: {}",
+                missing.join(", ")
+            );
+        }
+
+        Self {
+            instance,
+            device,
+            queue,
+            max_sampler_anisotropy,
+            capabilities,
+            max_texture_size,
+        }
+    }
+
+    /// Clamp a requested sampler anisotropy to what the device supports.
+    pub fn clamp_anisotropy(&self, anisotropy_clamp: u16) -> u16 {
+        anisotropy_clamp.clamp(1, self.max_sampler_anisotropy)
+    }
+
+    /// Collect the number of live resources of each type from wgpu.
+    pub fn resource_report(&self) -> ResourceReport {
+        let allocated_bytes = self
+            .device
+            .generate_allocator_report()
+            .map(|report| report.total_allocated_bytes);
+
+        let Some(report) = self.instance.generate_report() else {
+            return ResourceReport {
+                allocated_bytes,
+                ..Default::default()
+            };
+        };
+
+        let hub = report.hub_report();
+
+        ResourceReport {
+            buffers: hub.buffers.num_allocated,
+            textures: hub.textures.num_allocated,
+            texture_views: hub.texture_views.num_allocated,
+            samplers: hub.samplers.num_allocated,
+            shader_modules: hub.shader_modules.num_allocated,
+            render_pipelines: hub.render_pipelines.num_allocated,
+            compute_pipelines: hub.compute_pipelines.num_allocated,
+            bind_groups: hub.bind_groups.num_allocated,
+            allocated_bytes,
+        }
     }
 }
@@ -1,16 +1,43 @@
+pub mod bind_group_layout;
+pub mod blend;
+pub mod blit;
+mod color;
+mod depth_bias;
+mod format_dependent;
 mod gpu;
 mod mipmaps;
+mod pipeline_state;
+mod readback;
 mod surface;
+mod texture;
+mod vertex_layout;
+mod viewport;
 
 use std::sync::Arc;
 
 use glam::UVec2;
 use winit::window::Window;
 
+pub use bind_group_layout::BindGroupLayoutEntries;
+pub use blend::ColorTargetDescriptor;
+pub use color::Color;
+pub use depth_bias::DepthBiasDescriptor;
+pub use format_dependent::FormatDependent;
 pub use gpu::Gpu;
+pub use pipeline_state::{DepthStencilDescriptor, MultisampleDescriptor};
 pub use surface::{Surface, SurfaceDesc};
-
-pub fn create(window: Arc<Window>) -> (surface::Surface, Gpu) {
+pub use texture::{TextureDescriptor, TextureUsageIntent};
+pub use vertex_layout::{AsVertexLayout, AsVertexLayouts};
+pub use viewport::{RenderPassExt, ViewportRect};
+
+/// Format of every depth buffer in the engine. Pipelines and the depth
+/// attachments they render into must agree on it.
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
+/// Create the surface for `window` and the [Gpu] rendering to it. Textures are
+/// limited to `max_texture_size` pixels per side if given, and never exceed
+/// what the adapter supports.
+pub fn create(window: Arc<Window>, max_texture_size: Option<u32>) -> (surface::Surface, Gpu) {
     let winit::dpi::PhysicalSize { width, height } = window.inner_size();
 
     let instance = wgpu::Instance::default();
@@ -30,6 +57,11 @@ pub fn create(window: Arc<Window>) -> (surface::Surface, Gpu) {
         | wgpu::Features::TEXTURE_BINDING_ARRAY
         | wgpu::Features::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING;
 
+    let max_texture_dimension_2d = adapter.limits().max_texture_dimension_2d;
+    let max_texture_size = max_texture_size.map_or(max_texture_dimension_2d, |size| {
+        size.min(max_texture_dimension_2d)
+    });
+
     let surface_caps = surface.get_capabilities(&adapter);
 
     // Find a sRGB surface format or use the first.
@@ -49,12 +81,24 @@ pub fn create(window: Arc<Window>) -> (surface::Surface, Gpu) {
 
     let surface = surface::Surface::new(surface, surface_config);
 
+    // Binding arrays can only be sized when the adapter supports them at all.
+    let max_binding_array_elements_per_shader_stage =
+        if supported.contains(wgpu::Features::TEXTURE_BINDING_ARRAY) {
+            adapter
+                .limits()
+                .max_binding_array_elements_per_shader_stage
+                .min(1024)
+        } else {
+            0
+        };
+
     let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
         required_features: required & supported,
         required_limits: wgpu::Limits {
-            max_binding_array_elements_per_shader_stage: 1024,
+            max_binding_array_elements_per_shader_stage,
             max_bind_groups: 6,
             max_color_attachment_bytes_per_sample: 56,
+            max_texture_dimension_2d,
             ..Default::default()
         },
         ..Default::default()
@@ -63,7 +107,18 @@ pub fn create(window: Arc<Window>) -> (surface::Surface, Gpu) {
 
     surface.configure(&device);
 
-    let context = Gpu::new(device, queue);
+    let anisotropic_filtering = adapter
+        .get_downlevel_capabilities()
+        .flags
+        .contains(wgpu::DownlevelFlags::ANISOTROPIC_FILTERING);
+
+    let context = Gpu::new(
+        instance,
+        device,
+        queue,
+        anisotropic_filtering,
+        max_texture_size,
+    );
 
     (surface, context)
 }
@@ -73,7 +128,26 @@ pub struct RenderContext {
     pub frame_index: u64,
 }
 
+impl RenderContext {
+    /// Run `f` inside a debug group named `label`. Graphics debuggers (RenderDoc, Xcode) show all
+    /// passes recorded inside `f` nested under the label.
+    pub fn debug_group<R>(&mut self, label: &str, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.encoder.push_debug_group(label);
+        let result = f(self);
+        self.encoder.pop_debug_group();
+        result
+    }
+}
+
 pub struct RenderTarget {
     pub view: wgpu::TextureView,
     pub size: UVec2,
 }
+
+impl RenderTarget {
+    /// Format of the texture being rendered into.
+    #[inline]
+    pub fn format(&self) -> wgpu::TextureFormat {
+        self.view.texture().format()
+    }
+}
@@ -0,0 +1,75 @@
+use super::{DEPTH_FORMAT, DepthBiasDescriptor};
+
+/// Depth testing and writing done by a pipeline. Maps onto
+/// [wgpu::DepthStencilState] without a stencil test.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DepthStencilDescriptor {
+    pub format: wgpu::TextureFormat,
+    pub depth_write_enabled: bool,
+    pub depth_compare: wgpu::CompareFunction,
+    pub bias: DepthBiasDescriptor,
+}
+
+impl DepthStencilDescriptor {
+    /// Depth test against [DEPTH_FORMAT] targets without a bias.
+    pub const fn new(depth_compare: wgpu::CompareFunction, depth_write_enabled: bool) -> Self {
+        Self {
+            format: DEPTH_FORMAT,
+            depth_write_enabled,
+            depth_compare,
+            bias: DepthBiasDescriptor::NONE,
+        }
+    }
+
+    /// The same state with `bias` applied.
+    pub const fn with_bias(self, bias: DepthBiasDescriptor) -> Self {
+        Self { bias, ..self }
+    }
+}
+
+impl From<DepthStencilDescriptor> for wgpu::DepthStencilState {
+    fn from(value: DepthStencilDescriptor) -> Self {
+        wgpu::DepthStencilState {
+            format: value.format,
+            depth_write_enabled: Some(value.depth_write_enabled),
+            depth_compare: Some(value.depth_compare),
+            stencil: wgpu::StencilState::default(),
+            bias: value.bias.into(),
+        }
+    }
+}
+
+/// Multisampling done by a pipeline. Maps onto [wgpu::MultisampleState].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MultisampleDescriptor {
+    /// Samples per pixel. Has to match the sample count of the targets.
+    pub count: u32,
+    /// Which samples are written to.
+    pub mask: u64,
+    pub alpha_to_coverage_enabled: bool,
+}
+
+impl MultisampleDescriptor {
+    /// A single sample per pixel.
+    pub const NONE: Self = Self {
+        count: 1,
+        mask: !0,
+        alpha_to_coverage_enabled: false,
+    };
+}
+
+impl Default for MultisampleDescriptor {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+impl From<MultisampleDescriptor> for wgpu::MultisampleState {
+    fn from(value: MultisampleDescriptor) -> Self {
+        wgpu::MultisampleState {
+            count: value.count,
+            mask: value.mask,
+            alpha_to_coverage_enabled: value.alpha_to_coverage_enabled,
+        }
+    }
+}
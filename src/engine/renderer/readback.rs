@@ -0,0 +1,122 @@
+use crate::game::assets::image::{BlendMode, Image};
+
+use super::{Gpu, ViewportRect};
+
+impl Gpu {
+    /// Copy `rect` of the first mip level of `texture` back to the CPU, for
+    /// comparing rendered frames against reference images in tests. Blocks
+    /// until the GPU finished all submitted work.
+    ///
+    /// Only 8-bit RGBA and BGRA textures can be read back. The texture must
+    /// have been created with [wgpu::TextureUsages::COPY_SRC].
+    pub fn read_color_target(&self, texture: &wgpu::Texture, rect: ViewportRect) -> Image {
+        let swap_red_and_blue = match texture.format().remove_srgb_suffix() {
+            wgpu::TextureFormat::Rgba8Unorm => false,
+            wgpu::TextureFormat::Bgra8Unorm => true,
+            format => panic!("Can not read back textures with format {format:?}!"),
+        };
+        assert!(
+            rect.fits(glam::UVec2::new(texture.width(), texture.height())),
+            "Read back rect is outside of the texture!"
+        );
+
+        let bytes_per_row = rect.size.x * 4;
+        let padded_bytes_per_row = padded_bytes_per_row(bytes_per_row);
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("read_color_target_buffer"),
+            size: padded_bytes_per_row as u64 * rect.size.y as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("read_color_target_encoder"),
+            });
+        encoder.copy_texture_to_buffer(
+            wgpu::TexelCopyTextureInfo {
+                texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d {
+                    x: rect.position.x,
+                    y: rect.position.y,
+                    z: 0,
+                },
+                aspect: wgpu::TextureAspect::All,
+            },
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_bytes_per_row),
+                    rows_per_image: Some(rect.size.y),
+                },
+            },
+            wgpu::Extent3d {
+                width: rect.size.x,
+                height: rect.size.y,
+                depth_or_array_layers: 1,
+            },
+        );
+        self.queue.submit(Some(encoder.finish()));
+
+        buffer.map_async(wgpu::MapMode::Read, .., |result| {
+            if let Err(err) = result {
+                tracing::error!("Could not map read back buffer: {err}");
+            }
+        });
+        self.device
+            .poll(wgpu::PollType::wait_indefinitely())
+            .expect("Could not wait for the read back copy!");
+
+        let mut pixels = unpad_rows(
+            &buffer.get_mapped_range(..),
+            padded_bytes_per_row,
+            bytes_per_row,
+        );
+        buffer.unmap();
+
+        if swap_red_and_blue {
+            pixels
+                .chunks_exact_mut(4)
+                .for_each(|pixel| pixel.swap(0, 2));
+        }
+
+        let data = image::RgbaImage::from_raw(rect.size.x, rect.size.y, pixels)
+            .expect("Read back pixels do not match the rect!");
+        Image::from_rgba(data, BlendMode::Opaque)
+    }
+}
+
+/// Rows copied out of a texture must start at multiples of
+/// [wgpu::COPY_BYTES_PER_ROW_ALIGNMENT] bytes.
+fn padded_bytes_per_row(bytes_per_row: u32) -> u32 {
+    bytes_per_row.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+}
+
+/// Drop the padding at the end of each row.
+fn unpad_rows(data: &[u8], padded_bytes_per_row: u32, bytes_per_row: u32) -> Vec<u8> {
+    data.chunks_exact(padded_bytes_per_row as usize)
+        .flat_map(|row| &row[..bytes_per_row as usize])
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rows_are_padded_to_the_copy_alignment() {
+        assert_eq!(padded_bytes_per_row(4), 256);
+        assert_eq!(padded_bytes_per_row(256), 256);
+        assert_eq!(padded_bytes_per_row(260), 512);
+
+        let mut data = vec![0_u8; 512];
+        data[..4].copy_from_slice(&[1, 2, 3, 4]);
+        data[256..260].copy_from_slice(&[5, 6, 7, 8]);
+        assert_eq!(unpad_rows(&data, 256, 4), [1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+}
@@ -29,6 +29,10 @@ impl Surface {
         self.surface_config.format
     }
 
+    pub fn present_mode(&self) -> wgpu::PresentMode {
+        self.surface_config.present_mode
+    }
+
     pub fn size(&self) -> UVec2 {
         UVec2::new(self.surface_config.width, self.surface_config.height)
     }
@@ -37,7 +41,14 @@ impl Surface {
         self.surface.configure(device, &self.surface_config);
     }
 
+    /// Reconfigure the surface for a new window size. Zero sized surfaces
+    /// can't be configured, so the previous size is kept while the window is
+    /// minimized.
     pub fn resize(&mut self, device: &wgpu::Device, size: glam::UVec2) {
+        if size.x == 0 || size.y == 0 {
+            return;
+        }
+
         self.surface_config.width = size.x;
         self.surface_config.height = size.y;
         self.configure(device);
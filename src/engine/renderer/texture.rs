@@ -0,0 +1,205 @@
+use glam::UVec2;
+use thiserror::Error;
+
+/// Describes what a texture will be used for, which determines its usage flags and whether it
+/// carries a full mip chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextureUsageIntent {
+    /// Sampled in shaders with data uploaded from the CPU. Single mip level.
+    Sampled,
+    /// Sampled in shaders with data uploaded from the CPU and a full mip chain that is filled by
+    /// rendering into each level.
+    SampledMipped,
+    /// Rendered to and then sampled by a later pass.
+    RenderTarget,
+    /// Rendered to, sampled by a later pass and copied back to the CPU.
+    RenderTargetReadback,
+    /// Written by compute shaders as a storage texture and sampled by a later
+    /// pass.
+    Storage,
+}
+
+impl TextureUsageIntent {
+    /// The [wgpu::TextureUsages] required for the intent.
+    pub fn usages(self) -> wgpu::TextureUsages {
+        use wgpu::TextureUsages as U;
+
+        match self {
+            Self::Sampled => U::TEXTURE_BINDING | U::COPY_DST,
+            Self::SampledMipped => U::TEXTURE_BINDING | U::COPY_DST | U::RENDER_ATTACHMENT,
+            Self::RenderTarget => U::RENDER_ATTACHMENT | U::TEXTURE_BINDING,
+            Self::RenderTargetReadback => U::RENDER_ATTACHMENT | U::TEXTURE_BINDING | U::COPY_SRC,
+            Self::Storage => U::STORAGE_BINDING | U::TEXTURE_BINDING,
+        }
+    }
+
+    /// Whether textures with this intent should have a full mip chain.
+    pub fn is_mipped(self) -> bool {
+        matches!(self, Self::SampledMipped)
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum TextureViewError {
+    #[error("View format {view:?} was not declared for texture format {texture:?}")]
+    FormatNotDeclared {
+        view: wgpu::TextureFormat,
+        texture: wgpu::TextureFormat,
+    },
+}
+
+/// Description of a 2D texture that infers the mip level count and usage flags from a
+/// [TextureUsageIntent].
+pub struct TextureDescriptor<'a> {
+    pub label: &'a str,
+    pub size: UVec2,
+    pub format: wgpu::TextureFormat,
+    pub intent: TextureUsageIntent,
+    /// Additional formats views of the texture can be created with, e.g. the
+    /// sRGB variant of a linear storage texture.
+    pub view_formats: &'a [wgpu::TextureFormat],
+}
+
+impl<'a> TextureDescriptor<'a> {
+    /// Describe a single layer 2D texture. Its usages and mip levels follow
+    /// from `intent`. Add extra view formats with [Self::with_view_formats].
+    pub fn new(
+        label: &'a str,
+        size: UVec2,
+        format: wgpu::TextureFormat,
+        intent: TextureUsageIntent,
+    ) -> Self {
+        Self {
+            label,
+            size,
+            format,
+            intent,
+            view_formats: &[],
+        }
+    }
+
+    /// Declare the additional formats views of the texture can be created with.
+    pub fn with_view_formats(mut self, view_formats: &'a [wgpu::TextureFormat]) -> Self {
+        self.view_formats = view_formats;
+        self
+    }
+
+    /// The amount of mip levels the texture will be created with.
+    pub fn mip_level_count(&self) -> u32 {
+        if self.intent.is_mipped() {
+            full_mip_level_count(self.size)
+        } else {
+            1
+        }
+    }
+
+    /// The size of the texture as a [wgpu::Extent3d]. Sizes are clamped to at least 1 pixel.
+    pub fn extent(&self) -> wgpu::Extent3d {
+        wgpu::Extent3d {
+            width: self.size.x.max(1),
+            height: self.size.y.max(1),
+            depth_or_array_layers: 1,
+        }
+    }
+
+    /// Create the [wgpu::Texture] on the given device.
+    pub fn create(&self, device: &wgpu::Device) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(self.label),
+            size: self.extent(),
+            mip_level_count: self.mip_level_count(),
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: self.format,
+            usage: self.intent.usages(),
+            view_formats: self.view_formats,
+        })
+    }
+
+    /// Check that views of the texture can be created with `format`, which
+    /// has to be the texture format or one of the declared view formats.
+    pub fn check_view_format(
+        &self,
+        format: wgpu::TextureFormat,
+    ) -> Result<wgpu::TextureFormat, TextureViewError> {
+        if format == self.format || self.view_formats.contains(&format) {
+            Ok(format)
+        } else {
+            Err(TextureViewError::FormatNotDeclared {
+                view: format,
+                texture: self.format,
+            })
+        }
+    }
+
+    /// Create a view of `texture`, created from this descriptor, that reads
+    /// and writes the sRGB variant of its format.
+    pub fn create_srgb_view(
+        &self,
+        texture: &wgpu::Texture,
+    ) -> Result<wgpu::TextureView, TextureViewError> {
+        self.create_view_with_format(texture, self.format.add_srgb_suffix())
+    }
+
+    /// Create a view of `texture`, created from this descriptor, that reads
+    /// and writes the linear variant of its format. Storage bindings need
+    /// these, as sRGB formats can't be used for storage.
+    pub fn create_linear_view(
+        &self,
+        texture: &wgpu::Texture,
+    ) -> Result<wgpu::TextureView, TextureViewError> {
+        self.create_view_with_format(texture, self.format.remove_srgb_suffix())
+    }
+
+    fn create_view_with_format(
+        &self,
+        texture: &wgpu::Texture,
+        format: wgpu::TextureFormat,
+    ) -> Result<wgpu::TextureView, TextureViewError> {
+        let format = self.check_view_format(format)?;
+        Ok(texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some(self.label),
+            format: Some(format),
+            ..Default::default()
+        }))
+    }
+}
+
+/// Number of mip levels in a full chain down to 1x1 for a texture of the given size.
+pub fn full_mip_level_count(size: UVec2) -> u32 {
+    u32::BITS - size.x.max(size.y).max(1).leading_zeros()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn view_formats_must_be_declared() {
+        use wgpu::TextureFormat as F;
+
+        let descriptor = TextureDescriptor::new(
+            "storage",
+            UVec2::splat(16),
+            F::Rgba8Unorm,
+            TextureUsageIntent::Storage,
+        );
+        assert_eq!(
+            descriptor.check_view_format(F::Rgba8Unorm),
+            Ok(F::Rgba8Unorm)
+        );
+        assert_eq!(
+            descriptor.check_view_format(F::Rgba8UnormSrgb),
+            Err(TextureViewError::FormatNotDeclared {
+                view: F::Rgba8UnormSrgb,
+                texture: F::Rgba8Unorm,
+            })
+        );
+
+        let descriptor = descriptor.with_view_formats(&[F::Rgba8UnormSrgb]);
+        assert_eq!(
+            descriptor.check_view_format(F::Rgba8UnormSrgb),
+            Ok(F::Rgba8UnormSrgb)
+        );
+    }
+}
@@ -0,0 +1,75 @@
+/// A type stored in a vertex buffer that describes its own layout.
+pub trait AsVertexLayout: Sized {
+    /// Whether the buffer advances per vertex or per instance.
+    const STEP_MODE: wgpu::VertexStepMode;
+    /// The attributes read from each element.
+    const ATTRIBUTES: &'static [wgpu::VertexAttribute];
+
+    /// The layout of a buffer holding elements of this type.
+    fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as wgpu::BufferAddress,
+            step_mode: Self::STEP_MODE,
+            attributes: Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// The vertex buffers of a pipeline, one [AsVertexLayout] per buffer slot.
+pub trait AsVertexLayouts {
+    /// The layouts in buffer slot order.
+    fn layouts() -> Vec<wgpu::VertexBufferLayout<'static>>;
+}
+
+impl<A: AsVertexLayout> AsVertexLayouts for (A,) {
+    fn layouts() -> Vec<wgpu::VertexBufferLayout<'static>> {
+        vec![A::layout()]
+    }
+}
+
+impl<A: AsVertexLayout, B: AsVertexLayout> AsVertexLayouts for (A, B) {
+    fn layouts() -> Vec<wgpu::VertexBufferLayout<'static>> {
+        vec![A::layout(), B::layout()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[repr(C)]
+    struct Vertex {
+        _position: [f32; 3],
+    }
+
+    impl AsVertexLayout for Vertex {
+        const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Vertex;
+        const ATTRIBUTES: &'static [wgpu::VertexAttribute] =
+            &wgpu::vertex_attr_array![0 => Float32x3];
+    }
+
+    #[repr(C)]
+    struct Instance {
+        _offset: [f32; 2],
+        _index: u32,
+    }
+
+    impl AsVertexLayout for Instance {
+        const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Instance;
+        const ATTRIBUTES: &'static [wgpu::VertexAttribute] =
+            &wgpu::vertex_attr_array![1 => Float32x2, 2 => Uint32];
+    }
+
+    #[test]
+    fn tuples_list_a_layout_per_buffer() {
+        let layouts = <(Vertex, Instance)>::layouts();
+        assert_eq!(layouts.len(), 2);
+
+        assert_eq!(layouts[0].step_mode, wgpu::VertexStepMode::Vertex);
+        assert_eq!(layouts[0].array_stride, 12);
+
+        assert_eq!(layouts[1].step_mode, wgpu::VertexStepMode::Instance);
+        assert_eq!(layouts[1].array_stride, 12);
+        assert_eq!(layouts[1].attributes[1].offset, 8);
+    }
+}
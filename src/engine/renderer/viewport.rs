@@ -0,0 +1,116 @@
+use std::ops::Range;
+
+use glam::{IVec2, UVec2};
+
+/// A rectangle of pixels inside a render attachment, used for viewports and
+/// scissor rects.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ViewportRect {
+    pub position: UVec2,
+    pub size: UVec2,
+}
+
+impl ViewportRect {
+    pub fn new(position: UVec2, size: UVec2) -> Self {
+        Self { position, size }
+    }
+
+    /// The whole of an attachment of `size`.
+    pub fn full(size: UVec2) -> Self {
+        Self::new(UVec2::ZERO, size)
+    }
+
+    /// The part of the rect at `position` with `size` that lies inside an
+    /// attachment of `target_size`, or `None` if nothing of it is inside.
+    pub fn clipped(position: IVec2, size: IVec2, target_size: UVec2) -> Option<Self> {
+        let min = position.max(IVec2::ZERO);
+        let max = (position + size).min(target_size.as_ivec2());
+        let size = max - min;
+
+        (size.x > 0 && size.y > 0).then(|| Self::new(min.as_uvec2(), size.as_uvec2()))
+    }
+
+    /// Whether the rect is not empty and lies inside an attachment of
+    /// `target_size`.
+    pub fn fits(&self, target_size: UVec2) -> bool {
+        self.size.x > 0 && self.size.y > 0 && (self.position + self.size).cmple(target_size).all()
+    }
+}
+
+/// Viewport and scissor helpers for [wgpu::RenderPass] that check the rect
+/// against the size of the attachments. wgpu reports a rect outside the
+/// attachments as a validation error that ends the frame, so bad rects are
+/// skipped with a warning instead.
+pub trait RenderPassExt {
+    /// Render into `rect` of attachments of `target_size`, mapping depth to
+    /// `depth_range`. Returns `false` if the rect does not fit.
+    fn set_viewport_rect(
+        &mut self,
+        target_size: UVec2,
+        rect: ViewportRect,
+        depth_range: Range<f32>,
+    ) -> bool;
+
+    /// Discard fragments outside `rect` of attachments of `target_size`.
+    /// Returns `false` if the rect does not fit.
+    fn set_scissor(&mut self, target_size: UVec2, rect: ViewportRect) -> bool;
+}
+
+impl RenderPassExt for wgpu::RenderPass<'_> {
+    fn set_viewport_rect(
+        &mut self,
+        target_size: UVec2,
+        rect: ViewportRect,
+        depth_range: Range<f32>,
+    ) -> bool {
+        if !rect.fits(target_size) {
+            tracing::warn!("Viewport {rect:?} does not fit a {target_size} attachment.");
+            return false;
+        }
+
+        let position = rect.position.as_vec2();
+        let size = rect.size.as_vec2();
+        self.set_viewport(
+            position.x,
+            position.y,
+            size.x,
+            size.y,
+            depth_range.start,
+            depth_range.end,
+        );
+        true
+    }
+
+    fn set_scissor(&mut self, target_size: UVec2, rect: ViewportRect) -> bool {
+        if !rect.fits(target_size) {
+            tracing::warn!("Scissor rect {rect:?} does not fit a {target_size} attachment.");
+            return false;
+        }
+
+        self.set_scissor_rect(rect.position.x, rect.position.y, rect.size.x, rect.size.y);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rects_are_clipped_to_the_attachment() {
+        let target = UVec2::new(100, 50);
+
+        assert_eq!(
+            ViewportRect::clipped(IVec2::new(-10, 40), IVec2::new(30, 30), target),
+            Some(ViewportRect::new(UVec2::new(0, 40), UVec2::new(20, 10)))
+        );
+        assert_eq!(
+            ViewportRect::clipped(IVec2::new(100, 0), IVec2::new(10, 10), target),
+            None
+        );
+
+        assert!(ViewportRect::full(target).fits(target));
+        assert!(!ViewportRect::new(UVec2::new(90, 0), UVec2::new(20, 10)).fits(target));
+        assert!(!ViewportRect::new(UVec2::ZERO, UVec2::new(0, 10)).fits(target));
+    }
+}
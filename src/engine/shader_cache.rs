@@ -1,38 +1,509 @@
+#[cfg(feature = "shader-hot-reload")]
+mod watch;
+
+#[cfg(feature = "shader-hot-reload")]
+use std::path::Path;
+
 use ahash::HashMap;
+use thiserror::Error;
 
 pub use shader_source::ShaderSource;
 
-use shader_source::{shader_label, shader_source};
+use shader_source::{compute_entry_points, entry_point_bindings, shader_label, shader_source};
+
+use crate::{
+    engine::renderer::{DepthStencilDescriptor, MultisampleDescriptor},
+    game::globals,
+};
+
+/// WGSL that could not be turned into a shader module.
+#[derive(Debug, Error)]
+pub enum ShaderError {
+    /// The source could not be parsed. The message points at the offending
+    /// line.
+    #[error("Could not parse shader {label}:\n{message}")]
+    Parse { label: String, message: String },
+
+    /// The source parsed, but is not a valid shader.
+    #[error("Invalid shader {label}:\n{message}")]
+    Validation { label: String, message: String },
+}
+
+/// Parse and validate WGSL the same way wgpu does, so mistakes are reported
+/// with their location instead of as a device error when the module is
+/// used.
+pub fn validate_wgsl(label: &str, source: &str) -> Result<(), ShaderError> {
+    use wgpu::naga;
+
+    let module = naga::front::wgsl::parse_str(source).map_err(|err| ShaderError::Parse {
+        label: label.to_string(),
+        message: err.emit_to_string_with_path(source, label),
+    })?;
+
+    naga::valid::Validator::new(
+        naga::valid::ValidationFlags::all(),
+        naga::valid::Capabilities::all(),
+    )
+    .validate(&module)
+    .map_err(|err| ShaderError::Validation {
+        label: label.to_string(),
+        message: err.emit_to_string_with_path(source, label),
+    })?;
 
-use crate::game::globals;
+    Ok(())
+}
+
+/// Description of a compute pipeline running `entry_point` of a shader.
+pub struct ComputePipelineDescriptor<'a> {
+    pub label: &'a str,
+    pub module: ShaderSource,
+    pub entry_point: String,
+    /// The pipeline layout, or `None` to derive it from the shader.
+    pub layout: Option<&'a wgpu::PipelineLayout>,
+}
+
+/// Description of a render pipeline running entry points of a single shader.
+pub struct RenderPipelineDescriptor<'a> {
+    pub label: &'a str,
+    pub module: ShaderSource,
+    pub layout: Option<&'a wgpu::PipelineLayout>,
+    pub vertex_entry_point: &'a str,
+    pub buffers: &'a [wgpu::VertexBufferLayout<'a>],
+    pub primitive: wgpu::PrimitiveState,
+    /// The fragment entry point, or `None` for depth only pipelines.
+    pub fragment_entry_point: Option<&'a str>,
+    pub targets: &'a [Option<wgpu::ColorTargetState>],
+    /// Depth testing, or `None` when drawing without a depth target.
+    pub depth_stencil: Option<DepthStencilDescriptor>,
+    pub multisample: MultisampleDescriptor,
+}
+
+impl<'a> RenderPipelineDescriptor<'a> {
+    /// A pipeline running `vertex_main` without a fragment stage, depth test or
+    /// multisampling.
+    pub fn new(label: &'a str, module: ShaderSource) -> Self {
+        Self {
+            label,
+            module,
+            layout: None,
+            vertex_entry_point: "vertex_main",
+            buffers: &[],
+            primitive: wgpu::PrimitiveState::default(),
+            fragment_entry_point: None,
+            targets: &[],
+            depth_stencil: None,
+            multisample: MultisampleDescriptor::NONE,
+        }
+    }
+}
 
 #[derive(Clone, Default)]
 pub struct ShaderCache {
     modules: HashMap<ShaderSource, wgpu::ShaderModule>,
+    /// Set when reloading shaders edited on disk.
+    #[cfg(feature = "shader-hot-reload")]
+    watch: Option<watch::ShaderWatch>,
 }
 
 impl ShaderCache {
     pub fn get_or_create(&mut self, source: ShaderSource) -> &wgpu::ShaderModule {
-        self.modules.entry(source).or_insert_with_key(|source| {
+        self.modules
+            .entry(source)
+            .or_insert_with_key(|source| Self::create_module(*source))
+    }
+
+    /// Drop the cached module for `source`. It is compiled again the next
+    /// time it is requested. Returns `false` if the module was not cached.
+    pub fn destroy(&mut self, source: ShaderSource) -> bool {
+        self.modules.remove(&source).is_some()
+    }
+
+    /// Watch the shader sources under `dir`, the crate root they were built
+    /// from, so edits can be picked up by [Self::reload_changed].
+    #[cfg(feature = "shader-hot-reload")]
+    pub fn watch(&mut self, dir: &Path) {
+        self.watch = Some(watch::ShaderWatch::new(dir));
+    }
+
+    /// Recompile the cached modules whose sources changed on disk since the
+    /// last call and return them. A module that fails to compile keeps its
+    /// previous version and the error is logged. Pipelines have to be
+    /// recreated to use the new modules.
+    ///
+    /// Only shaders loaded through [ShaderSource] are watched. Renderers that
+    /// create their modules directly, like the UI mesh renderer, keep the
+    /// shader they were built with until the game is restarted.
+    #[cfg(feature = "shader-hot-reload")]
+    pub fn reload_changed(&mut self) -> Vec<ShaderSource> {
+        let Some(watch) = self.watch.as_mut() else {
+            return Vec::new();
+        };
+
+        let mut reloaded = Vec::new();
+        for source in watch.changed_sources() {
+            let module = watch
+                .compose(source)
+                .and_then(|wgsl| Self::create_module_from_wgsl(shader_label(source), &wgsl));
+
+            match module {
+                Ok(module) => {
+                    tracing::info!("Reloaded shader {}.", shader_label(source));
+                    self.modules.insert(source, module);
+                    reloaded.push(source);
+                }
+                Err(err) => tracing::error!("{err}"),
+            }
+        }
+
+        reloaded
+    }
+
+    /// Precompile everything so first-frame doesn't hitch. Modules are parsed
+    /// and validated on separate threads, which shortens the load stall when
+    /// there are many shaders.
+    pub fn preload_all(&mut self) {
+        let missing: Vec<ShaderSource> = ShaderSource::ALL
+            .iter()
+            .copied()
+            .filter(|source| !self.modules.contains_key(source))
+            .collect();
+
+        let modules: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = missing
+                .iter()
+                .map(|&source| scope.spawn(move || (source, Self::create_module(source))))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| handle.join().expect("Shader compile thread panicked."))
+                .collect()
+        });
+
+        self.modules.extend(modules);
+    }
+
+    /// Create a compute pipeline from a cached module. Returns `None` if the
+    /// shader has no compute entry point with the given name.
+    pub fn create_compute_pipeline(
+        &mut self,
+        descriptor: &ComputePipelineDescriptor,
+    ) -> Option<wgpu::ComputePipeline> {
+        if !is_compute_entry_point(descriptor.module, &descriptor.entry_point) {
+            tracing::warn!(
+                "Shader {} has no compute entry point named {}.",
+                shader_label(descriptor.module),
+                descriptor.entry_point,
+            );
+            return None;
+        }
+
+        let module = self.get_or_create(descriptor.module);
+
+        Some(
             globals::gpu()
                 .device
-                .create_shader_module(wgpu::ShaderModuleDescriptor {
-                    label: Some(shader_label(*source)),
-                    source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(shader_source(
-                        *source,
-                    ))),
-                })
-        })
+                .create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+                    label: Some(descriptor.label),
+                    layout: descriptor.layout,
+                    module,
+                    entry_point: Some(&descriptor.entry_point),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    cache: None,
+                }),
+        )
     }
 
-    /// Optional: precompile everything so first-frame doesn't hitch.
-    pub fn preload_all(&mut self) {
-        for &src in ShaderSource::ALL {
-            let _ = self.get_or_create(src);
+    /// Create a render pipeline from a cached module.
+    pub fn create_render_pipeline(
+        &mut self,
+        descriptor: &RenderPipelineDescriptor,
+    ) -> wgpu::RenderPipeline {
+        let module = self.get_or_create(descriptor.module);
+
+        globals::gpu()
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some(descriptor.label),
+                layout: descriptor.layout,
+                vertex: wgpu::VertexState {
+                    module,
+                    entry_point: Some(descriptor.vertex_entry_point),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: descriptor.buffers,
+                },
+                primitive: descriptor.primitive,
+                depth_stencil: descriptor.depth_stencil.map(Into::into),
+                multisample: descriptor.multisample.into(),
+                fragment: descriptor
+                    .fragment_entry_point
+                    .map(|entry_point| wgpu::FragmentState {
+                        module,
+                        entry_point: Some(entry_point),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: descriptor.targets,
+                    }),
+                multiview_mask: None,
+                cache: None,
+            })
+    }
+
+    /// Create a module from WGSL that was not validated by the build, such
+    /// as a shader edited on disk. Errors are returned instead of surfacing
+    /// later as a device error.
+    pub fn create_module_from_wgsl(
+        label: &str,
+        source: &str,
+    ) -> Result<wgpu::ShaderModule, ShaderError> {
+        validate_wgsl(label, source)?;
+        Ok(Self::create_module_unchecked(label, source))
+    }
+
+    /// Create a module from WGSL without validating it first. Only for
+    /// sources that are known to be valid, like the ones validated by the
+    /// build.
+    pub fn create_module_unchecked(label: &str, source: &str) -> wgpu::ShaderModule {
+        globals::gpu()
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(label),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Owned(source.to_string())),
+            })
+    }
+
+    fn create_module(source: ShaderSource) -> wgpu::ShaderModule {
+        // Shipped shaders are validated by the build script.
+        globals::gpu()
+            .device
+            .create_shader_module(wgpu::ShaderModuleDescriptor {
+                label: Some(shader_label(source)),
+                source: wgpu::ShaderSource::Wgsl(std::borrow::Cow::Borrowed(shader_source(source))),
+            })
+    }
+}
+
+/// A bind group layout used by a pipeline, together with the entries it was
+/// created from.
+pub type PipelineBindGroup<'a> = (&'a wgpu::BindGroupLayout, &'a [wgpu::BindGroupLayoutEntry]);
+
+/// Create the layout of a pipeline running `entry_points` of `source`.
+/// `groups[n]` is the bind group at index `n`, or `None` for a slot the
+/// pipeline leaves empty. The entries of each group are checked against the
+/// resources the shader uses, see [validate_bind_groups].
+pub fn create_pipeline_layout(
+    pipeline_label: &str,
+    source: ShaderSource,
+    entry_points: &[&str],
+    groups: &[Option<PipelineBindGroup>],
+) -> wgpu::PipelineLayout {
+    let entries: Vec<_> = groups
+        .iter()
+        .map(|group| group.map(|(_, entries)| entries))
+        .collect();
+    validate_bind_groups(pipeline_label, source, entry_points, &entries);
+
+    let bind_group_layouts: Vec<_> = groups
+        .iter()
+        .map(|group| group.map(|(layout, _)| layout))
+        .collect();
+
+    globals::gpu()
+        .device
+        .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some(&format!("{pipeline_label}_pipeline_layout")),
+            bind_group_layouts: &bind_group_layouts,
+            ..Default::default()
+        })
+}
+
+/// Check the bind group layouts of a pipeline against the resources used by
+/// its entry points, warning about every `@group`/`@binding` the layouts
+/// don't provide and every group none of the entry points use. `groups[n]`
+/// holds the entries of the layout at index `n`. Returns `true` when the
+/// layouts match the shader.
+pub fn validate_bind_groups(
+    pipeline_label: &str,
+    source: ShaderSource,
+    entry_points: &[&str],
+    groups: &[Option<&[wgpu::BindGroupLayoutEntry]>],
+) -> bool {
+    let missing = missing_bindings(source, entry_points, groups);
+    let unused = unused_groups(source, entry_points, groups);
+
+    for &(group, binding) in missing.iter() {
+        let layout = groups.get(group as usize).copied().flatten();
+        if layout.is_none() {
+            tracing::warn!(
+                "Pipeline {pipeline_label}: shader {} uses @group({group}) @binding({binding}), \
+                 but the pipeline layout has no bind group at index {group}.",
+                shader_label(source),
+            );
+        } else {
+            tracing::warn!(
+                "Pipeline {pipeline_label}: shader {} uses @group({group}) @binding({binding}), \
+                 but the bind group layout at index {group} has no binding {binding}.",
+                shader_label(source),
+            );
         }
     }
+
+    for &group in unused.iter() {
+        tracing::warn!(
+            "Pipeline {pipeline_label}: the bind group layout at index {group} is not used by \
+             shader {}.",
+            shader_label(source),
+        );
+    }
+
+    missing.is_empty() && unused.is_empty()
+}
+
+/// The `(group, binding)` pairs used by `entry_points` of `source`. Unknown
+/// entry points are skipped.
+fn used_bindings(source: ShaderSource, entry_points: &[&str]) -> Vec<(u32, u32)> {
+    let mut used: Vec<(u32, u32)> = entry_points
+        .iter()
+        .filter_map(|entry_point| entry_point_bindings(source, entry_point))
+        .flatten()
+        .copied()
+        .collect();
+
+    used.sort();
+    used.dedup();
+    used
+}
+
+/// Indices of the groups that have a layout, but no binding used by
+/// `entry_points` of `source`.
+fn unused_groups(
+    source: ShaderSource,
+    entry_points: &[&str],
+    groups: &[Option<&[wgpu::BindGroupLayoutEntry]>],
+) -> Vec<u32> {
+    let used = used_bindings(source, entry_points);
+
+    (0..groups.len() as u32)
+        .filter(|&group| groups[group as usize].is_some())
+        .filter(|&group| !used.iter().any(|&(used_group, _)| used_group == group))
+        .collect()
+}
+
+/// The `(group, binding)` pairs used by `entry_points` of `source` that are
+/// not in `groups`.
+fn missing_bindings(
+    source: ShaderSource,
+    entry_points: &[&str],
+    groups: &[Option<&[wgpu::BindGroupLayoutEntry]>],
+) -> Vec<(u32, u32)> {
+    let mut missing: Vec<(u32, u32)> = entry_points
+        .iter()
+        .filter_map(|entry_point| {
+            let bindings = entry_point_bindings(source, entry_point);
+            if bindings.is_none() {
+                tracing::warn!(
+                    "Shader {} has no entry point named {entry_point}.",
+                    shader_label(source)
+                );
+            }
+            bindings
+        })
+        .flatten()
+        .copied()
+        .filter(|&(group, binding)| {
+            let entries = groups.get(group as usize).copied().flatten();
+            !entries.is_some_and(|entries| entries.iter().any(|e| e.binding == binding))
+        })
+        .collect();
+
+    missing.sort();
+    missing.dedup();
+    missing
+}
+
+/// Whether `source` has a compute entry point called `name`.
+fn is_compute_entry_point(source: ShaderSource, name: &str) -> bool {
+    compute_entry_points(source).contains(&name)
 }
 
 mod shader_source {
     include!(concat!(env!("OUT_DIR"), "/shader_source.rs"));
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::renderer::BindGroupLayoutEntries;
+
+    #[test]
+    fn missing_bind_groups_are_reported() {
+        let entries = BindGroupLayoutEntries::new().storage(0).build();
+        let camera = BindGroupLayoutEntries::new().uniform(0).build();
+
+        // The depth-only model pipeline leaves out the materials.
+        let groups = [
+            Some(camera.as_slice()),
+            None,
+            Some(&entries),
+            Some(&entries),
+        ];
+        assert!(missing_bindings(ShaderSource::Models, &["vertex_main"], &groups).is_empty());
+
+        // The fragment shaders do need them.
+        assert_eq!(
+            missing_bindings(ShaderSource::Models, &["fragment_opaque"], &groups),
+            vec![(1, 0), (1, 1)]
+        );
+    }
+
+    #[test]
+    fn unused_bind_groups_are_reported() {
+        let entries = BindGroupLayoutEntries::new().storage(0).build();
+        let camera = BindGroupLayoutEntries::new().uniform(0).build();
+
+        let groups = [
+            Some(camera.as_slice()),
+            None,
+            Some(&entries),
+            Some(&entries),
+        ];
+        assert!(unused_groups(ShaderSource::Models, &["vertex_main"], &groups).is_empty());
+
+        // The vertex shader doesn't read the materials.
+        let with_materials = [
+            Some(camera.as_slice()),
+            Some(&entries),
+            Some(&entries),
+            Some(&entries),
+        ];
+        assert_eq!(
+            unused_groups(ShaderSource::Models, &["vertex_main"], &with_materials),
+            vec![1]
+        );
+    }
+
+    #[test]
+    fn wgsl_errors_are_reported_with_their_location() {
+        let typo =
+            "@vertex\nfn main() -> @builtin(position) vec4<f32> {\n    retrun vec4<f32>();\n}\n";
+        let Err(ShaderError::Parse { message, .. }) = validate_wgsl("typo.wgsl", typo) else {
+            panic!("expected a parse error");
+        };
+        assert!(message.contains("typo.wgsl:3"), "{message}");
+
+        let mismatch = "fn f() -> f32 {\n    return 1u;\n}\n";
+        assert!(matches!(
+            validate_wgsl("mismatch.wgsl", mismatch),
+            Err(ShaderError::Validation { .. })
+        ));
+
+        for &source in ShaderSource::ALL {
+            validate_wgsl(shader_label(source), shader_source(source)).unwrap();
+        }
+    }
+
+    #[test]
+    fn render_entry_points_are_not_compute() {
+        assert!(!is_compute_entry_point(ShaderSource::Models, "vertex_main"));
+        assert!(!is_compute_entry_point(ShaderSource::Models, "missing"));
+    }
+}
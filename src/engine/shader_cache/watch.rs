@@ -0,0 +1,161 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use ahash::HashMap;
+use naga::{
+    back::wgsl,
+    valid::{Capabilities, ValidationFlags, Validator},
+};
+use naga_oil::compose::{
+    ComposableModuleDescriptor, Composer, NagaModuleDescriptor, ShaderLanguage, ShaderType,
+};
+
+use super::{
+    ShaderError, ShaderSource,
+    shader_source::{SUPPORT_SHADER_PATHS, shader_label, shader_path},
+};
+
+/// Modified times of the shader source files under a directory, used to find
+/// the shaders that were edited since they were last checked.
+#[derive(Clone)]
+pub struct ShaderWatch {
+    root: PathBuf,
+    modified: HashMap<PathBuf, SystemTime>,
+}
+
+impl ShaderWatch {
+    /// Start watching the shader sources under `root`, which is the crate root
+    /// the build compiled them from.
+    pub fn new(root: &Path) -> Self {
+        let mut watch = Self {
+            root: root.to_path_buf(),
+            modified: HashMap::default(),
+        };
+        watch.changed_files();
+        watch
+    }
+
+    /// The shaders affected by files that changed since the last call. An
+    /// edit to an imported module affects every shader.
+    pub fn changed_sources(&mut self) -> Vec<ShaderSource> {
+        let changed = self.changed_files();
+        if changed.is_empty() {
+            return Vec::new();
+        }
+
+        if SUPPORT_SHADER_PATHS
+            .iter()
+            .any(|path| changed.contains(&self.root.join(path)))
+        {
+            return ShaderSource::ALL.to_vec();
+        }
+
+        ShaderSource::ALL
+            .iter()
+            .copied()
+            .filter(|&source| changed.contains(&self.root.join(shader_path(source))))
+            .collect()
+    }
+
+    /// Compose the shader from the sources on disk, resolving its imports.
+    pub fn compose(&self, source: ShaderSource) -> Result<String, ShaderError> {
+        let label = shader_label(source);
+        let compose_error = |message: String| ShaderError::Parse {
+            label: label.to_string(),
+            message,
+        };
+
+        let mut composer = Composer::default().with_capabilities(
+            Capabilities::PUSH_CONSTANT
+                | Capabilities::SAMPLED_TEXTURE_AND_STORAGE_BUFFER_ARRAY_NON_UNIFORM_INDEXING,
+        );
+
+        for path in SUPPORT_SHADER_PATHS {
+            let text = self.read(path).map_err(compose_error)?;
+            let result = composer.add_composable_module(ComposableModuleDescriptor {
+                source: &text,
+                file_path: path,
+                language: ShaderLanguage::Wgsl,
+                as_name: None,
+                ..Default::default()
+            });
+            if let Err(err) = result {
+                return Err(compose_error(err.emit_to_string(&composer)));
+            }
+        }
+
+        let path = shader_path(source);
+        let text = self.read(path).map_err(compose_error)?;
+        let module = composer
+            .make_naga_module(NagaModuleDescriptor {
+                source: &text,
+                file_path: path,
+                shader_type: ShaderType::Wgsl,
+                ..Default::default()
+            })
+            .map_err(|err| compose_error(err.emit_to_string(&composer)))?;
+
+        let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+            .validate(&module)
+            .map_err(|err| ShaderError::Validation {
+                label: label.to_string(),
+                message: format!("{err:?}"),
+            })?;
+
+        wgsl::write_string(&module, &info, wgsl::WriterFlags::EXPLICIT_TYPES)
+            .map_err(|err| compose_error(err.to_string()))
+    }
+
+    fn read(&self, path: &str) -> Result<String, String> {
+        std::fs::read_to_string(self.root.join(path))
+            .map_err(|err| format!("Could not read {path}: {err}"))
+    }
+
+    /// Record the modified times of all sources and return the files whose
+    /// time changed. Files that can't be read are skipped.
+    fn changed_files(&mut self) -> Vec<PathBuf> {
+        let paths = SUPPORT_SHADER_PATHS
+            .iter()
+            .copied()
+            .chain(ShaderSource::ALL.iter().map(|&source| shader_path(source)));
+
+        let mut changed = Vec::new();
+        for path in paths {
+            let path = self.root.join(path);
+            let Ok(modified) = std::fs::metadata(&path).and_then(|meta| meta.modified()) else {
+                continue;
+            };
+
+            if self.modified.insert(path.clone(), modified) != Some(modified) {
+                changed.push(path);
+            }
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::shader_cache::validate_wgsl;
+
+    #[test]
+    fn shaders_compose_from_the_sources_on_disk() {
+        let mut watch = ShaderWatch::new(Path::new(env!("CARGO_MANIFEST_DIR")));
+        assert!(watch.changed_sources().is_empty());
+
+        for &source in ShaderSource::ALL {
+            let wgsl = watch.compose(source).unwrap();
+            validate_wgsl(shader_label(source), &wgsl).unwrap();
+        }
+
+        let missing = ShaderWatch::new(Path::new("does-not-exist"));
+        assert!(matches!(
+            missing.compose(ShaderSource::Models),
+            Err(ShaderError::Parse { .. })
+        ));
+    }
+}
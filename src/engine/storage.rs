@@ -81,6 +81,32 @@ impl<T, Stored> Storage<T, Stored> {
     pub fn insert(&mut self, value: Stored) -> Handle<T> {
         Handle(self.arena.insert(value), PhantomData)
     }
+
+    /// Remove the value behind `id`. Handles are generational, so `id` and any
+    /// copies of it never resolve again, even after the slot is reused.
+    #[inline]
+    pub fn remove(&mut self, id: Handle<T>) -> Option<Stored> {
+        self.arena.remove(id.0)
+    }
+
+    /// The amount of stored values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Whether no values are stored.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.arena.is_empty()
+    }
+
+    /// Iterate all stored values with their handles.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &Stored)> {
+        self.arena
+            .iter()
+            .map(|(index, value)| (Handle(index, PhantomData), value))
+    }
 }
 
 impl<T, Stored> Default for Storage<T, Stored> {
@@ -120,6 +146,23 @@ where
         self.storage.get_mut(handle)
     }
 
+    /// The amount of stored values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    /// Whether no values are stored.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    /// Iterate all stored values with their handles.
+    pub fn iter(&self) -> impl Iterator<Item = (Handle<T>, &Stored)> {
+        self.storage.iter()
+    }
+
     pub fn get_handle_by_key(&self, key: &K) -> Option<Handle<T>> {
         self.lookup.get(key).cloned()
     }
@@ -137,4 +180,78 @@ where
         self.lookup.insert(key, handle);
         handle
     }
+
+    /// Remove the value behind `handle` along with its key, so the key can be
+    /// inserted again.
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<Stored> {
+        let value = self.storage.remove(handle)?;
+        self.lookup.retain(|_, h| *h != handle);
+        Some(value)
+    }
+
+    /// Swap the value behind `handle` for `value`, keeping the handle valid.
+    /// Returns the previous value, or `None` (dropping `value`) if the handle
+    /// is stale.
+    pub fn replace(&mut self, handle: Handle<T>, value: Stored) -> Option<Stored> {
+        self.storage
+            .get_mut(handle)
+            .map(|stored| std::mem::replace(stored, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stale_handles_do_not_resolve() {
+        let mut storage = Storage::<u32>::default();
+
+        let first = storage.insert(1);
+        assert_eq!(storage.remove(first), Some(1));
+        assert_eq!(storage.get(first), None);
+        assert_eq!(storage.remove(first), None);
+
+        // The freed slot is reused, but the old handle still doesn't match.
+        let second = storage.insert(2);
+        assert_ne!(first, second);
+        assert_eq!(storage.get(first), None);
+        assert_eq!(storage.get(second), Some(&2));
+    }
+
+    #[test]
+    fn storage_map_remove_and_replace() {
+        let mut storage = StorageMap::<&str, u32>::default();
+
+        let one = storage.insert("one", 1);
+        let two = storage.insert("two", 2);
+
+        assert_eq!(storage.replace(one, 10), Some(1));
+        assert_eq!(storage.get(one), Some(&10));
+        assert_eq!(storage.get_by_key(&"one"), Some(&10));
+
+        assert_eq!(storage.remove(one), Some(10));
+        assert_eq!(storage.get(one), None);
+        assert_eq!(storage.get_handle_by_key(&"one"), None);
+        assert_eq!(storage.replace(one, 11), None);
+        assert_eq!(storage.get(two), Some(&2));
+    }
+
+    #[test]
+    fn iter_visits_every_value() {
+        let mut storage = StorageMap::<&str, u32>::default();
+        let handles = [
+            storage.insert("one", 1),
+            storage.insert("two", 2),
+            storage.insert("three", 3),
+        ];
+        assert_eq!(storage.len(), 3);
+
+        let mut visited = storage.iter().collect::<Vec<_>>();
+        visited.sort_by_key(|(_, value)| **value);
+        assert_eq!(
+            visited,
+            vec![(handles[0], &1), (handles[1], &2), (handles[2], &3)]
+        );
+    }
 }
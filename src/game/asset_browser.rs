@@ -0,0 +1,154 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::game::globals;
+
+/// A directory in the asset tree. Directories and files are kept sorted by
+/// name so the tree renders in a stable order.
+#[derive(Default)]
+struct DirNode {
+    dirs: BTreeMap<String, DirNode>,
+    /// File names and their full paths relative to the data directory.
+    files: BTreeMap<String, PathBuf>,
+}
+
+impl DirNode {
+    /// Add `path` to the tree, creating directories for each of the
+    /// `components` leading up to the file.
+    fn insert(&mut self, components: &[String], path: PathBuf) {
+        match components {
+            [] => {}
+            [file_name] => {
+                self.files.insert(file_name.clone(), path);
+            }
+            [dir_name, rest @ ..] => self
+                .dirs
+                .entry(dir_name.clone())
+                .or_default()
+                .insert(rest, path),
+        }
+    }
+
+    /// Render the contents of the node, returning the path of a clicked file.
+    fn ui(&self, ui: &mut egui::Ui, selected: Option<&Path>) -> Option<PathBuf> {
+        let mut clicked = None;
+
+        for (name, dir) in self.dirs.iter() {
+            egui::CollapsingHeader::new(name)
+                .id_salt(ui.id().with(name))
+                .show(ui, |ui| {
+                    if let Some(path) = dir.ui(ui, selected) {
+                        clicked = Some(path);
+                    }
+                });
+        }
+
+        for (name, path) in self.files.iter() {
+            let is_selected = selected == Some(path.as_path());
+            if ui.selectable_label(is_selected, name).clicked() {
+                clicked = Some(path.clone());
+            }
+        }
+
+        clicked
+    }
+}
+
+/// A collapsing tree of the files below a directory in the game data, limited
+/// to a set of extensions.
+pub struct AssetBrowser {
+    root: PathBuf,
+    /// Lower case extensions to list, without the dot. Empty to list all files.
+    extensions: Vec<String>,
+    /// The tree of files, built from the file system on first use.
+    tree: Option<DirNode>,
+    /// The last clicked file.
+    selected: Option<PathBuf>,
+}
+
+impl AssetBrowser {
+    /// Create a browser for the files below `root` with any of `extensions`.
+    pub fn new(root: impl Into<PathBuf>, extensions: &[&str]) -> Self {
+        Self {
+            root: root.into(),
+            extensions: extensions.iter().map(|e| e.to_ascii_lowercase()).collect(),
+            tree: None,
+            selected: None,
+        }
+    }
+
+    /// The last clicked file.
+    pub fn selected(&self) -> Option<&Path> {
+        self.selected.as_deref()
+    }
+
+    /// Drop the cached tree so the files are listed again on the next render.
+    pub fn refresh(&mut self) {
+        self.tree = None;
+    }
+
+    /// Render the tree and return the path of the file clicked this frame.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> Option<PathBuf> {
+        if self.tree.is_none() {
+            self.tree = Some(self.build_tree());
+        }
+        let tree = self.tree.as_ref().expect("tree was just built");
+
+        let clicked = egui::ScrollArea::vertical()
+            .show(ui, |ui| {
+                ui.push_id(&self.root, |ui| tree.ui(ui, self.selected.as_deref()))
+                    .inner
+            })
+            .inner;
+
+        if let Some(path) = &clicked {
+            self.selected = Some(path.clone());
+        }
+
+        clicked
+    }
+
+    /// List the matching files below `root`, with paths relative to `root`
+    /// forming the tree.
+    fn build_tree(&self) -> DirNode {
+        let mut tree = DirNode::default();
+
+        let files = match globals::file_system().dir(&self.root) {
+            Ok(files) => files,
+            Err(err) => {
+                tracing::warn!("Could not list {}: {err}", self.root.display());
+                return tree;
+            }
+        };
+
+        for path in files {
+            if !self.matches_extension(&path) {
+                continue;
+            }
+
+            let Ok(relative) = path.strip_prefix(&self.root) else {
+                continue;
+            };
+            let components: Vec<String> = relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+
+            tree.insert(&components, path.clone());
+        }
+
+        tree
+    }
+
+    fn matches_extension(&self, path: &Path) -> bool {
+        if self.extensions.is_empty() {
+            return true;
+        }
+
+        path.extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .is_some_and(|e| self.extensions.contains(&e))
+    }
+}
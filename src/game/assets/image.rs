@@ -19,7 +19,7 @@ pub enum BlendMode {
     /// Use the alpha channel of the texture.
     Alpha = 2,
     /// Adds the values of the texture to the image.
-    _Additive = 3,
+    Additive = 3,
 }
 
 pub struct Image {
@@ -38,6 +38,29 @@ impl Image {
             data,
         }
     }
+
+    /// The fraction of pixels that differ from `other` by more than
+    /// `tolerance` in any channel. Images of different sizes differ
+    /// completely.
+    pub fn diff(&self, other: &Image, tolerance: u8) -> f32 {
+        if self.size != other.size {
+            return 1.0;
+        }
+
+        let pixel_count = self.data.pixels().len();
+        if pixel_count == 0 {
+            return 0.0;
+        }
+
+        let differing = self
+            .data
+            .pixels()
+            .zip(other.data.pixels())
+            .filter(|(a, b)| a.0.iter().zip(b.0).any(|(a, b)| a.abs_diff(b) > tolerance))
+            .count();
+
+        differing as f32 / pixel_count as f32
+    }
 }
 
 #[derive(Debug, Error)]
@@ -180,38 +203,81 @@ impl AssetFactory for Image {
     }
 }
 
-/// Read a three-channel image decoder into an RGBA8 byte buffer.
+/// Pixel layouts that can be converted to RGBA8 for upload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PixelLayout {
+    L8,
+    La8,
+    Rgb8,
+    Rgba8,
+}
+
+impl PixelLayout {
+    /// Number of bytes per pixel.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            Self::L8 => 1,
+            Self::La8 => 2,
+            Self::Rgb8 => 3,
+            Self::Rgba8 => 4,
+        }
+    }
+
+    /// The layout matching a decoder color type, if it can be converted.
+    fn from_color_type(color_type: ColorType) -> Option<Self> {
+        match color_type {
+            ColorType::L8 => Some(Self::L8),
+            ColorType::La8 => Some(Self::La8),
+            ColorType::Rgb8 => Some(Self::Rgb8),
+            ColorType::Rgba8 => Some(Self::Rgba8),
+            _ => None,
+        }
+    }
+}
+
+/// Read an image decoder into an RGBA8 byte buffer, converting from the
+/// decoder's pixel layout.
 fn read_as_rgba<D: ImageDecoder>(decoder: D) -> image::ImageResult<Vec<u8>> {
     let color_type = decoder.color_type();
-    if color_type != ColorType::Rgb8 {
+    let Some(layout) = PixelLayout::from_color_type(color_type) else {
         return Err(image::ImageError::Unsupported(
             image::error::UnsupportedError::from_format_and_kind(
                 image::error::ImageFormatHint::Unknown,
                 image::error::UnsupportedErrorKind::Color(color_type.into()),
             ),
         ));
-    }
+    };
 
     let (width, height) = decoder.dimensions();
     let pixel_count = width as usize * height as usize;
-    let rgb_len = pixel_count * 3;
     let mut rgba = vec![0; pixel_count * 4];
-    decoder.read_image(&mut rgba[..rgb_len])?;
-    expand_rgb_to_rgba(&mut rgba, pixel_count);
+    decoder.read_image(&mut rgba[..pixel_count * layout.bytes_per_pixel()])?;
+    expand_to_rgba(&mut rgba, pixel_count, layout);
 
     Ok(rgba)
 }
 
-/// Expand packed RGB8 pixels in-place into RGBA8 pixels.
-fn expand_rgb_to_rgba(rgba: &mut [u8], pixel_count: usize) {
+/// Expand `pixel_count` packed pixels of `layout` at the start of `rgba`
+/// in-place into RGBA8 pixels. `rgba` must hold `pixel_count * 4` bytes.
+pub fn expand_to_rgba(rgba: &mut [u8], pixel_count: usize, layout: PixelLayout) {
+    debug_assert!(rgba.len() >= pixel_count * 4);
+
+    let bytes_per_pixel = layout.bytes_per_pixel();
+
+    // Walk backwards so the packed source is never overwritten before it is read.
     for i in (0..pixel_count).rev() {
-        let read_index = i * 3;
-        let write_index = i * 4;
+        let read_index = i * bytes_per_pixel;
+        let src = &rgba[read_index..read_index + bytes_per_pixel];
+
+        let pixel = match layout {
+            PixelLayout::L8 => [src[0], src[0], src[0], 255],
+            PixelLayout::La8 => [src[0], src[0], src[0], src[1]],
+            PixelLayout::Rgb8 => [src[0], src[1], src[2], 255],
+            PixelLayout::Rgba8 => [src[0], src[1], src[2], src[3]],
+        };
 
-        rgba[write_index] = rgba[read_index];
-        rgba[write_index + 1] = rgba[read_index + 1];
-        rgba[write_index + 2] = rgba[read_index + 2];
-        rgba[write_index + 3] = 255;
+        let write_index = i * 4;
+        rgba[write_index..write_index + 4].copy_from_slice(&pixel);
     }
 }
 
@@ -247,3 +313,54 @@ fn quantize_rgb565(image: &mut image::RgbaImage) {
         pixel.0[2] = (blue << 3) | (blue >> 2);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(layout: PixelLayout, packed: &[u8]) -> Vec<u8> {
+        let pixel_count = packed.len() / layout.bytes_per_pixel();
+        let mut rgba = vec![0; pixel_count * 4];
+        rgba[..packed.len()].copy_from_slice(packed);
+        expand_to_rgba(&mut rgba, pixel_count, layout);
+        rgba
+    }
+
+    #[test]
+    fn diff_counts_pixels_outside_the_tolerance() {
+        let image = |pixels: &[[u8; 4]]| {
+            let data = RgbaImage::from_raw(2, 1, pixels.concat()).unwrap();
+            Image::from_rgba(data, BlendMode::Opaque)
+        };
+
+        let reference = image(&[[10, 20, 30, 255], [0, 0, 0, 255]]);
+        assert_eq!(reference.diff(&reference, 0), 0.0);
+
+        let close = image(&[[12, 20, 30, 255], [0, 0, 0, 255]]);
+        assert_eq!(reference.diff(&close, 2), 0.0);
+        assert_eq!(reference.diff(&close, 1), 0.5);
+
+        let other_size = Image::from_rgba(RgbaImage::new(1, 1), BlendMode::Opaque);
+        assert_eq!(reference.diff(&other_size, 255), 1.0);
+    }
+
+    #[test]
+    fn grayscale_expands_to_opaque_rgba() {
+        assert_eq!(
+            expand(PixelLayout::L8, &[0, 128, 255]),
+            [0, 0, 0, 255, 128, 128, 128, 255, 255, 255, 255, 255]
+        );
+        assert_eq!(
+            expand(PixelLayout::La8, &[10, 20, 30, 40]),
+            [10, 10, 10, 20, 30, 30, 30, 40]
+        );
+    }
+
+    #[test]
+    fn rgb_expands_to_opaque_rgba() {
+        assert_eq!(
+            expand(PixelLayout::Rgb8, &[1, 2, 3, 4, 5, 6]),
+            [1, 2, 3, 255, 4, 5, 6, 255]
+        );
+    }
+}
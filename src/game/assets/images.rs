@@ -17,6 +17,9 @@ use crate::{
 #[derive(Default)]
 pub struct Images {
     storage: RwLock<StorageMap<String, Image, Arc<Image>>>,
+    /// Image loaded by [Images::load_or_fallback] when the requested image
+    /// can't be loaded.
+    fallback: RwLock<Option<PathBuf>>,
 }
 
 impl Images {
@@ -24,6 +27,35 @@ impl Images {
         self.storage.read().unwrap().get(handle).map(Arc::clone)
     }
 
+    /// Call `f` with every loaded image. The storage is read locked while
+    /// iterating, so `f` must not load or remove images.
+    pub fn for_each(&self, mut f: impl FnMut(Handle<Image>, &Arc<Image>)) {
+        let storage = self.storage.read().unwrap();
+        for (handle, image) in storage.iter() {
+            f(handle, image);
+        }
+    }
+
+    /// The amount of loaded images.
+    pub fn count(&self) -> usize {
+        self.storage.read().unwrap().len()
+    }
+
+    /// Evict the image behind `handle`. Existing handles to it stop resolving
+    /// and the next load reads it from disk again.
+    pub fn remove(&self, handle: Handle<Image>) -> Option<Arc<Image>> {
+        self.storage.write().unwrap().remove(handle)
+    }
+
+    /// Swap the image behind `handle`, so existing handles resolve to the new
+    /// one. Returns the previous image, or `None` if the handle is stale.
+    pub fn replace(&self, handle: Handle<Image>, image: Image) -> Option<Arc<Image>> {
+        self.storage
+            .write()
+            .unwrap()
+            .replace(handle, Arc::new(image))
+    }
+
     pub fn load(&self, path: impl Into<PathBuf>) -> Result<Handle<Image>, AssetError> {
         let path = path.into();
 
@@ -46,6 +78,20 @@ impl Images {
         Ok(handle)
     }
 
+    /// Set the image [Images::load_or_fallback] loads in place of images that
+    /// fail to load.
+    pub fn set_fallback(&self, path: impl Into<PathBuf>) {
+        *self.fallback.write().unwrap() = Some(path.into());
+    }
+
+    /// Load an image, or the fallback image if it fails to load. Returns the
+    /// original error if there is no fallback or the fallback fails to load
+    /// as well.
+    pub fn load_or_fallback(&self, path: impl Into<PathBuf>) -> Result<Handle<Image>, AssetError> {
+        let fallback = self.fallback.read().unwrap().clone();
+        load_or_fallback(&path.into(), fallback.as_deref(), |path| self.load(path))
+    }
+
     /// Insert an image or return the existing handle if that key has already
     /// exists.
     pub fn insert(&self, key: impl Into<String>, image: Image) -> Handle<Image> {
@@ -70,3 +116,61 @@ impl Images {
         path.to_string_lossy().to_ascii_lowercase()
     }
 }
+
+/// Load `path`, or `fallback` if that fails. A failing fallback is never
+/// retried, so a missing fallback returns the original error.
+fn load_or_fallback<T>(
+    path: &Path,
+    fallback: Option<&Path>,
+    load: impl Fn(&Path) -> Result<T, AssetError>,
+) -> Result<T, AssetError> {
+    let err = match load(path) {
+        Ok(asset) => return Ok(asset),
+        Err(err) => err,
+    };
+
+    let Some(fallback) = fallback.filter(|fallback| *fallback != path) else {
+        return Err(err);
+    };
+
+    match load(fallback) {
+        Ok(asset) => {
+            tracing::warn!("{err}, using {} instead", fallback.display());
+            Ok(asset)
+        }
+        Err(_) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(path: &Path) -> Result<&'static str, AssetError> {
+        match path.to_str() {
+            Some("good.bmp") => Ok("good"),
+            Some("error.bmp") => Ok("error"),
+            _ => Err(AssetError::FileNotFound(path.to_path_buf())),
+        }
+    }
+
+    #[test]
+    fn fallback_replaces_failed_loads() {
+        let fallback = Some(Path::new("error.bmp"));
+
+        assert_eq!(
+            load_or_fallback(Path::new("good.bmp"), fallback, load).unwrap(),
+            "good"
+        );
+        assert_eq!(
+            load_or_fallback(Path::new("missing.bmp"), fallback, load).unwrap(),
+            "error"
+        );
+        assert!(load_or_fallback(Path::new("missing.bmp"), None, load).is_err());
+
+        // A missing fallback reports the original error.
+        let err = load_or_fallback(Path::new("missing.bmp"), Some(Path::new("gone.bmp")), load)
+            .unwrap_err();
+        assert!(matches!(err, AssetError::FileNotFound(path) if path == Path::new("missing.bmp")));
+    }
+}
@@ -4,7 +4,10 @@ use ahash::HashMap;
 use glam::{Mat4, Vec2, Vec3};
 
 use crate::{
-    engine::{mesh::IndexedMesh, storage::Handle},
+    engine::{
+        mesh::{IndexedMesh, VertexPosition},
+        storage::Handle,
+    },
     game::{
         assets::image::Image,
         math::{BoundingBox, Ray, RaySegment, triangle_intersect_ray_segment},
@@ -283,6 +286,12 @@ pub struct Vertex {
     pub node_index: u32,
 }
 
+impl VertexPosition for Vertex {
+    fn position(&self) -> Vec3 {
+        self.position
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct CollisionBox {
     /// An index to the [ModelNode] this mesh is attached to.
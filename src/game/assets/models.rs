@@ -71,6 +71,35 @@ impl Models {
         self.storage.read().unwrap().get(handle).map(Arc::clone)
     }
 
+    /// Call `f` with every loaded model. The storage is read locked while
+    /// iterating, so `f` must not load or remove models.
+    pub fn for_each(&self, mut f: impl FnMut(Handle<Model>, &Arc<Model>)) {
+        let storage = self.storage.read().unwrap();
+        for (handle, model) in storage.iter() {
+            f(handle, model);
+        }
+    }
+
+    /// The amount of loaded models.
+    pub fn count(&self) -> usize {
+        self.storage.read().unwrap().len()
+    }
+
+    /// Evict the model behind `handle`. Existing handles to it stop resolving
+    /// and the next load reads it from disk again.
+    pub fn remove(&self, handle: Handle<Model>) -> Option<Arc<Model>> {
+        self.storage.write().unwrap().remove(handle)
+    }
+
+    /// Swap the model behind `handle`, so existing handles resolve to the new
+    /// one. Returns the previous model, or `None` if the handle is stale.
+    pub fn replace(&self, handle: Handle<Model>, model: Model) -> Option<Arc<Model>> {
+        self.storage
+            .write()
+            .unwrap()
+            .replace(handle, Arc::new(model))
+    }
+
     pub fn load(&self, name: ModelName) -> Result<Handle<Model>, AssetError> {
         {
             let storage = self.storage.read().unwrap();
@@ -191,13 +220,23 @@ impl Models {
             });
 
             for smf_mesh in smf_node.meshes.iter() {
-                let mesh = smf_mesh_to_mesh(smf_mesh, node_index as u32);
+                let mut mesh = smf_mesh_to_mesh(smf_mesh, node_index as u32);
+
+                // Bad indices would only surface as a device loss once uploaded.
+                #[cfg(debug_assertions)]
+                mesh.validate().map_err(|err| {
+                    AssetError::custom(
+                        path,
+                        format!("Invalid mesh in node {}: {err}", smf_node.name),
+                    )
+                })?;
+                mesh.remove_degenerate_triangles();
 
                 let texture_path = PathBuf::from("textures")
                     .join("shared")
                     .join(&smf_mesh.texture_name);
 
-                let image_handle = match globals::images().load(texture_path) {
+                let image_handle = match globals::images().load_or_fallback(texture_path) {
                     Ok(handle) => handle,
                     Err(err) => {
                         tracing::warn!("Could not load mesh texture: {}", err);
@@ -229,7 +268,7 @@ impl Models {
                     parent: node.parent,
                     transform: node.transform.clone(),
                     id: node.bone_id,
-                    _name: node.name.clone(),
+                    name: node.name.clone(),
                 })
                 .collect(),
         };
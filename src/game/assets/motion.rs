@@ -3,6 +3,11 @@ use glam::{Quat, Vec3};
 use shadow_company_tools::bmf;
 use std::sync::atomic::{AtomicU32, Ordering};
 
+use crate::game::track::Track;
+
+/// Index of a bone in a skeleton, matching [bmf::Bone::bone_id].
+pub type BoneIndex = u32;
+
 bitflags! {
     /// Per-motion behavior flags used by sequencer/runtime systems.
     #[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
@@ -261,6 +266,67 @@ impl Motion {
             .lerp(Self::convert_source_translation(right.lve), t)
     }
 
+    /// Split the horizontal translation of `root_bone` out of the motion.
+    ///
+    /// Returns an in-place copy of the motion where the root bone keeps the
+    /// horizontal position of the first key frame, along with a track holding
+    /// the removed displacement (relative to the first key frame, in engine
+    /// space) keyed by frame. Gameplay code applies the displacement to the
+    /// entity transform instead. Vertical movement stays in the animation.
+    pub fn extract_root_motion(&self, root_bone: BoneIndex) -> (Motion, Track<Vec3>) {
+        let mut key_frames = self.key_frames.clone();
+        let mut displacement = Track::default();
+
+        let root_position = |key_frame: &bmf::KeyFrame| {
+            key_frame
+                .bones
+                .iter()
+                .find(|bone| bone.bone_id == root_bone)
+                .and_then(|bone| bone.position)
+        };
+
+        let Some(origin) = key_frames.iter().find_map(root_position) else {
+            // Nothing to extract; the motion is already in place.
+            return (self.clone_with_key_frames(key_frames), displacement);
+        };
+
+        for key_frame in key_frames.iter_mut() {
+            let Some(bone) = key_frame
+                .bones
+                .iter_mut()
+                .find(|bone| bone.bone_id == root_bone)
+            else {
+                continue;
+            };
+            let Some(position) = bone.position.as_mut() else {
+                continue;
+            };
+
+            let offset = Self::convert_source_translation(*position)
+                - Self::convert_source_translation(origin);
+            displacement.insert(key_frame.frame, Vec3::new(offset.x, offset.y, 0.0));
+
+            position.x = origin.x;
+            position.y = origin.y;
+        }
+
+        (self.clone_with_key_frames(key_frames), displacement)
+    }
+
+    /// Copy everything except the key frames, which are replaced.
+    fn clone_with_key_frames(&self, key_frames: Vec<bmf::KeyFrame>) -> Motion {
+        Motion {
+            name: self.name.clone(),
+            frame_count: self.frame_count,
+            last_frame: self.last_frame,
+            base_ticks_per_frame: self.base_ticks_per_frame,
+            from_state: self.from_state,
+            to_state: self.to_state,
+            key_frames,
+            flags: AtomicU32::new(self.flags.load(Ordering::Relaxed)),
+        }
+    }
+
     /// Return the motion declaration flags currently applied to this motion.
     #[inline]
     pub fn flags(&self) -> MotionFlags {
@@ -294,3 +360,60 @@ impl Default for Motion {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_frame(frame: u32, root: Vec3, other: Vec3) -> bmf::KeyFrame {
+        bmf::KeyFrame {
+            frame,
+            lve: Vec3::ZERO,
+            bones: vec![
+                bmf::Bone {
+                    bone_id: 0,
+                    position: Some(root),
+                    rotation: None,
+                },
+                bmf::Bone {
+                    bone_id: 1,
+                    position: Some(other),
+                    rotation: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn extracted_root_has_no_horizontal_displacement() {
+        let motion = Motion {
+            key_frames: vec![
+                key_frame(0, Vec3::new(1.0, 2.0, 3.0), Vec3::X),
+                key_frame(5, Vec3::new(6.0, 4.0, 5.0), Vec3::Y),
+                key_frame(10, Vec3::new(11.0, 6.0, 3.0), Vec3::Z),
+            ],
+            ..Default::default()
+        };
+
+        let (in_place, displacement) = motion.extract_root_motion(0);
+
+        let root_at = |key_frame_index| {
+            in_place.sample_bone_updates_at_key_frame(key_frame_index)[0]
+                .translation
+                .unwrap()
+        };
+        let first = root_at(0);
+        let last = root_at(2);
+        assert_eq!(first.truncate(), last.truncate());
+        // Vertical movement and other bones are untouched.
+        assert_eq!(root_at(1).z, 5.0);
+        assert_eq!(in_place.key_frames[2].bones[1].position, Some(Vec3::Z));
+
+        // The source basis flips x.
+        assert_eq!(
+            displacement.sample_sub_frame(10.0, false),
+            Vec3::new(-10.0, 4.0, 0.0)
+        );
+        assert_eq!(displacement.sample_sub_frame(0.0, false), Vec3::ZERO);
+    }
+}
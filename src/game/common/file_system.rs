@@ -16,14 +16,66 @@ pub enum FileSystemError {
     Io(#[from] std::io::Error),
 }
 
+/// The game data, with any number of overlay directories on top. Files in an
+/// overlay shadow the files with the same path below it, so mods can replace
+/// textures, models and configs without touching the original data.
 pub struct FileSystem {
-    root_dir: PathBuf,
-    gut_files: HashMap<String, GutFile>,
+    /// The game data first, then the overlays in the order they were added.
+    layers: Vec<Layer>,
 }
 
 impl FileSystem {
     /// Builds a virtual file system rooted at the game data directory.
     pub fn new(root_dir: impl AsRef<Path>) -> Self {
+        Self {
+            layers: vec![Layer::new(root_dir).expect("Could not canonicalize root path")],
+        }
+    }
+
+    /// Add a directory whose files take precedence over the game data and all
+    /// overlays added before it. Like the game data, the directory can hold
+    /// loose files and .gut archives.
+    pub fn add_overlay(&mut self, dir: impl AsRef<Path>) -> Result<(), FileSystemError> {
+        self.layers.push(Layer::new(dir)?);
+        Ok(())
+    }
+
+    /// Loads a file from the top most layer that has it, either from disk or
+    /// from a mounted .gut archive.
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<Vec<u8>, FileSystemError> {
+        for layer in self.layers.iter().rev() {
+            match layer.load(path.as_ref()) {
+                Err(FileSystemError::FileNotFound(_)) => continue,
+                result => return result,
+            }
+        }
+
+        Err(FileSystemError::FileNotFound(path.as_ref().to_path_buf()))
+    }
+
+    /// Returns all files below `root` in any layer, combining external files
+    /// and matching .gut entries.
+    pub fn dir(
+        &self,
+        root: impl AsRef<Path>,
+    ) -> Result<impl Iterator<Item = PathBuf>, FileSystemError> {
+        let mut result: HashSet<PathBuf> = HashSet::default();
+        for layer in self.layers.iter() {
+            layer.dir(root.as_ref(), &mut result);
+        }
+
+        Ok(result.into_iter())
+    }
+}
+
+/// A directory of loose files and the .gut archives found in it.
+struct Layer {
+    root_dir: PathBuf,
+    gut_files: HashMap<String, GutFile>,
+}
+
+impl Layer {
+    fn new(root_dir: impl AsRef<Path>) -> Result<Self, FileSystemError> {
         let mut gut_files = HashMap::default();
 
         WalkDir::new(&root_dir)
@@ -50,28 +102,25 @@ impl FileSystem {
                 }
             });
 
-        Self {
-            root_dir: root_dir
-                .as_ref()
-                .canonicalize()
-                .expect("Could not canonicalize root path"),
+        Ok(Self {
+            root_dir: root_dir.as_ref().canonicalize()?,
             gut_files,
-        }
+        })
     }
 
     /// Loads a file either from disk or from a mounted .gut archive.
-    pub fn load(&self, path: impl AsRef<Path>) -> Result<Vec<u8>, FileSystemError> {
-        let full_path = self.root_dir.join(path.as_ref());
+    fn load(&self, path: &Path) -> Result<Vec<u8>, FileSystemError> {
+        let full_path = self.root_dir.join(path);
 
         if !full_path.exists()
-            && let Some(gut_file) = self.gut_file_for_path(&path)
+            && let Some(gut_file) = self.gut_file_for_path(path)
         {
             return gut_file.load(path);
         }
 
         let mut file = std::fs::File::open(full_path).map_err(|err| {
             if let ErrorKind::NotFound = err.kind() {
-                FileSystemError::FileNotFound(path.as_ref().to_path_buf())
+                FileSystemError::FileNotFound(path.to_path_buf())
             } else {
                 FileSystemError::Io(err)
             }
@@ -82,17 +131,12 @@ impl FileSystem {
         Ok(data)
     }
 
-    /// Returns all files below `root`, combining external files and matching .gut entries.
-    pub fn dir(
-        &self,
-        root: impl AsRef<Path>,
-    ) -> Result<impl Iterator<Item = PathBuf>, FileSystemError> {
+    /// Adds all files below `root` to `result`, combining external files and
+    /// matching .gut entries.
+    fn dir(&self, root: &Path, result: &mut HashSet<PathBuf>) {
         // TODO: Enumerate the root directory?
-        let mut result: HashSet<PathBuf> = HashSet::default();
-
-        if let Some(gut_file) = self.gut_file_for_path(&root) {
+        if let Some(gut_file) = self.gut_file_for_path(root) {
             let search = root
-                .as_ref()
                 .to_string_lossy()
                 .to_ascii_lowercase()
                 .replace(std::path::MAIN_SEPARATOR, "\\");
@@ -124,8 +168,6 @@ impl FileSystem {
             .for_each(|path| {
                 result.insert(path);
             });
-
-        Ok(result.into_iter())
     }
 
     fn gut_file_for_path(&self, path: impl AsRef<Path>) -> Option<&GutFile> {
@@ -226,3 +268,46 @@ fn read_exact_at(file: &std::fs::File, mut buf: &mut [u8], mut offset: u64) -> s
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlays_shadow_the_layers_below() {
+        let root =
+            std::env::temp_dir().join(format!("file_system_overlays_{}", std::process::id()));
+        let write = |layer: &str, path: &str, contents: &str| {
+            let path = root.join(layer).join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        };
+        write("data", "config/a.txt", "base a");
+        write("data", "config/b.txt", "base b");
+        write("mod", "config/b.txt", "mod b");
+        write("mod", "config/c.txt", "mod c");
+
+        let mut file_system = FileSystem::new(root.join("data"));
+        file_system.add_overlay(root.join("mod")).unwrap();
+
+        let load = |path: &str| String::from_utf8(file_system.load(path).unwrap()).unwrap();
+        assert_eq!(load("config/a.txt"), "base a");
+        assert_eq!(load("config/b.txt"), "mod b");
+        assert_eq!(load("config/c.txt"), "mod c");
+        assert!(matches!(
+            file_system.load("config/d.txt"),
+            Err(FileSystemError::FileNotFound(_))
+        ));
+
+        let mut files: Vec<PathBuf> = file_system.dir("config").unwrap().collect();
+        files.sort();
+        assert_eq!(
+            files,
+            ["a.txt", "b.txt", "c.txt"].map(|name| Path::new("config").join(name))
+        );
+
+        assert!(file_system.add_overlay(root.join("missing")).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
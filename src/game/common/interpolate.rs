@@ -2,6 +2,33 @@ use glam::{Quat, Vec3};
 
 pub trait Interpolate: Copy {
     fn interpolate(left: Self, right: Self, n: f32) -> Self;
+
+    /// Interpolate between `left` and `right` along a curve through the values
+    /// before and after them. Falls back to [Interpolate::interpolate] for
+    /// types without a cubic curve.
+    #[inline]
+    fn interpolate_cubic(_before: Self, left: Self, right: Self, _after: Self, n: f32) -> Self {
+        Self::interpolate(left, right, n)
+    }
+}
+
+/// Catmull-Rom spline through `p1` and `p2`, with `p0` and `p3` shaping the
+/// tangents.
+#[inline]
+fn catmull_rom<T>(p0: T, p1: T, p2: T, p3: T, n: f32) -> T
+where
+    T: Copy
+        + std::ops::Add<Output = T>
+        + std::ops::Sub<Output = T>
+        + std::ops::Mul<f32, Output = T>,
+{
+    let n2 = n * n;
+    let n3 = n2 * n;
+    (p1 * 2.0
+        + (p2 - p0) * n
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * n2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * n3)
+        * 0.5
 }
 
 impl Interpolate for f32 {
@@ -9,6 +36,11 @@ impl Interpolate for f32 {
     fn interpolate(left: Self, right: Self, n: f32) -> Self {
         left + (right - left) * n
     }
+
+    #[inline]
+    fn interpolate_cubic(before: Self, left: Self, right: Self, after: Self, n: f32) -> Self {
+        catmull_rom(before, left, right, after, n)
+    }
 }
 
 impl Interpolate for Vec3 {
@@ -16,6 +48,11 @@ impl Interpolate for Vec3 {
     fn interpolate(left: Self, right: Self, n: f32) -> Self {
         left.lerp(right, n)
     }
+
+    #[inline]
+    fn interpolate_cubic(before: Self, left: Self, right: Self, after: Self, n: f32) -> Self {
+        catmull_rom(before, left, right, after, n)
+    }
 }
 
 impl Interpolate for Quat {
@@ -16,6 +16,23 @@ pub mod eps {
     pub const DENOM: f32 = 1e-8;
 }
 
+/// Return element `index` of the Halton low-discrepancy sequence for `base`, in the range
+/// `[0, 1)`.
+pub fn halton(mut index: u32, base: u32) -> f32 {
+    debug_assert!(base >= 2);
+
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+
+    result
+}
+
 /// Represents a view into the world by way of matrices.
 #[derive(Clone, Debug, Default)]
 pub struct ViewProjection {
@@ -440,6 +457,27 @@ impl BoundingBox {
         Some((t_enter, t_exit, enter_normal))
     }
 
+    /// The point inside the box closest to `point`.
+    #[inline]
+    pub fn closest_point(&self, point: Vec3) -> Vec3 {
+        point.clamp(self.min, self.max)
+    }
+
+    /// Distance from `point` to the surface of the box, `0.0` if the point is
+    /// inside.
+    #[inline]
+    pub fn distance_to_point(&self, point: Vec3) -> f32 {
+        self.closest_point(point).distance(point)
+    }
+
+    /// Whether any part of the box lies inside `sphere`.
+    #[inline]
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.closest_point(sphere.center)
+            .distance_squared(sphere.center)
+            <= sphere.radius * sphere.radius
+    }
+
     #[inline]
     pub fn contains_aabb(&self, other: &BoundingBox) -> bool {
         self.min.x <= other.min.x
@@ -717,3 +755,56 @@ pub fn triangle_intersect_ray_segment(
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit_box() -> BoundingBox {
+        BoundingBox {
+            min: Vec3::ZERO,
+            max: Vec3::ONE,
+        }
+    }
+
+    #[test]
+    fn distance_to_point_is_zero_inside_the_box() {
+        let bounding_box = unit_box();
+        assert_eq!(bounding_box.distance_to_point(Vec3::splat(0.5)), 0.0);
+        assert_eq!(
+            bounding_box.distance_to_point(Vec3::new(3.0, 0.5, 0.5)),
+            2.0
+        );
+        // Past a corner the distance is to the corner itself.
+        assert_eq!(
+            bounding_box.distance_to_point(Vec3::new(4.0, 5.0, 1.0)),
+            5.0
+        );
+    }
+
+    #[test]
+    fn spheres_intersect_boxes_they_touch() {
+        let bounding_box = unit_box();
+        let sphere = |x, radius| BoundingSphere::new(Vec3::new(x, 0.5, 0.5), radius);
+
+        assert!(bounding_box.intersects_sphere(&sphere(0.5, 0.1)));
+        assert!(bounding_box.intersects_sphere(&sphere(3.0, 2.0)));
+        assert!(!bounding_box.intersects_sphere(&sphere(3.0, 1.9)));
+        assert!(!bounding_box.intersects_sphere(&sphere(-2.0, 1.0)));
+        // A sphere past the corner misses, though it reaches every face plane.
+        let corner = BoundingSphere::new(Vec3::splat(1.5), 0.8);
+        assert!(!bounding_box.intersects_sphere(&corner));
+    }
+
+    #[test]
+    fn halton_matches_the_radical_inverse() {
+        let base_2: Vec<f32> = (0..5).map(|i| halton(i, 2)).collect();
+        assert_eq!(base_2, [0.0, 0.5, 0.25, 0.75, 0.125]);
+
+        let base_3: Vec<f32> = (1..5).map(|i| halton(i, 3)).collect();
+        let expected = [1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0, 4.0 / 9.0];
+        for (value, expected) in base_3.iter().zip(expected) {
+            assert!((value - expected).abs() < 1e-6, "{value} != {expected}");
+        }
+    }
+}
@@ -7,7 +7,7 @@ pub struct Bone {
     pub parent: u32,
     pub transform: Transform,
     pub id: u32,
-    pub _name: String,
+    pub name: String,
 }
 
 #[derive(Clone, Debug)]
@@ -16,6 +16,14 @@ pub struct Skeleton {
 }
 
 impl Skeleton {
+    /// Find the index of the bone with the given name.
+    pub fn bone_index(&self, name: &str) -> Option<u32> {
+        self.bones
+            .iter()
+            .position(|bone| bone.name.eq_ignore_ascii_case(name))
+            .map(|index| index as u32)
+    }
+
     pub fn local_transform(&self, bone_index: u32) -> Mat4 {
         let node = &self.bones[bone_index as usize];
         if node.parent == u32::MAX {
@@ -6,9 +6,37 @@ pub struct Key<V> {
     pub value: V,
 }
 
+/// How a [Track] is sampled outside of its key frames.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum PlaybackMode {
+    /// Hold the first and last values.
+    #[default]
+    Clamp,
+    /// Wrap around to the start after the last key frame.
+    Loop,
+    /// Play backwards after the last key frame, then forwards again after the
+    /// first.
+    PingPong,
+}
+
+/// How a [Track] interpolates between key frames.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Interpolation {
+    /// Straight lines between key frames.
+    #[default]
+    Linear,
+    /// A Catmull-Rom curve through the key frames, so the value changes
+    /// smoothly across them. Tracks with fewer than 4 key frames, and values
+    /// without a cubic curve like rotations, are interpolated linearly.
+    Cubic,
+    /// Hold the value of each key frame until the next.
+    Step,
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct Track<V: Interpolate> {
     keys: Vec<Key<V>>,
+    pub interpolation: Interpolation,
 }
 
 impl<V: Interpolate + Default> Track<V> {
@@ -18,6 +46,12 @@ impl<V: Interpolate + Default> Track<V> {
         self.keys.last().map(|k| k.frame)
     }
 
+    /// Interpolate between key frames with `interpolation`.
+    pub fn with_interpolation(mut self, interpolation: Interpolation) -> Self {
+        self.interpolation = interpolation;
+        self
+    }
+
     pub fn insert(&mut self, frame: u32, value: V) {
         match self.keys.binary_search_by_key(&frame, |k| k.frame) {
             Ok(i) => self.keys[i].value = value,                 // last wins
@@ -79,6 +113,27 @@ impl<V: Interpolate + Default> Track<V> {
     /// If `looping`, wrap to [0, last_frame).
     #[inline]
     pub fn sample_sub_frame(&self, frame_f: f32, looping: bool) -> V {
+        let mode = if looping {
+            PlaybackMode::Loop
+        } else {
+            PlaybackMode::Clamp
+        };
+        self.sample_mode(frame_f, mode)
+    }
+
+    /// Interpolated value at a fractional frame index, played back with
+    /// `mode`.
+    pub fn sample_mode(&self, frame_f: f32, mode: PlaybackMode) -> V {
+        self.sample_with(frame_f, mode, self.interpolation)
+    }
+
+    /// Value on a Catmull-Rom curve through the key frames at a fractional
+    /// frame index, no matter the [Interpolation] of the track.
+    pub fn sample_cubic(&self, frame_f: f32) -> V {
+        self.sample_with(frame_f, PlaybackMode::Clamp, Interpolation::Cubic)
+    }
+
+    fn sample_with(&self, frame_f: f32, mode: PlaybackMode, interpolation: Interpolation) -> V {
         if self.keys.is_empty() {
             return V::default();
         }
@@ -91,11 +146,17 @@ impl<V: Interpolate + Default> Track<V> {
         let first = self.keys[0].frame as f32;
         let last = self.keys[self.keys.len() - 1].frame as f32;
 
-        let f = if looping && last > first {
-            let span = last - first;
-            first + (frame_f - first).rem_euclid(span) // [first,last)
-        } else {
-            frame_f.clamp(first, last)
+        let span = last - first;
+        let f = match mode {
+            PlaybackMode::Loop if span > 0.0 => {
+                first + (frame_f - first).rem_euclid(span) // [first,last)
+            }
+            PlaybackMode::PingPong if span > 0.0 => {
+                // Forwards in [0, span), backwards in [span, 2 * span).
+                let t = (frame_f - first).rem_euclid(span * 2.0);
+                first + if t > span { span * 2.0 - t } else { t }
+            }
+            _ => frame_f.clamp(first, last),
         };
 
         if f <= first {
@@ -110,7 +171,16 @@ impl<V: Interpolate + Default> Track<V> {
         let b = self.keys[i];
         let t = ((f - a.frame as f32) / (b.frame as f32 - a.frame as f32)).clamp(0.0, 1.0);
 
-        V::interpolate(a.value, b.value, t)
+        match interpolation {
+            Interpolation::Cubic if self.keys.len() >= 4 => {
+                // The end keys are repeated to shape the first and last spans.
+                let before = self.keys[i.saturating_sub(2)];
+                let after = self.keys[(i + 1).min(self.keys.len() - 1)];
+                V::interpolate_cubic(before.value, a.value, b.value, after.value, t)
+            }
+            Interpolation::Step => a.value,
+            _ => V::interpolate(a.value, b.value, t),
+        }
     }
 }
 
@@ -184,6 +254,53 @@ mod tests {
         assert!(approx_v3(v2, Vec3::new(9.0, 0.0, 0.0)));
     }
 
+    #[test]
+    fn sampling_past_the_end_in_each_playback_mode() {
+        let mut t = Track::<Vec3>::default();
+        t.insert(0, Vec3::new(0.0, 0.0, 0.0));
+        t.insert(10, Vec3::new(10.0, 0.0, 0.0));
+
+        let sample = |mode| t.sample_mode(10.5, mode).x;
+        assert!(approx_f(sample(PlaybackMode::Clamp), 10.0));
+        assert!(approx_f(sample(PlaybackMode::Loop), 0.5));
+        assert!(approx_f(sample(PlaybackMode::PingPong), 9.5));
+
+        // Ping-pong turns around again at the start.
+        assert!(approx_f(t.sample_mode(20.5, PlaybackMode::PingPong).x, 0.5));
+        assert!(approx_f(t.sample_mode(-2.0, PlaybackMode::PingPong).x, 2.0));
+
+        // The boolean API matches the equivalent modes.
+        assert!(approx_f(t.sample_sub_frame(10.5, false).x, 10.0));
+        assert!(approx_f(t.sample_sub_frame(10.5, true).x, 0.5));
+    }
+
+    #[test]
+    fn cubic_interpolation_curves_through_the_keys() {
+        let mut t = Track::<f32>::default();
+        for (frame, value) in [(0, 0.0), (10, 0.0), (20, 10.0), (30, 10.0)] {
+            t.insert(frame, value);
+        }
+
+        // The keys around the middle span are symmetric, so the curve and the
+        // line meet halfway but differ elsewhere.
+        assert!(approx_f(t.sample_sub_frame(15.0, false), 5.0));
+        assert!(approx_f(t.sample_cubic(15.0), 5.0));
+        assert!(approx_f(t.sample_sub_frame(12.5, false), 2.5));
+        assert!(approx_f(t.sample_cubic(12.5), 2.03125));
+
+        // The curve still passes through the keys.
+        assert!(approx_f(t.sample_cubic(20.0), 10.0));
+
+        t.interpolation = Interpolation::Step;
+        assert!(approx_f(t.sample_sub_frame(19.0, false), 0.0));
+
+        // Too few keys for a curve.
+        let mut t = Track::<Vec3>::default();
+        t.insert(0, Vec3::ZERO);
+        t.insert(10, Vec3::X * 10.0);
+        assert!(approx_v3(t.sample_cubic(2.5), Vec3::X * 2.5));
+    }
+
     #[test]
     fn last_wins_on_duplicate_inserts() {
         let mut t = Track::<Vec3>::default();
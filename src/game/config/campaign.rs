@@ -72,6 +72,10 @@ pub struct Campaign {
     pub time_of_day: [TimeOfDayEntry; 24],
 
     pub sky_textures: Vec<SkyTexture>,
+
+    /// Color used to clear the world before anything is rendered. When not
+    /// specified, the fog color for the current time of day is used.
+    pub background_color: Option<Vec3>,
 }
 
 impl From<ConfigLines> for Campaign {
@@ -160,6 +164,14 @@ impl From<ConfigLines> for Campaign {
                     }
                 }
 
+                "BACKGROUND_COLOR" => {
+                    // BACKGROUND_COLOR 0.02 0.02 0.10
+                    match line.color(0) {
+                        Ok(color) => campaign.background_color = Some(color),
+                        Err(err) => tracing::warn!("Invalid BACKGROUND_COLOR: {err}"),
+                    }
+                }
+
                 "SKY_TEXTURE_TO_USE" => {
                     // SKY_TEXTURE_TO_USE 0 sky_cloud1.bmp 32000 0.00012 0.00002
                     let sky_texture = SkyTexture {
@@ -121,9 +121,21 @@ impl ObjectType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct ObjectTemplates {
-    pub _templates: HashMap<String, ObjectType>,
+    pub templates: HashMap<String, ObjectType>,
+}
+
+impl ObjectTemplates {
+    /// Look up the [ObjectType] of the template with the given name.
+    pub fn get(&self, name: &str) -> Option<ObjectType> {
+        self.templates.get(name).copied().or_else(|| {
+            self.templates
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, object_type)| *object_type)
+        })
+    }
 }
 
 impl From<ConfigLines> for ObjectTemplates {
@@ -140,8 +152,6 @@ impl From<ConfigLines> for ObjectTemplates {
             }
         }
 
-        ObjectTemplates {
-            _templates: templates,
-        }
+        ObjectTemplates { templates }
     }
 }
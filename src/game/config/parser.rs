@@ -1,3 +1,6 @@
+use glam::{IVec2, Vec2, Vec3};
+use thiserror::Error;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum ConfigToken {
     String(String),
@@ -76,6 +79,71 @@ impl ConfigLine {
     pub fn string(&self, index: usize) -> String {
         self.param::<String>(index)
     }
+
+    /// Read a color starting at param `index`. See [parse_color] for the
+    /// accepted forms.
+    pub fn color(&self, index: usize) -> Result<Vec3, ColorError> {
+        parse_color(self.params().get(index..).unwrap_or_default())
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ColorError {
+    #[error("Missing color component {0}")]
+    MissingComponent(usize),
+
+    #[error("Invalid color component ({0})")]
+    InvalidComponent(String),
+
+    #[error("Color component out of range 0-255 ({0})")]
+    OutOfRange(i32),
+}
+
+/// Parse a color from the start of `params`, normalized to 0.0..=1.0 per
+/// channel. Accepted forms are:
+///
+/// - `#RRGGBB` hex in a single param.
+/// - Three integers in 0..=255, if any of them is above 1.
+/// - Three floats (or 0/1 integers), used as they are.
+///
+/// Params after the color are ignored.
+pub fn parse_color(params: &[ConfigToken]) -> Result<Vec3, ColorError> {
+    if let Some(ConfigToken::String(s)) = params.first()
+        && let Some(hex) = s.strip_prefix('#')
+    {
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorError::InvalidComponent(s.clone()));
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        return Ok(Vec3::new(channel(0) as f32, channel(1) as f32, channel(2) as f32) / 255.0);
+    }
+
+    let mut components = [0.0; 3];
+    let mut bytes = [0; 3];
+    let mut all_integers = true;
+    for (i, component) in components.iter_mut().enumerate() {
+        match params.get(i) {
+            Some(ConfigToken::Float(value)) => {
+                *component = *value;
+                all_integers = false;
+            }
+            Some(ConfigToken::Number(value)) => {
+                *component = *value as f32;
+                bytes[i] = *value;
+            }
+            Some(ConfigToken::String(s)) => return Err(ColorError::InvalidComponent(s.clone())),
+            None => return Err(ColorError::MissingComponent(i)),
+        }
+    }
+
+    if all_integers && bytes.iter().any(|&value| value > 1) {
+        if let Some(&value) = bytes.iter().find(|&&value| !(0..=255).contains(&value)) {
+            return Err(ColorError::OutOfRange(value));
+        }
+        return Ok(Vec3::from(components) / 255.0);
+    }
+
+    Ok(Vec3::from(components))
 }
 
 fn parse_string(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
@@ -156,3 +224,228 @@ impl ConfigLines {
         self.lines.into_iter()
     }
 }
+
+/// A value that can be read from the params of a [ConfigLine], starting at a given index.
+pub trait ConfigValue: Sized {
+    /// Read the value from `line`, starting at param `index`. Missing or mismatched params fall
+    /// back to their defaults, the same as [ConfigLine::param].
+    fn read(line: &ConfigLine, index: usize) -> Self;
+}
+
+macro_rules! single_param_config_value {
+    ($($ty:ty),*) => {
+        $(
+            impl ConfigValue for $ty {
+                fn read(line: &ConfigLine, index: usize) -> Self {
+                    line.param(index)
+                }
+            }
+        )*
+    };
+}
+
+single_param_config_value!(String, i32, f32, bool);
+
+impl ConfigValue for Vec2 {
+    fn read(line: &ConfigLine, index: usize) -> Self {
+        Vec2::new(line.param(index), line.param(index + 1))
+    }
+}
+
+impl ConfigValue for Vec3 {
+    fn read(line: &ConfigLine, index: usize) -> Self {
+        Vec3::new(
+            line.param(index),
+            line.param(index + 1),
+            line.param(index + 2),
+        )
+    }
+}
+
+impl ConfigValue for IVec2 {
+    fn read(line: &ConfigLine, index: usize) -> Self {
+        IVec2::new(line.param(index), line.param(index + 1))
+    }
+}
+
+impl<V: ConfigValue, const N: usize> ConfigValue for [V; N] {
+    fn read(line: &ConfigLine, index: usize) -> Self {
+        std::array::from_fn(|i| V::read(line, index + i))
+    }
+}
+
+/// Writes the params of a matching [ConfigLine] into the target.
+type FieldSetter<T> = Box<dyn Fn(&mut T, &ConfigLine)>;
+
+struct ConfigField<T> {
+    key: &'static str,
+    /// For fields stored as `KEY name value...`, the name in the first param.
+    name: Option<&'static str>,
+    set: FieldSetter<T>,
+}
+
+/// Declarative description of a config file that maps line keys to fields on `T`.
+///
+/// Each entry names a key, the field it writes to and, through [ConfigValue], how the params are
+/// converted. Lines that match no entry are logged as unknown.
+pub struct ConfigSchema<T> {
+    /// Name used when logging unknown keys.
+    name: &'static str,
+    fields: Vec<ConfigField<T>>,
+    ignored: Vec<&'static str>,
+}
+
+impl<T> ConfigSchema<T> {
+    /// An empty schema. `name` identifies the file in warnings about unknown
+    /// lines.
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            fields: Vec::default(),
+            ignored: Vec::default(),
+        }
+    }
+
+    /// Map `KEY value...` lines to the field returned by `field`.
+    pub fn field<V: ConfigValue>(
+        mut self,
+        key: &'static str,
+        field: impl Fn(&mut T) -> &mut V + 'static,
+    ) -> Self {
+        self.fields.push(ConfigField {
+            key,
+            name: None,
+            set: Box::new(move |target, line| *field(target) = V::read(line, 0)),
+        });
+        self
+    }
+
+    /// Map `KEY name value...` lines to the field returned by `field`, e.g.
+    /// `SET map_dx 64`.
+    pub fn named_field<V: ConfigValue>(
+        mut self,
+        key: &'static str,
+        name: &'static str,
+        field: impl Fn(&mut T) -> &mut V + 'static,
+    ) -> Self {
+        self.fields.push(ConfigField {
+            key,
+            name: Some(name),
+            set: Box::new(move |target, line| *field(target) = V::read(line, 1)),
+        });
+        self
+    }
+
+    /// Accept lines with `key` without doing anything with them.
+    pub fn ignore(mut self, key: &'static str) -> Self {
+        self.ignored.push(key);
+        self
+    }
+
+    /// Apply a single line to `target`. Returns false if the line matched nothing in the schema.
+    pub fn apply(&self, target: &mut T, line: &ConfigLine) -> bool {
+        if self.ignored.contains(&line.key.as_str()) {
+            return true;
+        }
+
+        let first_param = match line.params().first() {
+            Some(ConfigToken::String(s)) => Some(s.as_str()),
+            _ => None,
+        };
+
+        let field = self.fields.iter().find(|field| {
+            field.key == line.key && field.name.is_none_or(|name| first_param == Some(name))
+        });
+
+        match field {
+            Some(field) => {
+                (field.set)(target, line);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Apply all `lines` to `target`, warning about lines the schema doesn't describe.
+    pub fn parse_into(&self, target: &mut T, lines: &ConfigLines) {
+        for line in lines.iter() {
+            if !self.apply(target, line) {
+                tracing::warn!(
+                    "Unknown {} line: {} {:?}",
+                    self.name,
+                    line.key,
+                    line.params()
+                );
+            }
+        }
+    }
+
+    /// Parse `lines` into a new `T`, starting from its default values.
+    pub fn parse(&self, lines: &ConfigLines) -> T
+    where
+        T: Default,
+    {
+        let mut target = T::default();
+        self.parse_into(&mut target, lines);
+        target
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Example {
+        size: i32,
+        scale: f32,
+        name: String,
+        offset: Vec2,
+    }
+
+    #[test]
+    fn schema_maps_keys_to_fields() {
+        let schema = ConfigSchema::<Example>::new("Example")
+            .named_field("SET", "size", |e| &mut e.size)
+            .named_field("SET", "scale", |e| &mut e.scale)
+            .field("NAME", |e| &mut e.name)
+            .field("OFFSET", |e| &mut e.offset)
+            .ignore("COMMENT");
+
+        let lines = ConfigLines::parse(
+            "SET size 64\nSET scale 2\nNAME \"some name\"\nOFFSET 1.5 -2\nCOMMENT whatever\n",
+        );
+        let example = schema.parse(&lines);
+
+        assert_eq!(example.size, 64);
+        assert_eq!(example.scale, 2.0);
+        assert_eq!(example.name, "some name");
+        assert_eq!(example.offset, Vec2::new(1.5, -2.0));
+
+        let unknown = ConfigLines::parse("SET unknown 1\nOTHER 2\n");
+        let mut example = Example::default();
+        assert!(unknown.iter().all(|line| !schema.apply(&mut example, line)));
+    }
+
+    #[test]
+    fn colors_parse_from_every_form() {
+        let color = |line: &str| parse_line(line).unwrap().color(0);
+
+        assert_eq!(color("C 0.5 0.25 1.0"), Ok(Vec3::new(0.5, 0.25, 1.0)));
+        assert_eq!(color("C 1 0 0"), Ok(Vec3::X));
+        assert_eq!(color("C 255 0 51"), Ok(Vec3::new(1.0, 0.0, 0.2)));
+        assert_eq!(color("C #FF0033"), Ok(Vec3::new(1.0, 0.0, 0.2)));
+        assert_eq!(color("C #ff0033 1000"), Ok(Vec3::new(1.0, 0.0, 0.2)));
+
+        assert_eq!(
+            color("C 0.5 red 1.0"),
+            Err(ColorError::InvalidComponent("red".to_string()))
+        );
+        assert_eq!(
+            color("C #FF00"),
+            Err(ColorError::InvalidComponent("#FF00".to_string()))
+        );
+        assert_eq!(color("C 0.5 0.5"), Err(ColorError::MissingComponent(2)));
+        assert_eq!(color("C 300 0 0"), Err(ColorError::OutOfRange(300)));
+    }
+}
@@ -6,7 +6,7 @@
 
 use glam::Vec2;
 
-use crate::game::config::parser::ConfigLines;
+use crate::game::config::parser::{ConfigLines, ConfigSchema};
 
 #[derive(Debug)]
 pub struct TerrainMapping {
@@ -89,78 +89,55 @@ impl Default for TerrainMapping {
     }
 }
 
+impl TerrainMapping {
+    /// Describes how each line in terrain_mapping.txt maps to a field.
+    fn schema() -> ConfigSchema<Self> {
+        ConfigSchema::<Self>::new("TerrainMapping")
+            .named_field("SET", "fully_textured_map", |t| &mut t.fully_textured_map)
+            .named_field("SET", "texture_map_base_name", |t| {
+                &mut t.texture_map_base_name
+            })
+            .named_field("SET", "terrain_textures_dx", |t| &mut t.terrain_textures_dx)
+            .named_field("SET", "terrain_textures_dy", |t| &mut t.terrain_textures_dy)
+            .named_field("SET", "map_dx", |t| &mut t.map_dx)
+            .named_field("SET", "map_dy", |t| &mut t.map_dy)
+            .named_field("SET", "water_level", |t| &mut t.water_level)
+            .named_field("SET", "nominal_edge_size", |t| &mut t.nominal_edge_size)
+            .named_field("SET", "map_smooth_passes", |t| &mut t.map_smooth_passes)
+            .named_field("SET", "constraint_passes", |t| &mut t.constraint_passes)
+            .named_field("SET", "altitude_map_height_base", |t| {
+                &mut t.altitude_map_height_base
+            })
+            .named_field("SET", "min_alt_grad", |t| &mut t.min_alt_grad)
+            .named_field("SET", "max_alt_grad", |t| &mut t.max_alt_grad)
+            // Note from terrain_mapping.txt:
+            //
+            // Next, define the textures that we want to be loaded (out of the TextureMaps
+            // directory.)  These TextureMaps should be 128x128 .BMP files. (starts counting
+            // at 1, as error.bmp is always texture index 0)
+            //
+            // LOAD_FULLY_TEXTURED_MAP_SET not_important_but_needs_a_field
+            .ignore("LOAD_FULLY_TEXTURED_MAP_SET")
+            .field("SET_WATER_MODULATE_TEXTURES", |t| {
+                &mut t.water_modulate_textures
+            })
+            .field("SET_W1_MODULATORS", |t| &mut t.w1_modulator)
+            .field("SET_W2_MODULATORS", |t| &mut t.w2_modulator)
+            .field("WATER_TRANS_DEPTH", |t| &mut t.water_trans_depth)
+            .field("WATER_TRANS_HIGH", |t| &mut t.water_trans_high)
+            .field("WATER_TRANS_LOW", |t| &mut t.water_trans_low)
+            .field("SET_WIND_DIRECTION", |t| &mut t.wind_direction)
+            .field("SET_WATER_PERIOD", |t| &mut t.water_period)
+            .field("SET_WATER_WAVELENGTH", |t| &mut t.water_wavelength)
+            .field("SET_WATER_AMPLITUDE", |t| &mut t.water_amplitude)
+            // TODO: Document why these are ignored.
+            .ignore("LOAD_TEXTURE")
+            .ignore("SET_WATER_TEXTURE")
+    }
+}
+
 impl From<ConfigLines> for TerrainMapping {
     fn from(value: ConfigLines) -> Self {
-        let mut terrain_mapping = Self::default();
-
-        for line in value.iter() {
-            match line.key.as_str() {
-                "SET" => match line.string(0).as_str() {
-                    "fully_textured_map" => terrain_mapping.fully_textured_map = line.param(1),
-                    "texture_map_base_name" => {
-                        terrain_mapping.texture_map_base_name = line.param(1)
-                    }
-                    "terrain_textures_dx" => terrain_mapping.terrain_textures_dx = line.param(1),
-                    "terrain_textures_dy" => terrain_mapping.terrain_textures_dy = line.param(1),
-                    "map_dx" => terrain_mapping.map_dx = line.param(1),
-                    "map_dy" => terrain_mapping.map_dy = line.param(1),
-                    "water_level" => terrain_mapping.water_level = line.param(1),
-                    "nominal_edge_size" => terrain_mapping.nominal_edge_size = line.param(1),
-                    "map_smooth_passes" => terrain_mapping.map_smooth_passes = line.param(1),
-                    "constraint_passes" => terrain_mapping.constraint_passes = line.param(1),
-                    "altitude_map_height_base" => {
-                        terrain_mapping.altitude_map_height_base = line.param(1)
-                    }
-                    "min_alt_grad" => terrain_mapping.min_alt_grad = line.param(1),
-                    "max_alt_grad" => terrain_mapping.max_alt_grad = line.param(1),
-
-                    _ => {
-                        tracing::warn!("Unknown TerrainMapping SET key: {}", line.string(0))
-                    }
-                },
-
-                "LOAD_FULLY_TEXTURED_MAP_SET" => {
-                    // Note from terrain_mapping.txt:
-                    //
-                    // Next, define the textures that we want to be loaded (out of the TextureMaps
-                    // directory.)  These TextureMaps should be 128x128 .BMP files. (starts counting
-                    // at 1, as error.bmp is always texture index 0)
-                    //
-                    // LOAD_FULLY_TEXTURED_MAP_SET not_important_but_needs_a_field
-                }
-
-                "SET_WATER_MODULATE_TEXTURES" => {
-                    terrain_mapping.water_modulate_textures = [line.param(0), line.param(1)];
-                }
-
-                "SET_W1_MODULATORS" => {
-                    terrain_mapping.w1_modulator = Vec2::new(line.param(0), line.param(1));
-                }
-
-                "SET_W2_MODULATORS" => {
-                    terrain_mapping.w2_modulator = Vec2::new(line.param(0), line.param(1))
-                }
-
-                "WATER_TRANS_DEPTH" => terrain_mapping.water_trans_depth = line.param(0),
-                "WATER_TRANS_HIGH" => terrain_mapping.water_trans_high = line.param(0),
-                "WATER_TRANS_LOW" => terrain_mapping.water_trans_low = line.param(0),
-
-                "SET_WIND_DIRECTION" => {
-                    terrain_mapping.wind_direction = Vec2::new(line.param(0), line.param(1));
-                }
-
-                "SET_WATER_PERIOD" => terrain_mapping.water_period = line.param(0),
-                "SET_WATER_WAVELENGTH" => terrain_mapping.water_wavelength = line.param(0),
-                "SET_WATER_AMPLITUDE" => terrain_mapping.water_amplitude = line.param(0),
-
-                "LOAD_TEXTURE" | "SET_WATER_TEXTURE" => {
-                    // TODO: Document why these are ignored.
-                }
-
-                _ => tracing::warn!("Unknown TerrainMapping key: {}", line.key),
-            }
-        }
-
-        terrain_mapping
+        Self::schema().parse(&value)
     }
 }
@@ -0,0 +1,136 @@
+use glam::{UVec2, Vec2};
+
+use crate::{
+    engine::{
+        egui_integration::EguiIntegration,
+        renderer::{RenderTarget, TextureDescriptor, TextureUsageIntent},
+    },
+    game::globals,
+};
+
+/// An offscreen render target that is shown inside an egui window instead of
+/// filling the whole surface, leaving room for docked tool windows.
+pub struct EditorViewport {
+    /// Format of the offscreen texture. Matches the surface so the same
+    /// compositor can write to either.
+    format: wgpu::TextureFormat,
+    /// Size of the offscreen texture in physical pixels.
+    size: UVec2,
+    /// The offscreen texture, created on demand after a resize.
+    view: Option<wgpu::TextureView>,
+    /// Id of the texture registered with egui.
+    texture_id: Option<egui::TextureId>,
+    /// Whether the view changed since it was last registered with egui.
+    texture_dirty: bool,
+    /// Where the image was drawn in the window last frame, in physical pixels.
+    image_rect: Option<egui::Rect>,
+    /// Whether the pointer was over the image last frame.
+    hovered: bool,
+}
+
+impl EditorViewport {
+    /// Create an empty viewport. The texture is created once the viewport has
+    /// been laid out. Keep the viewport around while it is hidden, as every
+    /// new viewport registers another egui texture.
+    pub fn new(format: wgpu::TextureFormat) -> Self {
+        Self {
+            format,
+            size: UVec2::ZERO,
+            view: None,
+            texture_id: None,
+            texture_dirty: false,
+            image_rect: None,
+            hovered: false,
+        }
+    }
+
+    /// Whether the pointer is over the viewport image.
+    #[inline]
+    pub fn hovered(&self) -> bool {
+        self.hovered
+    }
+
+    /// The render target to render the world into, or `None` if the viewport
+    /// has not been laid out yet.
+    pub fn render_target(&mut self) -> Option<RenderTarget> {
+        if self.size.x == 0 || self.size.y == 0 {
+            return None;
+        }
+
+        let view = self.view.get_or_insert_with(|| {
+            self.texture_dirty = true;
+            TextureDescriptor::new(
+                "editor_viewport",
+                self.size,
+                self.format,
+                TextureUsageIntent::RenderTarget,
+            )
+            .create(&globals::gpu().device)
+            .create_view(&wgpu::TextureViewDescriptor::default())
+        });
+
+        Some(RenderTarget {
+            view: view.clone(),
+            size: self.size,
+        })
+    }
+
+    /// Register the current texture with egui if it changed.
+    pub fn sync_texture(&mut self, egui_integration: &mut EguiIntegration) {
+        if !self.texture_dirty {
+            return;
+        }
+
+        if let Some(view) = &self.view {
+            self.texture_id = Some(egui_integration.register_native_texture(view, self.texture_id));
+            self.texture_dirty = false;
+        }
+    }
+
+    /// Draw the viewport image filling the available space of `ui`. The
+    /// offscreen texture is resized to match on the next render.
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let available = ui.available_size();
+        let pixels_per_point = ui.ctx().pixels_per_point();
+
+        let size = (Vec2::new(available.x, available.y) * pixels_per_point)
+            .round()
+            .max(Vec2::ONE)
+            .as_uvec2();
+        if size != self.size {
+            self.size = size;
+            self.view = None;
+        }
+
+        let Some(texture_id) = self.texture_id else {
+            self.image_rect = None;
+            self.hovered = false;
+            return;
+        };
+
+        let response =
+            ui.add(egui::Image::new((texture_id, available)).sense(egui::Sense::click_and_drag()));
+
+        self.image_rect = Some(response.rect * pixels_per_point);
+        self.hovered = response.hovered();
+    }
+
+    /// Translate a position in window pixels to a position in the viewport.
+    /// Returns `None` if the position is outside the viewport image.
+    pub fn window_to_viewport(&self, position: UVec2) -> Option<UVec2> {
+        let rect = self.image_rect?;
+        let position = egui::pos2(position.x as f32, position.y as f32);
+        if !rect.contains(position) {
+            return None;
+        }
+
+        let local = (position - rect.min) / rect.size();
+        let viewport = Vec2::new(local.x, local.y) * self.size.as_vec2();
+
+        Some(
+            viewport
+                .as_uvec2()
+                .min(self.size.saturating_sub(UVec2::ONE)),
+        )
+    }
+}
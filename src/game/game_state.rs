@@ -2,12 +2,15 @@ use std::path::PathBuf;
 
 use glam::UVec2;
 
+#[cfg(feature = "egui")]
+use super::editor_viewport::EditorViewport;
 use super::ui::windows::actions::WindowManagerAction;
 use crate::{
     engine::{
         assets::AssetError,
+        frame_pacing::PacingStrategy,
         input::InputEvent,
-        renderer::{RenderContext, RenderTarget, SurfaceDesc},
+        renderer::{Color, RenderContext, RenderTarget, SurfaceDesc},
     },
     game::{
         assets::config::campaign_def::CampaignDefs,
@@ -33,10 +36,21 @@ pub struct GameState {
     surface_format: wgpu::TextureFormat,
     world_layer: Option<WorldLayer>,
     window_renderer: WindowRenderer,
+    /// Replay to play back when a campaign starts.
+    replay: Option<PathBuf>,
+
+    /// The egui window the world is rendered into, created the first time it
+    /// is enabled and kept afterwards so its egui texture is reused.
+    #[cfg(feature = "egui")]
+    editor_viewport: Option<EditorViewport>,
+    /// When set, the world is rendered into the editor viewport instead of the
+    /// surface.
+    #[cfg(feature = "egui")]
+    editor_viewport_enabled: bool,
 }
 
 impl GameState {
-    pub fn new(surface_desc: &SurfaceDesc) -> Result<Self, AssetError> {
+    pub fn new(surface_desc: &SurfaceDesc, replay: Option<PathBuf>) -> Result<Self, AssetError> {
         let campaign_defs = load_config(PathBuf::from("config").join("campaign_defs.txt"))?;
 
         let window_renderer = WindowRenderer::new(surface_desc);
@@ -55,12 +69,20 @@ impl GameState {
             surface_format: surface_desc.format,
             world_layer: None,
             window_renderer,
+            replay,
+            #[cfg(feature = "egui")]
+            editor_viewport: None,
+            #[cfg(feature = "egui")]
+            editor_viewport_enabled: false,
         })
     }
 
     pub fn resize(&mut self, size: UVec2, scale_factor: f32) {
         self.surface_size = size;
-        if let Some(world_layer) = &mut self.world_layer {
+        let editor_viewport_active = self.editor_viewport_active();
+        if let Some(world_layer) = &mut self.world_layer
+            && !editor_viewport_active
+        {
             world_layer.resize(size);
         }
         globals::window_manager().resize(size, scale_factor, &mut self.window_renderer);
@@ -85,10 +107,37 @@ impl GameState {
         }
 
         if !ui_consumed && let Some(world_layer) = &mut self.world_layer {
+            #[cfg(feature = "egui")]
+            if let Some(editor_viewport) = self
+                .editor_viewport
+                .as_ref()
+                .filter(|_| self.editor_viewport_enabled)
+            {
+                // Mouse positions are relative to the viewport image.
+                let event = match *event {
+                    InputEvent::MouseMove(position) => editor_viewport
+                        .window_to_viewport(position)
+                        .map_or(InputEvent::MouseLeave, InputEvent::MouseMove),
+                    ref event => event.clone(),
+                };
+                world_layer.input(&event);
+                return;
+            }
+
             world_layer.input(event);
         }
     }
 
+    /// How the main loop should pace frames. Menus render almost nothing, so
+    /// they sleep between frames instead of spinning a core.
+    pub fn pacing_strategy(&self) -> PacingStrategy {
+        if self.world_layer.is_some() {
+            PacingStrategy::Yield
+        } else {
+            PacingStrategy::IDLE
+        }
+    }
+
     pub fn update(&mut self, delta_time: f32) {
         if let Some(world_layer) = &mut self.world_layer {
             world_layer.update(delta_time);
@@ -97,6 +146,26 @@ impl GameState {
     }
 
     pub fn render(&mut self, render_context: &mut RenderContext, render_target: &RenderTarget) {
+        #[cfg(feature = "egui")]
+        if let Some(editor_viewport) = self
+            .editor_viewport
+            .as_mut()
+            .filter(|_| self.editor_viewport_enabled)
+        {
+            if let Some(world_layer) = &mut self.world_layer
+                && let Some(viewport_target) = editor_viewport.render_target()
+            {
+                world_layer.render(render_context, &viewport_target);
+            }
+            clear_render_target(render_context, render_target);
+            globals::window_manager().render(
+                render_context,
+                render_target,
+                &mut self.window_renderer,
+            );
+            return;
+        }
+
         if let Some(world_layer) = &mut self.world_layer {
             world_layer.render(render_context, render_target);
         } else {
@@ -108,7 +177,106 @@ impl GameState {
 
     #[cfg(feature = "egui")]
     pub fn debug_panel(&mut self, egui: &egui::Context, frame_index: u64) {
-        let _ = (egui, frame_index);
+        let _ = frame_index;
+
+        egui::Window::new("Renderer").show(egui, |ui| {
+            let report = globals::gpu().resource_report();
+
+            egui::Grid::new("resource_report").show(ui, |ui| {
+                for (label, count) in [
+                    ("Buffers", report.buffers),
+                    ("Textures", report.textures),
+                    ("Texture views", report.texture_views),
+                    ("Samplers", report.samplers),
+                    ("Shader modules", report.shader_modules),
+                    ("Render pipelines", report.render_pipelines),
+                    ("Compute pipelines", report.compute_pipelines),
+                    ("Bind groups", report.bind_groups),
+                ] {
+                    ui.label(label);
+                    ui.label(count.to_string());
+                    ui.end_row();
+                }
+
+                ui.label("Allocated");
+                ui.label(match report.allocated_bytes {
+                    Some(bytes) => format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0)),
+                    None => String::from("n/a"),
+                });
+                ui.end_row();
+            });
+
+            ui.separator();
+
+            egui::Grid::new("renderer_capabilities").show(ui, |ui| {
+                for (label, enabled) in globals::gpu().capabilities.list() {
+                    ui.label(label);
+                    ui.label(if enabled { "yes" } else { "no" });
+                    ui.end_row();
+                }
+            });
+        });
+
+        if let Some(world_layer) = &mut self.world_layer {
+            let mut editor_viewport_enabled = self.editor_viewport_enabled;
+
+            egui::Window::new("World").show(egui, |ui| {
+                ui.checkbox(&mut editor_viewport_enabled, "Editor viewport");
+                world_layer.debug_panel(ui);
+            });
+
+            if editor_viewport_enabled != self.editor_viewport_enabled {
+                self.editor_viewport_enabled = editor_viewport_enabled;
+                if editor_viewport_enabled {
+                    let surface_format = self.surface_format;
+                    self.editor_viewport
+                        .get_or_insert_with(|| EditorViewport::new(surface_format));
+                } else {
+                    world_layer.resize(self.surface_size);
+                }
+            }
+        }
+
+        if let Some(editor_viewport) = self
+            .editor_viewport
+            .as_mut()
+            .filter(|_| self.editor_viewport_enabled)
+        {
+            egui::Window::new("Viewport")
+                .default_size([640.0, 480.0])
+                .resizable(true)
+                .show(egui, |ui| editor_viewport.ui(ui));
+        }
+    }
+
+    /// Whether the world is rendered into the editor viewport.
+    pub fn editor_viewport_active(&self) -> bool {
+        #[cfg(feature = "egui")]
+        return self.editor_viewport_enabled;
+        #[cfg(not(feature = "egui"))]
+        return false;
+    }
+
+    /// Whether the pointer is over the editor viewport, in which case pointer
+    /// events should reach the world even though egui claims them.
+    #[cfg(feature = "egui")]
+    pub fn editor_viewport_hovered(&self) -> bool {
+        self.editor_viewport_enabled
+            && self
+                .editor_viewport
+                .as_ref()
+                .is_some_and(EditorViewport::hovered)
+    }
+
+    /// Register the editor viewport texture with egui after it was (re)created.
+    #[cfg(feature = "egui")]
+    pub fn sync_editor_viewport(
+        &mut self,
+        egui_integration: &mut crate::engine::egui_integration::EguiIntegration,
+    ) {
+        if let Some(editor_viewport) = &mut self.editor_viewport {
+            editor_viewport.sync_texture(egui_integration);
+        }
     }
 
     fn start_campaign(&mut self, name: &str) -> Result<(), AssetError> {
@@ -126,7 +294,11 @@ impl GameState {
             ));
         };
 
-        let sim = SimWorld::new(campaign_def)?;
+        let mut sim = SimWorld::new(campaign_def)?;
+        if let Some(path) = &self.replay {
+            tracing::info!("Playing replay {}", path.display());
+            sim.replay_from(path)?;
+        }
 
         globals::window_manager().clear();
         globals::window_manager().set_ui_mode(UiMode::Native, &mut self.window_renderer);
@@ -159,7 +331,7 @@ fn clear_render_target(render_context: &mut RenderContext, render_target: &Rende
                 depth_slice: None,
                 resolve_target: None,
                 ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                    load: wgpu::LoadOp::Clear(Color::BLACK.into()),
                     store: wgpu::StoreOp::Store,
                 },
             })],
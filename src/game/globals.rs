@@ -1,6 +1,6 @@
 use std::{
     cell::{RefCell, RefMut},
-    path::Path,
+    path::{Path, PathBuf},
     sync::OnceLock,
 };
 
@@ -25,16 +25,24 @@ static TEXTURES: OnceLock<Textures> = OnceLock::new();
 static SPRITES: OnceLock<Sprites> = OnceLock::new();
 static WINDOW_MANAGER: OnceLock<SendWrapper<RefCell<WindowManager>>> = OnceLock::new();
 
-pub fn init(root_dir: impl AsRef<Path>, gpu: Gpu) -> bool {
+pub fn init(root_dir: impl AsRef<Path>, overlays: &[PathBuf], gpu: Gpu) -> bool {
     if GPU.set(gpu).is_err() {
         return false;
     }
 
-    if FILE_SYSTEM.set(FileSystem::new(root_dir)).is_err() {
+    let mut file_system = FileSystem::new(root_dir);
+    for overlay in overlays {
+        if let Err(err) = file_system.add_overlay(overlay) {
+            tracing::warn!("Could not add overlay {}: {err}", overlay.display());
+        }
+    }
+    if FILE_SYSTEM.set(file_system).is_err() {
         return false;
     }
 
-    if IMAGES.set(Images::default()).is_err() {
+    let images = Images::default();
+    images.set_fallback(PathBuf::from("textures").join("shared").join("error.bmp"));
+    if IMAGES.set(images).is_err() {
         return false;
     }
 
@@ -1,3 +1,5 @@
+#[cfg(feature = "egui")]
+pub mod asset_browser;
 pub mod assets;
 pub mod config;
 pub mod game_state;
@@ -7,6 +9,8 @@ pub mod sim;
 pub mod ui;
 
 mod common;
+#[cfg(feature = "egui")]
+mod editor_viewport;
 mod world_layer;
 
 pub use common::*;
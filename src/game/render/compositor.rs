@@ -1,13 +1,16 @@
 use crate::{
     engine::{
-        renderer::{RenderContext, RenderTarget},
+        renderer::{FormatDependent, RenderContext, RenderTarget},
         shader_cache::{ShaderCache, ShaderSource},
     },
     game::globals,
 };
 
 pub struct Compositor {
-    pipeline: wgpu::RenderPipeline,
+    module: wgpu::ShaderModule,
+    layout: wgpu::PipelineLayout,
+    /// Rebuilt when the format of the render target changes.
+    pipeline: FormatDependent<wgpu::RenderPipeline>,
 }
 
 impl Compositor {
@@ -16,7 +19,7 @@ impl Compositor {
         gbuffer_bind_group_layout: &wgpu::BindGroupLayout,
         shader_cache: &mut ShaderCache,
     ) -> Self {
-        let module = shader_cache.get_or_create(ShaderSource::Compositor);
+        let module = shader_cache.get_or_create(ShaderSource::Compositor).clone();
 
         let layout =
             globals::gpu()
@@ -27,65 +30,84 @@ impl Compositor {
                     ..Default::default()
                 });
 
-        let pipeline =
-            globals::gpu()
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("compositor_pipeline"),
-                    layout: Some(&layout),
-                    vertex: wgpu::VertexState {
-                        module,
-                        entry_point: Some("vertex"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        buffers: &[],
-                    },
-                    primitive: wgpu::PrimitiveState::default(),
-                    depth_stencil: None,
-                    multisample: wgpu::MultisampleState::default(),
-                    fragment: Some(wgpu::FragmentState {
-                        module,
-                        entry_point: Some("fragment"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        targets: &[Some(wgpu::ColorTargetState {
-                            format: target_format,
-                            blend: None,
-                            write_mask: wgpu::ColorWrites::ALL,
-                        })],
-                    }),
-                    multiview_mask: None,
-                    cache: None,
-                });
+        let pipeline = FormatDependent::new(target_format, |format| {
+            Self::create_pipeline(&module, &layout, format)
+        });
 
-        Self { pipeline }
+        Self {
+            module,
+            layout,
+            pipeline,
+        }
+    }
+
+    fn create_pipeline(
+        module: &wgpu::ShaderModule,
+        layout: &wgpu::PipelineLayout,
+        target_format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        globals::gpu()
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("compositor_pipeline"),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module,
+                    entry_point: Some("vertex"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module,
+                    entry_point: Some("fragment"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(wgpu::ColorTargetState {
+                        format: target_format,
+                        blend: None,
+                        write_mask: wgpu::ColorWrites::ALL,
+                    })],
+                }),
+                multiview_mask: None,
+                cache: None,
+            })
     }
 }
 
 impl Compositor {
     pub fn composite(
-        &self,
+        &mut self,
         render_context: &mut RenderContext,
         render_target: &RenderTarget,
         gbuffer_bind_group: &wgpu::BindGroup,
     ) {
-        let mut render_pass =
-            render_context
-                .encoder
-                .begin_render_pass(&wgpu::RenderPassDescriptor {
-                    label: Some("compositor_render_pass"),
-                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                        view: &render_target.view,
-                        depth_slice: None,
-                        resolve_target: None,
-                        ops: wgpu::Operations {
-                            load: wgpu::LoadOp::Load,
-                            store: wgpu::StoreOp::Store,
-                        },
-                    })],
-                    ..Default::default()
-                });
+        self.pipeline.update(render_target.format(), |format| {
+            Self::create_pipeline(&self.module, &self.layout, format)
+        });
+
+        render_context.debug_group("compositor", |render_context| {
+            let mut render_pass =
+                render_context
+                    .encoder
+                    .begin_render_pass(&wgpu::RenderPassDescriptor {
+                        label: Some("compositor_render_pass"),
+                        color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                            view: &render_target.view,
+                            depth_slice: None,
+                            resolve_target: None,
+                            ops: wgpu::Operations {
+                                load: wgpu::LoadOp::Load,
+                                store: wgpu::StoreOp::Store,
+                            },
+                        })],
+                        ..Default::default()
+                    });
 
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, gbuffer_bind_group, &[]);
-        render_pass.draw(0..3, 0..1);
+            render_pass.set_pipeline(self.pipeline.get());
+            render_pass.set_bind_group(0, gbuffer_bind_group, &[]);
+            render_pass.draw(0..3, 0..1);
+        });
     }
 }
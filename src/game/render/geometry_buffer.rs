@@ -2,7 +2,12 @@
 
 use glam::{UVec2, Vec3};
 
-use crate::{engine::renderer::Gpu, game::globals};
+use crate::{
+    engine::renderer::{
+        Color, ColorTargetDescriptor, DEPTH_FORMAT, Gpu, TextureDescriptor, TextureUsageIntent,
+    },
+    game::globals,
+};
 
 pub struct RenderTarget {
     pub view: wgpu::TextureView,
@@ -10,28 +15,15 @@ pub struct RenderTarget {
 
 impl RenderTarget {
     pub fn new(label: &str, size: UVec2, format: wgpu::TextureFormat) -> Self {
-        let size = wgpu::Extent3d {
-            width: size.x.max(1),
-            height: size.y.max(1),
-            depth_or_array_layers: 1,
-        };
-
         let full_label = format!("render_target_texture_{label}");
 
-        let texture = globals::gpu()
-            .device
-            .create_texture(&wgpu::TextureDescriptor {
-                label: Some(&full_label),
-                size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format,
-                usage: wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING
-                    | wgpu::TextureUsages::COPY_SRC,
-                view_formats: &[],
-            });
+        let texture = TextureDescriptor::new(
+            &full_label,
+            size,
+            format,
+            TextureUsageIntent::RenderTargetReadback,
+        )
+        .create(&globals::gpu().device);
 
         let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
 
@@ -45,54 +37,93 @@ struct Inner {
     pub color: RenderTarget,
     pub oit_accumulation: RenderTarget,
     pub oit_revealage: RenderTarget,
+    pub linear_depth: RenderTarget,
 
     pub bind_group: wgpu::BindGroup,
+    /// Binds `depth` for reading by the linear depth pass.
+    pub depth_bind_group: wgpu::BindGroup,
+    /// Binds `linear_depth` for reading by passes after the depth prepass.
+    pub linear_depth_bind_group: wgpu::BindGroup,
 }
 
 impl Inner {
-    fn new(bind_group_layout: &wgpu::BindGroupLayout, size: UVec2) -> Self {
+    fn new(layouts: &GeometryBufferLayouts, size: UVec2) -> Self {
         tracing::info!("Creating geometry buffers ({}x{})", size.x, size.y);
 
-        let depth = RenderTarget::new("depth", size, GeometryBuffer::DEPTH_FORMAT);
+        let depth = RenderTarget::new("depth", size, DEPTH_FORMAT);
         let color = RenderTarget::new("color", size, GeometryBuffer::COLOR_FORMAT);
         let oit_accumulation =
             RenderTarget::new("color", size, GeometryBuffer::OIT_ACCUMULATION_FORMAT);
         let oit_revealage = RenderTarget::new("color", size, GeometryBuffer::OIT_REVEALAGE_FORMAT);
+        let linear_depth =
+            RenderTarget::new("linear_depth", size, GeometryBuffer::LINEAR_DEPTH_FORMAT);
+
+        let device = &globals::gpu().device;
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("g_buffer_bind_group"),
+            layout: &layouts.gbuffer,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&color.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::TextureView(&oit_accumulation.view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 2,
+                    resource: wgpu::BindingResource::TextureView(&oit_revealage.view),
+                },
+            ],
+        });
 
-        let bind_group = globals::gpu()
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("g_buffer_bind_group"),
-                layout: bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&color.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::TextureView(&oit_accumulation.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 2,
-                        resource: wgpu::BindingResource::TextureView(&oit_revealage.view),
-                    },
-                ],
-            });
+        let depth_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("g_buffer_depth_bind_group"),
+            layout: &layouts.depth,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&depth.view),
+            }],
+        });
+
+        let linear_depth_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("g_buffer_linear_depth_bind_group"),
+            layout: &layouts.linear_depth,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&linear_depth.view),
+            }],
+        });
 
         Self {
             depth,
             color,
             oit_accumulation,
             oit_revealage,
+            linear_depth,
 
             bind_group,
+            depth_bind_group,
+            linear_depth_bind_group,
         }
     }
 }
 
+/// The bind group layouts a [GeometryBuffer] creates its bind groups with.
+#[derive(Clone)]
+pub struct GeometryBufferLayouts {
+    /// Layout of the color and OIT targets read by the compositor.
+    pub gbuffer: wgpu::BindGroupLayout,
+    /// Layout of the depth attachment read by the linear depth pass.
+    pub depth: wgpu::BindGroupLayout,
+    /// Layout of the linear depth target.
+    pub linear_depth: wgpu::BindGroupLayout,
+}
+
 pub struct GeometryBuffer {
-    bind_group_layout: wgpu::BindGroupLayout,
+    layouts: GeometryBufferLayouts,
 
     /// The current size of the buffers.
     pub size: UVec2,
@@ -101,10 +132,14 @@ pub struct GeometryBuffer {
 }
 
 impl GeometryBuffer {
-    pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
     pub const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
     pub const OIT_ACCUMULATION_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba16Float;
     pub const OIT_REVEALAGE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R16Float;
+    pub const LINEAR_DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::R32Float;
+
+    /// Linear depth of pixels not covered by the depth prepass. Far enough
+    /// that depth based fades have no effect when the prepass is disabled.
+    const FAR_LINEAR_DEPTH: f64 = f32::MAX as f64;
 
     pub fn create_bind_group_layout() -> wgpu::BindGroupLayout {
         globals::gpu()
@@ -149,22 +184,30 @@ impl GeometryBuffer {
             })
     }
 
-    pub fn new(bind_group_layout: wgpu::BindGroupLayout, size: UVec2) -> Self {
-        let inner = Inner::new(&bind_group_layout, size);
+    pub fn new(layouts: GeometryBufferLayouts, size: UVec2) -> Self {
+        let inner = Inner::new(&layouts, size);
 
         Self {
-            bind_group_layout,
+            layouts,
             size,
             inner,
         }
     }
 
     pub fn resize(&mut self, size: UVec2) {
-        self.inner = Inner::new(&self.bind_group_layout, size);
+        self.inner = Inner::new(&self.layouts, size);
         self.size = size;
     }
 
+    /// Clear all targets. `clear_color` is linear, see [Color].
     pub fn clear(&self, encoder: &mut wgpu::CommandEncoder, clear_color: Vec3) {
+        let clear_color = Color::new(
+            clear_color.x as f64,
+            clear_color.y as f64,
+            clear_color.z as f64,
+            1.0,
+        );
+
         encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("geometry_buffer_clear"),
             color_attachments: &[
@@ -173,12 +216,7 @@ impl GeometryBuffer {
                     depth_slice: None,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(wgpu::Color {
-                            r: clear_color.x as f64,
-                            g: clear_color.y as f64,
-                            b: clear_color.z as f64,
-                            a: 1.0,
-                        }),
+                        load: wgpu::LoadOp::Clear(clear_color.into()),
                         store: wgpu::StoreOp::Store,
                     },
                 }),
@@ -210,15 +248,22 @@ impl GeometryBuffer {
                         store: wgpu::StoreOp::Store,
                     },
                 }),
-            ],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.inner.depth.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(1.0),
-                    store: wgpu::StoreOp::Store,
+                Some(wgpu::RenderPassColorAttachment {
+                    view: &self.inner.linear_depth.view,
+                    depth_slice: None,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color {
+                            r: Self::FAR_LINEAR_DEPTH,
+                            g: 0.0,
+                            b: 0.0,
+                            a: 0.0,
+                        }),
+                        store: wgpu::StoreOp::Store,
+                    },
                 }),
-                stencil_ops: None,
-            }),
+            ],
+            depth_stencil_attachment: Some(self.depth_attachment(wgpu::LoadOp::Clear(1.0))),
             ..Default::default()
         });
     }
@@ -228,6 +273,20 @@ impl GeometryBuffer {
         &self.inner.bind_group
     }
 
+    /// Bind group of the depth attachment, for passes that don't write depth.
+    #[inline]
+    pub fn depth_bind_group(&self) -> &wgpu::BindGroup {
+        &self.inner.depth_bind_group
+    }
+
+    /// Bind group of the linear depth target. Holds the distance along the
+    /// camera's forward axis to the closest opaque surface once the depth
+    /// prepass ran, and a very large value otherwise.
+    #[inline]
+    pub fn linear_depth_bind_group(&self) -> &wgpu::BindGroup {
+        &self.inner.linear_depth_bind_group
+    }
+
     fn opaque_attachments(&self) -> [Option<wgpu::RenderPassColorAttachment<'_>>; 1] {
         [Some(wgpu::RenderPassColorAttachment {
             view: &self.inner.color.view,
@@ -241,30 +300,17 @@ impl GeometryBuffer {
     }
 
     pub fn opaque_targets() -> &'static [Option<wgpu::ColorTargetState>] {
-        &[Some(wgpu::ColorTargetState {
-            format: Self::COLOR_FORMAT,
-            blend: None,
-            write_mask: wgpu::ColorWrites::ALL,
-        })]
+        const TARGETS: &[Option<wgpu::ColorTargetState>] = &[Some(
+            ColorTargetDescriptor::opaque(GeometryBuffer::COLOR_FORMAT).state(),
+        )];
+        TARGETS
     }
 
-    fn additive_targets() -> &'static [Option<wgpu::ColorTargetState>] {
-        &[Some(wgpu::ColorTargetState {
-            format: Self::COLOR_FORMAT,
-            blend: Some(wgpu::BlendState {
-                color: wgpu::BlendComponent {
-                    src_factor: wgpu::BlendFactor::One,
-                    dst_factor: wgpu::BlendFactor::One,
-                    operation: wgpu::BlendOperation::Add,
-                },
-                alpha: wgpu::BlendComponent {
-                    src_factor: wgpu::BlendFactor::One,
-                    dst_factor: wgpu::BlendFactor::One,
-                    operation: wgpu::BlendOperation::Add,
-                },
-            }),
-            write_mask: wgpu::ColorWrites::ALL,
-        })]
+    pub fn additive_targets() -> &'static [Option<wgpu::ColorTargetState>] {
+        const TARGETS: &[Option<wgpu::ColorTargetState>] = &[Some(
+            ColorTargetDescriptor::additive(GeometryBuffer::COLOR_FORMAT).state(),
+        )];
+        TARGETS
     }
 
     fn alpha_attachments<'a>(&'a self) -> [Option<wgpu::RenderPassColorAttachment<'a>>; 2] {
@@ -291,67 +337,79 @@ impl GeometryBuffer {
     }
 
     pub fn alpha_targets() -> &'static [Option<wgpu::ColorTargetState>] {
-        &[
-            Some(wgpu::ColorTargetState {
-                format: Self::OIT_ACCUMULATION_FORMAT,
-                blend: Some(wgpu::BlendState {
-                    color: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::One,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::One,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                }),
-                write_mask: wgpu::ColorWrites::ALL,
-            }),
-            Some(wgpu::ColorTargetState {
-                format: Self::OIT_REVEALAGE_FORMAT,
-                blend: Some(wgpu::BlendState {
-                    // = 0 * src + (1 - src_alpha) * dst  ==> multiplicative by (1 - α)
-                    color: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::Zero,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                    alpha: wgpu::BlendComponent {
-                        src_factor: wgpu::BlendFactor::Zero,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                }),
-                write_mask: wgpu::ColorWrites::ALL,
-            }),
-        ]
+        const TARGETS: &[Option<wgpu::ColorTargetState>] = &[
+            Some(ColorTargetDescriptor::additive(GeometryBuffer::OIT_ACCUMULATION_FORMAT).state()),
+            Some(
+                ColorTargetDescriptor::opaque(GeometryBuffer::OIT_REVEALAGE_FORMAT)
+                    .with_blend(Some(wgpu::BlendState {
+                        // = 0 * src + (1 - src_alpha) * dst  ==> multiplicative by (1 - α)
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::Zero,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }))
+                    .state(),
+            ),
+        ];
+        TARGETS
     }
+}
 
-    fn depth_stencil_state(
-        depth_compare: wgpu::CompareFunction,
-        depth_write_enabled: bool,
-    ) -> wgpu::DepthStencilState {
-        wgpu::DepthStencilState {
-            format: Self::DEPTH_FORMAT,
-            depth_write_enabled: Some(depth_write_enabled),
-            depth_compare: Some(depth_compare),
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
+impl GeometryBuffer {
+    /// The depth target, attached with `load` and stored at the end of the
+    /// pass. Pipelines get their depth format from [DepthStencilDescriptor],
+    /// so the target has to match it.
+    ///
+    /// [DepthStencilDescriptor]: crate::engine::renderer::DepthStencilDescriptor
+    fn depth_attachment(
+        &self,
+        load: wgpu::LoadOp<f32>,
+    ) -> wgpu::RenderPassDepthStencilAttachment<'_> {
+        debug_assert_eq!(
+            self.inner.depth.view.texture().format(),
+            DEPTH_FORMAT,
+            "depth attachment does not match the pipeline depth format"
+        );
+
+        wgpu::RenderPassDepthStencilAttachment {
+            view: &self.inner.depth.view,
+            depth_ops: Some(wgpu::Operations {
+                load,
+                store: wgpu::StoreOp::Store,
+            }),
+            stencil_ops: None,
         }
     }
-}
 
-impl GeometryBuffer {
-    pub fn begin_opaque_render_pass<'rp>(
+    /// Begin a pass that only writes the depth attachment.
+    pub fn begin_depth_prepass<'rp>(
         &self,
         encoder: &'rp mut wgpu::CommandEncoder,
         label: &str,
     ) -> wgpu::RenderPass<'rp> {
         encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some(label),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(self.depth_attachment(wgpu::LoadOp::Load)),
+            ..Default::default()
+        })
+    }
+
+    /// Begin a pass writing the linear depth target.
+    pub fn begin_linear_depth_render_pass<'rp>(
+        &self,
+        encoder: &'rp mut wgpu::CommandEncoder,
+    ) -> wgpu::RenderPass<'rp> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("linear_depth_render_pass"),
             color_attachments: &[Some(wgpu::RenderPassColorAttachment {
-                view: &self.inner.color.view,
+                view: &self.inner.linear_depth.view,
                 depth_slice: None,
                 resolve_target: None,
                 ops: wgpu::Operations {
@@ -359,14 +417,27 @@ impl GeometryBuffer {
                     store: wgpu::StoreOp::Store,
                 },
             })],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.inner.depth.view,
-                depth_ops: Some(wgpu::Operations {
+            ..Default::default()
+        })
+    }
+
+    pub fn begin_opaque_render_pass<'rp>(
+        &self,
+        encoder: &'rp mut wgpu::CommandEncoder,
+        label: &str,
+    ) -> wgpu::RenderPass<'rp> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &self.inner.color.view,
+                depth_slice: None,
+                resolve_target: None,
+                ops: wgpu::Operations {
                     load: wgpu::LoadOp::Load,
                     store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: None,
-            }),
+                },
+            })],
+            depth_stencil_attachment: Some(self.depth_attachment(wgpu::LoadOp::Load)),
             ..Default::default()
         })
     }
@@ -398,14 +469,7 @@ impl GeometryBuffer {
                     },
                 }),
             ],
-            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
-                view: &self.inner.depth.view,
-                depth_ops: Some(wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: wgpu::StoreOp::Store,
-                }),
-                stencil_ops: None,
-            }),
+            depth_stencil_attachment: Some(self.depth_attachment(wgpu::LoadOp::Load)),
             ..Default::default()
         })
     }
@@ -0,0 +1,87 @@
+use std::{
+    collections::BTreeSet,
+    sync::{
+        Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use glam::Mat4;
+
+/// Sources that uploaded a non-finite matrix and have not uploaded a finite
+/// one since.
+static REPORTED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+/// Whether [REPORTED] holds any sources, so finite matrices can skip the lock.
+static ANY_REPORTED: AtomicBool = AtomicBool::new(false);
+
+/// Catch matrices with NaN or infinite components before they are uploaded.
+/// A single bad matrix can turn the whole frame black, so in debug builds the
+/// matrix is reported along with `source` and replaced by the identity. Each
+/// source is reported once until its matrix is finite again. Release builds
+/// pass the matrix through unchecked.
+pub fn checked_matrix(matrix: Mat4, source: std::fmt::Arguments) -> Mat4 {
+    if !cfg!(debug_assertions) {
+        return matrix;
+    }
+
+    if matrix.is_finite() {
+        clear_reported(source);
+        return matrix;
+    }
+
+    if mark_reported(source) {
+        tracing::error!("Non-finite matrix in {source}, uploading identity instead: {matrix:?}");
+    }
+    Mat4::IDENTITY
+}
+
+/// Remember that `source` uploaded a non-finite matrix. Returns `false` if it
+/// was already reported.
+fn mark_reported(source: std::fmt::Arguments) -> bool {
+    let mut reported = REPORTED.lock().unwrap();
+    let inserted = reported.insert(source.to_string());
+    ANY_REPORTED.store(true, Ordering::Relaxed);
+    inserted
+}
+
+/// Forget about `source`, so it is reported again the next time its matrix is
+/// not finite.
+fn clear_reported(source: std::fmt::Arguments) {
+    if !ANY_REPORTED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut reported = REPORTED.lock().unwrap();
+    reported.remove(&source.to_string());
+    ANY_REPORTED.store(!reported.is_empty(), Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_matrices_are_replaced_in_debug_builds() {
+        let matrix = Mat4::from_translation(glam::Vec3::new(1.0, 2.0, 3.0));
+        assert_eq!(checked_matrix(matrix, format_args!("test")), matrix);
+
+        let broken = Mat4::from_translation(glam::Vec3::new(f32::NAN, 0.0, 0.0));
+        let checked = checked_matrix(broken, format_args!("test"));
+        if cfg!(debug_assertions) {
+            assert_eq!(checked, Mat4::IDENTITY);
+        } else {
+            assert!(!checked.is_finite());
+        }
+    }
+
+    #[test]
+    fn sources_are_reported_once_until_finite_again() {
+        assert!(mark_reported(format_args!("report once {}", 1)));
+        assert!(!mark_reported(format_args!("report once {}", 1)));
+        assert!(mark_reported(format_args!("report once {}", 2)));
+
+        clear_reported(format_args!("report once {}", 1));
+        assert!(mark_reported(format_args!("report once {}", 1)));
+        assert!(!mark_reported(format_args!("report once {}", 2)));
+    }
+}
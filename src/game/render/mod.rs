@@ -1,6 +1,8 @@
 pub mod compositor;
 pub mod geometry_buffer;
+pub mod matrix_guard;
 pub mod per_frame;
+pub mod screenshot;
 pub mod textures;
 pub mod uniform_buffer;
 pub mod world;
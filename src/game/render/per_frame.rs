@@ -1,21 +1,72 @@
-const FRAME_COUNT: usize = 3;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Smallest amount of frames that can be in flight.
+pub const MIN_FRAME_COUNT: usize = 2;
+/// Largest amount of frames that can be in flight.
+pub const MAX_FRAME_COUNT: usize = 4;
+
+/// Amount of copies new [PerFrame]s are created with.
+static FRAME_COUNT: AtomicUsize = AtomicUsize::new(3);
+
+/// The amount of copies new [PerFrame]s are created with.
+pub fn frame_count() -> usize {
+    FRAME_COUNT.load(Ordering::Relaxed)
+}
+
+/// Set the amount of copies new [PerFrame]s are created with, clamped to
+/// [MIN_FRAME_COUNT]..=[MAX_FRAME_COUNT]. Existing [PerFrame]s keep their
+/// count, so they have to be recreated to pick up the change. Returns the
+/// count that was applied.
+pub fn set_frame_count(count: usize) -> usize {
+    let count = count.clamp(MIN_FRAME_COUNT, MAX_FRAME_COUNT);
+    FRAME_COUNT.store(count, Ordering::Relaxed);
+    count
+}
+
+/// The frame count that suits a present mode. Vsynced modes can queue more
+/// frames without stalling, while the low latency modes only need to overlap
+/// the CPU and GPU by a single frame.
+pub fn frame_count_for_present_mode(present_mode: wgpu::PresentMode) -> usize {
+    match present_mode {
+        wgpu::PresentMode::Immediate | wgpu::PresentMode::AutoNoVsync => MIN_FRAME_COUNT,
+        wgpu::PresentMode::AutoVsync
+        | wgpu::PresentMode::Fifo
+        | wgpu::PresentMode::FifoRelaxed
+        | wgpu::PresentMode::Mailbox => 3,
+    }
+}
 
 pub struct PerFrame<T> {
-    data: [T; FRAME_COUNT],
+    data: Vec<T>,
     index: usize,
 }
 
 impl<T> PerFrame<T> {
+    /// Create a copy for each of the current [frame_count] frames.
     pub fn new<F>(f: F) -> Self
     where
         F: Fn(usize) -> T,
     {
+        Self::with_count(frame_count(), f)
+    }
+
+    /// Create `count` copies, clamped to [MIN_FRAME_COUNT]..=[MAX_FRAME_COUNT].
+    pub fn with_count<F>(count: usize, f: F) -> Self
+    where
+        F: Fn(usize) -> T,
+    {
+        let count = count.clamp(MIN_FRAME_COUNT, MAX_FRAME_COUNT);
         Self {
-            data: std::array::from_fn(f),
+            data: (0..count).map(f).collect(),
             index: 0,
         }
     }
 
+    /// The amount of copies that are cycled through.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
     pub fn current(&self) -> &T {
         &self.data[self.index]
     }
@@ -25,7 +76,29 @@ impl<T> PerFrame<T> {
     }
 
     pub fn advance(&mut self) -> &mut T {
-        self.index = (self.index + 1) % FRAME_COUNT;
+        self.index = (self.index + 1) % self.data.len();
         self.current_mut()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn advance_wraps_at_the_frame_count() {
+        for count in MIN_FRAME_COUNT..=MAX_FRAME_COUNT {
+            let mut per_frame = PerFrame::with_count(count, |index| index);
+            assert_eq!(per_frame.len(), count);
+
+            for frame in 1..=count * 2 {
+                assert_eq!(*per_frame.advance(), frame % count);
+            }
+        }
+
+        assert_eq!(
+            PerFrame::with_count(10, |index| index).len(),
+            MAX_FRAME_COUNT
+        );
+    }
+}
@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use glam::UVec2;
+
+use crate::{
+    engine::{
+        assets::AssetError,
+        renderer::{RenderTarget, TextureDescriptor, TextureUsageIntent, ViewportRect},
+    },
+    game::globals,
+};
+
+/// Averages a number of rendered frames into a single image. When every frame
+/// is rendered with a different projection jitter, the result is a
+/// supersampled screenshot.
+pub struct AccumulatedScreenshot {
+    /// Where the image is written once all frames are accumulated.
+    path: PathBuf,
+    /// Number of frames to average.
+    frame_count: u32,
+    /// Number of frames added to `sum` so far.
+    frames_accumulated: u32,
+    texture: wgpu::Texture,
+    target: RenderTarget,
+    /// Per channel sum of every accumulated frame.
+    sum: Vec<u32>,
+    /// Whether `target` holds a frame that was not accumulated yet.
+    pending: bool,
+}
+
+impl AccumulatedScreenshot {
+    /// Start a screenshot of `size` pixels averaged over `frame_count` frames
+    /// rendered into a target of `format`.
+    pub fn new(path: PathBuf, frame_count: u32, size: UVec2, format: wgpu::TextureFormat) -> Self {
+        let size = size.max(UVec2::ONE);
+        let texture = TextureDescriptor::new(
+            "accumulated_screenshot",
+            size,
+            format,
+            TextureUsageIntent::RenderTargetReadback,
+        )
+        .create(&globals::gpu().device);
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            path,
+            frame_count: frame_count.max(1),
+            frames_accumulated: 0,
+            texture,
+            target: RenderTarget { view, size },
+            sum: vec![0; size.x as usize * size.y as usize * 4],
+            pending: false,
+        }
+    }
+
+    /// The target to render the next frame into, or `None` once enough frames
+    /// were rendered. Call [Self::frame_rendered] after rendering into it.
+    pub fn render_target(&self) -> Option<&RenderTarget> {
+        (!self.pending && self.frames_accumulated < self.frame_count).then_some(&self.target)
+    }
+
+    /// Mark the target as holding a new frame. It is read back by
+    /// [Self::accumulate] once the frame was submitted.
+    pub fn frame_rendered(&mut self) {
+        self.pending = true;
+    }
+
+    /// Add the frame rendered last to the average. Has to be called after the
+    /// frame was submitted, as it waits for the GPU. Returns `true` once all
+    /// frames were accumulated.
+    pub fn accumulate(&mut self) -> bool {
+        if self.pending {
+            let image = globals::gpu().read_color_target(
+                &self.texture,
+                ViewportRect::new(UVec2::ZERO, self.target.size),
+            );
+            accumulate(&mut self.sum, image.data.as_raw());
+            self.frames_accumulated += 1;
+            self.pending = false;
+        }
+
+        self.frames_accumulated >= self.frame_count
+    }
+
+    /// Write the average of the accumulated frames to the screenshot path.
+    pub fn save(&self) -> Result<(), AssetError> {
+        let size = self.target.size;
+        let pixels = average(&self.sum, self.frames_accumulated);
+        let image = image::RgbaImage::from_raw(size.x, size.y, pixels)
+            .expect("Accumulated pixels do not match the screenshot size!");
+
+        image
+            .save_with_format(&self.path, image::ImageFormat::Bmp)
+            .map_err(|err| AssetError::custom(&self.path, err.to_string()))
+    }
+}
+
+/// Add the 8-bit channels of `pixels` to `sum`.
+fn accumulate(sum: &mut [u32], pixels: &[u8]) {
+    debug_assert_eq!(sum.len(), pixels.len());
+
+    for (sum, &channel) in sum.iter_mut().zip(pixels) {
+        *sum += channel as u32;
+    }
+}
+
+/// The rounded average of `frame_count` accumulated frames.
+fn average(sum: &[u32], frame_count: u32) -> Vec<u8> {
+    let frame_count = frame_count.max(1);
+    sum.iter()
+        .map(|&sum| ((sum + frame_count / 2) / frame_count).min(255) as u8)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_average_per_channel() {
+        let mut sum = vec![0; 4];
+        accumulate(&mut sum, &[0, 255, 10, 255]);
+        accumulate(&mut sum, &[255, 255, 20, 255]);
+        accumulate(&mut sum, &[0, 255, 31, 255]);
+
+        assert_eq!(average(&sum, 3), [85, 255, 20, 255]);
+        assert_eq!(average(&[0; 4], 0), [0; 4]);
+    }
+}
@@ -4,7 +4,7 @@ use glam::UVec2;
 
 use crate::{
     engine::{
-        renderer::Gpu,
+        renderer::{Gpu, TextureDescriptor, TextureUsageIntent},
         storage::{Handle, StorageMap},
     },
     game::{
@@ -58,7 +58,7 @@ impl Textures {
             textures.insert(
                 image_handle,
                 Arc::new(TextureData {
-                    _image: image_handle,
+                    image: image_handle,
                     size: image.size,
                     blend_mode: image.blend_mode,
                     view,
@@ -70,26 +70,45 @@ impl Textures {
     }
 
     fn create_texture_internal(&self, image: &image::RgbaImage) -> wgpu::TextureView {
-        let Gpu { device, queue } = globals::gpu();
+        let Gpu {
+            device,
+            queue,
+            max_texture_size,
+            ..
+        } = globals::gpu();
+
+        let downscaled = downscale_to_fit(image, *max_texture_size);
+        if let Some(downscaled) = &downscaled {
+            tracing::warn!(
+                "Downscaling {}x{} texture to {}x{} to fit the maximum texture size of {max_texture_size}.",
+                image.width(),
+                image.height(),
+                downscaled.width(),
+                downscaled.height(),
+            );
+        }
+        let image = downscaled.as_ref().unwrap_or(image);
 
         let (width, height) = (image.width(), image.height());
 
-        let size = wgpu::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        };
+        // Images are converted to RGBA8 when decoded, whatever their source layout.
+        const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+        let bytes_per_pixel = FORMAT
+            .block_copy_size(None)
+            .expect("Texture format should be copyable.");
+        debug_assert_eq!(
+            image.as_raw().len(),
+            (width * height * bytes_per_pixel) as usize
+        );
 
-        let texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: None,
-            size,
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Rgba8UnormSrgb,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-            view_formats: &[],
-        });
+        let descriptor = TextureDescriptor::new(
+            "texture",
+            UVec2::new(width, height),
+            FORMAT,
+            TextureUsageIntent::Sampled,
+        );
+        let size = descriptor.extent();
+        let texture = descriptor.create(device);
 
         queue.write_texture(
             wgpu::TexelCopyTextureInfo {
@@ -101,7 +120,7 @@ impl Textures {
             image,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
-                bytes_per_row: Some(width * 4),
+                bytes_per_row: Some(width * bytes_per_pixel),
                 rows_per_image: Some(height),
             },
             size,
@@ -113,11 +132,68 @@ impl Textures {
 
 pub struct TextureData {
     /// The image used to create this texture.
-    pub _image: Handle<Image>,
-    /// Size of the full texture in pixels.
+    pub image: Handle<Image>,
+    /// Size of the source image in pixels. The GPU texture may be smaller if
+    /// the image was downscaled to fit the maximum texture size.
     pub size: UVec2,
     /// Blend mode inherited from the source image.
     pub blend_mode: BlendMode,
     /// The [wgpu::TextureView] used to access this texture during rendering.
     pub view: wgpu::TextureView,
 }
+
+/// Shrink `image` with a box filter so neither side exceeds `max_size`,
+/// keeping the aspect ratio. Returns `None` if the image already fits.
+fn downscale_to_fit(image: &image::RgbaImage, max_size: u32) -> Option<image::RgbaImage> {
+    let (width, height) = image.dimensions();
+    let largest = width.max(height);
+    if largest <= max_size {
+        return None;
+    }
+
+    let max_size = max_size.max(1) as u64;
+    let scaled = |side: u32| ((side as u64 * max_size / largest as u64) as u32).max(1);
+    let (new_width, new_height) = (scaled(width), scaled(height));
+
+    // The range of source pixels covered by destination pixel `index`.
+    let footprint = |index: u32, source: u32, destination: u32| {
+        let start = (index as u64 * source as u64 / destination as u64) as u32;
+        let end = ((index as u64 + 1) * source as u64 / destination as u64) as u32;
+        start..end.max(start + 1)
+    };
+
+    Some(image::RgbaImage::from_fn(new_width, new_height, |x, y| {
+        let mut sum = [0_u32; 4];
+        let mut count = 0;
+        for source_y in footprint(y, height, new_height) {
+            for source_x in footprint(x, width, new_width) {
+                let pixel = image.get_pixel(source_x, source_y);
+                for (total, channel) in sum.iter_mut().zip(pixel.0) {
+                    *total += channel as u32;
+                }
+                count += 1;
+            }
+        }
+        image::Rgba(sum.map(|total| ((total + count / 2) / count) as u8))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn oversized_images_are_box_filtered_to_fit() {
+        // Alternating black and white columns.
+        let image = image::RgbaImage::from_fn(256, 64, |x, _| {
+            let value = if x % 2 == 0 { 0 } else { 255 };
+            image::Rgba([value, value, value, 255])
+        });
+
+        let downscaled = downscale_to_fit(&image, 128).expect("image should be downscaled");
+        assert_eq!(downscaled.dimensions(), (128, 32));
+        assert_eq!(downscaled.get_pixel(10, 10).0, [128, 128, 128, 255]);
+
+        assert!(downscale_to_fit(&downscaled, 128).is_none());
+    }
+}
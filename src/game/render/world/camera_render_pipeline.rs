@@ -1,10 +1,14 @@
 use crate::{
-    engine::renderer::RenderContext,
+    engine::renderer::{BindGroupLayoutEntries, RenderContext},
     game::render::{
         geometry_buffer::GeometryBuffer,
+        matrix_guard::checked_matrix,
         world::{
-            render_bindings::RenderBindings, render_layouts::RenderLayout,
-            render_pipeline::RenderPipeline, world_render_snapshot::WorldRenderSnapshot,
+            render_bindings::RenderBindings,
+            render_graph::{PassResources, RenderResource},
+            render_layouts::RenderLayout,
+            render_pipeline::RenderPipeline,
+            world_render_snapshot::WorldRenderSnapshot,
         },
     },
 };
@@ -16,28 +20,31 @@ impl RenderLayout for CameraEnvironmentLayout {
         "camera_bind_group_layout"
     }
 
-    fn entries() -> &'static [wgpu::BindGroupLayoutEntry] {
-        const ENTRIES: &[wgpu::BindGroupLayoutEntry] = &[wgpu::BindGroupLayoutEntry {
-            binding: 0,
-            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
-            ty: wgpu::BindingType::Buffer {
-                ty: wgpu::BufferBindingType::Uniform,
-                has_dynamic_offset: false,
-                min_binding_size: None,
-            },
-            count: None,
-        }];
-
-        ENTRIES
+    fn entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+        // Read by vertex shaders for the matrices and by fragment shaders for
+        // lighting and fog.
+        BindGroupLayoutEntries::new().uniform(0).build()
     }
 }
 
 pub struct CameraRenderPipeline;
 
 impl RenderPipeline for CameraRenderPipeline {
+    fn name(&self) -> &'static str {
+        "camera"
+    }
+
+    fn resources(&self) -> PassResources {
+        PassResources {
+            reads: &[],
+            writes: &[RenderResource::CameraEnvironment],
+        }
+    }
+
     fn prepare(&mut self, bindings: &mut RenderBindings, snapshot: &WorldRenderSnapshot) {
         let data = gpu::CameraEnvironment {
-            proj_view: snapshot.camera.proj_view.to_cols_array_2d(),
+            proj_view: checked_matrix(snapshot.camera.proj_view, format_args!("camera proj_view"))
+                .to_cols_array_2d(),
             frustum: snapshot
                 .camera
                 .frustum
@@ -1,8 +1,11 @@
+use ahash::HashMap;
+use wgpu::util::DeviceExt;
+
 use crate::{
     engine::{
-        gizmos::GizmoVertex,
+        gizmos::{GizmoVertex, StaticGizmoHandle},
         growing_buffer::GrowingBuffer,
-        renderer::RenderContext,
+        renderer::{AsVertexLayout, DepthStencilDescriptor, RenderContext},
         shader_cache::{ShaderCache, ShaderSource},
     },
     game::{
@@ -11,9 +14,12 @@ use crate::{
             geometry_buffer::GeometryBuffer,
             per_frame::PerFrame,
             world::{
-                camera_render_pipeline::CameraEnvironmentLayout, render_bindings::RenderBindings,
-                render_layouts::RenderLayouts, render_pipeline::RenderPipeline,
-                world_render_snapshot::WorldRenderSnapshot,
+                camera_render_pipeline::CameraEnvironmentLayout,
+                render_bindings::RenderBindings,
+                render_graph::{PassResources, RenderResource},
+                render_layouts::RenderLayouts,
+                render_pipeline::RenderPipeline,
+                world_render_snapshot::{Gizmos, WorldRenderSnapshot},
             },
         },
     },
@@ -21,8 +27,14 @@ use crate::{
 
 pub struct GizmoRenderPipeline {
     pipeline: wgpu::RenderPipeline,
+    /// Same as `pipeline`, but shades lines darker the further they are from the camera.
+    depth_tinted_pipeline: wgpu::RenderPipeline,
 
     instances_buffer: PerFrame<GrowingBuffer<GizmoVertex>>,
+
+    /// Buffers of the static gizmos, uploaded the first time they are drawn
+    /// and kept until the static gizmo is removed.
+    static_buffers: HashMap<StaticGizmoHandle, (wgpu::Buffer, u32)>,
 }
 
 impl GizmoRenderPipeline {
@@ -38,46 +50,9 @@ impl GizmoRenderPipeline {
                     ..Default::default()
                 });
 
-        let pipeline =
-            globals::gpu()
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("gizmos_render_pipeline"),
-                    layout: Some(&layout),
-                    vertex: wgpu::VertexState {
-                        module,
-                        entry_point: Some("vertex_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        buffers: &[wgpu::VertexBufferLayout {
-                            array_stride: std::mem::size_of::<GizmoVertex>() as wgpu::BufferAddress,
-                            step_mode: wgpu::VertexStepMode::Vertex,
-                            attributes: &wgpu::vertex_attr_array![
-                                0 => Float32x4, // position
-                                1 => Float32x4, // color
-                            ],
-                        }],
-                    },
-                    primitive: wgpu::PrimitiveState {
-                        topology: wgpu::PrimitiveTopology::LineList,
-                        ..Default::default()
-                    },
-                    depth_stencil: Some(wgpu::DepthStencilState {
-                        format: GeometryBuffer::DEPTH_FORMAT,
-                        depth_write_enabled: Some(false),
-                        depth_compare: Some(wgpu::CompareFunction::LessEqual),
-                        stencil: wgpu::StencilState::default(),
-                        bias: wgpu::DepthBiasState::default(),
-                    }),
-                    multisample: wgpu::MultisampleState::default(),
-                    fragment: Some(wgpu::FragmentState {
-                        module,
-                        entry_point: Some("fragment_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        targets: GeometryBuffer::opaque_targets(),
-                    }),
-                    multiview_mask: None,
-                    cache: None,
-                });
+        let pipeline = Self::create_pipeline(&layout, module, "fragment_main");
+        let depth_tinted_pipeline =
+            Self::create_pipeline(&layout, module, "fragment_color_by_depth");
 
         let instances_buffer = PerFrame::new(|index| {
             GrowingBuffer::new(
@@ -89,15 +64,79 @@ impl GizmoRenderPipeline {
 
         Self {
             pipeline,
+            depth_tinted_pipeline,
             instances_buffer,
+            static_buffers: HashMap::default(),
         }
     }
+
+    /// Create the line pipeline using the given fragment entry point.
+    fn create_pipeline(
+        layout: &wgpu::PipelineLayout,
+        module: &wgpu::ShaderModule,
+        fragment_entry_point: &str,
+    ) -> wgpu::RenderPipeline {
+        globals::gpu()
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("gizmos_render_pipeline"),
+                layout: Some(layout),
+                vertex: wgpu::VertexState {
+                    module,
+                    entry_point: Some("vertex_main"),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[GizmoVertex::layout()],
+                },
+                primitive: wgpu::PrimitiveState {
+                    topology: wgpu::PrimitiveTopology::LineList,
+                    ..Default::default()
+                },
+                depth_stencil: Some(
+                    DepthStencilDescriptor::new(wgpu::CompareFunction::LessEqual, false).into(),
+                ),
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module,
+                    entry_point: Some(fragment_entry_point),
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: GeometryBuffer::opaque_targets(),
+                }),
+                multiview_mask: None,
+                cache: None,
+            })
+    }
 }
 
 impl RenderPipeline for GizmoRenderPipeline {
+    fn name(&self) -> &'static str {
+        "gizmos"
+    }
+
+    fn resources(&self) -> PassResources {
+        PassResources {
+            reads: &[
+                RenderResource::CameraEnvironment,
+                RenderResource::GeometryDepth,
+            ],
+            writes: &[RenderResource::GeometryColor],
+        }
+    }
+
     fn prepare(&mut self, _bindings: &mut RenderBindings, snapshot: &WorldRenderSnapshot) {
         let instances = self.instances_buffer.advance();
         instances.write(&snapshot.gizmos.vertices);
+
+        sync_static_buffers(&mut self.static_buffers, &snapshot.gizmos, |vertices| {
+            let buffer =
+                globals::gpu()
+                    .device
+                    .create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                        label: Some("static_gizmo_vertices"),
+                        contents: bytemuck::cast_slice(vertices),
+                        usage: wgpu::BufferUsages::VERTEX,
+                    });
+            (buffer, vertices.len() as u32)
+        });
     }
 
     fn queue(
@@ -107,12 +146,96 @@ impl RenderPipeline for GizmoRenderPipeline {
         geometry_buffer: &GeometryBuffer,
         snapshot: &WorldRenderSnapshot,
     ) {
-        let mut render_pass = geometry_buffer
-            .begin_opaque_render_pass(&mut render_context.encoder, "gizmos_render_pass");
+        render_context.debug_group("gizmos", |render_context| {
+            let mut render_pass = geometry_buffer
+                .begin_opaque_render_pass(&mut render_context.encoder, "gizmos_render_pass");
+
+            render_pass.set_pipeline(if snapshot.gizmos.color_by_depth {
+                &self.depth_tinted_pipeline
+            } else {
+                &self.pipeline
+            });
+            render_pass.set_vertex_buffer(0, self.instances_buffer.current().slice(..));
+            render_pass.set_bind_group(0, &bindings.camera_env_buffer.current().bind_group, &[]);
+            render_pass.draw(0..(snapshot.gizmos.vertices.len() as u32), 0..1);
+
+            for (handle, _) in snapshot.gizmos.static_draws.iter() {
+                if let Some((buffer, vertex_count)) = self.static_buffers.get(handle) {
+                    render_pass.set_vertex_buffer(0, buffer.slice(..));
+                    render_pass.draw(0..*vertex_count, 0..1);
+                }
+            }
+        });
+    }
+}
+
+/// Create buffers for the static gizmos drawn this frame that don't have one
+/// yet, then drop the buffers of removed static gizmos. Removals are applied
+/// last, so geometry drawn and removed in the same frame is not kept.
+fn sync_static_buffers<B>(
+    buffers: &mut HashMap<StaticGizmoHandle, B>,
+    gizmos: &Gizmos,
+    mut create_buffer: impl FnMut(&[GizmoVertex]) -> B,
+) {
+    for (handle, vertices) in gizmos.static_draws.iter() {
+        if vertices.is_empty() {
+            continue;
+        }
+        buffers
+            .entry(*handle)
+            .or_insert_with(|| create_buffer(vertices));
+    }
+
+    for handle in gizmos.removed_static.iter() {
+        buffers.remove(handle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, Vec4};
+
+    use super::*;
+
+    #[test]
+    fn removed_static_gizmos_have_no_buffer_left() {
+        let line: std::sync::Arc<[GizmoVertex]> =
+            vec![GizmoVertex::new(Vec3::ZERO, Vec4::ONE); 2].into();
+        let kept = StaticGizmoHandle(0);
+        let removed = StaticGizmoHandle(1);
+
+        let mut buffers = HashMap::default();
+        let mut created = 0;
+        let mut sync = |buffers: &mut HashMap<_, _>, gizmos: &Gizmos| {
+            sync_static_buffers(buffers, gizmos, |vertices| {
+                created += 1;
+                vertices.len()
+            })
+        };
+
+        // Drawn and removed in the same frame.
+        let gizmos = Gizmos {
+            static_draws: vec![(kept, line.clone()), (removed, line.clone())],
+            removed_static: vec![removed],
+            ..Default::default()
+        };
+        sync(&mut buffers, &gizmos);
+        assert_eq!(buffers.len(), 1);
+        assert_eq!(buffers.get(&kept), Some(&2));
+
+        // Kept buffers are not uploaded again.
+        let gizmos = Gizmos {
+            static_draws: vec![(kept, line.clone())],
+            ..Default::default()
+        };
+        sync(&mut buffers, &gizmos);
 
-        render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_vertex_buffer(0, self.instances_buffer.current().slice(..));
-        render_pass.set_bind_group(0, &bindings.camera_env_buffer.current().bind_group, &[]);
-        render_pass.draw(0..(snapshot.gizmos.vertices.len() as u32), 0..1);
+        let gizmos = Gizmos {
+            removed_static: vec![kept],
+            ..Default::default()
+        };
+        sync(&mut buffers, &gizmos);
+        assert!(buffers.is_empty());
+        assert_eq!(created, 2);
     }
 }
@@ -0,0 +1,131 @@
+use crate::{
+    engine::{
+        renderer::{BindGroupLayoutEntries, RenderContext},
+        shader_cache::{self, ShaderCache, ShaderSource},
+    },
+    game::{
+        globals,
+        render::{
+            geometry_buffer::GeometryBuffer,
+            world::{
+                camera_render_pipeline::CameraEnvironmentLayout,
+                render_bindings::RenderBindings,
+                render_layouts::{RenderLayout, RenderLayouts},
+            },
+        },
+    },
+};
+
+/// The geometry buffer depth attachment, bound for reading.
+pub struct DepthTextureLayout;
+
+impl RenderLayout for DepthTextureLayout {
+    fn label() -> &'static str {
+        "depth_texture_bind_group_layout"
+    }
+
+    fn entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+        BindGroupLayoutEntries::new()
+            .texture(
+                0,
+                wgpu::TextureViewDimension::D2,
+                wgpu::TextureSampleType::Depth,
+            )
+            .build()
+    }
+}
+
+/// The linear depth produced by the depth prepass, bound for reading.
+pub struct LinearDepthLayout;
+
+impl RenderLayout for LinearDepthLayout {
+    fn label() -> &'static str {
+        "linear_depth_bind_group_layout"
+    }
+
+    fn entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+        BindGroupLayoutEntries::new()
+            .texture(
+                0,
+                wgpu::TextureViewDimension::D2,
+                wgpu::TextureSampleType::Float { filterable: false },
+            )
+            .build()
+    }
+}
+
+/// Converts the depth written by the depth prepass into distances along the
+/// camera's forward axis, which later passes can compare against directly.
+pub struct LinearDepthPass {
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl LinearDepthPass {
+    pub fn new(layouts: &mut RenderLayouts, shader_cache: &mut ShaderCache) -> Self {
+        let module = shader_cache.get_or_create(ShaderSource::LinearDepth);
+
+        let camera_environment_layout = layouts.get::<CameraEnvironmentLayout>().clone();
+        let depth_texture_layout = layouts.get::<DepthTextureLayout>().clone();
+
+        let layout = shader_cache::create_pipeline_layout(
+            "linear_depth",
+            ShaderSource::LinearDepth,
+            &["vertex", "fragment"],
+            &[
+                Some((
+                    &camera_environment_layout,
+                    &CameraEnvironmentLayout::entries(),
+                )),
+                Some((&depth_texture_layout, &DepthTextureLayout::entries())),
+            ],
+        );
+
+        let pipeline =
+            globals::gpu()
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some("linear_depth_pipeline"),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module,
+                        entry_point: Some("vertex"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[],
+                    },
+                    primitive: wgpu::PrimitiveState::default(),
+                    depth_stencil: None,
+                    multisample: wgpu::MultisampleState::default(),
+                    fragment: Some(wgpu::FragmentState {
+                        module,
+                        entry_point: Some("fragment"),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        targets: &[Some(wgpu::ColorTargetState {
+                            format: GeometryBuffer::LINEAR_DEPTH_FORMAT,
+                            blend: None,
+                            write_mask: wgpu::ColorWrites::ALL,
+                        })],
+                    }),
+                    multiview_mask: None,
+                    cache: None,
+                });
+
+        Self { pipeline }
+    }
+
+    /// Fill the linear depth target of `geometry_buffer` from its depth
+    /// attachment.
+    pub fn queue(
+        &self,
+        bindings: &RenderBindings,
+        render_context: &mut RenderContext,
+        geometry_buffer: &GeometryBuffer,
+    ) {
+        let mut render_pass =
+            geometry_buffer.begin_linear_depth_render_pass(&mut render_context.encoder);
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &bindings.camera_env_buffer.current().bind_group, &[]);
+        render_pass.set_bind_group(1, geometry_buffer.depth_bind_group(), &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
@@ -1,7 +1,9 @@
 mod camera_render_pipeline;
 mod gizmo_render_pipeline;
+mod linear_depth;
 mod model_render_pipeline;
 mod render_bindings;
+mod render_graph;
 mod render_layouts;
 mod render_models;
 mod render_pipeline;
@@ -1,8 +1,15 @@
+use std::ops::Range;
+
 use ahash::HashMap;
+use glam::Mat4;
 
 use crate::{
     engine::{
-        growing_buffer::GrowingBuffer, renderer::RenderContext, shader_cache::ShaderCache,
+        growing_buffer::GrowingBuffer,
+        renderer::{
+            AsVertexLayouts, BindGroupLayoutEntries, DepthStencilDescriptor, RenderContext,
+        },
+        shader_cache::{self, RenderPipelineDescriptor, ShaderCache, ShaderSource},
         storage::Handle,
     },
     game::{
@@ -10,14 +17,16 @@ use crate::{
         globals,
         render::{
             geometry_buffer::GeometryBuffer,
+            matrix_guard::checked_matrix,
             per_frame::PerFrame,
-            textures::Texture,
             world::{
                 WorldRenderSnapshot,
                 camera_render_pipeline::CameraEnvironmentLayout,
+                linear_depth::LinearDepthLayout,
                 render_bindings::RenderBindings,
-                render_layouts::RenderLayouts,
-                render_models::{RenderMesh, RenderModel, RenderModels, RenderVertex},
+                render_graph::{PassResources, RenderResource},
+                render_layouts::{RenderLayout, RenderLayouts},
+                render_models::{RenderModel, RenderModels, RenderVertex},
                 render_pipeline::RenderPipeline,
             },
         },
@@ -40,19 +49,16 @@ struct Batch {
 pub struct ModelRenderPipeline {
     models: RenderModels,
 
-    /// Layout used for per-texture bind groups (texture + sampler).
-    texture_bind_group_layout: wgpu::BindGroupLayout,
-    /// Sampler shared across all model textures.
-    sampler: wgpu::Sampler,
-    /// Per-texture bind groups, lazily created when a texture is first used.
-    texture_bind_groups: HashMap<Handle<Texture>, wgpu::BindGroup>,
-
     /// Pipeline used for `BlendMode::Opaque` meshes.
     opaque_pipeline: wgpu::RenderPipeline,
     /// Pipeline used for `BlendMode::ColorKeyed` meshes (opaque pass + discard).
     keyed_pipeline: wgpu::RenderPipeline,
     /// Pipeline used for `BlendMode::Alpha` meshes.
     alpha_pipeline: wgpu::RenderPipeline,
+    /// Pipeline used for `BlendMode::Additive` meshes.
+    additive_pipeline: wgpu::RenderPipeline,
+    /// Depth-only pipeline for `BlendMode::Opaque` meshes in the depth prepass.
+    depth_pipeline: wgpu::RenderPipeline,
 
     /// Sorted indices into `snapshot.models.models`, grouping instances by
     /// `Handle<Model>` so they can be drawn as contiguous batches.
@@ -68,6 +74,10 @@ pub struct ModelRenderPipeline {
     /// Local cache where custom Pose data is stored per frame.
     poses_bind_group_layout: wgpu::BindGroupLayout,
     poses_cache: Vec<gpu::Bone>,
+    /// Index of the first bone in `poses_cache` of each uploaded pose, keyed
+    /// by the address of its bones. Instances sharing a cached pose share
+    /// the bones, so they are uploaded once.
+    pose_offsets_cache: HashMap<*const Mat4, u32>,
     poses: PerFrame<(GrowingBuffer<gpu::Bone>, wgpu::BindGroup)>,
 
     batches: Vec<Batch>,
@@ -77,102 +87,65 @@ impl ModelRenderPipeline {
     pub fn new(layouts: &mut RenderLayouts, shader_cache: &mut ShaderCache) -> Self {
         let models = RenderModels::default();
 
-        let texture_bind_group_layout =
-            globals::gpu()
-                .device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    label: Some("model_texture_bind_group_layout"),
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Texture {
-                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
-                                multisampled: false,
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                            count: None,
-                        },
-                    ],
-                });
-
-        let sampler = globals::gpu()
-            .device
-            .create_sampler(&wgpu::SamplerDescriptor {
-                label: Some("model_texture_sampler"),
-                address_mode_u: wgpu::AddressMode::Repeat,
-                address_mode_v: wgpu::AddressMode::Repeat,
-                address_mode_w: wgpu::AddressMode::Repeat,
-                mag_filter: wgpu::FilterMode::Linear,
-                min_filter: wgpu::FilterMode::Linear,
-                ..Default::default()
-            });
+        let poses_layout_entries = BindGroupLayoutEntries::new()
+            .storage(0)
+            .visibility(wgpu::ShaderStages::VERTEX)
+            .build();
 
         let poses_bind_group_layout =
             globals::gpu()
                 .device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                     label: Some("poses_bind_group_layout"),
-                    entries: &[wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: true },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    }],
-                });
-
-        let module = shader_cache.get_or_create(crate::engine::shader_cache::ShaderSource::Models);
-
-        let layout =
-            globals::gpu()
-                .device
-                .create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                    label: Some("models_pipeline_layout"),
-                    bind_group_layouts: &[
-                        Some(layouts.get::<CameraEnvironmentLayout>()),
-                        Some(&texture_bind_group_layout),
-                        Some(&models.nodes_bind_group_layout),
-                        Some(&poses_bind_group_layout),
-                    ],
-                    ..Default::default()
+                    entries: &poses_layout_entries,
                 });
 
-        let buffers = &[
-            wgpu::VertexBufferLayout {
-                array_stride: std::mem::size_of::<RenderVertex>() as wgpu::BufferAddress,
-                step_mode: wgpu::VertexStepMode::Vertex,
-                attributes: &wgpu::vertex_attr_array![
-                    0 => Float32x3,  // position
-                    1 => Float32x3,  // normal
-                    2 => Float32x2,  // tex_coord
-                    3 => Uint32,     // node_index
-                ],
-            },
-            wgpu::VertexBufferLayout {
-                array_stride: std::mem::size_of::<
-                    super::model_render_pipeline::gpu::ModelInstanceData,
-                >() as wgpu::BufferAddress,
-                step_mode: wgpu::VertexStepMode::Instance,
-                attributes: &wgpu::vertex_attr_array![
-                    4 => Float32x4,  // model_mat_0
-                    5 => Float32x4,  // model_mat_1
-                    6 => Float32x4,  // model_mat_2
-                    7 => Float32x4,  // model_mat_3
-                    8 => Uint32,     // first_node_index
-                    9 => Uint32,     // flags
-                ],
-            },
-        ];
+        let camera_env_layout = layouts.get::<CameraEnvironmentLayout>().clone();
+        let linear_depth_layout = layouts.get::<LinearDepthLayout>().clone();
+
+        let camera_env_entries = CameraEnvironmentLayout::entries();
+        let materials_entries = RenderModels::materials_layout_entries();
+        let nodes_entries = RenderModels::nodes_layout_entries();
+        let linear_depth_entries = LinearDepthLayout::entries();
+
+        let camera_env = (&camera_env_layout, camera_env_entries.as_slice());
+        let materials = (
+            &models.materials_bind_group_layout,
+            materials_entries.as_slice(),
+        );
+        let nodes = (&models.nodes_bind_group_layout, nodes_entries.as_slice());
+        let poses = (&poses_bind_group_layout, poses_layout_entries.as_slice());
+        let linear_depth = (&linear_depth_layout, linear_depth_entries.as_slice());
+
+        let layout = shader_cache::create_pipeline_layout(
+            "models",
+            ShaderSource::Models,
+            &[
+                "vertex_main",
+                "fragment_opaque",
+                "fragment_opaque_keyed",
+                "fragment_alpha",
+                "fragment_additive",
+            ],
+            &[
+                Some(camera_env),
+                Some(materials),
+                Some(nodes),
+                Some(poses),
+                Some(linear_depth),
+            ],
+        );
+
+        // The depth prepass only runs the vertex shader, so it doesn't need the
+        // materials or the linear depth it is about to produce.
+        let depth_layout = shader_cache::create_pipeline_layout(
+            "models_depth",
+            ShaderSource::Models,
+            &["vertex_main"],
+            &[Some(camera_env), None, Some(nodes), Some(poses)],
+        );
+
+        let buffers = &<(RenderVertex, gpu::ModelInstanceData)>::layouts();
 
         let primitive = wgpu::PrimitiveState {
             topology: wgpu::PrimitiveTopology::TriangleList,
@@ -182,93 +155,57 @@ impl ModelRenderPipeline {
             ..Default::default()
         };
 
-        let opaque_depth = wgpu::DepthStencilState {
-            format: wgpu::TextureFormat::Depth32Float,
-            depth_write_enabled: Some(true),
-            depth_compare: Some(wgpu::CompareFunction::LessEqual),
-            stencil: wgpu::StencilState::default(),
-            bias: wgpu::DepthBiasState::default(),
+        let opaque_depth = DepthStencilDescriptor::new(wgpu::CompareFunction::LessEqual, true);
+        let alpha_depth = DepthStencilDescriptor {
+            depth_write_enabled: false,
+            ..opaque_depth
         };
 
-        let alpha_depth = wgpu::DepthStencilState {
-            depth_write_enabled: Some(false),
-            ..opaque_depth.clone()
+        let descriptor = |label, entry_point, targets, depth_stencil| RenderPipelineDescriptor {
+            layout: Some(&layout),
+            buffers,
+            primitive,
+            fragment_entry_point: Some(entry_point),
+            targets,
+            depth_stencil: Some(depth_stencil),
+            ..RenderPipelineDescriptor::new(label, ShaderSource::Models)
         };
 
-        let opaque_pipeline =
-            globals::gpu()
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("models_opaque_pipeline"),
-                    layout: Some(&layout),
-                    vertex: wgpu::VertexState {
-                        module,
-                        entry_point: Some("vertex_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        buffers,
-                    },
-                    primitive,
-                    depth_stencil: Some(opaque_depth.clone()),
-                    multisample: wgpu::MultisampleState::default(),
-                    fragment: Some(wgpu::FragmentState {
-                        module,
-                        entry_point: Some("fragment_opaque"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        targets: GeometryBuffer::opaque_targets(),
-                    }),
-                    multiview_mask: None,
-                    cache: None,
-                });
-
-        let keyed_pipeline =
-            globals::gpu()
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("models_keyed_pipeline"),
-                    layout: Some(&layout),
-                    vertex: wgpu::VertexState {
-                        module,
-                        entry_point: Some("vertex_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        buffers,
-                    },
-                    primitive,
-                    depth_stencil: Some(opaque_depth),
-                    multisample: wgpu::MultisampleState::default(),
-                    fragment: Some(wgpu::FragmentState {
-                        module,
-                        entry_point: Some("fragment_opaque_keyed"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        targets: GeometryBuffer::opaque_targets(),
-                    }),
-                    multiview_mask: None,
-                    cache: None,
-                });
-
-        let alpha_pipeline =
-            globals::gpu()
-                .device
-                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-                    label: Some("models_alpha_pipeline"),
-                    layout: Some(&layout),
-                    vertex: wgpu::VertexState {
-                        module,
-                        entry_point: Some("vertex_main"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        buffers,
-                    },
-                    primitive,
-                    depth_stencil: Some(alpha_depth),
-                    multisample: wgpu::MultisampleState::default(),
-                    fragment: Some(wgpu::FragmentState {
-                        module,
-                        entry_point: Some("fragment_alpha"),
-                        compilation_options: wgpu::PipelineCompilationOptions::default(),
-                        targets: GeometryBuffer::alpha_targets(),
-                    }),
-                    multiview_mask: None,
-                    cache: None,
-                });
+        let opaque_pipeline = shader_cache.create_render_pipeline(&descriptor(
+            "models_opaque_pipeline",
+            "fragment_opaque",
+            GeometryBuffer::opaque_targets(),
+            opaque_depth,
+        ));
+
+        let keyed_pipeline = shader_cache.create_render_pipeline(&descriptor(
+            "models_keyed_pipeline",
+            "fragment_opaque_keyed",
+            GeometryBuffer::opaque_targets(),
+            opaque_depth,
+        ));
+
+        let additive_pipeline = shader_cache.create_render_pipeline(&descriptor(
+            "models_additive_pipeline",
+            "fragment_additive",
+            GeometryBuffer::additive_targets(),
+            alpha_depth,
+        ));
+
+        let alpha_pipeline = shader_cache.create_render_pipeline(&descriptor(
+            "models_alpha_pipeline",
+            "fragment_alpha",
+            GeometryBuffer::alpha_targets(),
+            alpha_depth,
+        ));
+
+        let depth_pipeline = shader_cache.create_render_pipeline(&RenderPipelineDescriptor {
+            layout: Some(&depth_layout),
+            buffers,
+            primitive,
+            depth_stencil: Some(opaque_depth),
+            ..RenderPipelineDescriptor::new("models_depth_pipeline", ShaderSource::Models)
+        });
 
         let model_instances = PerFrame::new(|index| {
             GrowingBuffer::new(
@@ -302,13 +239,11 @@ impl ModelRenderPipeline {
         Self {
             models,
 
-            texture_bind_group_layout,
-            sampler,
-            texture_bind_groups: HashMap::default(),
-
             opaque_pipeline,
             keyed_pipeline,
             alpha_pipeline,
+            additive_pipeline,
+            depth_pipeline,
 
             sorted_indices_cache: Vec::default(),
 
@@ -318,6 +253,7 @@ impl ModelRenderPipeline {
 
             poses_bind_group_layout,
             poses_cache: Vec::default(),
+            pose_offsets_cache: HashMap::default(),
             poses,
 
             batches: Vec::default(),
@@ -325,65 +261,36 @@ impl ModelRenderPipeline {
     }
 
     pub fn ensure_render_model(&mut self, model_handle: Handle<Model>) {
-        if self.models.contains(model_handle) {
-            return;
-        }
-
         self.models.add(model_handle);
+    }
+}
 
-        // Create per-texture bind groups for any new textures introduced by this model.
-        let new_textures: Vec<Handle<Texture>> = self
-            .models
-            .get(model_handle)
-            .map(|render_model| {
-                render_model
-                    .opaque_meshes
-                    .iter()
-                    .chain(render_model.keyed_meshes.iter())
-                    .chain(render_model.alpha_meshes.iter())
-                    .map(|mesh| mesh.texture)
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        for texture in new_textures {
-            self.ensure_texture_bind_group(texture);
-        }
+impl RenderPipeline for ModelRenderPipeline {
+    fn name(&self) -> &'static str {
+        "models"
     }
 
-    fn ensure_texture_bind_group(&mut self, texture: Handle<Texture>) {
-        if self.texture_bind_groups.contains_key(&texture) {
-            return;
+    fn resources(&self) -> PassResources {
+        PassResources {
+            reads: &[
+                RenderResource::CameraEnvironment,
+                RenderResource::GeometryDepth,
+                RenderResource::LinearDepth,
+            ],
+            writes: &[
+                RenderResource::GeometryColor,
+                RenderResource::GeometryDepth,
+                RenderResource::GeometryOit,
+            ],
         }
-
-        let Some(texture_data) = globals::textures().get(texture) else {
-            return;
-        };
-
-        let bind_group = globals::gpu()
-            .device
-            .create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("model_texture_bind_group"),
-                layout: &self.texture_bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry {
-                        binding: 0,
-                        resource: wgpu::BindingResource::TextureView(&texture_data.view),
-                    },
-                    wgpu::BindGroupEntry {
-                        binding: 1,
-                        resource: wgpu::BindingResource::Sampler(&self.sampler),
-                    },
-                ],
-            });
-
-        self.texture_bind_groups.insert(texture, bind_group);
     }
-}
 
-impl RenderPipeline for ModelRenderPipeline {
     fn prepare(&mut self, _bindings: &mut RenderBindings, snapshot: &WorldRenderSnapshot) {
+        // Only the snapshot instances are kept between frames. The GPU instance
+        // data and poses of every visible model are rebuilt and uploaded here
+        // each frame, whether the entity changed or not.
         let snapshot_models = &snapshot.models.models;
+        let instances = &snapshot.models.instances;
 
         // Sort by model handle so instances of the same model end up contiguous.
         // We sort indices into the snapshot rather than cloning the snapshot itself.
@@ -393,6 +300,7 @@ impl RenderPipeline for ModelRenderPipeline {
             .sort_unstable_by_key(|&i| snapshot_models[i].model);
 
         self.poses_cache.clear();
+        self.pose_offsets_cache.clear();
         self.model_instances_cache.clear();
         self.instance_models_cache.clear();
 
@@ -400,20 +308,34 @@ impl RenderPipeline for ModelRenderPipeline {
         // on first sight, and emitting per-instance data inline.
         for i in 0..self.sorted_indices_cache.len() {
             let idx = self.sorted_indices_cache[i];
-            let m = &snapshot_models[idx];
+            let highlighted = snapshot_models[idx].highlighted;
+            let Some(m) = instances.get(&snapshot_models[idx].entity) else {
+                continue;
+            };
 
             self.ensure_render_model(m.model);
 
             let mut flags = ModelRenderFlags::empty();
-            flags.set(ModelRenderFlags::HIGHLIGHTED, m.highlighted);
+            flags.set(ModelRenderFlags::HIGHLIGHTED, highlighted);
 
             let first_node_index = if let Some(ref pose) = m.pose {
-                let first = self.poses_cache.len() as u32;
-                for bone in pose.bones.iter() {
-                    self.poses_cache.push(gpu::Bone {
-                        transform: bone.to_cols_array_2d(),
+                let poses_cache = &mut self.poses_cache;
+                let first = *self
+                    .pose_offsets_cache
+                    .entry(pose.bones.as_ptr())
+                    .or_insert_with(|| {
+                        let first = poses_cache.len() as u32;
+                        for (bone_index, bone) in pose.bones.iter().enumerate() {
+                            let transform = checked_matrix(
+                                *bone,
+                                format_args!("pose bone {bone_index} of model {:?}", m.model),
+                            );
+                            poses_cache.push(gpu::Bone {
+                                transform: transform.to_cols_array_2d(),
+                            });
+                        }
+                        first
                     });
-                }
                 flags.set(ModelRenderFlags::CUSTOM_POSE, true);
                 first
             } else {
@@ -423,7 +345,11 @@ impl RenderPipeline for ModelRenderPipeline {
             };
 
             self.model_instances_cache.push(gpu::ModelInstanceData {
-                transform: m.transform.to_cols_array_2d(),
+                transform: checked_matrix(
+                    m.transform,
+                    format_args!("transform of model {:?}", m.model),
+                )
+                .to_cols_array_2d(),
                 first_node_index,
                 flags: flags.bits(),
                 _pad: Default::default(),
@@ -432,21 +358,7 @@ impl RenderPipeline for ModelRenderPipeline {
         }
 
         // Compute batches by walking the (sorted) parallel `instance_models_cache`.
-        self.batches.clear();
-        let instances_count = self.instance_models_cache.len();
-        let mut start = 0;
-        while start < instances_count {
-            let model = self.instance_models_cache[start];
-            let mut end = start + 1;
-            while end < instances_count && self.instance_models_cache[end] == model {
-                end += 1;
-            }
-            self.batches.push(Batch {
-                model,
-                range: start as u32..end as u32,
-            });
-            start = end;
-        }
+        Self::build_batches(&self.instance_models_cache, &mut self.batches);
 
         // Upload custom poses; rebuild the bind group if the buffer was reallocated.
         {
@@ -478,57 +390,105 @@ impl RenderPipeline for ModelRenderPipeline {
         geometry_buffer: &GeometryBuffer,
         _snapshot: &WorldRenderSnapshot,
     ) {
-        self.opaque_render_pass(&mut render_context.encoder, geometry_buffer, bindings);
-        self.alpha_render_pass(&mut render_context.encoder, geometry_buffer, bindings);
+        render_context.debug_group("models", |render_context| {
+            self.opaque_render_pass(&mut render_context.encoder, geometry_buffer, bindings);
+            self.additive_render_pass(&mut render_context.encoder, geometry_buffer, bindings);
+            self.alpha_render_pass(&mut render_context.encoder, geometry_buffer, bindings);
+        });
     }
-}
 
-impl ModelRenderPipeline {
-    fn bind_pass_resources(&self, render_pass: &mut wgpu::RenderPass, bindings: &RenderBindings) {
+    fn queue_depth_prepass(
+        &self,
+        bindings: &RenderBindings,
+        render_context: &mut RenderContext,
+        geometry_buffer: &GeometryBuffer,
+        _snapshot: &WorldRenderSnapshot,
+    ) {
+        let mut render_pass = geometry_buffer
+            .begin_depth_prepass(&mut render_context.encoder, "models_depth_prepass");
+
+        // Color keyed meshes discard pixels in the fragment shader, so only
+        // fully opaque meshes can be drawn without one.
+        render_pass.set_pipeline(&self.depth_pipeline);
         render_pass.set_bind_group(0, &bindings.camera_env_buffer.current().bind_group, &[]);
         render_pass.set_bind_group(3, &self.poses.current().1, &[]);
         render_pass.set_vertex_buffer(1, self.model_instances.current().slice(..));
+
+        for batch in self.batches.iter() {
+            let Some(render_model) = self.models.get(batch.model) else {
+                continue;
+            };
+            if render_model.opaque_indices.is_empty() {
+                continue;
+            }
+            render_pass.set_bind_group(2, &render_model.nodes_bind_group, &[]);
+            render_pass.set_vertex_buffer(0, render_model.vertex_buffer.slice(..));
+            render_pass.set_index_buffer(
+                render_model.index_buffer.slice(..),
+                wgpu::IndexFormat::Uint32,
+            );
+            render_pass.draw_indexed(render_model.opaque_indices.clone(), 0, batch.range.clone());
+        }
+    }
+}
+
+impl ModelRenderPipeline {
+    /// Fill `batches` with a batch for each run of the same model in
+    /// `instance_models`.
+    fn build_batches(instance_models: &[Handle<Model>], batches: &mut Vec<Batch>) {
+        batches.clear();
+        let mut start = 0;
+        for run in instance_models.chunk_by(|a, b| a == b) {
+            let end = start + run.len();
+            batches.push(Batch {
+                model: run[0],
+                range: start as u32..end as u32,
+            });
+            start = end;
+        }
     }
 
-    fn draw_meshes(
+    fn bind_pass_resources(
         &self,
         render_pass: &mut wgpu::RenderPass,
-        meshes: &[RenderMesh],
-        instance_range: std::ops::Range<u32>,
+        bindings: &RenderBindings,
+        geometry_buffer: &GeometryBuffer,
     ) {
-        for mesh in meshes {
-            let Some(bind_group) = self.texture_bind_groups.get(&mesh.texture) else {
-                continue;
-            };
-            render_pass.set_bind_group(1, bind_group, &[]);
-            render_pass.draw_indexed(mesh.index_range.clone(), 0, instance_range.clone());
-        }
+        render_pass.set_bind_group(0, &bindings.camera_env_buffer.current().bind_group, &[]);
+        render_pass.set_bind_group(3, &self.poses.current().1, &[]);
+        render_pass.set_bind_group(4, geometry_buffer.linear_depth_bind_group(), &[]);
+        render_pass.set_vertex_buffer(1, self.model_instances.current().slice(..));
     }
 
+    /// Draw the indices picked by `select_indices` for each batch. Meshes of
+    /// a model that share a blend mode are contiguous in its index buffer and
+    /// sample their material from the model's texture array, so each batch is
+    /// a single draw.
     fn run_pass<F>(
         &self,
         render_pass: &mut wgpu::RenderPass,
         pipeline: &wgpu::RenderPipeline,
-        select_meshes: F,
+        select_indices: F,
     ) where
-        F: Fn(&RenderModel) -> &[RenderMesh],
+        F: Fn(&RenderModel) -> &Range<u32>,
     {
         render_pass.set_pipeline(pipeline);
         for batch in self.batches.iter() {
             let Some(render_model) = self.models.get(batch.model) else {
                 continue;
             };
-            let meshes = select_meshes(render_model);
-            if meshes.is_empty() {
+            let indices = select_indices(render_model);
+            if indices.is_empty() {
                 continue;
             }
+            render_pass.set_bind_group(1, &render_model.materials_bind_group, &[]);
             render_pass.set_bind_group(2, &render_model.nodes_bind_group, &[]);
             render_pass.set_vertex_buffer(0, render_model.vertex_buffer.slice(..));
             render_pass.set_index_buffer(
                 render_model.index_buffer.slice(..),
                 wgpu::IndexFormat::Uint32,
             );
-            self.draw_meshes(render_pass, meshes, batch.range.clone());
+            render_pass.draw_indexed(indices.clone(), 0, batch.range.clone());
         }
     }
 
@@ -539,14 +499,33 @@ impl ModelRenderPipeline {
         bindings: &RenderBindings,
     ) {
         let mut render_pass = geometry_buffer.begin_opaque_render_pass(encoder, "models_opaque");
-        self.bind_pass_resources(&mut render_pass, bindings);
+        self.bind_pass_resources(&mut render_pass, bindings, geometry_buffer);
 
+        // The opaque pass is not sorted by depth; batching by model matters more.
         self.run_pass(&mut render_pass, &self.opaque_pipeline, |m| {
-            &m.opaque_meshes
+            &m.opaque_indices
         });
-        self.run_pass(&mut render_pass, &self.keyed_pipeline, |m| &m.keyed_meshes);
+        self.run_pass(&mut render_pass, &self.keyed_pipeline, |m| &m.keyed_indices);
     }
 
+    /// Adds additive meshes onto the opaque color. The blend is order
+    /// independent, so the model batches are used as is.
+    fn additive_render_pass(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        geometry_buffer: &GeometryBuffer,
+        bindings: &RenderBindings,
+    ) {
+        let mut render_pass = geometry_buffer.begin_opaque_render_pass(encoder, "models_additive");
+        self.bind_pass_resources(&mut render_pass, bindings, geometry_buffer);
+
+        self.run_pass(&mut render_pass, &self.additive_pipeline, |m| {
+            &m.additive_indices
+        });
+    }
+
+    /// Accumulates alpha meshes with weighted blended order independent
+    /// transparency, so the model batches are used as is.
     fn alpha_render_pass(
         &self,
         encoder: &mut wgpu::CommandEncoder,
@@ -554,15 +533,17 @@ impl ModelRenderPipeline {
         bindings: &RenderBindings,
     ) {
         let mut render_pass = geometry_buffer.begin_alpha_render_pass(encoder, "models_alpha");
-        self.bind_pass_resources(&mut render_pass, bindings);
+        self.bind_pass_resources(&mut render_pass, bindings, geometry_buffer);
 
-        self.run_pass(&mut render_pass, &self.alpha_pipeline, |m| &m.alpha_meshes);
+        self.run_pass(&mut render_pass, &self.alpha_pipeline, |m| &m.alpha_indices);
     }
 }
 
 pub mod gpu {
     use bytemuck::NoUninit;
 
+    use crate::engine::renderer::AsVertexLayout;
+
     #[derive(Clone, Copy, NoUninit)]
     #[repr(C)]
     pub struct ModelInstanceData {
@@ -572,6 +553,18 @@ pub mod gpu {
         pub _pad: [u32; 2],
     }
 
+    impl AsVertexLayout for ModelInstanceData {
+        const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Instance;
+        const ATTRIBUTES: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
+            4 => Float32x4,  // model_mat_0
+            5 => Float32x4,  // model_mat_1
+            6 => Float32x4,  // model_mat_2
+            7 => Float32x4,  // model_mat_3
+            8 => Uint32,     // first_node_index
+            9 => Uint32,     // flags
+        ];
+    }
+
     #[derive(Clone, Copy, NoUninit)]
     #[repr(C)]
     pub struct Bone {
@@ -1,3 +1,5 @@
+use bumpalo::Bump;
+
 use crate::game::{
     globals,
     render::{
@@ -8,6 +10,8 @@ use crate::game::{
 /// Set of data that changes on each frame.
 pub struct RenderBindings {
     pub camera_env_buffer: PerFrame<UniformBuffer>,
+    /// Arena for data that only lives until the end of the current frame.
+    scratch: Bump,
 }
 
 impl RenderBindings {
@@ -41,6 +45,22 @@ impl RenderBindings {
             UniformBuffer::new(buffer, bind_group)
         });
 
-        Self { camera_env_buffer }
+        Self {
+            camera_env_buffer,
+            scratch: Bump::new(),
+        }
+    }
+
+    /// Arena for transient per-frame collections, e.g.
+    /// `bumpalo::collections::Vec::new_in(bindings.scratch())`. Everything
+    /// allocated here is freed when the next frame starts.
+    pub fn scratch(&self) -> &Bump {
+        &self.scratch
+    }
+
+    /// Free all scratch allocations. The arena keeps its largest chunk, so
+    /// after a few frames it stops allocating altogether.
+    pub fn reset_scratch(&mut self) {
+        self.scratch.reset();
     }
 }
@@ -0,0 +1,98 @@
+use ahash::HashSet;
+
+/// A GPU resource that world render passes read from or write to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RenderResource {
+    /// The per-frame camera and environment uniform buffer.
+    CameraEnvironment,
+    /// The opaque color attachment of the geometry buffer.
+    GeometryColor,
+    /// The depth attachment of the geometry buffer.
+    GeometryDepth,
+    /// The order independent transparency attachments of the geometry buffer.
+    GeometryOit,
+    /// The linear depth target of the geometry buffer, filled by the depth
+    /// prepass.
+    LinearDepth,
+}
+
+impl RenderResource {
+    /// Resources written by [GeometryBuffer::clear](crate::game::render::geometry_buffer::GeometryBuffer::clear)
+    /// before any pass is queued.
+    pub const CLEARED: &[RenderResource] = &[
+        RenderResource::GeometryColor,
+        RenderResource::GeometryDepth,
+        RenderResource::GeometryOit,
+        RenderResource::LinearDepth,
+    ];
+}
+
+/// The resources a single pass depends on and produces.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PassResources {
+    pub reads: &'static [RenderResource],
+    pub writes: &'static [RenderResource],
+}
+
+/// Tracks which resources have been produced by earlier passes, so that passes
+/// can be validated as they are added in execution order.
+pub struct RenderGraph {
+    /// Names of the passes in the order they were added.
+    passes: Vec<&'static str>,
+    /// Resources that are valid to read by the next pass.
+    written: HashSet<RenderResource>,
+}
+
+impl RenderGraph {
+    /// Create a graph where `initial` resources are already valid before the
+    /// first pass executes.
+    pub fn new(initial: &[RenderResource]) -> Self {
+        Self {
+            passes: Vec::default(),
+            written: initial.iter().copied().collect(),
+        }
+    }
+
+    /// Add a pass to the end of the graph. Returns an error describing the
+    /// first resource the pass reads that no earlier pass has written.
+    pub fn add_pass(&mut self, name: &'static str, resources: PassResources) -> Result<(), String> {
+        if let Some(missing) = resources
+            .reads
+            .iter()
+            .find(|resource| !self.written.contains(resource))
+        {
+            return Err(format!(
+                "Render pass \"{name}\" reads {missing:?} before it is written. Passes so far: {:?}",
+                self.passes
+            ));
+        }
+
+        self.written.extend(resources.writes.iter().copied());
+        self.passes.push(name);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reading_before_writing_is_rejected() {
+        let mut graph = RenderGraph::new(RenderResource::CLEARED);
+
+        let terrain = PassResources {
+            reads: &[RenderResource::CameraEnvironment],
+            writes: &[RenderResource::GeometryColor],
+        };
+        assert!(graph.add_pass("terrain", terrain).is_err());
+
+        let camera = PassResources {
+            reads: &[],
+            writes: &[RenderResource::CameraEnvironment],
+        };
+        assert!(graph.add_pass("camera", camera).is_ok());
+        assert!(graph.add_pass("terrain", terrain).is_ok());
+    }
+}
@@ -4,7 +4,7 @@ use crate::game::globals;
 
 pub trait RenderLayout {
     fn label() -> &'static str;
-    fn entries() -> &'static [wgpu::BindGroupLayoutEntry];
+    fn entries() -> Vec<wgpu::BindGroupLayoutEntry>;
 }
 
 #[derive(Default)]
@@ -21,7 +21,7 @@ impl RenderLayouts {
                 .device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                     label: Some(L::label()),
-                    entries: L::entries(),
+                    entries: &L::entries(),
                 })
         })
     }
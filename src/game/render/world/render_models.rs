@@ -1,14 +1,22 @@
-use std::ops::Range;
+use std::{borrow::Cow, ops::Range, sync::Arc};
 
 use ahash::HashMap;
+use glam::UVec2;
 use wgpu::util::DeviceExt;
 
 use crate::{
-    engine::{mesh::IndexedMesh, storage::Handle},
+    engine::{
+        mesh::IndexedMesh,
+        renderer::{AsVertexLayout, BindGroupLayoutEntries},
+        storage::Handle,
+    },
     game::{
         assets::{image::BlendMode, model::Model},
         globals,
-        render::textures::Texture,
+        render::{
+            matrix_guard::checked_matrix,
+            textures::{Texture, TextureData},
+        },
     },
 };
 
@@ -19,59 +27,112 @@ pub struct RenderVertex {
     pub normal: [f32; 3],
     pub tex_coord: [f32; 2],
     pub node_index: u32,
+    /// Layer in the model's material texture array sampled by this vertex.
+    pub material_index: u32,
 }
 
-/// One drawable mesh: a contiguous range in its [RenderModel]'s index buffer
-/// plus the texture used to sample its pixels.
-pub struct RenderMesh {
-    pub index_range: Range<u32>,
-    pub texture: Handle<Texture>,
+impl AsVertexLayout for RenderVertex {
+    const STEP_MODE: wgpu::VertexStepMode = wgpu::VertexStepMode::Vertex;
+    const ATTRIBUTES: &'static [wgpu::VertexAttribute] = &wgpu::vertex_attr_array![
+        0 => Float32x3,  // position
+        1 => Float32x3,  // normal
+        2 => Float32x2,  // tex_coord
+        3 => Uint32,     // node_index
+        10 => Uint32,    // material_index
+    ];
 }
 
 /// GPU-side data for a single [Model]. Each model owns its own vertex/index/nodes
 /// buffers and the bind group used to access the nodes during rendering.
+///
+/// All textures used by the model are copied into the layers of a single
+/// texture array, so each blend mode can be drawn with one call: the index
+/// buffer is ordered by blend mode and every vertex carries its material layer.
 pub struct RenderModel {
     pub vertex_buffer: wgpu::Buffer,
     pub index_buffer: wgpu::Buffer,
     pub _nodes_buffer: wgpu::Buffer,
     pub nodes_bind_group: wgpu::BindGroup,
-    pub opaque_meshes: Vec<RenderMesh>,
-    pub keyed_meshes: Vec<RenderMesh>,
-    pub alpha_meshes: Vec<RenderMesh>,
+    pub _materials: wgpu::Texture,
+    pub materials_bind_group: wgpu::BindGroup,
+    pub opaque_indices: Range<u32>,
+    pub keyed_indices: Range<u32>,
+    pub alpha_indices: Range<u32>,
+    pub additive_indices: Range<u32>,
 }
 
 pub struct RenderModels {
     pub nodes_bind_group_layout: wgpu::BindGroupLayout,
+    /// Layout of the per-model material texture array and its sampler.
+    pub materials_bind_group_layout: wgpu::BindGroupLayout,
+    /// Sampler shared across all model materials.
+    sampler: wgpu::Sampler,
+    /// GPU data of every model uploaded so far, by model handle.
     models: HashMap<Handle<Model>, RenderModel>,
 }
 
+impl RenderModels {
+    /// Entries of [Self::nodes_bind_group_layout].
+    pub fn nodes_layout_entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+        BindGroupLayoutEntries::new()
+            .storage(0)
+            .visibility(wgpu::ShaderStages::VERTEX)
+            .build()
+    }
+
+    /// Entries of [Self::materials_bind_group_layout].
+    pub fn materials_layout_entries() -> Vec<wgpu::BindGroupLayoutEntry> {
+        BindGroupLayoutEntries::new()
+            .texture(
+                0,
+                wgpu::TextureViewDimension::D2Array,
+                wgpu::TextureSampleType::Float { filterable: true },
+            )
+            .sampler(1, wgpu::SamplerBindingType::Filtering)
+            .build()
+    }
+}
+
 impl Default for RenderModels {
     fn default() -> Self {
+        let device = &globals::gpu().device;
+
         let nodes_bind_group_layout =
-            globals::gpu()
-                .device
-                .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                    label: Some("model_nodes_bind_group_layout"),
-                    entries: &[wgpu::BindGroupLayoutEntry {
-                        binding: 0,
-                        visibility: wgpu::ShaderStages::VERTEX,
-                        ty: wgpu::BindingType::Buffer {
-                            ty: wgpu::BufferBindingType::Storage { read_only: true },
-                            has_dynamic_offset: false,
-                            min_binding_size: None,
-                        },
-                        count: None,
-                    }],
-                });
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("model_nodes_bind_group_layout"),
+                entries: &Self::nodes_layout_entries(),
+            });
+
+        let materials_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("model_materials_bind_group_layout"),
+                entries: &Self::materials_layout_entries(),
+            });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("model_texture_sampler"),
+            address_mode_u: wgpu::AddressMode::Repeat,
+            address_mode_v: wgpu::AddressMode::Repeat,
+            address_mode_w: wgpu::AddressMode::Repeat,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            ..Default::default()
+        });
 
         Self {
             nodes_bind_group_layout,
+            materials_bind_group_layout,
+            sampler,
             models: HashMap::default(),
         }
     }
 }
 
 impl RenderModels {
+    /// Format of the material texture arrays; matches the images they are
+    /// copied from.
+    const MATERIALS_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8UnormSrgb;
+
     pub fn add(&mut self, model_handle: Handle<Model>) {
         if self.models.contains_key(&model_handle) {
             return;
@@ -85,18 +146,24 @@ impl RenderModels {
         // vertex shader can do a single buffer lookup instead of walking the
         // parent chain per vertex.
         let nodes: Vec<[[f32; 4]; 4]> = (0..model.skeleton.bones.len() as u32)
-            .map(|i| model.skeleton.local_transform(i).to_cols_array_2d())
+            .map(|i| {
+                checked_matrix(
+                    model.skeleton.local_transform(i),
+                    format_args!("node {i} of model {model_handle:?}"),
+                )
+                .to_cols_array_2d()
+            })
             .collect();
 
-        // Build the per-model vertex and index buffers by concatenating all
-        // mesh data, with each mesh's indices rebased onto where its vertices
-        // land in the combined vertex buffer.
-        let mut vertices: Vec<RenderVertex> = Vec::new();
-        let mut indices: Vec<u32> = Vec::new();
+        // Assign every distinct texture a layer in the material array.
+        let mut materials: Vec<Arc<TextureData>> = Vec::new();
+        let mut material_indices: HashMap<Handle<Texture>, u32> = HashMap::default();
 
-        let mut opaque_meshes: Vec<RenderMesh> = Vec::new();
-        let mut keyed_meshes: Vec<RenderMesh> = Vec::new();
-        let mut alpha_meshes: Vec<RenderMesh> = Vec::new();
+        // Build the per-model vertex buffer by concatenating all mesh data and
+        // collect each mesh's indices, rebased onto where its vertices land in
+        // the combined vertex buffer, per blend mode.
+        let mut vertices: Vec<RenderVertex> = Vec::new();
+        let mut indices_by_blend_mode: [Vec<u32>; 4] = Default::default();
 
         for mesh in model.meshes.iter() {
             let texture_handle = globals::textures()
@@ -106,6 +173,12 @@ impl RenderModels {
                 .get(texture_handle)
                 .expect("Texture should exist immediately after creation.");
 
+            let blend_mode = texture_data.blend_mode;
+            let material_index = *material_indices.entry(texture_handle).or_insert_with(|| {
+                materials.push(texture_data);
+                materials.len() as u32 - 1
+            });
+
             let mut indexed_mesh: IndexedMesh<RenderVertex> = IndexedMesh {
                 vertices: mesh
                     .mesh
@@ -116,6 +189,7 @@ impl RenderModels {
                         normal: v.normal.to_array(),
                         tex_coord: v.tex_coord.to_array(),
                         node_index: v.node_index,
+                        material_index,
                     })
                     .collect(),
                 indices: mesh.mesh.indices.clone(),
@@ -124,25 +198,29 @@ impl RenderModels {
             let vertex_offset = vertices.len() as u32;
             vertices.append(&mut indexed_mesh.vertices);
 
-            let index_start = indices.len() as u32;
-            indices.extend(indexed_mesh.indices.iter().map(|i| i + vertex_offset));
-            let index_end = indices.len() as u32;
-
-            let render_mesh = RenderMesh {
-                index_range: index_start..index_end,
-                texture: texture_handle,
+            let slot = match blend_mode {
+                BlendMode::Opaque => 0,
+                BlendMode::ColorKeyed => 1,
+                BlendMode::Alpha => 2,
+                BlendMode::Additive => 3,
             };
-
-            match texture_data.blend_mode {
-                BlendMode::Opaque => opaque_meshes.push(render_mesh),
-                BlendMode::ColorKeyed => keyed_meshes.push(render_mesh),
-                BlendMode::Alpha => alpha_meshes.push(render_mesh),
-                BlendMode::_Additive => {
-                    // Additive blending is not currently rendered.
-                }
-            }
+            indices_by_blend_mode[slot]
+                .extend(indexed_mesh.indices.iter().map(|i| i + vertex_offset));
         }
 
+        // Concatenate the blend mode groups so each is a contiguous range.
+        let mut indices: Vec<u32> = Vec::new();
+        let [
+            opaque_indices,
+            keyed_indices,
+            alpha_indices,
+            additive_indices,
+        ] = indices_by_blend_mode.map(|group| {
+            let start = indices.len() as u32;
+            indices.extend(group);
+            start..indices.len() as u32
+        });
+
         let device = &globals::gpu().device;
 
         let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -172,6 +250,28 @@ impl RenderModels {
             }],
         });
 
+        let materials_texture = self.create_materials_texture(&materials);
+        let materials_view = materials_texture.create_view(&wgpu::TextureViewDescriptor {
+            label: Some("render_model_materials_view"),
+            dimension: Some(wgpu::TextureViewDimension::D2Array),
+            ..Default::default()
+        });
+
+        let materials_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("render_model_materials_bind_group"),
+            layout: &self.materials_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&materials_view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+
         self.models.insert(
             model_handle,
             RenderModel {
@@ -179,13 +279,79 @@ impl RenderModels {
                 index_buffer,
                 _nodes_buffer: nodes_buffer,
                 nodes_bind_group,
-                opaque_meshes,
-                keyed_meshes,
-                alpha_meshes,
+                _materials: materials_texture,
+                materials_bind_group,
+                opaque_indices,
+                keyed_indices,
+                alpha_indices,
+                additive_indices,
             },
         );
     }
 
+    /// Create a texture array with a layer per material. Layers share a size,
+    /// so materials are scaled to the largest width and height in the model.
+    fn create_materials_texture(&self, materials: &[Arc<TextureData>]) -> wgpu::Texture {
+        let gpu = globals::gpu();
+
+        let size = materials
+            .iter()
+            .fold(UVec2::ONE, |size, material| size.max(material.size))
+            .min(UVec2::splat(gpu.max_texture_size));
+
+        let texture = gpu.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render_model_materials"),
+            size: wgpu::Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: materials.len().max(1) as u32,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: Self::MATERIALS_FORMAT,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+
+        let bytes_per_pixel = Self::MATERIALS_FORMAT
+            .block_copy_size(None)
+            .expect("Texture format should be copyable.");
+
+        for (layer, material) in materials.iter().enumerate() {
+            let Some(image) = globals::images().get(material.image) else {
+                tracing::warn!("Material image {} is missing.", material.image);
+                continue;
+            };
+
+            gpu.queue.write_texture(
+                wgpu::TexelCopyTextureInfo {
+                    texture: &texture,
+                    mip_level: 0,
+                    origin: wgpu::Origin3d {
+                        x: 0,
+                        y: 0,
+                        z: layer as u32,
+                    },
+                    aspect: wgpu::TextureAspect::All,
+                },
+                &scale_nearest(&image.data, size),
+                wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(size.x * bytes_per_pixel),
+                    rows_per_image: Some(size.y),
+                },
+                wgpu::Extent3d {
+                    width: size.x,
+                    height: size.y,
+                    depth_or_array_layers: 1,
+                },
+            );
+        }
+
+        texture
+    }
+
     #[inline]
     pub fn get(&self, handle: Handle<Model>) -> Option<&RenderModel> {
         self.models.get(&handle)
@@ -195,4 +361,66 @@ impl RenderModels {
     pub fn contains(&self, handle: Handle<Model>) -> bool {
         self.models.contains_key(&handle)
     }
+
+    /// Free the GPU resources of a model right away instead of waiting for the
+    /// last reference to them to drop. Returns `false` if the model was not
+    /// uploaded.
+    pub fn destroy(&mut self, handle: Handle<Model>) -> bool {
+        let Some(render_model) = self.models.remove(&handle) else {
+            return false;
+        };
+
+        render_model.vertex_buffer.destroy();
+        render_model.index_buffer.destroy();
+        render_model._nodes_buffer.destroy();
+        render_model._materials.destroy();
+
+        true
+    }
+}
+
+/// Scale `image` to `size` by copying the nearest source texel. No new colors
+/// are made, so color keyed (black) texels stay exactly black and edges don't
+/// bleed into the key.
+fn scale_nearest(image: &image::RgbaImage, size: UVec2) -> Cow<'_, image::RgbaImage> {
+    if image.dimensions() == (size.x, size.y) {
+        return Cow::Borrowed(image);
+    }
+
+    Cow::Owned(image::imageops::resize(
+        image,
+        size.x,
+        size.y,
+        image::imageops::FilterType::Nearest,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_materials_keep_keyed_texels_black() {
+        const KEY: image::Rgba<u8> = image::Rgba([0, 0, 0, 255]);
+        const COLOR: image::Rgba<u8> = image::Rgba([200, 120, 40, 255]);
+
+        // A checker board of keyed and colored texels.
+        let image =
+            image::RgbaImage::from_fn(3, 3, |x, y| if (x + y) % 2 == 0 { KEY } else { COLOR });
+
+        let scaled = scale_nearest(&image, UVec2::new(7, 5));
+        assert_eq!(scaled.dimensions(), (7, 5));
+        assert!(
+            scaled
+                .pixels()
+                .all(|pixel| *pixel == KEY || *pixel == COLOR)
+        );
+        assert_eq!(*scaled.get_pixel(0, 0), KEY);
+        assert_eq!(*scaled.get_pixel(6, 4), KEY);
+
+        assert!(matches!(
+            scale_nearest(&image, UVec2::new(3, 3)),
+            Cow::Borrowed(_)
+        ));
+    }
 }
@@ -2,11 +2,21 @@ use crate::{
     engine::renderer::RenderContext,
     game::render::{
         geometry_buffer::GeometryBuffer,
-        world::{render_bindings::RenderBindings, world_render_snapshot::WorldRenderSnapshot},
+        world::{
+            render_bindings::RenderBindings,
+            render_graph::{PassResources, RenderGraph, RenderResource},
+            world_render_snapshot::WorldRenderSnapshot,
+        },
     },
 };
 
 pub trait RenderPipeline {
+    /// Name of the pipeline, used when reporting pass ordering errors.
+    fn name(&self) -> &'static str;
+
+    /// The resources read and written by the pipeline across `prepare` and `queue`.
+    fn resources(&self) -> PassResources;
+
     /// Prepare GPU resources that will be used when queueing commands to the GPU.
     fn prepare(&mut self, bindings: &mut RenderBindings, snapshot: &WorldRenderSnapshot);
 
@@ -18,20 +28,59 @@ pub trait RenderPipeline {
         geometry_buffer: &GeometryBuffer,
         snapshot: &WorldRenderSnapshot,
     );
+
+    /// Queue depth-only draws of opaque geometry. Runs before any pipeline's
+    /// `queue` when the depth prepass is enabled. Pipelines without opaque
+    /// geometry don't draw anything.
+    fn queue_depth_prepass(
+        &self,
+        bindings: &RenderBindings,
+        render_context: &mut RenderContext,
+        geometry_buffer: &GeometryBuffer,
+        snapshot: &WorldRenderSnapshot,
+    ) {
+        let _ = bindings;
+        let _ = render_context;
+        let _ = geometry_buffer;
+        let _ = snapshot;
+    }
 }
 
-#[derive(Default)]
+/// Pipelines executed in the order they were pushed. Each pipeline is
+/// validated against a [RenderGraph] when it is pushed, so a pipeline that
+/// reads a resource before an earlier one wrote it is caught at startup.
 pub struct RenderPipelineList {
     pipelines: Vec<Box<dyn RenderPipeline>>,
+    graph: RenderGraph,
 }
 
 impl RenderPipelineList {
+    /// Create an empty list where `initial` resources are valid before the
+    /// first pipeline runs.
+    pub fn new(initial: &[RenderResource]) -> Self {
+        Self {
+            pipelines: Vec::default(),
+            graph: RenderGraph::new(initial),
+        }
+    }
+
     pub fn push<T: 'static + RenderPipeline>(&mut self, pipeline: T) {
+        if let Err(err) = self.graph.add_pass(pipeline.name(), pipeline.resources()) {
+            panic!("{err}");
+        }
         self.pipelines.push(Box::new(pipeline));
     }
 }
 
 impl RenderPipeline for RenderPipelineList {
+    fn name(&self) -> &'static str {
+        "pipeline_list"
+    }
+
+    fn resources(&self) -> PassResources {
+        PassResources::default()
+    }
+
     fn prepare(&mut self, bindings: &mut RenderBindings, snapshot: &WorldRenderSnapshot) {
         for pipeline in self.pipelines.iter_mut() {
             pipeline.prepare(bindings, snapshot);
@@ -49,4 +98,16 @@ impl RenderPipeline for RenderPipelineList {
             pipeline.queue(bindings, render_context, geometry_buffer, snapshot);
         }
     }
+
+    fn queue_depth_prepass(
+        &self,
+        bindings: &RenderBindings,
+        render_context: &mut RenderContext,
+        geometry_buffer: &GeometryBuffer,
+        snapshot: &WorldRenderSnapshot,
+    ) {
+        for pipeline in self.pipelines.iter() {
+            pipeline.queue_depth_prepass(bindings, render_context, geometry_buffer, snapshot);
+        }
+    }
 }
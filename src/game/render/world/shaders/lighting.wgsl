@@ -0,0 +1,53 @@
+#define_import_path lighting
+
+#import camera_env::CameraEnv;
+
+/// Light arriving from the sun on a surface with the given normal, scaled by
+/// shadow visibility (0 = full shadow, 1 = fully lit).
+fn directional_light(env: CameraEnv, normal: vec3<f32>, visibility: f32) -> vec3<f32> {
+    let N = normalize(normal);
+    let L = -normalize(env.sun_dir.xyz); // from fragment toward sun
+
+    let n_dot_l = max(dot(N, L), 0.0);
+
+    return env.sun_color.rgb * n_dot_l * visibility;
+}
+
+/// Light arriving from all directions, regardless of the surface orientation.
+fn ambient_light(env: CameraEnv) -> vec3<f32> {
+    return env.ambient_color.rgb;
+}
+
+/// Diffuse + ambient lighting, modulated by shadow visibility.
+fn diffuse(
+    env: CameraEnv,
+    normal: vec3<f32>,
+    base_color: vec3<f32>,
+    visibility: f32,              // 0 = full shadow, 1 = fully lit
+) -> vec3<f32> {
+    let light = directional_light(env, normal, visibility) + ambient_light(env);
+
+    return light * base_color;
+}
+
+/// Same as diffuse(), but fades to the fog color over distance.
+fn diffuse_with_fog(
+    env: CameraEnv,
+    normal: vec3<f32>,
+    base_color: vec3<f32>,
+    distance: f32,
+    visibility: f32,
+) -> vec3<f32> {
+    let lit_color = diffuse(env, normal, base_color, visibility);
+
+    let fog_near = env.fog_distance * env.fog_near_fraction;
+    let fog_far = env.fog_distance;
+
+    let fog_factor = linear_fog_factor(fog_near, fog_far, distance);
+
+    return mix(lit_color, env.fog_color.rgb, fog_factor);
+}
+
+fn linear_fog_factor(fog_near: f32, fog_far: f32, distance: f32) -> f32 {
+    return clamp((distance - fog_near) / (fog_far - fog_near), 0.0, 1.0);
+}
@@ -0,0 +1,26 @@
+#import camera_env::CameraEnv;
+
+@group(0) @binding(0)
+var<uniform> u_camera_env: CameraEnv;
+
+@group(1) @binding(0) var t_depth: texture_depth_2d;
+
+@vertex
+fn vertex(@builtin(vertex_index) vertex_index: u32) -> @builtin(position) vec4<f32> {
+    return fullscreen::clip_position(vertex_index);
+}
+
+@fragment
+fn fragment(@builtin(position) position: vec4<f32>) -> @location(0) vec4<f32> {
+    let pixel = fullscreen::tex_coord(textureDimensions(t_depth), position.xy);
+    let depth = textureLoad(t_depth, pixel, 0);
+
+    let near = u_camera_env.position.w;
+    let far = u_camera_env.forward.w;
+
+    // Invert the left handed perspective depth mapping to get the distance
+    // along the camera's forward axis.
+    let linear_depth = near * far / (far - depth * (far - near));
+
+    return vec4<f32>(linear_depth, 0.0, 0.0, 0.0);
+}
@@ -1,4 +1,5 @@
-#import camera_env::{CameraEnv, diffuse_with_fog};
+#import camera_env::CameraEnv;
+#import lighting::diffuse_with_fog;
 
 const FLAGS_HIGHLIGHTED: u32 = 1 << 0;
 const FLAGS_CUSTOM_POSE: u32 = 1 << 1;
@@ -6,12 +7,22 @@ const FLAGS_CUSTOM_POSE: u32 = 1 << 1;
 @group(0) @binding(0)
 var<uniform> u_camera_env: CameraEnv;
 
-@group(1) @binding(0) var u_texture: texture_2d<f32>;
+// Every material of the model, one per layer; vertices select theirs with
+// `material_index`.
+@group(1) @binding(0) var u_materials: texture_2d_array<f32>;
 @group(1) @binding(1) var u_sampler: sampler;
 
 // Default-pose, precomposed bone transforms; one entry per bone in the model.
 @group(2) @binding(0) var<storage, read> u_nodes: array<mat4x4<f32>>;
 
+// Distance along the camera forward axis to the closest opaque surface, when
+// the depth prepass ran. Very large otherwise.
+@group(4) @binding(0) var t_linear_depth: texture_2d<f32>;
+
+// Depth range over which blended surfaces fade out as they approach opaque
+// geometry behind them.
+const SOFT_DEPTH_RANGE: f32 = 50.0;
+
 // Custom-pose composed bone transforms, packed contiguously across all custom-pose
 // instances; `first_node_index` selects this instance's slice.
 @group(3) @binding(0) var<storage, read> u_custom_nodes: array<mat4x4<f32>>;
@@ -21,6 +32,7 @@ struct VertexInput {
     @location(1) normal: vec3<f32>,
     @location(2) tex_coord: vec2<f32>,
     @location(3) node_index: u32,
+    @location(10) material_index: u32,
 }
 
 struct InstanceInput {
@@ -33,11 +45,14 @@ struct InstanceInput {
 }
 
 struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
+    // Invariant so the depth prepass and the color pass produce the same depth.
+    @builtin(position) @invariant clip_position: vec4<f32>,
     @location(0) world_position: vec3<f32>,
     @location(1) world_normal: vec3<f32>,
     @location(2) tex_coord: vec2<f32>,
     @location(3) flags: u32,
+    @location(4) material_index: u32,
+    @location(5) view_depth: f32,
 }
 
 @vertex
@@ -75,9 +90,22 @@ fn vertex_main(vertex: VertexInput, instance: InstanceInput) -> VertexOutput {
         world_normal,
         tex_coord,
         instance.flags,
+        vertex.material_index,
+        dot(world_position.xyz - u_camera_env.position.xyz, u_camera_env.forward.xyz),
     );
 }
 
+fn sample_material(vertex: VertexOutput) -> vec4<f32> {
+    return textureSample(u_materials, u_sampler, vertex.tex_coord, vertex.material_index);
+}
+
+/// Fade factor for blended surfaces close in front of opaque geometry, to
+/// avoid hard lines where they intersect.
+fn soft_fade(vertex: VertexOutput) -> f32 {
+    let scene_depth = textureLoad(t_linear_depth, vec2<i32>(vertex.clip_position.xy), 0).r;
+    return saturate((scene_depth - vertex.view_depth) / SOFT_DEPTH_RANGE);
+}
+
 fn shade(vertex: VertexOutput, base_color: vec4<f32>) -> vec3<f32> {
     let distance = length(vertex.world_position - u_camera_env.position.xyz);
 
@@ -92,7 +120,7 @@ fn shade(vertex: VertexOutput, base_color: vec4<f32>) -> vec3<f32> {
 
 @fragment
 fn fragment_opaque(vertex: VertexOutput) -> geometry_buffer::OpaqueGeometryBuffer {
-    let base_color = textureSample(u_texture, u_sampler, vertex.tex_coord);
+    let base_color = sample_material(vertex);
     let lit = shade(vertex, base_color);
 
     if (vertex.flags & FLAGS_HIGHLIGHTED) != 0 {
@@ -105,7 +133,7 @@ fn fragment_opaque(vertex: VertexOutput) -> geometry_buffer::OpaqueGeometryBuffe
 
 @fragment
 fn fragment_opaque_keyed(vertex: VertexOutput) -> geometry_buffer::OpaqueGeometryBuffer {
-    let base_color = textureSample(u_texture, u_sampler, vertex.tex_coord);
+    let base_color = sample_material(vertex);
 
     // Color-keyed: black is treated as transparent.
     if base_color.r + base_color.g + base_color.b == 0.0 {
@@ -124,15 +152,27 @@ fn fragment_opaque_keyed(vertex: VertexOutput) -> geometry_buffer::OpaqueGeometr
 
 @fragment
 fn fragment_alpha(vertex: VertexOutput) -> geometry_buffer::AlphaGeometryBuffer {
-    let base_color = textureSample(u_texture, u_sampler, vertex.tex_coord);
+    let base_color = sample_material(vertex);
     let lit = shade(vertex, base_color);
 
+    let alpha = base_color.a * soft_fade(vertex);
+
     if (vertex.flags & FLAGS_HIGHLIGHTED) != 0 {
-        let h = highlight(vec4<f32>(lit, base_color.a));
+        let h = highlight(vec4<f32>(lit, alpha));
         return geometry_buffer::to_alpha_geometry_buffer(h.rgb, h.a, 1.0);
     }
 
-    return geometry_buffer::to_alpha_geometry_buffer(lit, base_color.a, 1.0);
+    return geometry_buffer::to_alpha_geometry_buffer(lit, alpha, 1.0);
+}
+
+@fragment
+fn fragment_additive(vertex: VertexOutput) -> geometry_buffer::OpaqueGeometryBuffer {
+    let base_color = sample_material(vertex);
+
+    // Additive surfaces emit light, so they are not shaded or fogged.
+    return geometry_buffer::to_opaque_geometry_buffer(
+        base_color.rgb * base_color.a * soft_fade(vertex),
+    );
 }
 
 fn highlight(color: vec4<f32>) -> vec4<f32> {
@@ -1,4 +1,5 @@
-#import camera_env::{CameraEnv, diffuse_with_fog};
+#import camera_env::CameraEnv;
+#import lighting::diffuse_with_fog;
 
 @group(0) @binding(0)
 var<uniform> u_camera_env: CameraEnv;
@@ -8,6 +9,7 @@ struct TerrainData {
     chunks_dim: vec2<u32>,
     cell_size: f32,
     strata_descent: f32,
+    strata_blend_height: f32,
 }
 
 @group(1) @binding(0) var<uniform> u_terrain_data: TerrainData;
@@ -23,7 +25,8 @@ struct InstanceInput {
 }
 
 struct VertexOutput {
-    @builtin(position) clip_position: vec4<f32>,
+    // Invariant so the depth prepass and the color pass produce the same depth.
+    @builtin(position) @invariant clip_position: vec4<f32>,
     @location(0) world_position: vec3<f32>,
     @location(1) normal: vec3<f32>,
     @location(2) tex_coord: vec2<f32>,
@@ -133,9 +136,80 @@ fn vertex_terrain(
     return make_vertex_terrain(chunk, vertex_index, 0.0);
 }
 
+/// Size of the whole terrain in world units.
+fn terrain_world_size() -> vec2<f32> {
+    return vec2<f32>(u_terrain_data.cells_dim) * u_terrain_data.cell_size;
+}
+
+/// Terrain texture coordinates for a world position.
+fn terrain_tex_coord(world_position: vec3<f32>) -> vec2<f32> {
+    return world_position.xy / terrain_world_size();
+}
+
+/// Height of the terrain at a world position, bilinearly interpolated between nodes.
+fn terrain_height_at(world_position: vec3<f32>) -> f32 {
+    let cell = max(world_position.xy / u_terrain_data.cell_size, vec2<f32>(0.0));
+    let base = vec2<u32>(floor(cell));
+    let t = fract(cell);
+
+    let h00 = get_node(base).w;
+    let h10 = get_node(base + vec2<u32>(1u, 0u)).w;
+    let h01 = get_node(base + vec2<u32>(0u, 1u)).w;
+    let h11 = get_node(base + vec2<u32>(1u, 1u)).w;
+
+    return mix(mix(h00, h10, t.x), mix(h01, h11, t.x), t.y);
+}
+
+/// Strata texture coordinates for a point on the terrain edge, laid out the
+/// same way as `strata_vertex` so both sides of the seam sample the same texels.
+fn strata_tex_coord_at_edge(world_position: vec3<f32>, side: u32) -> vec2<f32> {
+    let chunk_size = f32(CELLS_PER_CHUNK) * u_terrain_data.cell_size;
+    let along = fract(world_position.xy / chunk_size);
+
+    var u: f32;
+    switch side {
+        case SOUTH: { u = 1.0 - along.x; }
+        case WEST:  { u = 1.0 - along.y; }
+        case NORTH: { u = along.x; }
+        default:    { u = along.y; } // EAST
+    }
+
+    return vec2<f32>(u, world_position.z / chunk_size);
+}
+
+/// How much of the other surface's texture to mix in at `distance` from the
+/// seam. Both sides use half at the seam so the colors meet without a line.
+fn strata_blend_factor(distance: f32) -> f32 {
+    let band = max(u_terrain_data.strata_blend_height, 1e-3);
+    return 0.5 * (1.0 - smoothstep(0.0, band, distance));
+}
+
 @fragment
 fn fragment_terrain(vertex: VertexOutput) -> geometry_buffer::OpaqueGeometryBuffer {
-    let base_color = textureSample(u_terrain_texture, u_terrain_sampler, vertex.tex_coord);
+    var base_color = textureSample(u_terrain_texture, u_terrain_sampler, vertex.tex_coord);
+
+    // Fade toward the strata texture near the edge of the terrain.
+    let world_size = terrain_world_size();
+    let p = vertex.world_position.xy;
+    let edge_distances = vec4<f32>(p.y, world_size.x - p.x, world_size.y - p.y, p.x);
+    let edge_distance = min(min(edge_distances.x, edge_distances.y), min(edge_distances.z, edge_distances.w));
+
+    var side = EAST;
+    if edge_distance == edge_distances.x {
+        side = SOUTH;
+    } else if edge_distance == edge_distances.y {
+        side = WEST;
+    } else if edge_distance == edge_distances.z {
+        side = NORTH;
+    }
+
+    let strata_color = textureSample(
+        u_strata_texture,
+        u_terrain_sampler,
+        strata_tex_coord_at_edge(vertex.world_position, side),
+    );
+    base_color = mix(base_color, strata_color, strata_blend_factor(edge_distance));
+
     let distance = length(vertex.world_position - u_camera_env.position.xyz);
 
     let d = diffuse_with_fog(
@@ -251,7 +325,16 @@ fn strata_vertex(
 
 @fragment
 fn strata_fragment(vertex: VertexOutput) -> geometry_buffer::OpaqueGeometryBuffer {
-    let base_color = textureSample(u_strata_texture, u_terrain_sampler, vertex.tex_coord);
+    var base_color = textureSample(u_strata_texture, u_terrain_sampler, vertex.tex_coord);
+
+    // Fade toward the terrain texture close to the top edge of the strata.
+    let depth = terrain_height_at(vertex.world_position) - vertex.world_position.z;
+    let terrain_color = textureSample(
+        u_terrain_texture,
+        u_terrain_sampler,
+        terrain_tex_coord(vertex.world_position),
+    );
+    base_color = mix(base_color, terrain_color, strata_blend_factor(max(depth, 0.0)));
     let distance = length(vertex.world_position - u_camera_env.position.xyz);
 
     let d = diffuse_with_fog(
@@ -1,9 +1,13 @@
+use glam::UVec2;
 use wgpu::util::DeviceExt;
 
 use crate::{
     engine::{
         growing_buffer::GrowingBuffer,
-        renderer::RenderContext,
+        renderer::{
+            BindGroupLayoutEntries, DepthStencilDescriptor, RenderContext, TextureDescriptor,
+            TextureUsageIntent,
+        },
         shader_cache::{ShaderCache, ShaderSource},
     },
     game::{
@@ -14,6 +18,7 @@ use crate::{
             world::{
                 camera_render_pipeline::CameraEnvironmentLayout,
                 render_bindings::RenderBindings,
+                render_graph::{PassResources, RenderResource},
                 render_layouts::RenderLayouts,
                 render_pipeline::RenderPipeline,
                 world_render_snapshot::{TerrainChunk, WorldRenderSnapshot},
@@ -29,6 +34,9 @@ pub struct TerrainRenderPipeline {
     /// Buffer holding indices to render a wireframe over a single chunk of various LOD's.
     chunk_wireframe_indices_buffer: wgpu::Buffer,
 
+    /// The height map nodes as [normal, altitude].
+    height_map_buffer: wgpu::Buffer,
+
     /// Bind group for all terrain GPU resources.
     terrain_bind_group: wgpu::BindGroup,
 
@@ -44,6 +52,11 @@ pub struct TerrainRenderPipeline {
     /// Pipeline to render the stratas.
     strata_pipeline: wgpu::RenderPipeline,
 
+    /// Depth-only variants of `terrain_pipeline` and `strata_pipeline` for the
+    /// depth prepass.
+    terrain_depth_pipeline: wgpu::RenderPipeline,
+    strata_depth_pipeline: wgpu::RenderPipeline,
+
     /// Buffer holding instance data for strata to be rendered per frame.
     strata_instances_buffer: PerFrame<GrowingBuffer<gpu::ChunkInstanceData>>,
 }
@@ -67,7 +80,8 @@ impl TerrainRenderPipeline {
                 chunks_dim: [u32; 2],
                 cell_size: f32,
                 strata_descent: f32,
-                _pad: [u32; 2],
+                strata_blend_height: f32,
+                _pad: u32,
             }
 
             let terrain_data = TerrainData {
@@ -75,6 +89,7 @@ impl TerrainRenderPipeline {
                 chunks_dim: chunks_dim.to_array(),
                 cell_size: height_map.cell_size,
                 strata_descent: Self::STRATA_DESCENT,
+                strata_blend_height: Self::STRATA_BLEND_HEIGHT,
                 _pad: Default::default(),
             };
 
@@ -120,6 +135,7 @@ impl TerrainRenderPipeline {
             wgpu::AddressMode::Repeat,
             wgpu::FilterMode::Linear,
             wgpu::FilterMode::Linear,
+            Self::TERRAIN_ANISOTROPY,
         );
 
         let terrain_bind_group_layout = {
@@ -127,54 +143,23 @@ impl TerrainRenderPipeline {
                 .device
                 .create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
                     label: Some("terrain_bind_group_layout"),
-                    entries: &[
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 0,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Uniform,
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 1,
-                            visibility: wgpu::ShaderStages::VERTEX,
-                            ty: wgpu::BindingType::Buffer {
-                                ty: wgpu::BufferBindingType::Storage { read_only: true },
-                                has_dynamic_offset: false,
-                                min_binding_size: None,
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 2,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Texture {
-                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
-                                multisampled: false,
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 3,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Texture {
-                                sample_type: wgpu::TextureSampleType::Float { filterable: true },
-                                view_dimension: wgpu::TextureViewDimension::D2,
-                                multisampled: false,
-                            },
-                            count: None,
-                        },
-                        wgpu::BindGroupLayoutEntry {
-                            binding: 4,
-                            visibility: wgpu::ShaderStages::FRAGMENT,
-                            ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
-                            count: None,
-                        },
-                    ],
+                    entries: &BindGroupLayoutEntries::new()
+                        .uniform(0)
+                        .visibility(wgpu::ShaderStages::VERTEX)
+                        .storage(1)
+                        .visibility(wgpu::ShaderStages::VERTEX)
+                        .texture(
+                            2,
+                            wgpu::TextureViewDimension::D2,
+                            wgpu::TextureSampleType::Float { filterable: true },
+                        )
+                        .texture(
+                            3,
+                            wgpu::TextureViewDimension::D2,
+                            wgpu::TextureSampleType::Float { filterable: true },
+                        )
+                        .sampler(4, wgpu::SamplerBindingType::Filtering)
+                        .build(),
                 })
         };
 
@@ -280,13 +265,9 @@ impl TerrainRenderPipeline {
                         }],
                     },
                     primitive: wgpu::PrimitiveState::default(),
-                    depth_stencil: Some(wgpu::DepthStencilState {
-                        format: wgpu::TextureFormat::Depth32Float,
-                        depth_write_enabled: Some(true),
-                        depth_compare: Some(wgpu::CompareFunction::LessEqual),
-                        stencil: wgpu::StencilState::default(),
-                        bias: wgpu::DepthBiasState::default(),
-                    }),
+                    depth_stencil: Some(
+                        DepthStencilDescriptor::new(wgpu::CompareFunction::LessEqual, true).into(),
+                    ),
                     multisample: wgpu::MultisampleState::default(),
                     fragment: Some(wgpu::FragmentState {
                         module,
@@ -320,13 +301,9 @@ impl TerrainRenderPipeline {
                         cull_mode: None,
                         ..Default::default()
                     },
-                    depth_stencil: Some(wgpu::DepthStencilState {
-                        format: wgpu::TextureFormat::Depth32Float,
-                        depth_write_enabled: Some(false),
-                        depth_compare: Some(wgpu::CompareFunction::LessEqual),
-                        stencil: wgpu::StencilState::default(),
-                        bias: wgpu::DepthBiasState::default(),
-                    }),
+                    depth_stencil: Some(
+                        DepthStencilDescriptor::new(wgpu::CompareFunction::LessEqual, false).into(),
+                    ),
                     multisample: wgpu::MultisampleState::default(),
                     fragment: Some(wgpu::FragmentState {
                         module,
@@ -360,13 +337,9 @@ impl TerrainRenderPipeline {
                         cull_mode: Some(wgpu::Face::Back),
                         ..Default::default()
                     },
-                    depth_stencil: Some(wgpu::DepthStencilState {
-                        format: wgpu::TextureFormat::Depth32Float,
-                        depth_write_enabled: Some(true),
-                        depth_compare: Some(wgpu::CompareFunction::LessEqual),
-                        stencil: wgpu::StencilState::default(),
-                        bias: wgpu::DepthBiasState::default(),
-                    }),
+                    depth_stencil: Some(
+                        DepthStencilDescriptor::new(wgpu::CompareFunction::LessEqual, true).into(),
+                    ),
                     multisample: wgpu::MultisampleState::default(),
                     fragment: Some(wgpu::FragmentState {
                         module,
@@ -378,6 +351,49 @@ impl TerrainRenderPipeline {
                     cache: None,
                 });
 
+        let create_depth_pipeline = |label, entry_point, primitive| {
+            globals::gpu()
+                .device
+                .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                    label: Some(label),
+                    layout: Some(&layout),
+                    vertex: wgpu::VertexState {
+                        module,
+                        entry_point: Some(entry_point),
+                        compilation_options: wgpu::PipelineCompilationOptions::default(),
+                        buffers: &[wgpu::VertexBufferLayout {
+                            array_stride: std::mem::size_of::<gpu::ChunkInstanceData>()
+                                as wgpu::BufferAddress,
+                            step_mode: wgpu::VertexStepMode::Instance,
+                            attributes: &instance_attrs,
+                        }],
+                    },
+                    primitive,
+                    depth_stencil: Some(
+                        DepthStencilDescriptor::new(wgpu::CompareFunction::LessEqual, true).into(),
+                    ),
+                    multisample: wgpu::MultisampleState::default(),
+                    fragment: None,
+                    multiview_mask: None,
+                    cache: None,
+                })
+        };
+
+        let terrain_depth_pipeline = create_depth_pipeline(
+            "terrain_depth_pipeline",
+            "vertex_terrain",
+            wgpu::PrimitiveState::default(),
+        );
+        let strata_depth_pipeline = create_depth_pipeline(
+            "strata_depth_pipeline",
+            "strata_vertex",
+            wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                cull_mode: Some(wgpu::Face::Back),
+                ..Default::default()
+            },
+        );
+
         let capacity = 1 << 7;
         let strata_instances_buffer = PerFrame::new(|index| {
             GrowingBuffer::new(
@@ -388,6 +404,7 @@ impl TerrainRenderPipeline {
         });
 
         Self {
+            height_map_buffer,
             chunk_indices_buffer,
             chunk_wireframe_indices_buffer,
 
@@ -396,6 +413,8 @@ impl TerrainRenderPipeline {
             terrain_pipeline,
             terrain_wireframe_pipeline,
             strata_pipeline,
+            terrain_depth_pipeline,
+            strata_depth_pipeline,
             terrain_chunk_instances_buffer,
 
             strata_instances_buffer,
@@ -404,31 +423,60 @@ impl TerrainRenderPipeline {
 }
 
 impl RenderPipeline for TerrainRenderPipeline {
-    fn prepare(&mut self, _bindings: &mut RenderBindings, snapshot: &WorldRenderSnapshot) {
-        let chunk_instances: Vec<_> = snapshot
-            .terrain
-            .chunks
-            .iter()
-            .map(|chunk| gpu::ChunkInstanceData {
-                coord: chunk.coord.as_uvec2().to_array(),
-                lod: chunk.lod,
-                flags: chunk.flags,
-            })
-            .collect();
+    fn name(&self) -> &'static str {
+        "terrain"
+    }
+
+    fn resources(&self) -> PassResources {
+        PassResources {
+            reads: &[
+                RenderResource::CameraEnvironment,
+                RenderResource::GeometryDepth,
+            ],
+            writes: &[RenderResource::GeometryColor, RenderResource::GeometryDepth],
+        }
+    }
+
+    fn prepare(&mut self, bindings: &mut RenderBindings, snapshot: &WorldRenderSnapshot) {
+        // Only the edited parts of the height map are uploaded again.
+        for row in snapshot.terrain.height_map_updates.iter() {
+            globals::gpu().queue.write_buffer(
+                &self.height_map_buffer,
+                row.first_node as u64 * std::mem::size_of::<[f32; 4]>() as u64,
+                bytemuck::cast_slice(&row.nodes),
+            );
+        }
+
+        let scratch = bindings.scratch();
+
+        let chunk_instances = bumpalo::collections::Vec::from_iter_in(
+            snapshot
+                .terrain
+                .chunks
+                .iter()
+                .map(|chunk| gpu::ChunkInstanceData {
+                    coord: chunk.coord.as_uvec2().to_array(),
+                    lod: chunk.lod,
+                    flags: chunk.flags,
+                }),
+            scratch,
+        );
 
         let terrain_chunk_instances_buffer = self.terrain_chunk_instances_buffer.advance();
         terrain_chunk_instances_buffer.write(chunk_instances.as_slice());
 
-        let strata_instances: Vec<_> = snapshot
-            .terrain
-            .strata
-            .iter()
-            .map(|chunk| gpu::ChunkInstanceData {
-                coord: chunk.coord.as_uvec2().to_array(),
-                lod: chunk.lod,
-                flags: chunk.flags,
-            })
-            .collect();
+        let strata_instances = bumpalo::collections::Vec::from_iter_in(
+            snapshot
+                .terrain
+                .strata
+                .iter()
+                .map(|chunk| gpu::ChunkInstanceData {
+                    coord: chunk.coord.as_uvec2().to_array(),
+                    lod: chunk.lod,
+                    flags: chunk.flags,
+                }),
+            scratch,
+        );
 
         let strata_instances_buffer = self.strata_instances_buffer.advance();
         strata_instances_buffer.write(strata_instances.as_slice());
@@ -441,122 +489,173 @@ impl RenderPipeline for TerrainRenderPipeline {
         geometry_buffer: &GeometryBuffer,
         snapshot: &WorldRenderSnapshot,
     ) {
-        let mut render_pass = geometry_buffer
-            .begin_opaque_render_pass(&mut render_context.encoder, "terrain_render_pass");
-
-        // Strata
-        {
-            render_pass.set_pipeline(&self.strata_pipeline);
-            render_pass.set_vertex_buffer(0, self.strata_instances_buffer.current().slice(..));
-            render_pass.set_bind_group(
-                0,
-                &render_bindings.camera_env_buffer.current().bind_group,
-                &[],
+        render_context.debug_group("terrain", |render_context| {
+            let mut render_pass = geometry_buffer
+                .begin_opaque_render_pass(&mut render_context.encoder, "terrain_render_pass");
+
+            render_pass.insert_debug_marker("strata");
+            self.draw_strata(
+                &mut render_pass,
+                render_bindings,
+                snapshot,
+                &self.strata_pipeline,
             );
-            render_pass.set_bind_group(1, &self.terrain_bind_group, &[]);
 
-            for (i, strata_instance) in snapshot.terrain.strata.iter().enumerate() {
-                let lod = strata_instance.lod;
+            render_pass.insert_debug_marker("chunks");
+            self.draw_chunks(
+                &mut render_pass,
+                render_bindings,
+                snapshot,
+                &self.terrain_pipeline,
+            );
 
-                // Cells along this edge at this LOD.
-                let cells = Terrain::CELLS_PER_CHUNK >> lod;
-                let nodes = cells + 1;
+            if snapshot.terrain.render_wireframe {
+                render_pass.insert_debug_marker("wireframe");
+                render_pass.set_pipeline(&self.terrain_wireframe_pipeline);
+                render_pass
+                    .set_vertex_buffer(0, self.terrain_chunk_instances_buffer.current().slice(..));
+                render_pass.set_index_buffer(
+                    self.chunk_wireframe_indices_buffer.slice(..),
+                    wgpu::IndexFormat::Uint32,
+                );
+                render_pass.set_bind_group(
+                    0,
+                    &render_bindings.camera_env_buffer.current().bind_group,
+                    &[],
+                );
+                render_pass.set_bind_group(1, &self.terrain_bind_group, &[]);
+
+                let draw_commands = Self::build_draw_commands(
+                    &snapshot.terrain.chunks,
+                    &Self::WIREFRAME_INDEX_RANGES,
+                );
+
+                for (indices, instances) in draw_commands {
+                    if instances.is_empty() {
+                        continue;
+                    }
+                    render_pass.draw_indexed(indices, 0, instances);
+                }
+            }
+        });
+    }
 
-                // 2 vertices per node: bottom + top
-                let vertex_count = 2 * nodes;
+    fn queue_depth_prepass(
+        &self,
+        render_bindings: &RenderBindings,
+        render_context: &mut RenderContext,
+        geometry_buffer: &GeometryBuffer,
+        snapshot: &WorldRenderSnapshot,
+    ) {
+        let mut render_pass = geometry_buffer
+            .begin_depth_prepass(&mut render_context.encoder, "terrain_depth_prepass");
 
-                let vertices = 0..vertex_count;
-                let instances = (i as u32)..(i as u32 + 1);
+        self.draw_strata(
+            &mut render_pass,
+            render_bindings,
+            snapshot,
+            &self.strata_depth_pipeline,
+        );
+        self.draw_chunks(
+            &mut render_pass,
+            render_bindings,
+            snapshot,
+            &self.terrain_depth_pipeline,
+        );
+    }
+}
 
-                render_pass.draw(vertices, instances);
-            }
-        }
+impl TerrainRenderPipeline {
+    /// Draw the strata around the edges of the terrain with `pipeline`.
+    fn draw_strata(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        render_bindings: &RenderBindings,
+        snapshot: &WorldRenderSnapshot,
+        pipeline: &wgpu::RenderPipeline,
+    ) {
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, self.strata_instances_buffer.current().slice(..));
+        render_pass.set_bind_group(
+            0,
+            &render_bindings.camera_env_buffer.current().bind_group,
+            &[],
+        );
+        render_pass.set_bind_group(1, &self.terrain_bind_group, &[]);
 
-        // Terrain Chunks
-        {
-            render_pass.set_pipeline(&self.terrain_pipeline);
-            render_pass
-                .set_vertex_buffer(0, self.terrain_chunk_instances_buffer.current().slice(..));
-            render_pass.set_index_buffer(
-                self.chunk_indices_buffer.slice(..),
-                wgpu::IndexFormat::Uint32,
-            );
-            render_pass.set_bind_group(
-                0,
-                &render_bindings.camera_env_buffer.current().bind_group,
-                &[],
-            );
-            render_pass.set_bind_group(1, &self.terrain_bind_group, &[]);
+        for (i, strata_instance) in snapshot.terrain.strata.iter().enumerate() {
+            let lod = strata_instance.lod;
 
-            let draw_commands =
-                Self::build_draw_commands(&snapshot.terrain.chunks, &Self::INDEX_RANGES);
+            // Cells along this edge at this LOD.
+            let cells = Terrain::CELLS_PER_CHUNK >> lod;
+            let nodes = cells + 1;
 
-            for (indices, instances) in draw_commands {
-                if instances.is_empty() {
-                    continue;
-                }
-                render_pass.draw_indexed(indices, 0, instances);
-            }
+            // 2 vertices per node: bottom + top
+            let vertex_count = 2 * nodes;
+
+            let vertices = 0..vertex_count;
+            let instances = (i as u32)..(i as u32 + 1);
+
+            render_pass.draw(vertices, instances);
         }
+    }
 
-        if snapshot.terrain.render_wireframe {
-            render_pass.set_pipeline(&self.terrain_wireframe_pipeline);
-            render_pass
-                .set_vertex_buffer(0, self.terrain_chunk_instances_buffer.current().slice(..));
-            render_pass.set_index_buffer(
-                self.chunk_wireframe_indices_buffer.slice(..),
-                wgpu::IndexFormat::Uint32,
-            );
-            render_pass.set_bind_group(
-                0,
-                &render_bindings.camera_env_buffer.current().bind_group,
-                &[],
-            );
-            render_pass.set_bind_group(1, &self.terrain_bind_group, &[]);
+    /// Draw the visible terrain chunks with `pipeline`.
+    fn draw_chunks(
+        &self,
+        render_pass: &mut wgpu::RenderPass,
+        render_bindings: &RenderBindings,
+        snapshot: &WorldRenderSnapshot,
+        pipeline: &wgpu::RenderPipeline,
+    ) {
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, self.terrain_chunk_instances_buffer.current().slice(..));
+        render_pass.set_index_buffer(
+            self.chunk_indices_buffer.slice(..),
+            wgpu::IndexFormat::Uint32,
+        );
+        render_pass.set_bind_group(
+            0,
+            &render_bindings.camera_env_buffer.current().bind_group,
+            &[],
+        );
+        render_pass.set_bind_group(1, &self.terrain_bind_group, &[]);
 
-            let draw_commands =
-                Self::build_draw_commands(&snapshot.terrain.chunks, &Self::WIREFRAME_INDEX_RANGES);
+        let draw_commands =
+            Self::build_draw_commands(&snapshot.terrain.chunks, &Self::INDEX_RANGES);
 
-            for (indices, instances) in draw_commands {
-                if instances.is_empty() {
-                    continue;
-                }
-                render_pass.draw_indexed(indices, 0, instances);
+        for (indices, instances) in draw_commands {
+            if instances.is_empty() {
+                continue;
             }
+            render_pass.draw_indexed(indices, 0, instances);
         }
     }
-}
 
-impl TerrainRenderPipeline {
     const STRATA_DESCENT: f32 = -20_000.0;
 
+    /// Height of the band below the terrain edge over which the strata texture
+    /// fades into the terrain texture, hiding the hard seam between them.
+    const STRATA_BLEND_HEIGHT: f32 = 150.0;
+
+    /// Anisotropic filtering applied to the terrain sampler to keep distant ground sharp at
+    /// grazing angles.
+    const TERRAIN_ANISOTROPY: u16 = 8;
+
     const INDEX_RANGES: [std::ops::Range<u32>; 4] = [0..384, 384..480, 480..504, 504..510];
     const WIREFRAME_INDEX_RANGES: [std::ops::Range<u32>; 4] =
         [0..512, 512..640, 640..672, 672..680];
 
     fn create_texture(label: &str, image: &image::RgbaImage) -> wgpu::TextureView {
         let (width, height) = (image.width(), image.height());
-        let size = wgpu::Extent3d {
-            width,
-            height,
-            depth_or_array_layers: 1,
-        };
-        let mip_level_count = (width.max(height) as f32).log2().floor() as u32 + 1;
-
-        let texture = globals::gpu()
-            .device
-            .create_texture(&wgpu::TextureDescriptor {
-                label: Some(label),
-                size,
-                mip_level_count,
-                sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::Rgba8UnormSrgb,
-                usage: wgpu::TextureUsages::COPY_DST
-                    | wgpu::TextureUsages::RENDER_ATTACHMENT
-                    | wgpu::TextureUsages::TEXTURE_BINDING,
-                view_formats: &[],
-            });
+        let descriptor = TextureDescriptor::new(
+            label,
+            UVec2::new(width, height),
+            wgpu::TextureFormat::Rgba8UnormSrgb,
+            TextureUsageIntent::SampledMipped,
+        );
+        let size = descriptor.extent();
+        let texture = descriptor.create(&globals::gpu().device);
 
         globals::gpu().queue.write_texture(
             wgpu::TexelCopyTextureInfo {
@@ -577,23 +676,42 @@ impl TerrainRenderPipeline {
         texture.create_view(&wgpu::TextureViewDescriptor::default())
     }
 
+    /// Create a sampler. `anisotropy_clamp` is clamped to what the device supports; a value of 1
+    /// disables anisotropic filtering.
     fn create_sampler(
         label: &str,
         address_mode: wgpu::AddressMode,
         mag_filter: wgpu::FilterMode,
         min_filter: wgpu::FilterMode,
+        anisotropy_clamp: u16,
     ) -> wgpu::Sampler {
-        globals::gpu()
-            .device
-            .create_sampler(&wgpu::SamplerDescriptor {
-                label: Some(label),
-                address_mode_u: address_mode,
-                address_mode_v: address_mode,
-                address_mode_w: address_mode,
-                mag_filter,
-                min_filter,
-                ..Default::default()
-            })
+        let gpu = globals::gpu();
+
+        // Anisotropic filtering requires all filters to be linear.
+        let anisotropy_clamp =
+            if mag_filter == wgpu::FilterMode::Linear && min_filter == wgpu::FilterMode::Linear {
+                gpu.clamp_anisotropy(anisotropy_clamp)
+            } else {
+                1
+            };
+
+        let mipmap_filter = if anisotropy_clamp > 1 {
+            wgpu::MipmapFilterMode::Linear
+        } else {
+            wgpu::MipmapFilterMode::Nearest
+        };
+
+        gpu.device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some(label),
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            mag_filter,
+            min_filter,
+            mipmap_filter,
+            anisotropy_clamp,
+            ..Default::default()
+        })
     }
 }
 
@@ -1,8 +1,14 @@
+use std::sync::Arc;
+
+use ahash::HashMap;
 use bevy_ecs::prelude::*;
 use glam::{IVec2, Mat4, Vec2, Vec3, Vec4};
 
 use crate::{
-    engine::{gizmos::GizmoVertex, storage::Handle},
+    engine::{
+        gizmos::{GizmoVertex, StaticGizmoHandle},
+        storage::Handle,
+    },
     game::{assets::model::Model, math::Frustum, sim::sequences::Pose},
 };
 
@@ -36,22 +42,34 @@ pub struct Environment {
     /// Ambient term for lighting.
     pub ambient_color: Vec3,
 
-    /// Color of the fog.
+    /// Color of the fog, in linear space.
     pub fog_color: Vec3,
     /// Far distance of the fog.
     pub fog_distance: f32,
     /// Near distance for the fog calculation as a fraction of the far distance.
     pub fog_near_fraction: f32,
+
+    /// Color the geometry buffer is cleared to before rendering, in linear space.
+    pub clear_color: Vec3,
 }
 
 #[derive(Default)]
 pub struct Terrain {
+    /// Rows of height map nodes changed since the last frame.
+    pub height_map_updates: Vec<HeightMapRow>,
     pub chunks: Vec<TerrainChunk>,
     pub strata: Vec<TerrainChunk>,
     pub strata_side_count: [u32; 4],
     pub render_wireframe: bool,
 }
 
+/// Consecutive height map nodes as [normal, altitude].
+pub struct HeightMapRow {
+    /// Index of the first node in the height map.
+    pub first_node: u32,
+    pub nodes: Vec<[f32; 4]>,
+}
+
 #[derive(Clone, Copy, Default)]
 pub struct TerrainChunk {
     pub coord: IVec2,
@@ -61,16 +79,29 @@ pub struct TerrainChunk {
 
 #[derive(Default)]
 pub struct Models {
+    /// Every model instance in the world. Kept between frames and only copied
+    /// from the world again for entities whose transform, model or pose
+    /// changed. This only saves the copy out of the world; the renderer still
+    /// builds and uploads GPU data for every visible instance each frame.
+    pub instances: HashMap<Entity, ModelInstance>,
     /// A list of models to render.
     pub models: Vec<ModelToRender>,
 }
 
 #[derive(Clone)]
-pub struct ModelToRender {
+pub struct ModelInstance {
     pub model: Handle<Model>,
     pub transform: Mat4,
     /// If specified, overrides the default skeleton pose.
     pub pose: Option<Pose>,
+}
+
+#[derive(Clone)]
+pub struct ModelToRender {
+    /// The entity of the instance in [Models::instances].
+    pub entity: Entity,
+    /// The model of the instance, used to batch instances by model.
+    pub model: Handle<Model>,
     pub highlighted: bool,
 }
 
@@ -83,6 +114,12 @@ pub struct UiRect {
 #[derive(Default)]
 pub struct Gizmos {
     pub vertices: Vec<GizmoVertex>,
+    /// Geometry that is uploaded once and kept on the GPU while it is drawn.
+    pub static_draws: Vec<(StaticGizmoHandle, Arc<[GizmoVertex]>)>,
+    /// Static geometry that was removed and can be freed.
+    pub removed_static: Vec<StaticGizmoHandle>,
+    /// Modulate line colors by view depth.
+    pub color_by_depth: bool,
 }
 
 #[derive(Default, Resource)]
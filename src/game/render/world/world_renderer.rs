@@ -8,13 +8,15 @@ use crate::{
     },
     game::{
         render::{
-            geometry_buffer::GeometryBuffer,
+            geometry_buffer::{GeometryBuffer, GeometryBufferLayouts},
             world::{
                 WorldRenderSnapshot,
                 camera_render_pipeline::CameraRenderPipeline,
                 gizmo_render_pipeline::GizmoRenderPipeline,
+                linear_depth::{DepthTextureLayout, LinearDepthLayout, LinearDepthPass},
                 model_render_pipeline::ModelRenderPipeline,
                 render_bindings::RenderBindings,
+                render_graph::RenderResource,
                 render_pipeline::{RenderPipeline, RenderPipelineList},
                 terrain_render_pipeline::TerrainRenderPipeline,
             },
@@ -26,47 +28,70 @@ use crate::{
 use super::render_layouts::RenderLayouts;
 
 pub struct WorldRenderer {
-    gbuffer_layout: wgpu::BindGroupLayout,
+    gbuffer_layouts: GeometryBufferLayouts,
     gbuffers: Storage<GeometryBuffer>,
 
     pipelines: RenderPipelineList,
     bindings: RenderBindings,
+
+    /// Fills the gbuffer linear depth after the depth prepass.
+    linear_depth_pass: LinearDepthPass,
+    /// Render opaque depth before the main passes, so they can read the
+    /// linear depth of the scene.
+    pub depth_prepass: bool,
 }
 
 impl WorldRenderer {
     #[allow(clippy::too_many_arguments)]
-    pub fn new(gbuffer_layout: &wgpu::BindGroupLayout, terrain: &Terrain) -> Self {
+    pub fn new(
+        gbuffer_layout: &wgpu::BindGroupLayout,
+        terrain: &Terrain,
+        shader_cache: &mut ShaderCache,
+    ) -> Self {
+        let start = std::time::Instant::now();
+
         // Warm up the shader cache.
-        let mut shader_cache = ShaderCache::default();
         shader_cache.preload_all();
 
         let mut layouts = RenderLayouts::default();
         let bindings = RenderBindings::new(&mut layouts);
 
-        let mut pipelines = RenderPipelineList::default();
+        let gbuffer_layouts = GeometryBufferLayouts {
+            gbuffer: gbuffer_layout.clone(),
+            depth: layouts.get::<DepthTextureLayout>().clone(),
+            linear_depth: layouts.get::<LinearDepthLayout>().clone(),
+        };
+
+        let linear_depth_pass = LinearDepthPass::new(&mut layouts, shader_cache);
+
+        let mut pipelines = RenderPipelineList::new(RenderResource::CLEARED);
 
         pipelines.push(CameraRenderPipeline);
 
         pipelines.push(TerrainRenderPipeline::new(
             &mut layouts,
-            &mut shader_cache,
+            shader_cache,
             terrain,
         ));
 
-        pipelines.push(ModelRenderPipeline::new(&mut layouts, &mut shader_cache));
-        pipelines.push(GizmoRenderPipeline::new(&mut layouts, &mut shader_cache));
+        pipelines.push(ModelRenderPipeline::new(&mut layouts, shader_cache));
+        pipelines.push(GizmoRenderPipeline::new(&mut layouts, shader_cache));
+
+        tracing::info!("Created world render pipelines in {:?}.", start.elapsed());
 
         Self {
-            gbuffer_layout: gbuffer_layout.clone(),
+            gbuffer_layouts,
             gbuffers: Storage::default(),
             pipelines,
             bindings,
+            linear_depth_pass,
+            depth_prepass: false,
         }
     }
 
     /// Register a new gbuffer of the given size and return a handle to it.
     pub fn register_gbuffer(&mut self, size: UVec2) -> Handle<GeometryBuffer> {
-        let gbuffer = GeometryBuffer::new(self.gbuffer_layout.clone(), size);
+        let gbuffer = GeometryBuffer::new(self.gbuffer_layouts.clone(), size);
         self.gbuffers.insert(gbuffer)
     }
 
@@ -91,11 +116,12 @@ impl WorldRenderer {
     }
 
     pub fn prepare(&mut self, snapshot: &WorldRenderSnapshot) {
+        self.bindings.reset_scratch();
         self.pipelines.prepare(&mut self.bindings, snapshot);
     }
 
     /// Clear the gbuffer behind `handle` and queue every gbuffer-writing
-    /// pipeline into it. The compositor (owned by `WindowRenderer`) is
+    /// pipeline into it, preceded by the depth prepass if enabled. The compositor (owned by `WindowRenderer`) is
     /// responsible for moving the result onto a render target later.
     pub fn render_to(
         &self,
@@ -107,7 +133,23 @@ impl WorldRenderer {
             return;
         };
 
-        gbuffer.clear(&mut render_context.encoder, snapshot.environment.fog_color);
+        gbuffer.clear(
+            &mut render_context.encoder,
+            snapshot.environment.clear_color,
+        );
+
+        if self.depth_prepass {
+            render_context.debug_group("depth_prepass", |render_context| {
+                self.pipelines.queue_depth_prepass(
+                    &self.bindings,
+                    render_context,
+                    gbuffer,
+                    snapshot,
+                );
+                self.linear_depth_pass
+                    .queue(&self.bindings, render_context, gbuffer);
+            });
+        }
 
         self.pipelines
             .queue(&self.bindings, render_context, gbuffer, snapshot);
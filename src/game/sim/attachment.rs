@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use bevy_ecs::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    engine::{storage::Handle, transform::Transform},
+    game::{assets::model::Model, globals},
+};
+
+/// Attaches an entity to a bone of another entity's model. The [Transform] of
+/// the attached entity is recalculated every frame from the parent's pose.
+#[derive(Component, Debug)]
+pub struct BoneAttachment {
+    /// The entity the attachment follows.
+    pub parent: Entity,
+    /// Index of the bone in the parent model's skeleton.
+    pub bone_index: u32,
+    /// Offset relative to the bone.
+    pub local_offset: Transform,
+}
+
+/// Reasons a bone attachment could not be made.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AttachError {
+    #[error("attachment parent {0} does not have a model")]
+    NoModel(Entity),
+
+    #[error("model of attachment parent {0} is not loaded")]
+    ModelNotLoaded(Entity),
+
+    #[error("bone not found: {0}")]
+    BoneNotFound(String),
+}
+
+/// Attach `child` to the bone named `bone_name` in the model of `parent`.
+pub fn attach_to_bone(
+    world: &mut World,
+    child: Entity,
+    parent: Entity,
+    bone_name: &str,
+    local_offset: Transform,
+) -> Result<(), AttachError> {
+    attach_to_model_bone(world, child, parent, bone_name, local_offset, |handle| {
+        globals::models().get(handle)
+    })
+}
+
+/// [attach_to_bone] with the parent's model looked up through `get_model`.
+fn attach_to_model_bone(
+    world: &mut World,
+    child: Entity,
+    parent: Entity,
+    bone_name: &str,
+    local_offset: Transform,
+    get_model: impl FnOnce(Handle<Model>) -> Option<Arc<Model>>,
+) -> Result<(), AttachError> {
+    let Some(&model_handle) = world.get::<Handle<Model>>(parent) else {
+        return Err(AttachError::NoModel(parent));
+    };
+
+    let Some(model) = get_model(model_handle) else {
+        return Err(AttachError::ModelNotLoaded(parent));
+    };
+
+    let Some(bone_index) = model.skeleton.bone_index(bone_name) else {
+        return Err(AttachError::BoneNotFound(bone_name.to_string()));
+    };
+
+    world.entity_mut(child).insert(BoneAttachment {
+        parent,
+        bone_index,
+        local_offset,
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        engine::storage::Storage,
+        game::skeleton::{Bone, Skeleton},
+    };
+
+    use super::*;
+
+    fn model_with_bone(name: &str) -> Arc<Model> {
+        Arc::new(Model::from_skeleton(Skeleton {
+            bones: vec![Bone {
+                parent: u32::MAX,
+                transform: Transform::default(),
+                id: 0,
+                name: name.to_string(),
+            }],
+        }))
+    }
+
+    #[test]
+    fn attaching_to_a_missing_bone_fails() {
+        let mut world = World::new();
+        let model = Storage::<Model, ()>::default().insert(());
+        let parent = world.spawn(model).id();
+        let child = world.spawn_empty().id();
+        let model = model_with_bone("head");
+
+        let attach = |world: &mut World, bone_name: &str, model: Option<Arc<Model>>| {
+            attach_to_model_bone(
+                world,
+                child,
+                parent,
+                bone_name,
+                Transform::default(),
+                |_| model,
+            )
+        };
+
+        assert_eq!(
+            attach(&mut world, "hand", Some(Arc::clone(&model))),
+            Err(AttachError::BoneNotFound(String::from("hand")))
+        );
+        assert_eq!(
+            attach(&mut world, "head", None),
+            Err(AttachError::ModelNotLoaded(parent))
+        );
+        assert!(world.get::<BoneAttachment>(child).is_none());
+
+        assert_eq!(attach(&mut world, "HEAD", Some(model)), Ok(()));
+        assert_eq!(world.get::<BoneAttachment>(child).unwrap().bone_index, 0);
+    }
+
+    #[test]
+    fn parents_without_a_model_are_rejected() {
+        let mut world = World::new();
+        let parent = world.spawn(Transform::default()).id();
+        let child = world.spawn_empty().id();
+
+        assert_eq!(
+            attach_to_model_bone(
+                &mut world,
+                child,
+                parent,
+                "head",
+                Transform::default(),
+                |_| None
+            ),
+            Err(AttachError::NoModel(parent))
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::game::math::{Frustum, Ray, RaySegment, ViewProjection};
+use crate::game::math::{Frustum, Ray, RaySegment, ViewProjection, halton};
 
 use bevy_ecs::prelude::*;
 use glam::{Mat4, Quat, UVec2, Vec2, Vec3, Vec4};
@@ -12,6 +12,9 @@ pub struct Camera {
     pub aspect_ratio: f32,
     pub near: f32,
     pub far: f32,
+    /// Sub-pixel offset applied to the rendered projection, in NDC units.
+    /// Picking and culling use the projection without it.
+    pub jitter: Vec2,
 }
 
 impl Camera {
@@ -34,21 +37,57 @@ impl Camera {
             aspect_ratio,
             near,
             far,
+            jitter: Vec2::ZERO,
         }
     }
 
+    /// Offset the projection by `jitter` in NDC units. Used to render sub-pixel
+    /// shifted frames for temporal anti-aliasing or supersampled screenshots.
+    pub fn with_projection_jitter(mut self, jitter: Vec2) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
     #[inline]
     pub fn calculate_view_projection(&self) -> ViewProjection {
         ViewProjection::from_projection_view(self.calculate_projection(), self.calculate_view())
     }
 
-    pub fn _look_at(&mut self, camera_to: Vec3) {
-        let forward = (camera_to - self.position).normalize();
-        let world_up = Self::UP;
-        let right = world_up.cross(forward).normalize();
-        let up = forward.cross(right);
-        let rotation_matrix = glam::Mat3::from_cols(right, up, forward);
-        self.rotation = Quat::from_mat3(&rotation_matrix);
+    /// Rotate the camera to face `target`, keeping the top of the view as close
+    /// to `up` as possible. Does nothing if `target` is the camera position.
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        let Some(forward) = (target - self.position).try_normalize() else {
+            return;
+        };
+
+        // Looking straight along `up` leaves the roll undefined, so pick any
+        // other axis to keep the basis valid.
+        let x_axis = forward
+            .cross(up)
+            .try_normalize()
+            .unwrap_or_else(|| forward.any_orthonormal_vector());
+        let z_axis = x_axis.cross(forward);
+
+        // Local axes map as X -> x_axis, FORWARD (Y) -> forward and UP (Z) -> z_axis.
+        self.rotation = Quat::from_mat3(&glam::Mat3::from_cols(x_axis, forward, z_axis));
+    }
+
+    /// Place the camera `distance` away from `target` and face it. `yaw`
+    /// rotates around [Self::UP] and `pitch` tilts the view up from the
+    /// horizon, both in radians, matching the rotation built by the camera
+    /// controllers.
+    pub fn orbit(&mut self, target: Vec3, yaw: f32, pitch: f32, distance: f32) {
+        self.rotation = Quat::from_rotation_z(yaw) * Quat::from_rotation_x(pitch);
+        self.position = target - self.rotation * Self::FORWARD * distance;
+    }
+
+    /// The yaw and pitch in radians that produce the current view direction
+    /// when used with [Self::orbit].
+    pub fn yaw_pitch(&self) -> (f32, f32) {
+        let forward = self.rotation * Self::FORWARD;
+        let yaw = (-forward.x).atan2(forward.y);
+        let pitch = forward.z.atan2(forward.truncate().length());
+        (yaw, pitch)
     }
 
     pub fn _view_slice_planes(&self, count: u32, lambda: f32) -> Vec<[Vec3; 4]> {
@@ -95,6 +134,18 @@ impl Camera {
         Mat4::perspective_lh(self.fov, self.aspect_ratio, self.near, self.far)
     }
 
+    /// The projection offset by [Self::jitter], used to render the frame.
+    pub fn calculate_jittered_projection(&self) -> Mat4 {
+        let projection = self.calculate_projection();
+        if self.jitter == Vec2::ZERO {
+            projection
+        } else {
+            // The translation is scaled by clip space w, so after the perspective divide the
+            // offset is exactly `jitter` in NDC.
+            Mat4::from_translation(self.jitter.extend(0.0)) * projection
+        }
+    }
+
     #[inline]
     pub fn calculate_view(&self) -> Mat4 {
         let target = self.position + self.rotation * Self::FORWARD;
@@ -106,9 +157,11 @@ impl Camera {
         let view_proj = self.calculate_view_projection();
         let frustum = view_proj.frustum();
         let forward = (self.rotation * Camera::FORWARD).normalize();
+        let render_proj_view = self.calculate_jittered_projection() * self.calculate_view();
 
         ComputedCamera {
             view_proj,
+            render_proj_view,
             frustum,
             position: self.position,
             forward,
@@ -118,9 +171,49 @@ impl Camera {
     }
 }
 
+/// Drives the projection jitter of the active camera with a Halton (2, 3)
+/// sequence so that consecutive frames sample different sub-pixel positions.
+#[derive(Clone, Debug, PartialEq, Resource)]
+pub struct ProjectionJitter {
+    /// Whether the active camera should be jittered.
+    pub enabled: bool,
+    /// Number of samples before the sequence repeats.
+    pub sample_count: u32,
+    /// Index of the next sample in the sequence.
+    pub sample_index: u32,
+}
+
+impl Default for ProjectionJitter {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sample_count: 8,
+            sample_index: 0,
+        }
+    }
+}
+
+impl ProjectionJitter {
+    /// Return the jitter for the next frame in NDC units for a viewport of
+    /// `viewport_size` pixels, and advance the sequence.
+    pub fn next_jitter(&mut self, viewport_size: UVec2) -> Vec2 {
+        // Halton sequences start at index 1; index 0 is always 0.
+        let index = self.sample_index % self.sample_count.max(1) + 1;
+        self.sample_index = self.sample_index.wrapping_add(1);
+
+        let offset = Vec2::new(halton(index, 2), halton(index, 3)) - Vec2::splat(0.5);
+
+        // One pixel is 2 / size in NDC.
+        offset * 2.0 / viewport_size.max(UVec2::ONE).as_vec2()
+    }
+}
+
 #[derive(Component, Default)]
 pub struct ComputedCamera {
+    /// The camera matrices without jitter, for picking and culling.
     pub view_proj: ViewProjection,
+    /// The matrix the frame is rendered with, including [Camera::jitter].
+    pub render_proj_view: Mat4,
     pub frustum: Frustum,
     pub position: Vec3,
     pub forward: Vec3,
@@ -154,3 +247,95 @@ impl ComputedCamera {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_camera(position: Vec3) -> Camera {
+        Camera::new(
+            position,
+            Quat::IDENTITY,
+            45.0_f32.to_radians(),
+            1.5,
+            1.0,
+            10_000.0,
+        )
+    }
+
+    fn assert_centered(camera: &Camera, point: Vec3) {
+        let ndc = camera.calculate_view_projection().mat.project_point3(point);
+        assert!(ndc.x.abs() < 1e-4 && ndc.y.abs() < 1e-4, "{ndc}");
+        assert!((0.0..=1.0).contains(&ndc.z), "{ndc}");
+    }
+
+    #[test]
+    fn look_at_centers_the_target() {
+        let target = Vec3::new(100.0, 250.0, 0.0);
+
+        let mut camera = test_camera(Vec3::new(-300.0, 20.0, 500.0));
+        camera.look_at(target, Camera::UP);
+        assert_centered(&camera, target);
+
+        // Straight down, where `up` is parallel to the view direction.
+        let mut camera = test_camera(Vec3::new(100.0, 250.0, 1000.0));
+        camera.look_at(target, Camera::UP);
+        assert_centered(&camera, target);
+    }
+
+    #[test]
+    fn jitter_only_moves_the_render_projection() {
+        let jitter = Vec2::new(0.01, -0.02);
+        let camera = test_camera(Vec3::ZERO).with_projection_jitter(jitter);
+        let point = Vec3::new(50.0, 500.0, 20.0);
+
+        let computed = camera.compute();
+        let unjittered = computed.view_proj.mat.project_point3(point);
+        let rendered = computed.render_proj_view.project_point3(point);
+
+        assert_eq!(
+            computed.view_proj.mat,
+            test_camera(Vec3::ZERO).compute().view_proj.mat
+        );
+        assert!((rendered.truncate() - unjittered.truncate() - jitter).length() < 1e-5);
+        assert!((rendered.z - unjittered.z).abs() < 1e-6);
+    }
+
+    #[test]
+    fn jitter_stays_within_a_pixel_and_repeats() {
+        let mut projection_jitter = ProjectionJitter {
+            enabled: true,
+            sample_count: 4,
+            sample_index: 0,
+        };
+        let viewport = UVec2::new(200, 100);
+        let pixel = Vec2::new(2.0 / 200.0, 2.0 / 100.0);
+
+        let samples: Vec<Vec2> = (0..8)
+            .map(|_| projection_jitter.next_jitter(viewport))
+            .collect();
+
+        // Halton (2, 3) at index 1 is (1/2, 1/3), centered on the pixel.
+        assert!((samples[0] - Vec2::new(0.0, -1.0 / 6.0) * pixel).length() < 1e-6);
+        for sample in samples.iter() {
+            assert!(sample.abs().cmple(pixel * 0.5).all(), "{sample}");
+        }
+        assert_eq!(samples[..4], samples[4..]);
+        assert_ne!(samples[0], samples[1]);
+    }
+
+    #[test]
+    fn orbit_centers_the_target() {
+        let target = Vec3::new(-40.0, 80.0, 10.0);
+
+        let mut camera = test_camera(Vec3::ZERO);
+        camera.orbit(target, 30.0_f32.to_radians(), -45.0_f32.to_radians(), 800.0);
+
+        assert!((camera.position.distance(target) - 800.0).abs() < 1e-2);
+        assert_centered(&camera, target);
+
+        let (yaw, pitch) = camera.yaw_pitch();
+        assert!((yaw - 30.0_f32.to_radians()).abs() < 1e-4);
+        assert!((pitch + 45.0_f32.to_radians()).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,99 @@
+use bevy_ecs::prelude::*;
+use glam::{Vec2, Vec3};
+
+use super::HeightMap;
+
+/// Moves a character over the terrain instead of letting root motion carry it
+/// freely. Characters slide along slopes that are too steep to climb and stick
+/// to the ground when walking down hill.
+#[derive(Clone, Component, Debug)]
+pub struct CharacterController {
+    /// Steepest slope, in radians, the character can walk up.
+    pub max_slope_climb_angle: f32,
+    /// Characters below the ground, or less than this distance above it,
+    /// after moving are put onto it.
+    pub snap_to_ground: f32,
+}
+
+impl Default for CharacterController {
+    fn default() -> Self {
+        Self {
+            max_slope_climb_angle: super::MAX_WALKABLE_SLOPE,
+            snap_to_ground: 50.0,
+        }
+    }
+}
+
+impl CharacterController {
+    /// Move a character standing at `position` by `desired` and return the
+    /// movement that is actually possible on the terrain.
+    pub fn move_character(&self, height_map: &HeightMap, position: Vec3, desired: Vec3) -> Vec3 {
+        let start = position.truncate();
+        let mut horizontal = desired.truncate();
+
+        // Walking up a slope that is too steep slides along it instead.
+        let target = start + horizontal;
+        let normal = height_map.normal_at(target);
+        if normal.z < self.max_slope_climb_angle.cos()
+            && height_map.elevation_at(target) > height_map.elevation_at(start)
+        {
+            let uphill = -normal.truncate().normalize_or_zero();
+            horizontal -= uphill * horizontal.dot(uphill).max(0.0);
+        }
+
+        let max = (height_map.size.as_vec2() - Vec2::ONE).max(Vec2::ZERO) * height_map.cell_size;
+        let target = (start + horizontal).clamp(Vec2::ZERO, max);
+
+        let ground = height_map.elevation_at(target);
+        let mut z = position.z + desired.z;
+        if z - ground <= self.snap_to_ground {
+            z = ground;
+        }
+
+        target.extend(z) - position
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::UVec2;
+
+    use super::*;
+
+    /// Flat for x < 4, then a ramp rising `rise` per unit along x.
+    fn ramp(rise: f32) -> HeightMap {
+        let size = UVec2::new(10, 10);
+        HeightMap::from_iter(
+            size,
+            1.0,
+            (0..100).map(move |index| ((index % 10) as f32 - 4.0).max(0.0) * rise),
+        )
+    }
+
+    #[test]
+    fn characters_stay_grounded_and_slide_along_steep_slopes() {
+        let controller = CharacterController::default();
+
+        // A gentle slope can be walked up.
+        let gentle = ramp(0.25);
+        let moved = controller.move_character(&gentle, Vec3::new(5.0, 5.0, 0.25), Vec3::X);
+        assert!(
+            moved.abs_diff_eq(Vec3::new(1.0, 0.0, 0.25), 1e-5),
+            "{moved}"
+        );
+
+        // Walking down hill snaps onto the ground.
+        let moved = controller.move_character(&gentle, Vec3::new(6.0, 5.0, 0.5), Vec3::NEG_X);
+        assert!(
+            moved.abs_diff_eq(Vec3::new(-1.0, 0.0, -0.25), 1e-5),
+            "{moved}"
+        );
+
+        // A steep slope blocks movement into it but not along it.
+        let steep = ramp(3.0);
+        let moved =
+            controller.move_character(&steep, Vec3::new(5.0, 5.0, 3.0), Vec3::new(1.0, 1.0, 0.0));
+        assert!(moved.x.abs() < 1e-5, "{moved}");
+        assert!((moved.y - 1.0).abs() < 1e-5, "{moved}");
+    }
+}
@@ -1,7 +1,10 @@
 use bevy_ecs::prelude::*;
 use glam::Vec3;
 
-use crate::game::{config::Campaign, track::Track};
+use crate::game::{
+    config::Campaign,
+    track::{Interpolation, Track},
+};
 
 /// Holds data for the sun and fog values throughout the day and night.
 #[derive(Default, Resource)]
@@ -12,11 +15,16 @@ pub struct DayNightCycle {
     pub fog_distance: Track<f32>,
     pub fog_near_fraction: Track<f32>,
     pub fog_color: Track<Vec3>,
+
+    /// Fixed color to clear the world to. Falls back to the fog color if not set.
+    pub background_color: Option<Vec3>,
 }
 
 impl DayNightCycle {
     pub fn from_campaign(campaign: &Campaign) -> Self {
-        let mut sun_dir = Track::default();
+        // The sun moves along a curve, so it doesn't visibly change direction
+        // at every key frame.
+        let mut sun_dir = Track::default().with_interpolation(Interpolation::Cubic);
         let mut sun_color = Track::default();
 
         let mut fog_distance = Track::default();
@@ -44,6 +52,112 @@ impl DayNightCycle {
             fog_distance,
             fog_near_fraction,
             fog_color,
+            background_color: campaign.background_color,
+        }
+    }
+}
+
+/// A directional light with a fixed direction and color.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DirectionalLight {
+    /// Compass direction the light comes from, in degrees counter clockwise
+    /// from +X.
+    pub azimuth: f32,
+    /// Angle of the light above the horizon, in degrees.
+    pub elevation: f32,
+    pub color: Vec3,
+    pub ambient_color: Vec3,
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        Self {
+            azimuth: 135.0,
+            elevation: 45.0,
+            color: Vec3::ONE,
+            ambient_color: Vec3::splat(0.3),
+        }
+    }
+}
+
+impl DirectionalLight {
+    /// Direction the light travels in, like [DayNightCycle::sun_dir].
+    pub fn direction(&self) -> Vec3 {
+        let (azimuth, elevation) = (self.azimuth.to_radians(), self.elevation.to_radians());
+        -Vec3::new(
+            elevation.cos() * azimuth.cos(),
+            elevation.cos() * azimuth.sin(),
+            elevation.sin(),
+        )
+    }
+}
+
+/// Replaces the background and lighting of the [DayNightCycle], so models and
+/// terrain can be inspected under controlled lighting.
+#[derive(Default, Resource)]
+pub struct LightingOverride {
+    /// sRGB color to clear the world to.
+    pub background_color: Option<Vec3>,
+    pub light: Option<DirectionalLight>,
+}
+
+impl LightingOverride {
+    #[cfg(feature = "egui")]
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let mut override_background = self.background_color.is_some();
+        ui.checkbox(&mut override_background, "Background color");
+        if override_background {
+            let color = self
+                .background_color
+                .get_or_insert(Vec3::new(0.1, 0.2, 0.3));
+            let mut rgb = color.to_array();
+            ui.color_edit_button_rgb(&mut rgb);
+            *color = Vec3::from_array(rgb);
+        } else {
+            self.background_color = None;
         }
+
+        let mut override_light = self.light.is_some();
+        ui.checkbox(&mut override_light, "Directional light");
+        if override_light {
+            let light = self.light.get_or_insert_with(DirectionalLight::default);
+            ui.add(egui::Slider::new(&mut light.azimuth, 0.0..=360.0).text("Azimuth"));
+            ui.add(egui::Slider::new(&mut light.elevation, -90.0..=90.0).text("Elevation"));
+
+            let edit_color = |ui: &mut egui::Ui, label: &str, color: &mut Vec3| {
+                ui.horizontal(|ui| {
+                    let mut rgb = color.to_array();
+                    ui.color_edit_button_rgb(&mut rgb);
+                    *color = Vec3::from_array(rgb);
+                    ui.label(label);
+                });
+            };
+            edit_color(ui, "Color", &mut light.color);
+            edit_color(ui, "Ambient", &mut light.ambient_color);
+        } else {
+            self.light = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directional_light_shines_down_from_the_azimuth() {
+        let light = DirectionalLight {
+            azimuth: 90.0,
+            elevation: 90.0,
+            ..Default::default()
+        };
+        assert!(light.direction().abs_diff_eq(Vec3::NEG_Z, 1e-5));
+
+        let light = DirectionalLight {
+            azimuth: 180.0,
+            elevation: 0.0,
+            ..Default::default()
+        };
+        assert!(light.direction().abs_diff_eq(Vec3::X, 1e-5));
     }
 }
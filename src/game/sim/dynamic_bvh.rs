@@ -1,12 +1,25 @@
-use bevy_ecs::prelude::*;
+use bevy_ecs::{lifecycle::HookContext, prelude::*, world::DeferredWorld};
 use generational_arena::{Arena, Index as Handle};
 use glam::Vec3;
 
-use crate::game::math::{BoundingBox, Containment, Frustum, RaySegment};
+use crate::game::math::{BoundingBox, BoundingSphere, Containment, Frustum, RaySegment};
 
+/// The leaf of an object in the [DynamicBvh]. The leaf is removed from the
+/// tree when the component is removed or its entity is despawned.
 #[derive(Clone, Copy, Component)]
+#[component(on_remove = remove_from_dynamic_bvh)]
 pub struct DynamicBvhHandle(Handle);
 
+fn remove_from_dynamic_bvh(mut world: DeferredWorld, context: HookContext) {
+    let Some(&handle) = world.get::<DynamicBvhHandle>(context.entity) else {
+        return;
+    };
+
+    if let Some(mut dynamic_bvh) = world.get_resource_mut::<DynamicBvh>() {
+        dynamic_bvh.remove(handle);
+    }
+}
+
 /// Node stored in the arena. Internal nodes have two children; leaf nodes have
 /// an object payload.
 #[derive(Clone)]
@@ -54,8 +67,31 @@ impl DynamicBvh {
         DynamicBvhHandle(leaf)
     }
 
+    /// The amount of objects in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| matches!(node.kind, NodeKind::Leaf { .. }))
+            .count()
+    }
+
+    /// Whether the tree holds no objects.
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// The fattened bounds of every node in the tree, with `true` for leaves.
+    pub fn node_bounds(&self) -> impl Iterator<Item = (&BoundingBox, bool)> {
+        self.nodes.iter().map(|(_, node)| {
+            (
+                &node.bounding_box,
+                matches!(node.kind, NodeKind::Leaf { .. }),
+            )
+        })
+    }
+
     /// Remove a previously inserted handle.
-    pub fn _remove(&mut self, handle: DynamicBvhHandle) -> Option<Entity> {
+    pub fn remove(&mut self, handle: DynamicBvhHandle) -> Option<Entity> {
         let handle = handle.0;
 
         if !self.nodes.contains(handle) {
@@ -194,6 +230,34 @@ impl DynamicBvh {
         }
     }
 
+    /// Writes objects whose fattened bounding boxes intersect the sphere into
+    /// `out`. Callers wanting exact results test the tight bounds themselves.
+    pub fn query_sphere(&self, sphere: &BoundingSphere, out: &mut Vec<Entity>) {
+        let Some(root) = self.root else {
+            return;
+        };
+
+        let mut stack: Vec<Handle> = Vec::new();
+        stack.push(root);
+
+        while let Some(h) = stack.pop() {
+            let node = &self.nodes[h];
+            if !node.bounding_box.intersects_sphere(sphere) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { object } => {
+                    out.push(object);
+                }
+                NodeKind::Internal { child1, child2, .. } => {
+                    stack.push(child1);
+                    stack.push(child2);
+                }
+            }
+        }
+    }
+
     // --------------------
     // Internals
     // --------------------
@@ -707,3 +771,72 @@ impl Default for DynamicBvh {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sphere_queries_return_the_boxes_in_range() {
+        let mut world = World::default();
+        let mut bvh = DynamicBvh::default();
+        let entities: Vec<Entity> = (0..10)
+            .map(|i| {
+                let entity = world.spawn_empty().id();
+                let min = Vec3::new(i as f32 * 10.0, 0.0, 0.0);
+                bvh.insert(
+                    entity,
+                    BoundingBox {
+                        min,
+                        max: min + Vec3::ONE,
+                    },
+                );
+                entity
+            })
+            .collect();
+
+        let mut found = Vec::new();
+        bvh.query_sphere(
+            &BoundingSphere::new(Vec3::new(25.0, 0.5, 0.5), 6.0),
+            &mut found,
+        );
+        found.sort_by_key(|entity| entity.index());
+        assert_eq!(found, [entities[2], entities[3]]);
+
+        found.clear();
+        bvh.query_sphere(
+            &BoundingSphere::new(Vec3::new(0.5, 50.0, 0.5), 6.0),
+            &mut found,
+        );
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn despawning_removes_objects_from_the_tree() {
+        let mut world = World::default();
+        world.insert_resource(DynamicBvh::default());
+
+        let spawn = |world: &mut World| {
+            let entity = world.spawn_empty().id();
+            let bounding_box = BoundingBox {
+                min: Vec3::ZERO,
+                max: Vec3::ONE,
+            };
+            let handle = world
+                .resource_mut::<DynamicBvh>()
+                .insert(entity, bounding_box);
+            world.entity_mut(entity).insert(handle);
+            entity
+        };
+
+        let first = spawn(&mut world);
+        let second = spawn(&mut world);
+        assert_eq!(world.resource::<DynamicBvh>().len(), 2);
+
+        world.despawn(first);
+        assert_eq!(world.resource::<DynamicBvh>().len(), 1);
+
+        world.entity_mut(second).remove::<DynamicBvhHandle>();
+        assert!(world.resource::<DynamicBvh>().is_empty());
+    }
+}
@@ -1,8 +1,14 @@
+use std::sync::Arc;
+
+use ahash::HashMap;
 use bevy_ecs::prelude::*;
 use glam::{UVec2, Vec3, Vec4};
 
 use crate::{
-    engine::gizmos::{GizmoVertex, create_bounding_box},
+    engine::gizmos::{
+        GizmoVertex, StaticGizmoHandle, create_bounding_box, create_circle, create_grid,
+        create_line_strip, create_wire_sphere,
+    },
     game::math::BoundingBox,
 };
 
@@ -20,9 +26,38 @@ pub struct ActiveCamera;
 #[derive(Component)]
 pub struct BoundingBoxComponent(pub BoundingBox);
 
+/// The `OBJECT_ID` of an object spawned from the map's MTF file. Unlike the
+/// [Entity], it doesn't depend on what else was spawned, so it is what saved
+/// selections and replays should refer to.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ObjectId(pub [i32; 2]);
+
+/// Overrides how an object takes part in visibility culling. Objects without the component are
+/// culled normally.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VisibilityFlags {
+    /// Rendered when inside the camera frustum.
+    #[default]
+    Normal,
+    /// Never rendered, regardless of the camera.
+    Hidden,
+    /// Always rendered, even when outside the camera frustum.
+    AlwaysVisible,
+}
+
 #[derive(Resource)]
 pub struct GizmoVertices {
     pub vertices: Vec<GizmoVertex>,
+    /// Shade gizmo lines by their distance to the camera, brighter when closer.
+    pub color_by_depth: bool,
+    /// Geometry that doesn't change between frames. The renderer uploads it
+    /// once instead of every frame.
+    static_gizmos: HashMap<StaticGizmoHandle, Arc<[GizmoVertex]>>,
+    next_static_gizmo: u64,
+    /// Static geometry to draw this frame.
+    static_draws: Vec<(StaticGizmoHandle, Arc<[GizmoVertex]>)>,
+    /// Static geometry removed since the renderer was last told about it.
+    removed_static: Vec<StaticGizmoHandle>,
 }
 
 impl GizmoVertices {
@@ -30,12 +65,61 @@ impl GizmoVertices {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             vertices: Vec::with_capacity(capacity),
+            color_by_depth: false,
+            static_gizmos: HashMap::default(),
+            next_static_gizmo: 0,
+            static_draws: Vec::default(),
+            removed_static: Vec::default(),
         }
     }
 
+    /// Clear the gizmos drawn this frame. Static geometry is kept, but has to
+    /// be drawn again with [GizmoVertices::draw_static].
     #[inline]
     pub fn clear(&mut self) {
-        self.vertices.clear()
+        self.vertices.clear();
+        self.static_draws.clear();
+    }
+
+    /// Keep line list `vertices` that don't change between frames, like a
+    /// grid. The renderer uploads them the first time they are drawn and
+    /// keeps them on the GPU until they are removed. The vertices can't be
+    /// changed; create new static geometry when they change and remove the
+    /// old one.
+    pub fn create_static(&mut self, vertices: Vec<GizmoVertex>) -> StaticGizmoHandle {
+        let handle = StaticGizmoHandle(self.next_static_gizmo);
+        self.next_static_gizmo += 1;
+        self.static_gizmos.insert(handle, vertices.into());
+        handle
+    }
+
+    /// Draw static geometry this frame. Removed handles are ignored.
+    pub fn draw_static(&mut self, handle: StaticGizmoHandle) {
+        if let Some(vertices) = self.static_gizmos.get(&handle) {
+            self.static_draws.push((handle, Arc::clone(vertices)));
+        }
+    }
+
+    /// Remove static geometry, including any draws of it this frame.
+    /// Returns false if it was already removed.
+    pub fn remove_static(&mut self, handle: StaticGizmoHandle) -> bool {
+        let removed = self.static_gizmos.remove(&handle).is_some();
+        if removed {
+            self.static_draws.retain(|(drawn, _)| *drawn != handle);
+            self.removed_static.push(handle);
+        }
+        removed
+    }
+
+    /// Take the static geometry removed since the last call, so the renderer
+    /// can free its buffers.
+    pub fn take_removed_static(&mut self) -> Vec<StaticGizmoHandle> {
+        std::mem::take(&mut self.removed_static)
+    }
+
+    /// The static geometry drawn this frame.
+    pub fn static_draws(&self) -> &[(StaticGizmoHandle, Arc<[GizmoVertex]>)] {
+        &self.static_draws
     }
 
     #[inline]
@@ -57,6 +141,37 @@ impl GizmoVertices {
         self.vertices
             .extend(create_bounding_box(bounding_box, color));
     }
+
+    /// Connect consecutive `points` with lines.
+    pub fn draw_line_strip(&mut self, points: &[Vec3], color: Vec4) {
+        self.vertices.extend(create_line_strip(points, color));
+    }
+
+    /// Draw a circle around `center` in the plane facing `normal`. See
+    /// [create_circle].
+    pub fn draw_circle(
+        &mut self,
+        center: Vec3,
+        normal: Vec3,
+        radius: f32,
+        segments: u32,
+        color: Vec4,
+    ) {
+        self.vertices
+            .extend(create_circle(center, normal, radius, segments, color));
+    }
+
+    /// Draw a sphere as a circle around each axis.
+    pub fn draw_wire_sphere(&mut self, center: Vec3, radius: f32, color: Vec4) {
+        self.vertices
+            .extend(create_wire_sphere(center, radius, color));
+    }
+
+    /// Draw a ground plane grid around `center`. See [create_grid].
+    pub fn draw_grid(&mut self, center: Vec3, extent: f32, spacing: f32, color: Vec4) {
+        self.vertices
+            .extend(create_grid(center, extent, spacing, color));
+    }
 }
 
 #[derive(Default, Resource)]
@@ -70,3 +185,37 @@ impl Viewport {
         self.size = size;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn static_gizmos_are_kept_until_removed() {
+        let mut gizmos = GizmoVertices::with_capacity(0);
+        let line = vec![GizmoVertex::new(Vec3::ZERO, Vec4::ONE); 2];
+
+        let handle = gizmos.create_static(line);
+        gizmos.draw_static(handle);
+        assert_eq!(gizmos.static_draws().len(), 1);
+
+        // Every frame draws the static geometry again.
+        gizmos.clear();
+        assert!(gizmos.static_draws().is_empty());
+        gizmos.draw_static(handle);
+        assert_eq!(gizmos.static_draws()[0].1.len(), 2);
+
+        // Removing drops the draw from this frame as well.
+        assert!(gizmos.remove_static(handle));
+        assert!(gizmos.static_draws().is_empty());
+        assert!(!gizmos.remove_static(handle));
+        gizmos.draw_static(handle);
+        assert!(gizmos.static_draws().is_empty());
+
+        // The renderer is told once to free the removed geometry.
+        assert_eq!(gizmos.take_removed_static(), vec![handle]);
+        assert!(gizmos.take_removed_static().is_empty());
+
+        assert_ne!(gizmos.create_static(Vec::default()), handle);
+    }
+}
@@ -0,0 +1,39 @@
+use bevy_ecs::prelude::*;
+
+/// Queue `event` for systems that read messages of type `E`. The message
+/// channel is created the first time an event of that type is sent.
+pub fn send_event<E: Message>(world: &mut World, event: E) {
+    world.get_resource_or_init::<Messages<E>>().write(event);
+}
+
+/// Remove and return all queued events of type `E` in the order they were sent.
+pub fn drain_events<E: Message>(world: &mut World) -> Vec<E> {
+    world
+        .get_resource_mut::<Messages<E>>()
+        .map(|mut messages| messages.drain().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Message, PartialEq)]
+    struct TestEvent(u32);
+
+    #[test]
+    fn drain_returns_sent_events_in_order() {
+        let mut world = World::new();
+
+        assert!(drain_events::<TestEvent>(&mut world).is_empty());
+
+        send_event(&mut world, TestEvent(1));
+        send_event(&mut world, TestEvent(2));
+
+        assert_eq!(
+            drain_events::<TestEvent>(&mut world),
+            vec![TestEvent(1), TestEvent(2)]
+        );
+        assert!(drain_events::<TestEvent>(&mut world).is_empty());
+    }
+}
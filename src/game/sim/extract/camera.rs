@@ -14,7 +14,7 @@ pub fn extract_camera(
         forward: camera.forward,
         _near: camera.near,
         far: camera.far,
-        proj_view: camera.view_proj.mat,
+        proj_view: camera.render_proj_view,
         frustum: camera.frustum.clone(),
     }
 }
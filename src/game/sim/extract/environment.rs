@@ -1,14 +1,18 @@
 use bevy_ecs::prelude::*;
 use glam::Vec3;
 
-use crate::game::{
-    render::world::WorldRenderSnapshot,
-    sim::{DayNightCycle, SimWorldState, systems::Time},
+use crate::{
+    engine::renderer::Color,
+    game::{
+        render::world::WorldRenderSnapshot,
+        sim::{DayNightCycle, LightingOverride, SimWorldState, systems::Time},
+    },
 };
 
 pub fn extract_environment(
     mut snapshot: ResMut<WorldRenderSnapshot>,
     day_night_cycle: Res<DayNightCycle>,
+    lighting_override: Res<LightingOverride>,
     time: Res<Time>,
     state: Res<SimWorldState>,
 ) {
@@ -22,10 +26,24 @@ pub fn extract_environment(
     env.sun_dir = day_night_cycle.sun_dir.sample_sub_frame(tod, true);
     env.sun_color = day_night_cycle.sun_color.sample_sub_frame(tod, true);
     env.ambient_color = Vec3::splat(0.3);
+    if let Some(light) = lighting_override.light {
+        env.sun_dir = light.direction();
+        env.sun_color = light.color;
+        env.ambient_color = light.ambient_color;
+    }
 
-    env.fog_color = day_night_cycle.fog_color.sample_sub_frame(tod, true);
+    // Fog and background colors are authored in sRGB. Both are converted so the
+    // fogged horizon still matches the cleared background.
+    let fog_color = day_night_cycle.fog_color.sample_sub_frame(tod, true);
+    env.fog_color = Color::from_srgb_vec3(fog_color).to_vec3();
     env.fog_distance = day_night_cycle.fog_distance.sample_sub_frame(tod, true);
     env.fog_near_fraction = day_night_cycle
         .fog_near_fraction
         .sample_sub_frame(tod, true);
+
+    let clear_color = lighting_override
+        .background_color
+        .or(day_night_cycle.background_color)
+        .unwrap_or(fog_color);
+    env.clear_color = Color::from_srgb_vec3(clear_color).to_vec3();
 }
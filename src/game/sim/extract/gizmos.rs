@@ -4,7 +4,20 @@ use crate::game::{render::world::WorldRenderSnapshot, sim::ecs::GizmoVertices};
 
 pub fn extract_gizmos(
     mut snapshot: ResMut<WorldRenderSnapshot>,
-    gizmo_vertices: Res<GizmoVertices>,
+    mut gizmo_vertices: ResMut<GizmoVertices>,
 ) {
-    snapshot.gizmos.vertices = gizmo_vertices.vertices.clone();
+    // Reuse the snapshot's allocation instead of cloning a new one each frame.
+    snapshot.gizmos.vertices.clear();
+    snapshot
+        .gizmos
+        .vertices
+        .extend_from_slice(&gizmo_vertices.vertices);
+    snapshot.gizmos.color_by_depth = gizmo_vertices.color_by_depth;
+
+    snapshot.gizmos.static_draws.clear();
+    snapshot
+        .gizmos
+        .static_draws
+        .extend_from_slice(gizmo_vertices.static_draws());
+    snapshot.gizmos.removed_static = gizmo_vertices.take_removed_static();
 }
@@ -17,7 +17,11 @@ pub fn create_extract_schedule() -> Schedule {
             // These can be done in any order.
             (
                 terrain::extract_terrain_snapshot,
-                models::extract_model_snapshot,
+                (
+                    models::extract_model_instances,
+                    models::extract_model_snapshot,
+                )
+                    .chain(),
                 gizmos::extract_gizmos,
             ),
         )
@@ -4,17 +4,59 @@ use crate::{
     engine::{storage::Handle, transform::Transform},
     game::{
         assets::model::Model,
-        render::world::{ModelToRender, WorldRenderSnapshot},
+        render::world::{ModelInstance, ModelToRender, WorldRenderSnapshot},
         sim::{
-            ComputedCamera, DynamicBvh, StaticBvh, ecs::ActiveCamera, sequences::Pose,
+            ComputedCamera, DynamicBvh, StaticBvh,
+            ecs::{ActiveCamera, VisibilityFlags},
+            sequences::Pose,
             systems::world_interaction::WorldInteraction,
         },
     },
 };
 
+/// Bring the model instances in the snapshot up to date. Only entities whose
+/// transform, model or pose changed since the last extract are copied, so
+/// static scenery is not copied again after it was extracted once.
+#[allow(clippy::type_complexity)]
+pub fn extract_model_instances(
+    mut snapshot: ResMut<WorldRenderSnapshot>,
+    changed: Query<
+        (Entity, &Transform, &Handle<Model>, Option<&Pose>),
+        Or<(Changed<Transform>, Changed<Handle<Model>>, Changed<Pose>)>,
+    >,
+    mut removed_models: RemovedComponents<Handle<Model>>,
+    mut removed_transforms: RemovedComponents<Transform>,
+    mut removed_poses: RemovedComponents<Pose>,
+) {
+    let instances = &mut snapshot.models.instances;
+
+    for entity in removed_models.read().chain(removed_transforms.read()) {
+        instances.remove(&entity);
+    }
+
+    for entity in removed_poses.read() {
+        if let Some(instance) = instances.get_mut(&entity) {
+            instance.pose = None;
+        }
+    }
+
+    for (entity, transform, model_handle, pose) in changed.iter() {
+        instances.insert(
+            entity,
+            ModelInstance {
+                model: *model_handle,
+                transform: transform.to_mat4(),
+                pose: pose.cloned(),
+            },
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn extract_model_snapshot(
     mut snapshot: ResMut<WorldRenderSnapshot>,
-    models: Query<(Entity, &Transform, &Handle<Model>, Option<&Pose>)>,
+    models: Query<(Entity, &Handle<Model>), With<Transform>>,
+    visibility_flags: Query<(Entity, &VisibilityFlags)>,
     static_bvh: Res<StaticBvh>,
     dynamic_bvh: Res<DynamicBvh>,
     computed_camera: Single<&ComputedCamera, With<ActiveCamera>>,
@@ -29,11 +71,24 @@ pub fn extract_model_snapshot(
         static_bvh.objects_in_frustum(&computed_camera.frustum, &mut visible_objects_cache);
         dynamic_bvh.query_frustum(&computed_camera.frustum, &mut visible_objects_cache);
 
-        for (entity, transform, model_handle, pose) in models.iter_many(&visible_objects_cache) {
+        for (entity, flags) in visibility_flags.iter() {
+            if *flags == VisibilityFlags::AlwaysVisible && !visible_objects_cache.contains(&entity)
+            {
+                visible_objects_cache.push(entity);
+            }
+        }
+
+        for (entity, model_handle) in models.iter_many(&visible_objects_cache) {
+            if visibility_flags
+                .get(entity)
+                .is_ok_and(|(_, flags)| *flags == VisibilityFlags::Hidden)
+            {
+                continue;
+            }
+
             snapshot.models.models.push(ModelToRender {
+                entity,
                 model: *model_handle,
-                transform: transform.to_mat4(),
-                pose: pose.cloned(),
                 highlighted: world_interaction
                     .selected_entity
                     .map(|e| e == entity)
@@ -42,3 +97,56 @@ pub fn extract_model_snapshot(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use glam::{Mat4, Vec3};
+
+    use crate::engine::storage::Storage;
+
+    use super::*;
+
+    #[test]
+    fn only_changed_instances_are_extracted() {
+        let mut world = World::new();
+        world.init_resource::<WorldRenderSnapshot>();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(extract_model_instances);
+
+        let model = Storage::<Model, ()>::default().insert(());
+        let entity = world.spawn((Transform::default(), model)).id();
+
+        schedule.run(&mut world);
+        let instance = |world: &World| {
+            world.resource::<WorldRenderSnapshot>().models.instances[&entity].transform
+        };
+        assert_eq!(instance(&world), Mat4::IDENTITY);
+
+        // Unchanged entities are not copied again.
+        world
+            .resource_mut::<WorldRenderSnapshot>()
+            .models
+            .instances
+            .get_mut(&entity)
+            .unwrap()
+            .transform = Mat4::ZERO;
+        schedule.run(&mut world);
+        assert_eq!(instance(&world), Mat4::ZERO);
+
+        // Moving the entity extracts it again.
+        world.get_mut::<Transform>(entity).unwrap().translation = Vec3::X;
+        schedule.run(&mut world);
+        assert_eq!(instance(&world), Mat4::from_translation(Vec3::X));
+
+        world.despawn(entity);
+        schedule.run(&mut world);
+        assert!(
+            world
+                .resource::<WorldRenderSnapshot>()
+                .models
+                .instances
+                .is_empty()
+        );
+    }
+}
@@ -3,7 +3,7 @@ use bevy_ecs::prelude::*;
 use glam::IVec2;
 
 use crate::game::{
-    render::world::{Camera, TerrainChunk, WorldRenderSnapshot},
+    render::world::{Camera, HeightMapRow, TerrainChunk, WorldRenderSnapshot},
     sim::{ComputedCamera, SimWorldState, Terrain, ecs::ActiveCamera},
 };
 
@@ -18,6 +18,24 @@ pub fn extract_terrain_snapshot(
 ) {
     chunk_lod_cache.clear();
 
+    snapshot.terrain.height_map_updates.clear();
+    if let Some(rect) = terrain.changed_nodes {
+        let height_map = &terrain.height_map;
+
+        let width = height_map.size.x as usize;
+        for y in rect.min.y..=rect.max.y {
+            let first_node = y as usize * width + rect.min.x as usize;
+            let last_node = y as usize * width + rect.max.x as usize;
+            snapshot.terrain.height_map_updates.push(HeightMapRow {
+                first_node: first_node as u32,
+                nodes: height_map.nodes[first_node..=last_node]
+                    .iter()
+                    .map(|node| node.to_array())
+                    .collect(),
+            });
+        }
+    }
+
     snapshot.terrain.chunks.clear();
     snapshot.terrain.strata.clear();
     snapshot.terrain.strata_side_count = [0; 4];
@@ -3,7 +3,7 @@ use glam::{Quat, Vec3};
 use winit::{event::MouseButton, keyboard::KeyCode};
 
 use crate::{
-    engine::input::InputState,
+    engine::{input::InputState, mouse_settings::MouseSettings},
     game::{
         interpolate::Interpolate,
         sim::{Camera, ecs::ActiveCamera, systems::Time},
@@ -68,19 +68,16 @@ pub struct FreeCameraController {
 
     /// The speed at which movements will be calculated.
     pub movement_speed: f32,
-    /// The rotation sensitivity of mouse movement.
-    pub mouse_sensitivity: f32,
     /// Controls used to manipulate the camera.
     pub controls: FreeCameraControls,
 }
 
 impl FreeCameraController {
-    pub fn new(movement_speed: f32, mouse_sensitivity: f32) -> Self {
+    pub fn new(movement_speed: f32) -> Self {
         Self {
             current: State::default(),
             target: State::default(),
             movement_speed,
-            mouse_sensitivity,
             controls: FreeCameraControls::default(),
         }
     }
@@ -89,6 +86,7 @@ impl FreeCameraController {
 pub fn input(
     mut cameras: Query<(&mut Camera, &mut FreeCameraController), With<ActiveCamera>>,
     input_state: Res<InputState>,
+    mouse_settings: Res<MouseSettings>,
     time: Res<Time>,
 ) {
     for (mut camera, mut controller) in cameras.iter_mut() {
@@ -120,7 +118,7 @@ pub fn input(
         };
 
         {
-            let delta = input_state.wheel_delta();
+            let delta = input_state.scroll_delta();
             if delta < 0.0 {
                 controller.movement_speed *= 0.9;
             } else if delta > 0.0 {
@@ -131,9 +129,9 @@ pub fn input(
         if input_state.mouse_pressed(controller.controls.mouse_button)
             && let Some(delta) = input_state.mouse_delta()
         {
-            let delta = delta.as_vec2();
-            controller.target.yaw -= delta.x * controller.mouse_sensitivity;
-            controller.target.pitch += delta.y * controller.mouse_sensitivity;
+            let delta = mouse_settings.look_delta(delta.as_vec2());
+            controller.target.yaw -= delta.x;
+            controller.target.pitch += delta.y;
         }
 
         let rotation = Quat::from_rotation_z(controller.target.yaw.to_radians())
@@ -1,5 +1,22 @@
 use glam::{IVec2, UVec2, Vec2, Vec3, Vec4, ivec2};
 
+/// A rectangle of height map nodes. Both corners are included.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NodeRect {
+    pub min: IVec2,
+    pub max: IVec2,
+}
+
+impl NodeRect {
+    /// The smallest rect containing both rects.
+    pub fn union(self, other: NodeRect) -> NodeRect {
+        NodeRect {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+}
+
 pub struct HeightMap {
     /// Amount of nodes in the height map.
     pub size: UVec2,
@@ -7,6 +24,8 @@ pub struct HeightMap {
     pub cell_size: f32,
     /// Each node of the height map represented as [normal, altitude].
     pub nodes: Vec<Vec4>,
+    /// Nodes changed since the last [HeightMap::take_dirty_rect].
+    dirty: Option<NodeRect>,
 }
 
 impl HeightMap {
@@ -15,9 +34,13 @@ impl HeightMap {
             size,
             cell_size,
             nodes: nodes.map(|n| Vec3::ZERO.extend(n)).collect(),
+            dirty: None,
         };
 
-        height_map.recalculate_normals();
+        height_map.recalculate_normals(NodeRect {
+            min: IVec2::ZERO,
+            max: size.as_ivec2() - IVec2::ONE,
+        });
 
         height_map
     }
@@ -40,7 +63,7 @@ impl HeightMap {
     }
 
     /// Return the elevation at the given world space coordinate.
-    pub fn _elevation_at(&self, coord: Vec2) -> f32 {
+    pub fn elevation_at(&self, coord: Vec2) -> f32 {
         let node_space = (coord / self.cell_size).clamp(
             Vec2::ZERO,
             (self.size.as_vec2() - Vec2::ONE).max(Vec2::ZERO),
@@ -62,12 +85,131 @@ impl HeightMap {
         bottom + (top - bottom) * frac.y
     }
 
-    fn recalculate_normals(&mut self) {
+    /// Return the surface normal at the given world space coordinate,
+    /// interpolated between the surrounding nodes.
+    pub fn normal_at(&self, coord: Vec2) -> Vec3 {
+        let node_space = (coord / self.cell_size).clamp(
+            Vec2::ZERO,
+            (self.size.as_vec2() - Vec2::ONE).max(Vec2::ZERO),
+        );
+        let base = node_space.floor().as_ivec2();
+        let frac = node_space.fract();
+
+        let max_node = self.size.as_ivec2() - IVec2::ONE;
+        let next = (base + IVec2::ONE).min(max_node);
+
+        let n00 = self.node_at(base).truncate();
+        let n10 = self.node_at(IVec2::new(next.x, base.y)).truncate();
+        let n01 = self.node_at(IVec2::new(base.x, next.y)).truncate();
+        let n11 = self.node_at(next).truncate();
+
+        let bottom = n00.lerp(n10, frac.x);
+        let top = n01.lerp(n11, frac.x);
+
+        bottom.lerp(top, frac.y).try_normalize().unwrap_or(Vec3::Z)
+    }
+
+    /// Return the surface normal at the given world space coordinate, from the
+    /// slope of the interpolated elevation around it. Unlike [Self::normal_at],
+    /// which follows the game in keeping the edge nodes flat, this gives the
+    /// actual slope everywhere. Positions outside of the map use the nearest
+    /// edge.
+    pub fn normal_at_world(&self, coord: Vec2) -> Vec3 {
+        // Sample half a cell to each side, so the slope of the cell containing
+        // `coord` is found even on its edges.
+        let offset = self.cell_size * 0.5;
+        let dx = self.elevation_at(coord + Vec2::new(offset, 0.0))
+            - self.elevation_at(coord - Vec2::new(offset, 0.0));
+        let dy = self.elevation_at(coord + Vec2::new(0.0, offset))
+            - self.elevation_at(coord - Vec2::new(0.0, offset));
+
+        Vec3::new(-dx, -dy, offset * 2.0)
+            .try_normalize()
+            .unwrap_or(Vec3::Z)
+    }
+
+    /// Set the elevation of the node at `coord`. Nodes outside of the map are
+    /// ignored.
+    pub fn set_elevation(&mut self, coord: IVec2, elevation: f32) {
+        if coord.cmplt(IVec2::ZERO).any() || coord.cmpge(self.size.as_ivec2()).any() {
+            return;
+        }
+
+        self.nodes[coord.y as usize * self.size.x as usize + coord.x as usize].w = elevation;
+        self.changed(NodeRect {
+            min: coord,
+            max: coord,
+        });
+    }
+
+    /// Raise the nodes within `radius` of the world position `center` by
+    /// `delta`, falling off smoothly towards the edge. A negative `delta`
+    /// lowers them.
+    pub fn raise_region(&mut self, center: Vec2, radius: f32, delta: f32) {
+        if radius <= 0.0 || self.size.x == 0 || self.size.y == 0 {
+            return;
+        }
+
+        let max_node = self.size.as_ivec2() - IVec2::ONE;
+        let rect = NodeRect {
+            min: ((center - radius) / self.cell_size)
+                .ceil()
+                .as_ivec2()
+                .clamp(IVec2::ZERO, max_node),
+            max: ((center + radius) / self.cell_size)
+                .floor()
+                .as_ivec2()
+                .clamp(IVec2::ZERO, max_node),
+        };
+        if rect.min.cmpgt(rect.max).any() {
+            return;
+        }
+
+        for y in rect.min.y..=rect.max.y {
+            for x in rect.min.x..=rect.max.x {
+                let distance = (IVec2::new(x, y).as_vec2() * self.cell_size).distance(center);
+                if distance > radius {
+                    continue;
+                }
+
+                let t = 1.0 - distance / radius;
+                let falloff = t * t * (3.0 - 2.0 * t);
+                self.nodes[y as usize * self.size.x as usize + x as usize].w += delta * falloff;
+            }
+        }
+
+        self.changed(rect);
+    }
+
+    /// The nodes changed since the last [HeightMap::take_dirty_rect],
+    /// including nodes whose normals changed.
+    pub fn dirty_rect(&self) -> Option<NodeRect> {
+        self.dirty
+    }
+
+    /// Return the nodes changed since the last call, including nodes whose
+    /// normals changed, and start tracking changes again.
+    pub fn take_dirty_rect(&mut self) -> Option<NodeRect> {
+        self.dirty.take()
+    }
+
+    /// Update the normals around changed elevations and mark them dirty.
+    fn changed(&mut self, rect: NodeRect) {
+        // The normals of the neighbors depend on the changed nodes.
+        let rect = NodeRect {
+            min: (rect.min - IVec2::ONE).max(IVec2::ZERO),
+            max: (rect.max + IVec2::ONE).min(self.size.as_ivec2() - IVec2::ONE),
+        };
+        self.recalculate_normals(rect);
+        self.dirty = Some(self.dirty.map_or(rect, |dirty| dirty.union(rect)));
+    }
+
+    fn recalculate_normals(&mut self, rect: NodeRect) {
         let size = self.size.as_ivec2();
 
         // Force normals on the edges to be straight up. This is what the game does.
-        for y in 0..size.y {
-            for x in 0..size.x {
+        for y in rect.min.y..=rect.max.y {
+            for x in rect.min.x..=rect.max.x {
                 if x == 0 || y == 0 || x == size.x - 1 || y == size.y - 1 {
                     let node = &mut self.nodes[y as usize * size.x as usize + x as usize];
                     node.x = 0.0;
@@ -101,3 +243,73 @@ impl HeightMap {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normals_follow_a_slope() {
+        // A ramp rising 1 unit per cell along x.
+        let size = UVec2::new(4, 4);
+        let height_map = HeightMap::from_iter(size, 1.0, (0..16).map(|index| (index % 4) as f32));
+
+        assert_eq!(height_map.elevation_at(Vec2::new(1.5, 1.5)), 1.5);
+
+        let normal = height_map.normal_at(Vec2::new(1.5, 1.5));
+        let expected = Vec3::new(-1.0, 0.0, 1.0).normalize();
+        assert!(normal.abs_diff_eq(expected, 1e-5), "{normal}");
+    }
+
+    #[test]
+    fn edits_track_the_changed_nodes() {
+        let size = UVec2::new(8, 8);
+        let mut height_map = HeightMap::from_iter(size, 10.0, std::iter::repeat_n(0.0, 64));
+        assert_eq!(height_map.take_dirty_rect(), None);
+
+        height_map.set_elevation(IVec2::new(3, 4), 20.0);
+        assert_eq!(height_map.node_at(IVec2::new(3, 4)).w, 20.0);
+        // The normals of the neighbors now tilt away from the raised node.
+        assert!(height_map.node_at(IVec2::new(2, 4)).x < 0.0);
+
+        height_map.set_elevation(IVec2::new(5, 4), 20.0);
+        height_map.set_elevation(IVec2::new(50, 4), 20.0);
+        assert_eq!(
+            height_map.take_dirty_rect(),
+            Some(NodeRect {
+                min: IVec2::new(2, 3),
+                max: IVec2::new(6, 5),
+            })
+        );
+        assert_eq!(height_map.take_dirty_rect(), None);
+
+        height_map.raise_region(Vec2::new(10.0, 10.0), 15.0, 5.0);
+        assert_eq!(height_map.node_at(IVec2::new(1, 1)).w, 5.0);
+        assert!(height_map.node_at(IVec2::new(2, 1)).w < 5.0);
+        assert_eq!(height_map.node_at(IVec2::new(3, 1)).w, 0.0);
+        assert_eq!(
+            height_map.take_dirty_rect(),
+            Some(NodeRect {
+                min: IVec2::ZERO,
+                max: IVec2::new(3, 3),
+            })
+        );
+    }
+
+    #[test]
+    fn world_normals_include_the_edges_and_clamp_outside() {
+        let size = UVec2::new(4, 4);
+        let height_map = HeightMap::from_iter(size, 1.0, (0..16).map(|index| (index % 4) as f32));
+        let slope = Vec3::new(-1.0, 0.0, 1.0).normalize();
+
+        // The edge nodes are flat for normal_at, but not here.
+        let normal = height_map.normal_at_world(Vec2::new(0.5, 0.0));
+        assert!(normal.abs_diff_eq(slope, 1e-5), "{normal}");
+
+        // Outside of the map the elevation is clamped to the nearest edge.
+        assert_eq!(height_map.elevation_at(Vec2::new(-10.0, 1.0)), 0.0);
+        assert_eq!(height_map.elevation_at(Vec2::new(10.0, 50.0)), 3.0);
+        let normal = height_map.normal_at_world(Vec2::new(100.0, 100.0));
+        assert!(normal.is_finite() && normal.z > 0.0, "{normal}");
+    }
+}
@@ -0,0 +1,148 @@
+use bevy_ecs::prelude::*;
+use glam::{EulerRot, Quat, Vec3};
+
+use crate::{
+    engine::{storage::Handle, transform::Transform},
+    game::{
+        assets::model::Model,
+        globals,
+        sim::{
+            DynamicBvhHandle, StaticBvhHandle,
+            sequences::MotionController,
+            spawner::SpawnInfo,
+            systems::{self, world_interaction::WorldInteraction},
+        },
+    },
+};
+
+/// Show the object selected in the world and allow editing its transform.
+/// Edits are written back to the [Transform] component, which refreshes the
+/// object's bounding volume and render instance.
+pub fn inspector_ui(world: &mut World, ui: &mut egui::Ui) {
+    let Some(entity) = world.resource::<WorldInteraction>().selected_entity else {
+        ui.label("No object selected.");
+        return;
+    };
+
+    let Ok(entity_ref) = world.get_entity(entity) else {
+        ui.label("The selected object no longer exists.");
+        return;
+    };
+
+    egui::Grid::new("object_inspector").show(ui, |ui| {
+        ui.label("Entity");
+        ui.label(entity.to_string());
+        ui.end_row();
+
+        if let Some(spawn_info) = entity_ref.get::<SpawnInfo>() {
+            ui.label("Name");
+            ui.label(&spawn_info.name);
+            ui.end_row();
+
+            ui.label("Title");
+            ui.label(&spawn_info.title);
+            ui.end_row();
+
+            ui.label("Type");
+            ui.label(format!("{:?}", spawn_info.object_type));
+            ui.end_row();
+        }
+
+        ui.label("Model");
+        ui.label(match entity_ref.get::<Handle<Model>>() {
+            Some(handle) => format!("{handle:?}"),
+            None => String::from("None"),
+        });
+        ui.end_row();
+
+        ui.label("Animation");
+        ui.label(animation_state(entity_ref.get::<MotionController>()));
+        ui.end_row();
+
+        ui.label("Bounding volume");
+        ui.label(if entity_ref.contains::<StaticBvhHandle>() {
+            "Static"
+        } else if entity_ref.contains::<DynamicBvhHandle>() {
+            "Dynamic"
+        } else {
+            "None"
+        });
+        ui.end_row();
+    });
+
+    let Some(mut transform) = entity_ref.get::<Transform>().cloned() else {
+        return;
+    };
+    let is_static = entity_ref.contains::<StaticBvhHandle>();
+
+    if !edit_transform(ui, &mut transform) {
+        return;
+    }
+
+    if let Some(mut current) = world.get_mut::<Transform>(entity) {
+        *current = transform;
+    }
+
+    // The dynamic BVH follows changed transforms on the next update, but the
+    // static BVH is only built when objects are added.
+    if is_static && let Err(err) = world.run_system_cached(systems::rebuild_static_bvh) {
+        tracing::warn!("Could not rebuild the static BVH: {err}");
+    }
+}
+
+/// Drag values for the translation and the rotation in degrees. Returns true
+/// if anything changed.
+fn edit_transform(ui: &mut egui::Ui, transform: &mut Transform) -> bool {
+    let mut changed = false;
+
+    let (yaw, pitch, roll) = transform.rotation.to_euler(EulerRot::ZXY);
+    let mut rotation = Vec3::new(yaw, pitch, roll) * 180.0 / std::f32::consts::PI;
+
+    egui::Grid::new("object_inspector_transform").show(ui, |ui| {
+        ui.label("Translation");
+        for value in transform.translation.as_mut() {
+            changed |= ui.add(egui::DragValue::new(value).speed(1.0)).changed();
+        }
+        ui.end_row();
+
+        ui.label("Yaw / Pitch / Roll");
+        let mut rotation_changed = false;
+        for value in rotation.as_mut() {
+            rotation_changed |= ui
+                .add(egui::DragValue::new(value).speed(0.5).suffix("°"))
+                .changed();
+        }
+        ui.end_row();
+
+        if rotation_changed {
+            let radians = rotation * std::f32::consts::PI / 180.0;
+            transform.rotation =
+                Quat::from_euler(EulerRot::ZXY, radians.x, radians.y, radians.z).normalize();
+            changed = true;
+        }
+    });
+
+    changed
+}
+
+/// Describe the motion an object is playing.
+fn animation_state(motion_controller: Option<&MotionController>) -> String {
+    let Some(motion_controller) = motion_controller else {
+        return String::from("None");
+    };
+
+    let Some(active) = &motion_controller.active else {
+        return format!("Idle ({} pending)", motion_controller.pending.len());
+    };
+
+    let name = globals::motions()
+        .get(active.motion_info.motion)
+        .map(|motion| motion.name.clone())
+        .unwrap_or_default();
+
+    format!(
+        "{name} at tick {} ({} pending)",
+        active.current_time_ticks,
+        motion_controller.pending.len()
+    )
+}
@@ -0,0 +1,320 @@
+use bevy_ecs::prelude::*;
+use glam::{IVec2, UVec2, Vec2, Vec3};
+
+use crate::{
+    engine::transform::Transform,
+    game::{
+        config::ObjectType,
+        sim::{Terrain, height_map::NodeRect, spawner::SpawnInfo, systems::Time},
+    },
+};
+
+/// An RGBA color of a minimap pixel.
+pub type MinimapColor = [u8; 4];
+
+/// A top-down map of the terrain with a marker for each unit and structure,
+/// for the UI to display. The terrain is shaded once; the markers are redrawn
+/// every [Minimap::REFRESH_INTERVAL] seconds.
+#[derive(Resource)]
+pub struct Minimap {
+    /// Size of the map in pixels.
+    size: UVec2,
+    /// Size of the area covered by the map in world units.
+    world_size: Vec2,
+    /// The shaded terrain without markers.
+    terrain: Vec<MinimapColor>,
+    /// Lowest and highest elevation the terrain colors are scaled between.
+    elevation_range: (f32, f32),
+    /// The terrain with the markers drawn over it.
+    pixels: Vec<MinimapColor>,
+    /// Increased every time the pixels change.
+    generation: u64,
+    /// Seconds since the markers were last drawn.
+    since_refresh: f32,
+    /// The pixels uploaded for display and the generation they are from.
+    #[cfg(feature = "egui")]
+    texture: Option<(u64, egui::TextureHandle)>,
+}
+
+impl Minimap {
+    /// Size of the longest side of the map in pixels.
+    pub const MAX_SIZE: u32 = 256;
+    /// Seconds between redrawing the markers.
+    pub const REFRESH_INTERVAL: f32 = 1.0;
+    /// Size of a marker in pixels.
+    const MARKER_SIZE: i32 = 3;
+
+    /// Shade the terrain into a map no larger than [Minimap::MAX_SIZE] pixels.
+    pub fn new(terrain: &Terrain) -> Self {
+        let height_map = &terrain.height_map;
+        let world_size =
+            (height_map.size.as_vec2() - Vec2::ONE).max(Vec2::ONE) * height_map.cell_size;
+
+        let scale = Self::MAX_SIZE as f32 / world_size.max_element();
+        let size = (world_size * scale).round().as_uvec2().max(UVec2::ONE);
+
+        let mut minimap = Self {
+            size,
+            world_size,
+            terrain: Vec::default(),
+            elevation_range: height_map
+                .nodes
+                .iter()
+                .fold((f32::MAX, f32::MIN), |(min, max), node| {
+                    (min.min(node.w), max.max(node.w))
+                }),
+            pixels: Vec::default(),
+            generation: 0,
+            since_refresh: 0.0,
+            #[cfg(feature = "egui")]
+            texture: None,
+        };
+        minimap.terrain = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| UVec2::new(x, y)))
+            .map(|pixel| minimap.shade_pixel(terrain, pixel))
+            .collect();
+        minimap.pixels = minimap.terrain.clone();
+
+        minimap
+    }
+
+    /// Size of the map in pixels.
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// The pixels of the map in rows, with north (+Y) at the top.
+    pub fn pixels(&self) -> &[MinimapColor] {
+        &self.pixels
+    }
+
+    /// Increased every time the pixels change, so users know when to upload
+    /// them again.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The pixel showing the world position, or `None` if it is off the map.
+    pub fn world_to_minimap(&self, position: Vec3) -> Option<UVec2> {
+        let uv = position.truncate() / self.world_size;
+        if !(0.0..=1.0).contains(&uv.x) || !(0.0..=1.0).contains(&uv.y) {
+            return None;
+        }
+
+        let pixel = Vec2::new(uv.x, 1.0 - uv.y) * self.size.as_vec2();
+        Some(pixel.as_uvec2().min(self.size - UVec2::ONE))
+    }
+
+    /// Redraw the markers over the terrain.
+    fn draw_markers(&mut self, markers: impl Iterator<Item = (Vec3, MinimapColor)>) {
+        self.pixels.copy_from_slice(&self.terrain);
+
+        let half = Self::MARKER_SIZE / 2;
+        for (position, color) in markers {
+            let Some(center) = self.world_to_minimap(position) else {
+                continue;
+            };
+
+            for y in -half..=half {
+                for x in -half..=half {
+                    let pixel = center.as_ivec2() + glam::IVec2::new(x, y);
+                    if pixel.cmplt(glam::IVec2::ZERO).any()
+                        || pixel.cmpge(self.size.as_ivec2()).any()
+                    {
+                        continue;
+                    }
+                    self.pixels[(pixel.y as u32 * self.size.x + pixel.x as u32) as usize] = color;
+                }
+            }
+        }
+
+        self.generation += 1;
+    }
+
+    /// Shade the terrain again where the height map nodes in `rect` changed.
+    /// The markers are redrawn over it on the next update.
+    pub fn update_terrain(&mut self, terrain: &Terrain, rect: NodeRect) {
+        // Pixels sample the cells around each changed node.
+        let cell_size = terrain.height_map.cell_size;
+        let scale = self.size.as_vec2() / self.world_size;
+        let last = self.size.as_ivec2() - IVec2::ONE;
+        let min = ((rect.min - IVec2::ONE).as_vec2() * cell_size * scale)
+            .floor()
+            .as_ivec2()
+            .clamp(IVec2::ZERO, last);
+        let max = ((rect.max + IVec2::ONE).as_vec2() * cell_size * scale)
+            .floor()
+            .as_ivec2()
+            .clamp(IVec2::ZERO, last);
+
+        // Rows are stored with north at the top.
+        for y in (last.y - max.y)..=(last.y - min.y) {
+            for x in min.x..=max.x {
+                let pixel = UVec2::new(x as u32, y as u32);
+                self.terrain[(pixel.y * self.size.x + pixel.x) as usize] =
+                    self.shade_pixel(terrain, pixel);
+            }
+        }
+
+        self.since_refresh = Self::REFRESH_INTERVAL;
+    }
+
+    /// Color the terrain under a pixel by elevation, lit from the north west
+    /// so slopes stand out.
+    fn shade_pixel(&self, terrain: &Terrain, pixel: UVec2) -> MinimapColor {
+        const LOW: Vec3 = Vec3::new(0.20, 0.30, 0.15);
+        const HIGH: Vec3 = Vec3::new(0.65, 0.60, 0.45);
+
+        let height_map = &terrain.height_map;
+        let (min, max) = self.elevation_range;
+        let range = (max - min).max(1.0);
+
+        let light = Vec3::new(-1.0, 1.0, 1.0).normalize();
+
+        let uv = (Vec2::new(pixel.x as f32, (self.size.y - 1 - pixel.y) as f32) + 0.5)
+            / self.size.as_vec2();
+        let position = uv * self.world_size;
+
+        let elevation = ((height_map.elevation_at(position) - min) / range).clamp(0.0, 1.0);
+        let shade = height_map.normal_at(position).dot(light).clamp(0.0, 1.0);
+
+        let color = LOW.lerp(HIGH, elevation) * (0.4 + 0.6 * shade);
+        let color = (color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0).round();
+        [color.x as u8, color.y as u8, color.z as u8, 255]
+    }
+
+    /// Show the map, uploading the pixels when they changed.
+    #[cfg(feature = "egui")]
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        let image = || {
+            egui::ColorImage::from_rgba_unmultiplied(
+                [self.size.x as usize, self.size.y as usize],
+                bytemuck::cast_slice(&self.pixels),
+            )
+        };
+
+        match &mut self.texture {
+            Some((generation, texture)) => {
+                if *generation != self.generation {
+                    texture.set(image(), egui::TextureOptions::NEAREST);
+                    *generation = self.generation;
+                }
+            }
+            None => {
+                let texture =
+                    ui.ctx()
+                        .load_texture("minimap", image(), egui::TextureOptions::NEAREST);
+                self.texture = Some((self.generation, texture));
+            }
+        }
+
+        if let Some((_, texture)) = &self.texture {
+            ui.image((texture.id(), texture.size_vec2()));
+        }
+    }
+}
+
+/// Color of the marker for objects of a type, or `None` for objects that are
+/// not shown on the map.
+fn marker_color(object_type: ObjectType) -> Option<MinimapColor> {
+    use ObjectType as O;
+
+    match object_type {
+        O::Scenery
+        | O::SceneryAlarm
+        | O::SceneryBush
+        | O::SceneryFragile
+        | O::SceneryLit
+        | O::SceneryShadowed
+        | O::SceneryStripLight
+        | O::SceneryTree => None,
+
+        O::Ape
+        | O::Bipedal
+        | O::Bird
+        | O::Boat
+        | O::FourByFour
+        | O::Helicopter
+        | O::Howitzer
+        | O::SentryGun
+        | O::SixBySix
+        | O::SnowMobile
+        | O::Treaded
+        | O::TreadedBMP2
+        | O::TreadedChallenger
+        | O::TreadedScorpion
+        | O::TreadedT55 => Some([220, 40, 40, 255]),
+
+        _ => Some([230, 230, 230, 255]),
+    }
+}
+
+/// Redraw the minimap markers every [Minimap::REFRESH_INTERVAL] seconds.
+pub fn update_minimap(
+    time: Res<Time>,
+    mut minimap: ResMut<Minimap>,
+    objects: Query<(&Transform, &SpawnInfo)>,
+) {
+    minimap.since_refresh += time.delta_time;
+    if minimap.generation > 0 && minimap.since_refresh < Minimap::REFRESH_INTERVAL {
+        return;
+    }
+    minimap.since_refresh = 0.0;
+
+    minimap.draw_markers(objects.iter().filter_map(|(transform, spawn_info)| {
+        marker_color(spawn_info.object_type).map(|color| (transform.translation, color))
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn minimap(size: UVec2, world_size: Vec2) -> Minimap {
+        let pixels = vec![[0, 0, 0, 255]; (size.x * size.y) as usize];
+        Minimap {
+            size,
+            world_size,
+            terrain: pixels.clone(),
+            elevation_range: (0.0, 1.0),
+            pixels,
+            generation: 0,
+            since_refresh: 0.0,
+            #[cfg(feature = "egui")]
+            texture: None,
+        }
+    }
+
+    #[test]
+    fn world_to_minimap_puts_north_at_the_top() {
+        let minimap = minimap(UVec2::new(100, 50), Vec2::new(1000.0, 500.0));
+
+        assert_eq!(
+            minimap.world_to_minimap(Vec3::new(0.0, 0.0, 0.0)),
+            Some(UVec2::new(0, 49))
+        );
+        assert_eq!(
+            minimap.world_to_minimap(Vec3::new(1000.0, 500.0, 30.0)),
+            Some(UVec2::new(99, 0))
+        );
+        assert_eq!(
+            minimap.world_to_minimap(Vec3::new(505.0, 255.0, 0.0)),
+            Some(UVec2::new(50, 24))
+        );
+        assert_eq!(minimap.world_to_minimap(Vec3::new(-1.0, 10.0, 0.0)), None);
+        assert_eq!(minimap.world_to_minimap(Vec3::new(10.0, 501.0, 0.0)), None);
+    }
+
+    #[test]
+    fn markers_are_drawn_over_the_terrain() {
+        let mut minimap = minimap(UVec2::new(10, 10), Vec2::new(100.0, 100.0));
+        let red = [255, 0, 0, 255];
+
+        minimap.draw_markers([(Vec3::new(55.0, 45.0, 0.0), red)].into_iter());
+        assert_eq!(minimap.generation(), 1);
+        assert_eq!(minimap.pixels()[5 * 10 + 5], red);
+
+        minimap.draw_markers(std::iter::empty());
+        assert!(minimap.pixels().iter().all(|pixel| *pixel != red));
+    }
+}
@@ -2,25 +2,30 @@ use std::path::{Path, PathBuf};
 
 use ahash::HashSet;
 use bevy_ecs::prelude::*;
-use glam::{IVec2, Quat, Vec2, Vec3};
+use glam::{IVec2, Quat, Vec3};
 
 use crate::{
     engine::{
         assets::AssetError,
         input::{InputEvent, InputState},
+        mouse_settings::MouseSettings,
         transform::Transform,
     },
     game::{
         assets::config::campaign_def::CampaignDef,
-        config::{CharacterProfiles, Mtf, ObjectType, TerrainMapping, load_config},
+        config::{
+            self, CharacterProfiles, Mtf, ObjectTemplates, ObjectType, TerrainMapping, load_config,
+        },
         globals,
+        math::BoundingSphere,
         render::world::WorldRenderSnapshot,
     },
 };
 
-use ecs::{ActiveCamera, GizmoVertices, Viewport};
+use ecs::{ActiveCamera, GizmoVertices, ObjectId, Viewport, VisibilityFlags};
 use free_camera_controller::FreeCameraController;
 use orders::OrderRequest;
+use replay::Replay;
 use sequences::MotionSequencer;
 use systems::{
     SimulationControl, Time, build_extract_schedule, build_update_schedule,
@@ -29,15 +34,25 @@ use systems::{
 use top_down_camera_controller::TopDownCameraController;
 use ui::Ui;
 
+pub mod attachment;
 mod camera;
+pub mod character_controller;
 mod day_night_cycle;
 mod dynamic_bvh;
 pub mod ecs;
+pub mod events;
 pub mod extract;
 pub mod free_camera_controller;
 mod height_map;
+#[cfg(feature = "egui")]
+mod inspector;
+pub mod minimap;
 pub mod orders;
+pub mod pathfinding;
+#[cfg(feature = "egui")]
+mod placed_models;
 mod quad_tree;
+mod replay;
 pub mod sequences;
 mod spawner;
 mod static_bvh;
@@ -45,10 +60,12 @@ pub mod systems;
 mod terrain;
 pub mod top_down_camera_controller;
 mod ui;
+pub mod walkability;
 
 pub use camera::Camera;
 pub use camera::ComputedCamera;
-pub use day_night_cycle::DayNightCycle;
+pub use camera::ProjectionJitter;
+pub use day_night_cycle::{DayNightCycle, LightingOverride};
 pub use dynamic_bvh::{DynamicBvh, DynamicBvhHandle};
 pub use height_map::HeightMap;
 pub use static_bvh::{StaticBvh, StaticBvhHandle};
@@ -59,6 +76,14 @@ pub struct SimWorld {
     world: World,
     update_schedule: Schedule,
     extract_schedule: Schedule,
+    /// Input recording or playback.
+    replay: Replay,
+    /// File the debug panel records replays to and plays them from.
+    #[cfg(feature = "egui")]
+    replay_path: String,
+    /// Radius of the debug panel query for objects near the selection.
+    #[cfg(feature = "egui")]
+    nearby_radius: f32,
 }
 
 impl SimWorld {
@@ -73,6 +98,11 @@ impl SimWorld {
             world,
             update_schedule,
             extract_schedule,
+            replay: Replay::default(),
+            #[cfg(feature = "egui")]
+            replay_path: String::from("replay.txt"),
+            #[cfg(feature = "egui")]
+            nearby_radius: 1_000.0,
         })
     }
 
@@ -81,29 +111,301 @@ impl SimWorld {
         self.world.resource::<Terrain>()
     }
 
+    /// The top-down map of the terrain and objects.
+    pub fn minimap(&self) -> &minimap::Minimap {
+        self.world.resource::<minimap::Minimap>()
+    }
+
     /// Update the viewport size used by camera systems.
     pub fn resize_viewport(&mut self, size: glam::UVec2) {
         self.world.resource_mut::<Viewport>().resize(size);
     }
 
-    /// Forward an input event into the simulation's `InputState`.
+    /// Forward an input event into the simulation's `InputState`. Live input
+    /// is ignored while a replay is playing.
     pub fn input(&mut self, event: &InputEvent) {
-        self.world.resource_mut::<InputState>().apply(event);
+        self.replay.input(&mut self.world, event);
     }
 
-    /// Advance the simulation by `delta_time` seconds.
+    /// Advance the simulation by `delta_time` seconds. While a replay is
+    /// playing, the recorded delta time and input are used instead.
     pub fn update(&mut self, delta_time: f32) {
-        self.world.resource_mut::<Time>().next_frame(delta_time);
+        self.replay.begin_update(&mut self.world, delta_time);
         self.update_schedule.run(&mut self.world);
     }
 
+    /// Start recording the input and delta time of every update. The
+    /// recording is written to `path` when [Self::stop_recording] is called.
+    pub fn record_to(&mut self, path: impl Into<PathBuf>) {
+        self.replay.start_recording(path.into());
+    }
+
+    /// Stop recording and write the replay file.
+    pub fn stop_recording(&mut self) -> Result<(), AssetError> {
+        let Some((path, ticks)) = self.replay.stop_recording() else {
+            return Ok(());
+        };
+
+        tracing::info!(
+            "Writing {} replay ticks to {}.",
+            ticks.len(),
+            path.display()
+        );
+        replay::write_replay(&path, &ticks)
+    }
+
+    /// Play back a replay written by [Self::record_to]. Replays only reproduce
+    /// a run when started from the same initial world state.
+    pub fn replay_from(&mut self, path: impl AsRef<Path>) -> Result<(), AssetError> {
+        let ticks = replay::read_replay(path.as_ref())?;
+        self.replay.start_playback(ticks);
+        Ok(())
+    }
+
     /// Run the extract schedule to populate the snapshot, then return a
     /// reference to it. The snapshot is owned by the simulation `World`; a
     /// later call will overwrite it.
     pub fn extract_snapshot(&mut self) -> &WorldRenderSnapshot {
         self.extract_schedule.run(&mut self.world);
+        // Extract was the last reader of this frame's removed components, so
+        // drop them before the buffers grow without bound.
+        self.world.clear_trackers();
         self.world.resource::<WorldRenderSnapshot>()
     }
+
+    /// The sub-pixel jitter applied to the projection of the active camera.
+    pub fn projection_jitter(&self) -> &camera::ProjectionJitter {
+        self.world.resource::<camera::ProjectionJitter>()
+    }
+
+    /// Replace the projection jitter settings, e.g. to render a sequence of
+    /// jittered frames for an accumulated screenshot.
+    pub fn set_projection_jitter(&mut self, projection_jitter: camera::ProjectionJitter) {
+        self.world.insert_resource(projection_jitter);
+    }
+
+    /// Draws debug controls for the simulation.
+    #[cfg(feature = "egui")]
+    pub fn debug_panel(&mut self, ui: &mut egui::Ui) {
+        use crate::engine::egui_integration::UiExt;
+
+        ui.h2("Camera");
+
+        let mut projection_jitter = self.world.resource_mut::<camera::ProjectionJitter>();
+        ui.checkbox(&mut projection_jitter.enabled, "Projection jitter");
+
+        ui.h2("Selected model");
+
+        let mut model_debug_draw = self.world.resource_mut::<systems::debug::ModelDebugDraw>();
+        ui.checkbox(&mut model_debug_draw.wireframe, "Wireframe");
+        ui.checkbox(&mut model_debug_draw.normals, "Normals");
+
+        ui.h2("Mouse");
+
+        let mut mouse_settings = self.world.resource_mut::<MouseSettings>();
+        // Save once the slider is released rather than on every step of a drag.
+        let sensitivity_response = ui.add(
+            egui::Slider::new(&mut mouse_settings.sensitivity, 0.01..=1.0).text("Sensitivity"),
+        );
+        let sensitivity_committed = sensitivity_response.drag_stopped()
+            || sensitivity_response.lost_focus()
+            || (sensitivity_response.changed() && !sensitivity_response.dragged());
+        let invert_y_changed = ui
+            .checkbox(&mut mouse_settings.invert_y, "Invert Y")
+            .changed();
+        if (sensitivity_committed || invert_y_changed)
+            && let Err(err) = mouse_settings.save(Path::new(MouseSettings::PATH))
+        {
+            tracing::warn!("Could not save mouse settings: {err}");
+        }
+
+        ui.h2("Replay");
+
+        ui.horizontal(|ui| {
+            ui.label("File");
+            ui.add_enabled(
+                !self.replay.is_recording() && !self.replay.is_playing(),
+                egui::TextEdit::singleline(&mut self.replay_path),
+            );
+        });
+        ui.horizontal(|ui| {
+            if self.replay.is_recording() {
+                if ui.button("Stop recording").clicked()
+                    && let Err(err) = self.stop_recording()
+                {
+                    tracing::warn!("Could not write replay: {err}");
+                }
+            } else if self.replay.is_playing() {
+                ui.label("Playing...");
+            } else {
+                if ui.button("Record").clicked() {
+                    self.record_to(self.replay_path.clone());
+                }
+                if ui.button("Play").clicked()
+                    && let Err(err) = self.replay_from(self.replay_path.clone())
+                {
+                    tracing::warn!("Could not read replay: {err}");
+                }
+            }
+        });
+
+        ui.h2("Gizmos");
+
+        let mut grid = self.world.resource_mut::<systems::debug::GroundGrid>();
+        let grid_enabled = ui.checkbox(&mut grid.visible, "Grid").changed() && grid.visible;
+        ui.add_enabled(
+            grid.visible,
+            egui::Slider::new(&mut grid.spacing, 10.0..=1_000.0)
+                .logarithmic(true)
+                .text("Grid spacing"),
+        );
+
+        let mut gizmo_vertices = self.world.resource_mut::<GizmoVertices>();
+        // Fade the grid towards the horizon so it doesn't clutter the view.
+        if grid_enabled {
+            gizmo_vertices.color_by_depth = true;
+        }
+        ui.checkbox(&mut gizmo_vertices.color_by_depth, "Color by depth");
+
+        ui.h2("Collision");
+
+        self.world
+            .resource_mut::<systems::debug::CollisionDebugDraw>()
+            .ui(ui);
+
+        ui.h2("Lighting");
+
+        self.world.resource_mut::<LightingOverride>().ui(ui);
+
+        ui.h2("Minimap");
+
+        self.world.resource_mut::<minimap::Minimap>().ui(ui);
+
+        ui.h2("Placed models");
+
+        placed_models::placed_models_ui(self, ui);
+
+        ui.h2("Inspector");
+
+        inspector::inspector_ui(&mut self.world, ui);
+
+        ui.h2("Nearby objects");
+
+        self.nearby_objects_ui(ui);
+    }
+
+    /// List the objects within a radius of the selected object, nearest
+    /// first, and select one when clicked.
+    #[cfg(feature = "egui")]
+    fn nearby_objects_ui(&mut self, ui: &mut egui::Ui) {
+        let selected = self.world.resource::<WorldInteraction>().selected_entity;
+        let Some((selected, center)) = selected.and_then(|entity| {
+            let transform = self.world.get::<Transform>(entity)?;
+            Some((entity, transform.translation))
+        }) else {
+            ui.label("Select an object to list the objects around it.");
+            return;
+        };
+
+        ui.add(egui::Slider::new(&mut self.nearby_radius, 10.0..=10_000.0).text("Radius"));
+
+        let mut select = None;
+        for (entity, distance) in self.objects_in_sphere(center, self.nearby_radius) {
+            if entity == selected {
+                continue;
+            }
+
+            let name = self
+                .world
+                .get::<spawner::SpawnInfo>(entity)
+                .map_or_else(|| entity.to_string(), |info| info.name.clone());
+            if ui
+                .selectable_label(false, format!("{name} ({distance:.0})"))
+                .clicked()
+            {
+                select = Some(entity);
+            }
+        }
+
+        if select.is_some() {
+            self.world
+                .resource_mut::<WorldInteraction>()
+                .selected_entity = select;
+        }
+    }
+
+    /// Objects whose bounding boxes intersect the sphere at `center`, with the
+    /// distance from `center` to each box (`0.0` when inside), nearest first.
+    /// Used to apply area effects like explosions with a distance falloff.
+    pub fn objects_in_sphere(&self, center: Vec3, radius: f32) -> Vec<(Entity, f32)> {
+        objects_in_sphere(&self.world, center, radius)
+    }
+
+    /// Snap `entity` onto the terrain surface at its current position,
+    /// optionally tilting it to match the slope. Returns `false` if the entity
+    /// has no [Transform].
+    pub fn place_on_terrain(&mut self, entity: Entity, align_to_normal: bool) -> bool {
+        place_on_terrain(&mut self.world, entity, align_to_normal)
+    }
+
+    /// Queue an event for systems reading messages of type `E`.
+    pub fn send_event<E: Message>(&mut self, event: E) {
+        events::send_event(&mut self.world, event);
+    }
+
+    /// Remove and return all queued events of type `E`.
+    pub fn drain_events<E: Message>(&mut self) -> Vec<E> {
+        events::drain_events(&mut self.world)
+    }
+
+    /// Attach `child` to the bone named `bone_name` of `parent`'s model. The
+    /// child follows the animated bone from the next update onwards.
+    pub fn attach_to_bone(
+        &mut self,
+        child: Entity,
+        parent: Entity,
+        bone_name: &str,
+        local_offset: Transform,
+    ) -> Result<(), attachment::AttachError> {
+        attachment::attach_to_bone(&mut self.world, child, parent, bone_name, local_offset)
+    }
+
+    /// Move many objects at once. Bounding volumes and render instances are
+    /// refreshed once per object during the next update and extract, no
+    /// matter how many objects changed. Entities without a [Transform] are
+    /// skipped.
+    pub fn set_transforms(&mut self, transforms: &[(Entity, Transform)]) {
+        for (entity, new_transform) in transforms {
+            if let Some(mut transform) = self.world.get_mut::<Transform>(*entity) {
+                *transform = new_transform.clone();
+            } else {
+                tracing::warn!("Can't set transform of entity without one: {entity}");
+            }
+        }
+    }
+
+    /// Override how `entity` takes part in culling. Setting
+    /// [VisibilityFlags::Normal] removes the override.
+    pub fn set_visibility(&mut self, entity: Entity, flags: VisibilityFlags) {
+        let Ok(mut entity) = self.world.get_entity_mut(entity) else {
+            tracing::warn!("Can't set visibility of missing entity {entity}.");
+            return;
+        };
+
+        if flags == VisibilityFlags::Normal {
+            entity.remove::<VisibilityFlags>();
+        } else {
+            entity.insert(flags);
+        }
+    }
+
+    /// The culling override of `entity`.
+    pub fn visibility(&self, entity: Entity) -> VisibilityFlags {
+        self.world
+            .get::<VisibilityFlags>(entity)
+            .copied()
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Resource)]
@@ -116,6 +418,50 @@ pub struct SimWorldState {
     pub ui: Ui,
 }
 
+/// Objects in the static and dynamic BVH within `radius` of `center`. See
+/// [SimWorld::objects_in_sphere].
+fn objects_in_sphere(world: &World, center: Vec3, radius: f32) -> Vec<(Entity, f32)> {
+    let sphere = BoundingSphere::new(center, radius);
+
+    let mut candidates = Vec::new();
+    world
+        .resource::<StaticBvh>()
+        .objects_in_sphere(&sphere, &mut candidates);
+    world
+        .resource::<DynamicBvh>()
+        .query_sphere(&sphere, &mut candidates);
+
+    let mut result: Vec<(Entity, f32)> = candidates
+        .into_iter()
+        .filter_map(|entity| {
+            let transform = world.get::<Transform>(entity)?;
+            let bounding_box = world.get::<ecs::BoundingBoxComponent>(entity)?;
+            let distance = bounding_box
+                .0
+                .transformed(transform.to_mat4())
+                .distance_to_point(center);
+            (distance <= radius).then_some((entity, distance))
+        })
+        .collect();
+
+    result.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+    result
+}
+
+/// Move `entity` onto the terrain. See [Terrain::place_on_surface].
+fn place_on_terrain(world: &mut World, entity: Entity, align_to_normal: bool) -> bool {
+    let Some(mut transform) = world.get::<Transform>(entity).cloned() else {
+        return false;
+    };
+
+    world
+        .resource::<Terrain>()
+        .place_on_surface(&mut transform, align_to_normal);
+    world.entity_mut(entity).insert(transform);
+
+    true
+}
+
 fn init_sim_world(world: &mut World, campaign_def: &CampaignDef) -> Result<(), AssetError> {
     let campaign = load_config::<crate::game::config::Campaign>(
         PathBuf::from("campaign")
@@ -127,8 +473,14 @@ fn init_sim_world(world: &mut World, campaign_def: &CampaignDef) -> Result<(), A
     world.init_resource::<Time>();
     world.init_resource::<SimulationControl>();
     world.init_resource::<InputState>();
+    world.insert_resource(MouseSettings::load_or_default(Path::new(
+        MouseSettings::PATH,
+    )));
 
     world.init_resource::<WorldInteraction>();
+    world.init_resource::<systems::debug::ModelDebugDraw>();
+    world.init_resource::<systems::debug::GroundGrid>();
+    world.init_resource::<systems::debug::CollisionDebugDraw>();
 
     world.init_resource::<WorldRenderSnapshot>();
 
@@ -137,8 +489,10 @@ fn init_sim_world(world: &mut World, campaign_def: &CampaignDef) -> Result<(), A
     let time_of_day = 12.0;
 
     world.insert_resource(day_night_cycle::DayNightCycle::from_campaign(&campaign));
+    world.init_resource::<LightingOverride>();
 
     world.add_observer(world_interaction::on_clicked);
+    world.init_resource::<Messages<world_interaction::ObjectClicked>>();
 
     let motion_sequencer = {
         let mut motion_sequencer = MotionSequencer::default();
@@ -179,29 +533,21 @@ fn init_sim_world(world: &mut World, campaign_def: &CampaignDef) -> Result<(), A
 
     // Cameras
 
-    world.spawn((
-        Camera::new(
-            Vec3::ZERO,
+    {
+        let mut camera = Camera::new(
+            campaign.view_initial.from.extend(2500.0),
             Quat::IDENTITY,
             45.0_f32.to_radians(),
             1.0,
             10.0,
             13_300.0,
-        ),
-        {
-            let camera_from = campaign.view_initial.from.extend(2500.0);
-            let camera_to = campaign.view_initial.to.extend(0.0);
+        );
+        camera.look_at(campaign.view_initial.to.extend(0.0), Camera::UP);
 
-            let dir = (camera_to - camera_from).normalize();
+        let controller = TopDownCameraController::from_camera(&camera, 4_000.0, 100.0);
 
-            let flat = Vec2::new(dir.x, dir.y);
-            let yaw = (-dir.x).atan2(dir.y).to_degrees();
-            let pitch = dir.z.atan2(flat.length()).to_degrees();
-
-            TopDownCameraController::new(camera_from, yaw, pitch, 4_000.0, 100.0)
-        },
-        ActiveCamera,
-    ));
+        world.spawn((camera, controller, ActiveCamera));
+    }
 
     world.spawn((
         Camera::new(
@@ -212,7 +558,7 @@ fn init_sim_world(world: &mut World, campaign_def: &CampaignDef) -> Result<(), A
             10.0,
             13_300.0,
         ),
-        FreeCameraController::new(1000.0, 0.2),
+        FreeCameraController::new(1000.0),
     ));
 
     init_terrain(world, campaign_def)?;
@@ -230,10 +576,14 @@ fn init_sim_world(world: &mut World, campaign_def: &CampaignDef) -> Result<(), A
     world.insert_resource(sim_world_state);
 
     world.init_resource::<Viewport>();
+    world.init_resource::<camera::ProjectionJitter>();
 
     Ok(())
 }
 
+/// Steepest slope, in radians, that units can walk on.
+pub(crate) const MAX_WALKABLE_SLOPE: f32 = 40.0_f32.to_radians();
+
 fn init_terrain(world: &mut World, campaign_def: &CampaignDef) -> Result<(), AssetError> {
     let terrain = {
         let terrain_mapping = load_config::<TerrainMapping>(
@@ -303,6 +653,8 @@ fn init_terrain(world: &mut World, campaign_def: &CampaignDef) -> Result<(), Ass
 
         Terrain::new(height_map, terrain_texture, strata_texture)
     };
+    world.insert_resource(terrain.walkability_grid(MAX_WALKABLE_SLOPE));
+    world.insert_resource(minimap::Minimap::new(&terrain));
     world.insert_resource(terrain);
     Ok(())
 }
@@ -334,33 +686,144 @@ fn init_objects(
         character_profiles
     };
 
-    let mut object_spawner = spawner::Spawner::new(character_profiles);
+    let object_templates = {
+        let path = PathBuf::from("config").join("object_templates.txt");
+        load_config::<ObjectTemplates>(&path).unwrap_or_else(|err| {
+            tracing::warn!("Could not load object templates! ({err})");
+            ObjectTemplates::default()
+        })
+    };
+
+    let mut object_spawner = spawner::Spawner::new(character_profiles, object_templates);
 
     if let Some(ref mtf_name) = campaign.mtf_name {
         let mtf = load_config::<Mtf>(PathBuf::from("maps").join(mtf_name))?;
 
-        for object in mtf.objects.iter() {
-            let object_type = ObjectType::from_string(&object.typ)
-                .unwrap_or_else(|| panic!("missing object type: {}", object.typ));
-
+        spawn_mtf_objects(world, &mtf, |world, object| {
             let transform = Transform::from_translation(object.position)
                 .with_euler_rotation(object.rotation * Vec3::new(1.0, 1.0, -1.0));
 
-            let _ = match object_spawner.spawn(
-                world,
-                &object.title,
-                &object.name,
-                object_type,
-                transform.clone(),
-            ) {
-                Ok(handle) => handle,
-                Err(err) => {
-                    tracing::warn!("Could not spawn object! ({})", err);
-                    continue;
+            // Objects with an explicit type are spawned as that type, otherwise
+            // the type is resolved from the object templates.
+            match ObjectType::from_string(&object.typ) {
+                Some(object_type) => {
+                    object_spawner.spawn(world, &object.title, &object.name, object_type, transform)
                 }
-            };
-        }
+                None => object_spawner.spawn_from_template(
+                    world,
+                    &object.name,
+                    &object.title,
+                    transform,
+                ),
+            }
+        });
     }
 
     Ok(())
 }
+
+/// Spawn the objects of `mtf` with `spawn`, tagging each with its [ObjectId].
+///
+/// Objects are spawned one at a time in the order they appear in the file, so
+/// loading the same map always assigns the same entities to the same objects.
+fn spawn_mtf_objects(
+    world: &mut World,
+    mtf: &Mtf,
+    mut spawn: impl FnMut(&mut World, &config::Object) -> Result<Entity, AssetError>,
+) {
+    for object in mtf.objects.iter() {
+        match spawn(world, object) {
+            Ok(entity) => {
+                world.entity_mut(entity).insert(ObjectId(object.id));
+            }
+            Err(err) => tracing::warn!("Could not spawn object! ({})", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::math::BoundingBox;
+
+    #[test]
+    fn objects_in_sphere_are_sorted_by_distance() {
+        let mut world = World::default();
+        let unit_box = BoundingBox {
+            min: Vec3::splat(-1.0),
+            max: Vec3::splat(1.0),
+        };
+        let spawn = |world: &mut World, x: f32| {
+            world
+                .spawn((
+                    Transform::from_translation(Vec3::new(x, 0.0, 0.0)),
+                    ecs::BoundingBoxComponent(unit_box),
+                ))
+                .id()
+        };
+
+        let far_static = spawn(&mut world, 9.0);
+        let near_static = spawn(&mut world, 3.0);
+        let outside = spawn(&mut world, 30.0);
+        let mut static_bvh = StaticBvh::new(8);
+        static_bvh.rebuild(&[far_static, near_static, outside].map(|entity| {
+            let transform = world.get::<Transform>(entity).unwrap();
+            (entity, unit_box.transformed(transform.to_mat4()))
+        }));
+        world.insert_resource(static_bvh);
+
+        let inside = spawn(&mut world, 0.5);
+        let middle = spawn(&mut world, 6.0);
+        let mut dynamic_bvh = DynamicBvh::default();
+        for entity in [middle, inside] {
+            let transform = world.get::<Transform>(entity).unwrap();
+            dynamic_bvh.insert(entity, unit_box.transformed(transform.to_mat4()));
+        }
+        world.insert_resource(dynamic_bvh);
+
+        assert_eq!(
+            objects_in_sphere(&world, Vec3::ZERO, 10.0),
+            [
+                (inside, 0.0),
+                (near_static, 2.0),
+                (middle, 5.0),
+                (far_static, 8.0)
+            ]
+        );
+        assert_eq!(
+            objects_in_sphere(&world, Vec3::ZERO, 4.0),
+            [(inside, 0.0), (near_static, 2.0)]
+        );
+    }
+
+    #[test]
+    fn mtf_objects_spawn_in_file_order() {
+        const MTF: &str = "
+            OBJECT Structure Barrel \"Barrel\"
+            OBJECT_ID 1 20
+            OBJECT Bipedal Guard \"Guard\"
+            OBJECT_ID 1 10
+            OBJECT Scenery Tree \"Tree\"
+            OBJECT_ID 1 30
+        ";
+
+        let load = || {
+            let mtf = Mtf::from(crate::game::config::parser::ConfigLines::parse(MTF));
+            let mut world = World::default();
+            spawn_mtf_objects(&mut world, &mtf, |world, _| Ok(world.spawn_empty().id()));
+
+            world
+                .query::<(Entity, &ObjectId)>()
+                .iter(&world)
+                .map(|(entity, id)| (entity, *id))
+                .collect::<Vec<_>>()
+        };
+
+        let first = load();
+        assert_eq!(
+            first.iter().map(|(_, id)| *id).collect::<Vec<_>>(),
+            vec![ObjectId([1, 20]), ObjectId([1, 10]), ObjectId([1, 30])]
+        );
+        assert_eq!(first, load());
+    }
+}
@@ -1,8 +1,17 @@
+use crate::engine::transform::Transform;
+
 pub mod move_to;
 pub mod move_to_attack;
 
+/// The parts of the entity executing an order that the order can change.
+pub struct OrderContext<'a> {
+    pub transform: &'a mut Transform,
+    /// Seconds since the previous update.
+    pub delta_time: f32,
+}
+
 pub trait RuntimeOrder {
-    fn execute(&mut self) -> ExecuteOutcome;
+    fn execute(&mut self, context: &mut OrderContext) -> ExecuteOutcome;
 }
 
 #[derive(Debug)]
@@ -12,10 +21,10 @@ pub enum ActiveOrder {
 }
 
 impl RuntimeOrder for ActiveOrder {
-    fn execute(&mut self) -> ExecuteOutcome {
+    fn execute(&mut self, context: &mut OrderContext) -> ExecuteOutcome {
         match self {
-            ActiveOrder::MoveTo(order) => order.execute(),
-            ActiveOrder::MoveToAttack(order) => order.execute(),
+            ActiveOrder::MoveTo(order) => order.execute(context),
+            ActiveOrder::MoveToAttack(order) => order.execute(context),
         }
     }
 }
@@ -29,10 +38,10 @@ pub enum ExecuteOutcome {
 
 impl ActiveOrder {
     /// Executes the active order and returns its outcome.
-    pub fn execute(&mut self) -> ExecuteOutcome {
+    pub fn execute(&mut self, context: &mut OrderContext) -> ExecuteOutcome {
         match self {
-            ActiveOrder::MoveTo(order) => order.execute(),
-            ActiveOrder::MoveToAttack(order) => order.execute(),
+            ActiveOrder::MoveTo(order) => order.execute(context),
+            ActiveOrder::MoveToAttack(order) => order.execute(context),
         }
     }
 }
@@ -5,10 +5,72 @@ use super::*;
 #[derive(Debug)]
 pub struct MoveToOrder {
     pub location: Vec3,
+    /// Waypoints from the starting position to `location`. Waypoints are
+    /// removed as they are reached.
+    pub path: Vec<Vec3>,
+}
+
+impl MoveToOrder {
+    /// Distance covered per second while following the path.
+    pub const WALK_SPEED: f32 = 300.0;
 }
 
 impl RuntimeOrder for MoveToOrder {
-    fn execute(&mut self) -> ExecuteOutcome {
+    fn execute(&mut self, context: &mut OrderContext) -> ExecuteOutcome {
+        let mut distance = Self::WALK_SPEED * context.delta_time;
+        let position = &mut context.transform.translation;
+
+        // Reaching a waypoint carries the rest of the step over to the next.
+        while let Some(&waypoint) = self.path.first() {
+            let to_waypoint = waypoint - *position;
+            let length = to_waypoint.length();
+            if length > distance {
+                *position += to_waypoint / length * distance;
+                return ExecuteOutcome::Executing;
+            }
+
+            *position = waypoint;
+            distance -= length;
+            self.path.remove(0);
+        }
+
         ExecuteOutcome::Complete
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::transform::Transform;
+
+    use super::*;
+
+    #[test]
+    fn orders_follow_the_waypoints_in_order() {
+        let mut order = MoveToOrder {
+            location: Vec3::new(300.0, 300.0, 0.0),
+            path: vec![
+                Vec3::ZERO,
+                Vec3::new(300.0, 0.0, 0.0),
+                Vec3::new(300.0, 300.0, 0.0),
+            ],
+        };
+        let mut transform = Transform::default();
+        let mut step = |order: &mut MoveToOrder, delta_time| {
+            let outcome = order.execute(&mut OrderContext {
+                transform: &mut transform,
+                delta_time,
+            });
+            (
+                matches!(outcome, ExecuteOutcome::Complete),
+                transform.translation,
+            )
+        };
+
+        assert_eq!(step(&mut order, 0.5), (false, Vec3::new(150.0, 0.0, 0.0)));
+        // Turning the corner mid-step.
+        assert_eq!(step(&mut order, 1.0), (false, Vec3::new(300.0, 150.0, 0.0)));
+        assert_eq!(order.path, [Vec3::new(300.0, 300.0, 0.0)]);
+        assert_eq!(step(&mut order, 1.0), (true, Vec3::new(300.0, 300.0, 0.0)));
+        assert!(order.path.is_empty());
+    }
+}
@@ -8,7 +8,7 @@ pub struct MoveToAttackOrder {
 }
 
 impl RuntimeOrder for MoveToAttackOrder {
-    fn execute(&mut self) -> ExecuteOutcome {
+    fn execute(&mut self, _context: &mut OrderContext) -> ExecuteOutcome {
         ExecuteOutcome::Complete
     }
 }
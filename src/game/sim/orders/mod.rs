@@ -2,6 +2,6 @@ mod active_order;
 mod order_request;
 mod orders_controller;
 
-pub use active_order::ActiveOrder;
+pub use active_order::{ActiveOrder, OrderContext};
 pub use order_request::{OrderRequest, RequestedOrder};
 pub use orders_controller::OrdersController;
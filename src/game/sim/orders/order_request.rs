@@ -44,8 +44,14 @@ pub enum OrderCancelPolicy {
 
 #[derive(Clone, Debug)]
 pub enum RequestedOrder {
-    MoveTo { location: Vec3 },
-    MoveToAttack { entity: Entity },
+    /// Move to `location` along `path`, a list of waypoints in world space.
+    MoveTo {
+        location: Vec3,
+        path: Vec<Vec3>,
+    },
+    MoveToAttack {
+        entity: Entity,
+    },
 }
 
 impl RequestedOrder {
@@ -60,7 +66,9 @@ impl RequestedOrder {
     /// Instantiates a runtime order from the request payload.
     pub fn into_active(self) -> ActiveOrder {
         match self {
-            RequestedOrder::MoveTo { location } => ActiveOrder::MoveTo(MoveToOrder { location }),
+            RequestedOrder::MoveTo { location, path } => {
+                ActiveOrder::MoveTo(MoveToOrder { location, path })
+            }
             RequestedOrder::MoveToAttack { entity } => {
                 ActiveOrder::MoveToAttack(MoveToAttackOrder { entity })
             }
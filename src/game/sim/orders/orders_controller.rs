@@ -2,7 +2,11 @@ use std::collections::VecDeque;
 
 use bevy_ecs::prelude::*;
 
-use crate::game::sim::orders::{ActiveOrder, OrderRequest, order_request::OrderPriority};
+use crate::game::sim::orders::{
+    ActiveOrder, OrderRequest,
+    active_order::{ExecuteOutcome, OrderContext},
+    order_request::OrderPriority,
+};
 
 #[derive(Component, Default)]
 pub struct OrdersController {
@@ -24,8 +28,13 @@ impl OrdersController {
         });
     }
 
+    /// Whether there is no order to execute.
+    pub fn is_idle(&self) -> bool {
+        self.active_order.is_none() && self.pending_orders.is_empty()
+    }
+
     /// Execute the current order.
-    pub fn execute(&mut self, frame_index: u64) {
+    pub fn execute(&mut self, frame_index: u64, context: &mut OrderContext) {
         // TODO: Even if there is an active order, peek any pending orders for one with a higher
         //       priority.
 
@@ -44,7 +53,7 @@ impl OrdersController {
 
         if let Some(order) = self.active_order.as_mut() {
             tracing::info!("({}) Executing active order: {order:?}", frame_index);
-            if let super::active_order::ExecuteOutcome::Complete = order.runtime.execute() {
+            if let ExecuteOutcome::Complete = order.runtime.execute(context) {
                 self.active_order = None;
             }
         }
@@ -0,0 +1,242 @@
+use std::{cmp::Ordering, collections::BinaryHeap};
+
+use glam::{IVec2, Vec2, Vec3};
+
+use super::{HeightMap, walkability::WalkabilityGrid};
+
+/// Extra cost per radian of slope, relative to walking on flat ground. Keeps
+/// paths on flat ground when a detour is cheap.
+const SLOPE_COST: f32 = 2.0;
+
+/// The 8 neighbors of a cell with the length of the step to each.
+const NEIGHBORS: [(IVec2, f32); 8] = [
+    (IVec2::new(1, 0), 1.0),
+    (IVec2::new(-1, 0), 1.0),
+    (IVec2::new(0, 1), 1.0),
+    (IVec2::new(0, -1), 1.0),
+    (IVec2::new(1, 1), std::f32::consts::SQRT_2),
+    (IVec2::new(1, -1), std::f32::consts::SQRT_2),
+    (IVec2::new(-1, 1), std::f32::consts::SQRT_2),
+    (IVec2::new(-1, -1), std::f32::consts::SQRT_2),
+];
+
+/// An entry in the open set, ordered so the [BinaryHeap] pops the lowest
+/// estimated total cost first.
+struct OpenNode {
+    estimate: f32,
+    index: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+/// Find the cheapest path of cells from `start` to `goal` with A*. Moves can
+/// be diagonal, but not past the corner of an unwalkable cell, and sloped
+/// cells cost more to enter. The result includes `start` and `goal` and only
+/// keeps the cells where the direction changes. Returns `None` if either end
+/// is not walkable or the goal can not be reached.
+pub fn pathfind(grid: &WalkabilityGrid, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+    if !grid.is_walkable(start) || !grid.is_walkable(goal) {
+        return None;
+    }
+
+    let size = grid.size();
+    let index_of = |coord: IVec2| coord.y as usize * size.x as usize + coord.x as usize;
+    let coord_of = |index: usize| {
+        IVec2::new(
+            (index % size.x as usize) as i32,
+            (index / size.x as usize) as i32,
+        )
+    };
+
+    let cell_count = size.x as usize * size.y as usize;
+    let mut cost = vec![f32::INFINITY; cell_count];
+    let mut came_from = vec![usize::MAX; cell_count];
+    let mut open = BinaryHeap::new();
+
+    cost[index_of(start)] = 0.0;
+    open.push(OpenNode {
+        estimate: heuristic(start, goal),
+        index: index_of(start),
+    });
+
+    let goal_index = index_of(goal);
+
+    while let Some(OpenNode { estimate, index }) = open.pop() {
+        if index == goal_index {
+            let mut path = vec![goal];
+            let mut current = index;
+            while came_from[current] != usize::MAX {
+                current = came_from[current];
+                path.push(coord_of(current));
+            }
+            path.reverse();
+            remove_collinear(&mut path);
+            return Some(path);
+        }
+
+        let coord = coord_of(index);
+
+        // Skip stale entries that were pushed before a cheaper route was found.
+        if estimate > cost[index] + heuristic(coord, goal) {
+            continue;
+        }
+
+        for (offset, distance) in NEIGHBORS {
+            let next = coord + offset;
+            if !grid.is_walkable(next) {
+                continue;
+            }
+
+            // Don't cut corners around cells that can't be walked on.
+            if offset.x != 0
+                && offset.y != 0
+                && (!grid.is_walkable(IVec2::new(next.x, coord.y))
+                    || !grid.is_walkable(IVec2::new(coord.x, next.y)))
+            {
+                continue;
+            }
+
+            let next_index = index_of(next);
+            let next_cost =
+                cost[index] + distance * (1.0 + grid.slope(next).unwrap_or(0.0) * SLOPE_COST);
+
+            if next_cost < cost[next_index] {
+                cost[next_index] = next_cost;
+                came_from[next_index] = index;
+                open.push(OpenNode {
+                    estimate: next_cost + heuristic(next, goal),
+                    index: next_index,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// Convert a cell path to world positions at the center of each cell, on the
+/// surface of the terrain.
+pub fn path_to_world(height_map: &HeightMap, path: &[IVec2]) -> Vec<Vec3> {
+    path.iter()
+        .map(|coord| {
+            let position = (coord.as_vec2() + Vec2::splat(0.5)) * height_map.cell_size;
+            position.extend(height_map.elevation_at(position))
+        })
+        .collect()
+}
+
+/// The cell containing the world `position`.
+pub fn world_to_cell(height_map: &HeightMap, position: Vec3) -> IVec2 {
+    (position.truncate() / height_map.cell_size)
+        .floor()
+        .as_ivec2()
+}
+
+/// Octile distance, the exact cost of the shortest path on flat open ground.
+fn heuristic(from: IVec2, to: IVec2) -> f32 {
+    let delta = (to - from).abs();
+    let (min, max) = (delta.min_element() as f32, delta.max_element() as f32);
+    max + (std::f32::consts::SQRT_2 - 1.0) * min
+}
+
+/// Drop waypoints that lie on a straight line between their neighbors.
+fn remove_collinear(path: &mut Vec<IVec2>) {
+    if path.len() < 3 {
+        return;
+    }
+
+    let mut smoothed = Vec::with_capacity(path.len());
+    smoothed.push(path[0]);
+    for window in path.windows(3) {
+        let [previous, current, next] = [window[0], window[1], window[2]];
+        if (current - previous) != (next - current) {
+            smoothed.push(current);
+        }
+    }
+    smoothed.push(path[path.len() - 1]);
+
+    *path = smoothed;
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::UVec2;
+
+    use super::*;
+
+    fn flat_grid(size: u32) -> WalkabilityGrid {
+        let size = UVec2::splat(size);
+        let height_map = HeightMap::from_iter(
+            size,
+            100.0,
+            std::iter::repeat_n(0.0, (size.x * size.y) as usize),
+        );
+        WalkabilityGrid::new(&height_map, 30.0_f32.to_radians())
+    }
+
+    #[test]
+    fn straight_paths_are_reduced_to_their_ends() {
+        let grid = flat_grid(8);
+
+        let path = pathfind(&grid, IVec2::new(1, 1), IVec2::new(6, 1)).unwrap();
+        assert_eq!(path, vec![IVec2::new(1, 1), IVec2::new(6, 1)]);
+
+        let path = pathfind(&grid, IVec2::new(0, 0), IVec2::new(5, 5)).unwrap();
+        assert_eq!(path, vec![IVec2::new(0, 0), IVec2::new(5, 5)]);
+    }
+
+    #[test]
+    fn paths_route_around_walls() {
+        let mut grid = flat_grid(10);
+
+        // A wall at x == 5 with a single gap at y == 8.
+        for y in 0..10 {
+            if y != 8 {
+                grid.set_blocked(IVec2::new(5, y), true);
+            }
+        }
+
+        let start = IVec2::new(2, 2);
+        let goal = IVec2::new(8, 2);
+        let path = pathfind(&grid, start, goal).unwrap();
+
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&goal));
+
+        // Walk every cell between the waypoints and make sure none are blocked
+        // and the path goes through the gap.
+        let mut cells = vec![start];
+        for pair in path.windows(2) {
+            let step = (pair[1] - pair[0]).signum();
+            let mut cell = pair[0];
+            while cell != pair[1] {
+                cell += step;
+                cells.push(cell);
+            }
+        }
+        assert!(cells.iter().all(|&cell| grid.is_walkable(cell)));
+        assert!(cells.contains(&IVec2::new(5, 8)));
+
+        // Closing the gap leaves no way through.
+        grid.set_blocked(IVec2::new(5, 8), true);
+        assert_eq!(pathfind(&grid, start, goal), None);
+    }
+}
@@ -0,0 +1,170 @@
+use bevy_ecs::prelude::*;
+use glam::Vec3;
+
+use crate::{
+    engine::{assets::AssetError, transform::Transform},
+    game::{
+        asset_browser::AssetBrowser,
+        config::ObjectType,
+        sim::{
+            Camera, SimWorld, Terrain,
+            ecs::{ActiveCamera, VisibilityFlags},
+            spawner::{SpawnInfo, Spawner},
+            systems::world_interaction::WorldInteraction,
+        },
+    },
+};
+
+/// Distance in front of the camera where models are placed when nothing is
+/// selected.
+const PLACE_DISTANCE: f32 = 1_000.0;
+
+/// Models added to the world from the debug panel, to check how separate
+/// models line up next to each other.
+#[derive(Resource)]
+pub struct PlacedModels {
+    browser: AssetBrowser,
+    entities: Vec<Entity>,
+    /// Place new models on the terrain surface.
+    snap_to_ground: bool,
+    /// Tilt models placed on the terrain to match its slope.
+    align_to_slope: bool,
+}
+
+impl Default for PlacedModels {
+    fn default() -> Self {
+        Self {
+            browser: AssetBrowser::new("models", &["smf"]),
+            entities: Vec::default(),
+            snap_to_ground: true,
+            align_to_slope: false,
+        }
+    }
+}
+
+/// List the placed models and allow adding more from the model files. Each
+/// model is a separate entity, so it can be selected and moved with the
+/// inspector.
+pub fn placed_models_ui(sim: &mut SimWorld, ui: &mut egui::Ui) {
+    let mut placed = sim
+        .world
+        .remove_resource::<PlacedModels>()
+        .unwrap_or_default();
+
+    placed
+        .entities
+        .retain(|&entity| sim.world.get_entity(entity).is_ok());
+
+    let mut remove = None;
+    let mut set_visibility = None;
+    for &entity in placed.entities.iter() {
+        let mut visible = sim.visibility(entity) != VisibilityFlags::Hidden;
+        let world = &mut sim.world;
+        ui.horizontal(|ui| {
+            let name = world
+                .get::<SpawnInfo>(entity)
+                .map_or_else(|| entity.to_string(), |info| info.name.clone());
+
+            let selected = world.resource::<WorldInteraction>().selected_entity == Some(entity);
+            if ui.selectable_label(selected, name).clicked() {
+                world.resource_mut::<WorldInteraction>().selected_entity = Some(entity);
+            }
+
+            if ui.checkbox(&mut visible, "Visible").changed() {
+                let flags = if visible {
+                    VisibilityFlags::Normal
+                } else {
+                    VisibilityFlags::Hidden
+                };
+                set_visibility = Some((entity, flags));
+            }
+
+            if ui.button("Ground").clicked() {
+                super::place_on_terrain(world, entity, placed.align_to_slope);
+            }
+
+            if ui.button("Remove").clicked() {
+                remove = Some(entity);
+            }
+        });
+    }
+
+    if let Some((entity, flags)) = set_visibility {
+        sim.set_visibility(entity, flags);
+    }
+
+    if ui.button("Ground all").clicked() {
+        let terrain = sim.world.resource::<Terrain>();
+        let transforms: Vec<_> = placed
+            .entities
+            .iter()
+            .filter_map(|&entity| {
+                let mut transform = sim.world.get::<Transform>(entity)?.clone();
+                terrain.place_on_surface(&mut transform, placed.align_to_slope);
+                Some((entity, transform))
+            })
+            .collect();
+        sim.set_transforms(&transforms);
+    }
+
+    let world = &mut sim.world;
+
+    if let Some(entity) = remove {
+        despawn_placed_model(world, entity);
+        placed.entities.retain(|&e| e != entity);
+    }
+
+    ui.checkbox(&mut placed.snap_to_ground, "Snap to ground");
+    ui.checkbox(&mut placed.align_to_slope, "Align to slope");
+
+    ui.collapsing("Add model", |ui| {
+        if let Some(path) = placed.browser.ui(ui)
+            && let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string())
+        {
+            match place_model(world, &name) {
+                Ok(entity) => {
+                    if placed.snap_to_ground {
+                        super::place_on_terrain(world, entity, placed.align_to_slope);
+                    }
+                    placed.entities.push(entity);
+                }
+                Err(err) => tracing::warn!("Could not place model {name}: {err}"),
+            }
+        }
+    });
+
+    world.insert_resource(placed);
+}
+
+/// Spawn the object model `name` at the selected object, or in front of the
+/// camera if nothing is selected, and select it.
+fn place_model(world: &mut World, name: &str) -> Result<Entity, AssetError> {
+    let selected = world.resource::<WorldInteraction>().selected_entity;
+    let transform = match selected.and_then(|entity| world.get::<Transform>(entity)) {
+        Some(transform) => transform.clone(),
+        None => {
+            let mut cameras = world.query_filtered::<&Camera, With<ActiveCamera>>();
+            let translation = cameras.iter(world).next().map_or(Vec3::ZERO, |camera| {
+                camera.position + camera.rotation * Camera::FORWARD * PLACE_DISTANCE
+            });
+            Transform::from_translation(translation)
+        }
+    };
+
+    let entity =
+        Spawner::spawn_movable(world, "Placed model", name, ObjectType::Scenery, transform)?;
+
+    world.resource_mut::<WorldInteraction>().selected_entity = Some(entity);
+
+    Ok(entity)
+}
+
+/// Remove a placed model from the world. Its BVH handle removes it from the
+/// dynamic BVH.
+fn despawn_placed_model(world: &mut World, entity: Entity) {
+    if world.resource::<WorldInteraction>().selected_entity == Some(entity) {
+        world.resource_mut::<WorldInteraction>().selected_entity = None;
+    }
+
+    world.despawn(entity);
+}
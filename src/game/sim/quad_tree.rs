@@ -17,6 +17,27 @@ impl Level {
     fn index(&self, x: u32, y: u32) -> usize {
         (y as usize) * (self.size.x as usize) + (x as usize)
     }
+
+    /// The combined bounds of the up to 4 children of a parent node.
+    fn children_min_max(&self, parent_x: u32, parent_y: u32) -> MinMax {
+        let mut min_z = f32::INFINITY;
+        let mut max_z = f32::NEG_INFINITY;
+
+        for dy in 0..2 {
+            for dx in 0..2 {
+                let cx = parent_x * 2 + dx;
+                let cy = parent_y * 2 + dy;
+
+                if cx < self.size.x && cy < self.size.y {
+                    let ci = self.index(cx, cy);
+                    min_z = min_z.min(self.min_z[ci]);
+                    max_z = max_z.max(self.max_z[ci]);
+                }
+            }
+        }
+
+        MinMax(min_z, max_z)
+    }
 }
 
 pub struct QuadTree {
@@ -71,21 +92,7 @@ impl QuadTree {
 
             for parent_y in 0..parent_height {
                 for parent_x in 0..parent_width {
-                    let mut min_z = f32::INFINITY;
-                    let mut max_z = f32::NEG_INFINITY;
-
-                    for dy in 0..2 {
-                        for dx in 0..2 {
-                            let cx = parent_x * 2 + dx;
-                            let cy = parent_y * 2 + dy;
-
-                            if cx < child.size.x && cy < child.size.y {
-                                let ci = child.index(cx, cy);
-                                min_z = min_z.min(child.min_z[ci]);
-                                max_z = max_z.max(child.max_z[ci]);
-                            }
-                        }
-                    }
+                    let MinMax(min_z, max_z) = child.children_min_max(parent_x, parent_y);
 
                     let pi = parent.index(parent_x, parent_y);
                     parent.min_z[pi] = min_z;
@@ -103,6 +110,40 @@ impl QuadTree {
         }
     }
 
+    /// Replace the bounds of the chunks in `rect` with the ones returned by
+    /// `leaf`, and update the nodes containing them.
+    pub fn update_leaves(&mut self, rect: ChunkRect, leaf: impl Fn(UVec2) -> MinMax) {
+        let mut min = rect.min.min(self.chunk_count);
+        let mut max = rect.max.min(self.chunk_count);
+
+        let leaves = &mut self.levels[0];
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let i = leaves.index(x, y);
+                let MinMax(min_z, max_z) = leaf(UVec2::new(x, y));
+                leaves.min_z[i] = min_z;
+                leaves.max_z[i] = max_z;
+            }
+        }
+
+        for level in 1..self.levels.len() {
+            min /= 2;
+            max = (max + UVec2::ONE) / 2;
+
+            let (children, parents) = self.levels.split_at_mut(level);
+            let child = &children[level - 1];
+            let parent = &mut parents[0];
+            for y in min.y..max.y {
+                for x in min.x..max.x {
+                    let i = parent.index(x, y);
+                    let MinMax(min_z, max_z) = child.children_min_max(x, y);
+                    parent.min_z[i] = min_z;
+                    parent.max_z[i] = max_z;
+                }
+            }
+        }
+    }
+
     #[inline]
     fn node_span_chunks(level: usize) -> u32 {
         1_u32 << level
@@ -0,0 +1,625 @@
+use std::{
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use bevy_ecs::prelude::*;
+use glam::UVec2;
+
+use crate::{
+    engine::{
+        assets::AssetError,
+        input::{InputEvent, InputState, KeyCode, MouseButton},
+    },
+    game::sim::systems::Time,
+};
+
+/// Input applied during a single simulation update.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ReplayTick {
+    /// The delta time the update ran with.
+    pub delta_time: f32,
+    /// Input events received before the update, in order.
+    pub events: Vec<InputEvent>,
+}
+
+/// Records or plays back the input of a [super::SimWorld].
+#[derive(Default)]
+pub enum Replay {
+    #[default]
+    Off,
+    /// Collecting ticks to be written to `path` when recording stops.
+    Recording {
+        path: PathBuf,
+        ticks: Vec<ReplayTick>,
+        pending_events: Vec<InputEvent>,
+    },
+    /// Feeding recorded ticks to the simulation instead of live input.
+    Playing { ticks: Vec<ReplayTick>, next: usize },
+}
+
+impl Replay {
+    /// Start recording, discarding any previous recording or playback.
+    pub fn start_recording(&mut self, path: PathBuf) {
+        *self = Self::Recording {
+            path,
+            ticks: Vec::default(),
+            pending_events: Vec::default(),
+        };
+    }
+
+    /// Start playing back `ticks` from the beginning.
+    pub fn start_playback(&mut self, ticks: Vec<ReplayTick>) {
+        *self = Self::Playing { ticks, next: 0 };
+    }
+
+    /// Whether live input should be ignored.
+    pub fn is_playing(&self) -> bool {
+        matches!(self, Self::Playing { .. })
+    }
+
+    /// Whether live input is being recorded.
+    pub fn is_recording(&self) -> bool {
+        matches!(self, Self::Recording { .. })
+    }
+
+    /// Apply a live input event to the [InputState] of `world` and record it
+    /// when recording. Live input is ignored while a replay is playing.
+    pub fn input(&mut self, world: &mut World, event: &InputEvent) {
+        if self.is_playing() {
+            return;
+        }
+
+        self.record_event(event);
+        world.resource_mut::<InputState>().apply(event);
+    }
+
+    /// Prepare `world` for an update of `delta_time` seconds. While playing,
+    /// the recorded input is applied and the recorded delta time is used
+    /// instead. The input clock and [Time] are advanced by the delta time.
+    pub fn begin_update(&mut self, world: &mut World, delta_time: f32) {
+        let mut input_state = world.resource_mut::<InputState>();
+        let delta_time = match self.next_tick(delta_time) {
+            Some(tick) => {
+                for event in tick.events.iter() {
+                    input_state.apply(event);
+                }
+                tick.delta_time
+            }
+            None => delta_time,
+        };
+        input_state.advance(delta_time);
+
+        world.resource_mut::<Time>().next_frame(delta_time);
+    }
+
+    /// Record a live input event. Ignored unless recording.
+    pub fn record_event(&mut self, event: &InputEvent) {
+        if let Self::Recording { pending_events, .. } = self {
+            pending_events.push(event.clone());
+        }
+    }
+
+    /// Close the current tick when recording, or fetch the next recorded tick
+    /// when playing. Playback stops after the last tick.
+    pub fn next_tick(&mut self, delta_time: f32) -> Option<ReplayTick> {
+        match self {
+            Self::Off => None,
+            Self::Recording {
+                ticks,
+                pending_events,
+                ..
+            } => {
+                ticks.push(ReplayTick {
+                    delta_time,
+                    events: std::mem::take(pending_events),
+                });
+                None
+            }
+            Self::Playing { ticks, next } => {
+                let tick = ticks.get(*next).cloned();
+                *next += 1;
+                if tick.is_none() {
+                    tracing::info!("Replay finished after {} ticks.", ticks.len());
+                    *self = Self::Off;
+                }
+                tick
+            }
+        }
+    }
+
+    /// Stop recording and return the path to write to and the recorded ticks.
+    pub fn stop_recording(&mut self) -> Option<(PathBuf, Vec<ReplayTick>)> {
+        match std::mem::take(self) {
+            Self::Recording { path, ticks, .. } => Some((path, ticks)),
+            other => {
+                *self = other;
+                None
+            }
+        }
+    }
+}
+
+/// Write `ticks` to `path` in the text replay format.
+pub fn write_replay(path: &Path, ticks: &[ReplayTick]) -> Result<(), AssetError> {
+    std::fs::write(path, encode(ticks)).map_err(|err| AssetError::from_io_error(err, path))
+}
+
+/// Read ticks written by [write_replay].
+pub fn read_replay(path: &Path) -> Result<Vec<ReplayTick>, AssetError> {
+    let text = std::fs::read_to_string(path).map_err(|err| AssetError::from_io_error(err, path))?;
+    decode(&text).map_err(|err| AssetError::custom(path, err))
+}
+
+/// Encode ticks as one `tick <delta_time>` line per update, each followed by
+/// a line per input event.
+fn encode(ticks: &[ReplayTick]) -> String {
+    let mut out = String::new();
+
+    for tick in ticks {
+        let _ = writeln!(out, "tick {}", tick.delta_time);
+
+        for event in tick.events.iter() {
+            let _ = match event {
+                InputEvent::MouseMove(pos) => writeln!(out, "move {} {}", pos.x, pos.y),
+                InputEvent::MouseDown(button) => writeln!(out, "down {}", button_name(*button)),
+                InputEvent::MouseUp(button) => writeln!(out, "up {}", button_name(*button)),
+                InputEvent::MouseWheel(delta) => writeln!(out, "wheel {delta}"),
+                InputEvent::MouseLeave => writeln!(out, "leave"),
+                InputEvent::Focused(focused) => writeln!(out, "focus {}", *focused as u8),
+                InputEvent::KeyDown(key) | InputEvent::KeyUp(key) => {
+                    let Some(name) = key_name(*key) else {
+                        tracing::warn!("Key {key:?} can not be stored in a replay.");
+                        continue;
+                    };
+                    let kind = if matches!(event, InputEvent::KeyDown(_)) {
+                        "keydown"
+                    } else {
+                        "keyup"
+                    };
+                    writeln!(out, "{kind} {name}")
+                }
+            };
+        }
+    }
+
+    out
+}
+
+/// Parse the format written by [encode].
+fn decode(text: &str) -> Result<Vec<ReplayTick>, String> {
+    let mut ticks: Vec<ReplayTick> = Vec::new();
+
+    for (line_index, line) in text.lines().enumerate() {
+        let line_number = line_index + 1;
+        let mut parts = line.split_whitespace();
+        let Some(kind) = parts.next() else {
+            continue;
+        };
+        let args: Vec<&str> = parts.collect();
+
+        let error = || format!("invalid replay line {line_number}: {line}");
+        let arg = |index: usize| args.get(index).copied().ok_or_else(error);
+
+        if kind == "tick" {
+            ticks.push(ReplayTick {
+                delta_time: arg(0)?.parse().map_err(|_| error())?,
+                events: Vec::default(),
+            });
+            continue;
+        }
+
+        let event = match kind {
+            "move" => InputEvent::MouseMove(UVec2::new(
+                arg(0)?.parse().map_err(|_| error())?,
+                arg(1)?.parse().map_err(|_| error())?,
+            )),
+            "down" => InputEvent::MouseDown(parse_button(arg(0)?).ok_or_else(error)?),
+            "up" => InputEvent::MouseUp(parse_button(arg(0)?).ok_or_else(error)?),
+            "wheel" => InputEvent::MouseWheel(arg(0)?.parse().map_err(|_| error())?),
+            "leave" => InputEvent::MouseLeave,
+            "focus" => InputEvent::Focused(match arg(0)? {
+                "0" => false,
+                "1" => true,
+                _ => return Err(error()),
+            }),
+            "keydown" => InputEvent::KeyDown(parse_key(arg(0)?).ok_or_else(error)?),
+            "keyup" => InputEvent::KeyUp(parse_key(arg(0)?).ok_or_else(error)?),
+            _ => return Err(error()),
+        };
+
+        ticks
+            .last_mut()
+            .ok_or_else(|| format!("event before the first tick on line {line_number}"))?
+            .events
+            .push(event);
+    }
+
+    Ok(ticks)
+}
+
+fn button_name(button: MouseButton) -> String {
+    match button {
+        MouseButton::Left => String::from("left"),
+        MouseButton::Right => String::from("right"),
+        MouseButton::Middle => String::from("middle"),
+        MouseButton::Back => String::from("back"),
+        MouseButton::Forward => String::from("forward"),
+        MouseButton::Other(index) => index.to_string(),
+    }
+}
+
+fn parse_button(name: &str) -> Option<MouseButton> {
+    Some(match name {
+        "left" => MouseButton::Left,
+        "right" => MouseButton::Right,
+        "middle" => MouseButton::Middle,
+        "back" => MouseButton::Back,
+        "forward" => MouseButton::Forward,
+        other => MouseButton::Other(other.parse().ok()?),
+    })
+}
+
+/// Define [key_name] and [parse_key] from a single list of [KeyCode]
+/// variants, so both directions always agree.
+macro_rules! replay_keys {
+    ($($key:ident),* $(,)?) => {
+        /// The name of `key` in a replay, or `None` for keys without a name,
+        /// like [KeyCode::Unidentified].
+        fn key_name(key: KeyCode) -> Option<&'static str> {
+            match key {
+                $(KeyCode::$key => Some(stringify!($key)),)*
+                _ => None,
+            }
+        }
+
+        /// The key written by [key_name].
+        fn parse_key(name: &str) -> Option<KeyCode> {
+            match name {
+                $(stringify!($key) => Some(KeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+// Every named key winit knows about.
+replay_keys!(
+    Backquote,
+    Backslash,
+    BracketLeft,
+    BracketRight,
+    Comma,
+    Digit0,
+    Digit1,
+    Digit2,
+    Digit3,
+    Digit4,
+    Digit5,
+    Digit6,
+    Digit7,
+    Digit8,
+    Digit9,
+    Equal,
+    IntlBackslash,
+    IntlRo,
+    IntlYen,
+    KeyA,
+    KeyB,
+    KeyC,
+    KeyD,
+    KeyE,
+    KeyF,
+    KeyG,
+    KeyH,
+    KeyI,
+    KeyJ,
+    KeyK,
+    KeyL,
+    KeyM,
+    KeyN,
+    KeyO,
+    KeyP,
+    KeyQ,
+    KeyR,
+    KeyS,
+    KeyT,
+    KeyU,
+    KeyV,
+    KeyW,
+    KeyX,
+    KeyY,
+    KeyZ,
+    Minus,
+    Period,
+    Quote,
+    Semicolon,
+    Slash,
+    AltLeft,
+    AltRight,
+    Backspace,
+    CapsLock,
+    ContextMenu,
+    ControlLeft,
+    ControlRight,
+    Enter,
+    SuperLeft,
+    SuperRight,
+    ShiftLeft,
+    ShiftRight,
+    Space,
+    Tab,
+    Convert,
+    KanaMode,
+    Lang1,
+    Lang2,
+    Lang3,
+    Lang4,
+    Lang5,
+    NonConvert,
+    Delete,
+    End,
+    Help,
+    Home,
+    Insert,
+    PageDown,
+    PageUp,
+    ArrowDown,
+    ArrowLeft,
+    ArrowRight,
+    ArrowUp,
+    NumLock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadBackspace,
+    NumpadClear,
+    NumpadClearEntry,
+    NumpadComma,
+    NumpadDecimal,
+    NumpadDivide,
+    NumpadEnter,
+    NumpadEqual,
+    NumpadHash,
+    NumpadMemoryAdd,
+    NumpadMemoryClear,
+    NumpadMemoryRecall,
+    NumpadMemoryStore,
+    NumpadMemorySubtract,
+    NumpadMultiply,
+    NumpadParenLeft,
+    NumpadParenRight,
+    NumpadStar,
+    NumpadSubtract,
+    Escape,
+    Fn,
+    FnLock,
+    PrintScreen,
+    ScrollLock,
+    Pause,
+    BrowserBack,
+    BrowserFavorites,
+    BrowserForward,
+    BrowserHome,
+    BrowserRefresh,
+    BrowserSearch,
+    BrowserStop,
+    Eject,
+    LaunchApp1,
+    LaunchApp2,
+    LaunchMail,
+    MediaPlayPause,
+    MediaSelect,
+    MediaStop,
+    MediaTrackNext,
+    MediaTrackPrevious,
+    Power,
+    Sleep,
+    AudioVolumeDown,
+    AudioVolumeMute,
+    AudioVolumeUp,
+    WakeUp,
+    Meta,
+    Hyper,
+    Turbo,
+    Abort,
+    Resume,
+    Suspend,
+    Again,
+    Copy,
+    Cut,
+    Find,
+    Open,
+    Paste,
+    Props,
+    Select,
+    Undo,
+    Hiragana,
+    Katakana,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    F25,
+    F26,
+    F27,
+    F28,
+    F29,
+    F30,
+    F31,
+    F32,
+    F33,
+    F34,
+    F35,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ticks_survive_encoding() {
+        let ticks = vec![
+            ReplayTick {
+                delta_time: 1.0 / 60.0,
+                events: vec![
+                    InputEvent::MouseMove(UVec2::new(10, 20)),
+                    InputEvent::MouseDown(MouseButton::Left),
+                    InputEvent::KeyDown(KeyCode::KeyW),
+                ],
+            },
+            ReplayTick {
+                delta_time: 0.0171,
+                events: vec![],
+            },
+            ReplayTick {
+                delta_time: 0.5,
+                events: vec![
+                    InputEvent::MouseUp(MouseButton::Other(7)),
+                    InputEvent::MouseWheel(-1.5),
+                    InputEvent::KeyUp(KeyCode::KeyW),
+                    InputEvent::MouseLeave,
+                    InputEvent::Focused(false),
+                    InputEvent::Focused(true),
+                ],
+            },
+        ];
+
+        assert_eq!(decode(&encode(&ticks)), Ok(ticks));
+    }
+
+    #[test]
+    fn replays_reproduce_the_same_camera_positions() {
+        use glam::{Quat, Vec3};
+
+        use crate::{
+            engine::mouse_settings::MouseSettings,
+            game::sim::{
+                Camera, ecs::ActiveCamera, free_camera_controller,
+                free_camera_controller::FreeCameraController,
+            },
+        };
+
+        fn camera_world() -> (World, Schedule) {
+            let mut world = World::new();
+            world.init_resource::<InputState>();
+            world.init_resource::<Time>();
+            world.init_resource::<MouseSettings>();
+            world.spawn((
+                Camera::new(Vec3::ZERO, Quat::IDENTITY, 45.0, 1.0, 1.0, 1000.0),
+                FreeCameraController::new(100.0),
+                ActiveCamera,
+            ));
+
+            let mut schedule = Schedule::default();
+            schedule.add_systems(
+                (
+                    free_camera_controller::input,
+                    |mut input: ResMut<InputState>| input.reset_per_frame(),
+                )
+                    .chain(),
+            );
+            (world, schedule)
+        }
+
+        fn camera_position(world: &mut World) -> Vec3 {
+            world
+                .query_filtered::<&Camera, With<ActiveCamera>>()
+                .single(world)
+                .unwrap()
+                .position
+        }
+
+        // Live input, with uneven frame times.
+        let (mut world, mut schedule) = camera_world();
+        let mut replay = Replay::default();
+        replay.start_recording(PathBuf::from("replay.txt"));
+
+        let mut positions = Vec::new();
+        for frame in 0..20 {
+            let events: &[InputEvent] = match frame {
+                1 => &[
+                    InputEvent::KeyDown(KeyCode::KeyW),
+                    InputEvent::MouseMove(UVec2::new(100, 100)),
+                    InputEvent::MouseDown(MouseButton::Right),
+                ],
+                4 => &[InputEvent::MouseMove(UVec2::new(130, 90))],
+                9 => &[
+                    InputEvent::KeyUp(KeyCode::KeyW),
+                    InputEvent::KeyDown(KeyCode::KeyD),
+                ],
+                _ => &[],
+            };
+            for event in events {
+                replay.input(&mut world, event);
+            }
+            replay.begin_update(&mut world, 0.01 + frame as f32 * 0.003);
+            schedule.run(&mut world);
+            positions.push(camera_position(&mut world));
+        }
+
+        let (_, ticks) = replay.stop_recording().unwrap();
+        let ticks = decode(&encode(&ticks)).unwrap();
+
+        // Play back with different live delta times and input, which are
+        // ignored.
+        let (mut world, mut schedule) = camera_world();
+        let mut replay = Replay::default();
+        replay.start_playback(ticks);
+        for expected in positions {
+            replay.input(&mut world, &InputEvent::KeyDown(KeyCode::KeyS));
+            replay.begin_update(&mut world, 1.0);
+            schedule.run(&mut world);
+            assert_eq!(camera_position(&mut world), expected);
+        }
+        assert_ne!(camera_position(&mut world), Vec3::ZERO);
+    }
+
+    #[test]
+    fn every_named_key_can_be_replayed() {
+        for key in [
+            KeyCode::Backquote,
+            KeyCode::KeyW,
+            KeyCode::NumpadEnter,
+            KeyCode::F35,
+        ] {
+            assert_eq!(parse_key(key_name(key).unwrap()), Some(key));
+        }
+        assert_eq!(key_name(KeyCode::ShiftLeft), Some("ShiftLeft"));
+        assert_eq!(parse_key("NotAKey"), None);
+    }
+
+    #[test]
+    fn events_need_a_tick() {
+        assert!(decode("move 1 2\n").is_err());
+        assert!(decode("tick 0.1\nexplode\n").is_err());
+    }
+}
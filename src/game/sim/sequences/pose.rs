@@ -1,16 +1,251 @@
 use bevy_ecs::prelude::*;
-use glam::{Mat4, Vec3};
-use std::collections::HashMap;
+use glam::{Mat4, Quat, Vec3};
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
-    engine::transform::Transform,
-    game::{assets::motion::Motion, skeleton::Skeleton},
+    engine::{storage::Handle, transform::Transform},
+    game::{
+        assets::{model::Model, motion::Motion},
+        skeleton::Skeleton,
+    },
 };
 
 #[derive(Clone, Component, Debug, Default)]
 pub struct Pose {
-    pub bones: Vec<Mat4>,
-    pub local_transforms: Vec<Transform>,
+    /// Model-space bone transforms. Instances sharing a cached pose share the
+    /// same allocation, so the renderer can upload it once.
+    pub bones: Arc<[Mat4]>,
+    /// Bone transforms relative to their parents.
+    pub local_transforms: Arc<[Transform]>,
+}
+
+impl Pose {
+    /// Build a pose from bone transforms relative to their parents.
+    pub fn from_local_transforms(skeleton: &Skeleton, local_transforms: Vec<Transform>) -> Self {
+        debug_assert_eq!(skeleton.bones.len(), local_transforms.len());
+
+        let mut bones: Vec<Mat4> = Vec::with_capacity(skeleton.bones.len());
+        for (bone_index, bone) in skeleton.bones.iter().enumerate() {
+            // Get the parent transform.
+            let parent_transform = if bone.parent == u32::MAX {
+                Mat4::IDENTITY
+            } else {
+                bones[bone.parent as usize]
+            };
+
+            let local = local_transforms[bone_index].to_mat4();
+            bones.push(parent_transform * local);
+        }
+
+        Self {
+            bones: bones.into(),
+            local_transforms: local_transforms.into(),
+        }
+    }
+
+    /// Layer an additive animation on top of `base` and store the result in
+    /// `self`.
+    ///
+    /// `additive` is authored as a delta from `reference`; the per-bone
+    /// difference between the two is scaled by `weight` and applied to `base`.
+    /// All poses must be generated for `skeleton`.
+    pub fn add_additive(
+        &mut self,
+        skeleton: &Skeleton,
+        base: &Pose,
+        additive: &Pose,
+        reference: &Pose,
+        weight: f32,
+    ) {
+        let bone_count = skeleton.bones.len();
+        if base.local_transforms.len() != bone_count
+            || additive.local_transforms.len() != bone_count
+            || reference.local_transforms.len() != bone_count
+        {
+            tracing::warn!("Additive pose does not match the skeleton!");
+            *self = base.clone();
+            return;
+        }
+
+        let weight = weight.clamp(0.0, 1.0);
+
+        let local_transforms = base
+            .local_transforms
+            .iter()
+            .zip(additive.local_transforms.iter())
+            .zip(reference.local_transforms.iter())
+            .map(|((base, additive), reference)| {
+                let delta_rotation = reference.rotation.inverse() * additive.rotation;
+                let delta_translation = additive.translation - reference.translation;
+
+                Transform {
+                    translation: base.translation + delta_translation * weight,
+                    rotation: (base.rotation * Quat::IDENTITY.slerp(delta_rotation, weight))
+                        .normalize(),
+                }
+            })
+            .collect();
+
+        *self = Self::from_local_transforms(skeleton, local_transforms);
+    }
+
+    /// Transform each model-space bone matrix into world space using the
+    /// transform of the object that owns the pose.
+    pub fn to_world_matrices(&self, object_to_world: Mat4) -> Vec<Mat4> {
+        self.bones
+            .iter()
+            .map(|bone| object_to_world * *bone)
+            .collect()
+    }
+}
+
+/// Blend between two poses of `skeleton`, lerping the translations and
+/// slerping the rotations of each bone relative to its parent. `t = 0` gives
+/// `a` and `t = 1` gives `b`.
+pub fn blend_poses(skeleton: &Skeleton, a: &Pose, b: &Pose, t: f32) -> Pose {
+    debug_assert_eq!(
+        a.local_transforms.len(),
+        b.local_transforms.len(),
+        "Blended poses must match!"
+    );
+
+    let t = t.clamp(0.0, 1.0);
+    if t == 0.0 {
+        return a.clone();
+    }
+    if t == 1.0 {
+        return b.clone();
+    }
+
+    let local_transforms = a
+        .local_transforms
+        .iter()
+        .zip(b.local_transforms.iter())
+        .map(|(a, b)| Transform {
+            translation: a.translation.lerp(b.translation, t),
+            rotation: a.rotation.slerp(b.rotation, t).normalize(),
+        })
+        .collect();
+
+    // Child bones follow the blended rotation of their parents, so the
+    // model-space bones have to be rebuilt from the blended local transforms.
+    Pose::from_local_transforms(skeleton, local_transforms)
+}
+
+/// Layer an additive pose, like an aim offset, on top of `base`. The additive
+/// pose is a delta from the rest pose of `skeleton`, so a layer with zero
+/// weight, or one that matches the rest pose, leaves `base` unchanged.
+pub fn apply_additive(skeleton: &Skeleton, base: &mut Pose, additive: &Pose, weight: f32) {
+    let rest = skeleton.to_pose();
+    let layered = base.clone();
+    base.add_additive(skeleton, &layered, additive, &rest, weight);
+}
+
+/// Where in a motion a cached pose was sampled.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PoseSample {
+    /// A time in motion ticks, played at a speed of `ticks_per_frame`.
+    Ticks { time: i32, ticks_per_frame: i32 },
+    /// An exact keyframe.
+    KeyFrame(u32),
+}
+
+/// Identifies the inputs of a generated pose.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct PoseKey {
+    pub model: Handle<Model>,
+    pub motion: Handle<Motion>,
+    pub sample: PoseSample,
+    pub looping: bool,
+    /// Bit patterns of the root translation override.
+    pub root_translation_override: Option<[u32; 3]>,
+}
+
+impl PoseKey {
+    pub fn new(
+        model: Handle<Model>,
+        motion: Handle<Motion>,
+        sample: PoseSample,
+        looping: bool,
+        root_translation_override: Option<Vec3>,
+    ) -> Self {
+        Self {
+            model,
+            motion,
+            sample,
+            looping,
+            root_translation_override: root_translation_override
+                .map(|translation| translation.to_array().map(f32::to_bits)),
+        }
+    }
+}
+
+/// Poses generated during a single frame, so instances playing the same
+/// motion at the same time share one pose instead of each sampling their own.
+#[derive(Default)]
+pub struct PoseCache {
+    entries: HashMap<PoseKey, CachedPose>,
+    hits: usize,
+    misses: usize,
+}
+
+struct CachedPose {
+    /// Bones not animated by the motion keep their transforms from the
+    /// previous pose, so a cached pose is only reused for the same previous
+    /// pose: the same shared allocation, or none for the rest pose.
+    previous: Option<Arc<[Transform]>>,
+    pose: Pose,
+}
+
+impl PoseCache {
+    /// Drop the poses of the previous frame.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.hits = 0;
+        self.misses = 0;
+    }
+
+    /// Return the cached pose for `key`, or build it with `generate` and cache
+    /// it. `previous` is the pose `generate` builds on, or `None` if it starts
+    /// from the rest pose. Poses built on a previous pose are only shared by
+    /// instances that shared that previous pose as well.
+    pub fn get_or_generate(
+        &mut self,
+        key: PoseKey,
+        previous: Option<&Pose>,
+        generate: impl FnOnce() -> Pose,
+    ) -> Pose {
+        let previous = previous.map(|pose| &pose.local_transforms);
+
+        if let Some(cached) = self.entries.get(&key)
+            && match (&cached.previous, previous) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (None, None) => true,
+                _ => false,
+            }
+        {
+            self.hits += 1;
+            return cached.pose.clone();
+        }
+
+        self.misses += 1;
+        let pose = generate();
+        self.entries.entry(key).or_insert_with(|| CachedPose {
+            previous: previous.cloned(),
+            pose: pose.clone(),
+        });
+        pose
+    }
+
+    /// Lookups answered from the cache since the last [PoseCache::clear].
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Poses generated since the last [PoseCache::clear].
+    pub fn misses(&self) -> usize {
+        self.misses
+    }
 }
 
 /// Generate a model-space pose for `motion` at `time`.
@@ -37,6 +272,23 @@ pub fn generate_pose(
     )
 }
 
+/// Generate a pose crossfading from `from` at `from_time` to `to` at
+/// `to_time`, with `blend` going from 0 (only `from`) to 1 (only `to`). Both
+/// motions are sampled like [generate_pose] does.
+pub fn generate_blended_pose(
+    skeleton: &Skeleton,
+    from: &Motion,
+    to: &Motion,
+    from_time: f32,
+    to_time: f32,
+    blend: f32,
+    looping: bool,
+) -> Pose {
+    let from_pose = generate_pose(skeleton, from, from_time, looping, None, None);
+    let to_pose = generate_pose(skeleton, to, to_time, looping, None, None);
+    blend_poses(skeleton, &from_pose, &to_pose, blend)
+}
+
 /// Generate a model-space pose for `motion` at an exact keyframe index.
 ///
 /// This is used for terminal motion application where the original runtime
@@ -70,7 +322,7 @@ fn generate_pose_impl(
 ) -> Pose {
     let mut local_transforms = previous_pose
         .filter(|pose| pose.local_transforms.len() == skeleton.bones.len())
-        .map(|pose| pose.local_transforms.clone())
+        .map(|pose| pose.local_transforms.to_vec())
         .unwrap_or_else(|| {
             skeleton
                 .bones
@@ -119,21 +371,163 @@ fn generate_pose_impl(
         local_transforms[root_index].translation = override_translation;
     }
 
-    let mut bones: Vec<Mat4> = Vec::with_capacity(skeleton.bones.len());
-    for (bone_index, bone) in skeleton.bones.iter().enumerate() {
-        // Get the parent transform.
-        let parent_transform = if bone.parent == u32::MAX {
-            Mat4::IDENTITY
-        } else {
-            bones[bone.parent as usize]
+    Pose::from_local_transforms(skeleton, local_transforms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::storage::Storage;
+
+    fn three_bone_skeleton() -> Skeleton {
+        use crate::game::skeleton::Bone;
+
+        let bone = |id: u32, parent: u32| Bone {
+            parent,
+            transform: Transform::from_translation(Vec3::Z * 10.0),
+            id,
+            name: format!("bone_{id}"),
         };
+        Skeleton {
+            bones: vec![bone(1, u32::MAX), bone(2, 0), bone(3, 1)],
+        }
+    }
+
+    #[test]
+    fn additive_layers_are_deltas_from_the_rest_pose() {
+        let skeleton = three_bone_skeleton();
+        let rest = skeleton.to_pose();
+
+        let mut local_transforms = rest.local_transforms.to_vec();
+        local_transforms[1].rotation = Quat::from_rotation_x(0.5);
+        let base = Pose::from_local_transforms(&skeleton, local_transforms);
 
-        let local = local_transforms[bone_index].to_mat4();
-        bones.push(parent_transform * local);
+        let mut local_transforms = rest.local_transforms.to_vec();
+        local_transforms[2].rotation = Quat::from_rotation_y(0.25);
+        let aim = Pose::from_local_transforms(&skeleton, local_transforms);
+
+        let same = |a: &Pose, b: &Pose| {
+            a.bones
+                .iter()
+                .zip(b.bones.iter())
+                .all(|(a, b)| a.abs_diff_eq(*b, 1e-5))
+        };
+
+        let mut pose = base.clone();
+        apply_additive(&skeleton, &mut pose, &aim, 0.0);
+        assert!(same(&pose, &base));
+
+        let mut pose = base.clone();
+        apply_additive(&skeleton, &mut pose, &rest, 1.0);
+        assert!(same(&pose, &base));
+
+        let mut pose = base.clone();
+        apply_additive(&skeleton, &mut pose, &aim, 1.0);
+        assert!(
+            pose.local_transforms[2]
+                .rotation
+                .abs_diff_eq(Quat::from_rotation_y(0.25), 1e-5)
+        );
+        assert!(same(
+            &Pose::from_local_transforms(&skeleton, pose.local_transforms.to_vec()),
+            &pose
+        ));
+        assert!(!same(&pose, &base));
+    }
+
+    #[test]
+    fn blending_goes_from_the_first_pose_to_the_second() {
+        let skeleton = Skeleton {
+            bones: vec![crate::game::skeleton::Bone {
+                parent: u32::MAX,
+                transform: Transform::default(),
+                id: 1,
+                name: "root".to_string(),
+            }],
+        };
+        let pose = |translation: Vec3, rotation: Quat| {
+            let transform = Transform {
+                translation,
+                rotation,
+            };
+            Pose {
+                bones: vec![transform.to_mat4()].into(),
+                local_transforms: vec![transform].into(),
+            }
+        };
+        let a = pose(Vec3::ZERO, Quat::IDENTITY);
+        let b = pose(Vec3::new(2.0, 0.0, 4.0), Quat::from_rotation_z(1.0));
+
+        assert_eq!(blend_poses(&skeleton, &a, &b, 0.0).bones, a.bones);
+        assert_eq!(blend_poses(&skeleton, &a, &b, 1.0).bones, b.bones);
+
+        let half = blend_poses(&skeleton, &a, &b, 0.5);
+        let expected = pose(Vec3::new(1.0, 0.0, 2.0), Quat::from_rotation_z(0.5));
+        assert!(half.bones[0].abs_diff_eq(expected.bones[0], 1e-5));
+        assert!(
+            half.local_transforms[0]
+                .rotation
+                .abs_diff_eq(expected.local_transforms[0].rotation, 1e-5)
+        );
+    }
+
+    #[test]
+    fn blended_child_bones_follow_their_parents() {
+        use std::f32::consts::FRAC_PI_2;
+
+        let skeleton = three_bone_skeleton();
+        let chain = |rotation: Quat| {
+            let mut local_transforms = vec![Transform::default(); 3];
+            local_transforms[0].rotation = rotation;
+            local_transforms[1].translation = Vec3::X;
+            Pose::from_local_transforms(&skeleton, local_transforms)
+        };
+        let a = chain(Quat::IDENTITY);
+        let b = chain(Quat::from_rotation_z(FRAC_PI_2));
+
+        // Halfway the parent is rotated 45 degrees and the child keeps its
+        // distance from it.
+        let half = blend_poses(&skeleton, &a, &b, 0.5);
+        let child = half.bones[1].w_axis.truncate();
+        let expected = Vec3::new(1.0, 1.0, 0.0).normalize();
+        assert!(child.abs_diff_eq(expected, 1e-5), "{child}");
+        assert!(half.bones[2].w_axis.truncate().abs_diff_eq(expected, 1e-5));
     }
 
-    Pose {
-        bones,
-        local_transforms,
+    #[test]
+    fn cached_poses_are_shared_for_the_same_previous_pose() {
+        let model = Storage::<Model, ()>::default().insert(());
+        let motion = Storage::<Motion, ()>::default().insert(());
+
+        let sample = PoseSample::Ticks {
+            time: 230,
+            ticks_per_frame: 100,
+        };
+        let key = PoseKey::new(model, motion, sample, true, None);
+        let generated = |x: f32| Pose {
+            bones: vec![Mat4::from_translation(Vec3::X * x)].into(),
+            local_transforms: vec![Transform::from_translation(Vec3::X * x)].into(),
+        };
+
+        let mut cache = PoseCache::default();
+        let first = cache.get_or_generate(key, None, || generated(1.0));
+        let shared = cache.get_or_generate(key, None, || generated(2.0));
+        assert!(Arc::ptr_eq(&shared.bones, &first.bones));
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+        // A different previous pose can leave different bones untouched.
+        let own = cache.get_or_generate(key, Some(&generated(5.0)), || generated(3.0));
+        assert_eq!(own.bones, generated(3.0).bones);
+        assert_eq!((cache.hits(), cache.misses()), (1, 2));
+
+        // Instances that shared their previous pose share the next one too.
+        let previous = generated(5.0);
+        let mut cache = PoseCache::default();
+        let first = cache.get_or_generate(key, Some(&previous), || generated(1.0));
+        let shared = cache.get_or_generate(key, Some(&previous.clone()), || generated(2.0));
+        assert!(Arc::ptr_eq(&shared.bones, &first.bones));
+
+        cache.clear();
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
     }
 }
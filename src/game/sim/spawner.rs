@@ -6,7 +6,7 @@ use crate::{
     engine::{assets::AssetError, transform::Transform},
     game::{
         assets::model::{Mesh, Model},
-        config::{BodyDefinition, CharacterProfiles, ObjectType},
+        config::{BodyDefinition, CharacterProfiles, ObjectTemplates, ObjectType},
         globals,
         math::BoundingBox,
         models::ModelName,
@@ -16,23 +16,47 @@ use crate::{
     },
 };
 
-use super::{orders::OrdersController, sequences::Pose};
+use super::{character_controller::CharacterController, orders::OrdersController, sequences::Pose};
 
 #[derive(Component)]
 pub struct SpawnInfo {
-    pub _name: String,
-    pub _title: String,
-    pub _object_type: ObjectType,
+    pub name: String,
+    pub title: String,
+    pub object_type: ObjectType,
 }
 
 #[derive(Resource)]
 pub struct Spawner {
     character_profiles: CharacterProfiles,
+    object_templates: ObjectTemplates,
 }
 
 impl Spawner {
-    pub fn new(character_profiles: CharacterProfiles) -> Self {
-        Self { character_profiles }
+    pub fn new(character_profiles: CharacterProfiles, object_templates: ObjectTemplates) -> Self {
+        Self {
+            character_profiles,
+            object_templates,
+        }
+    }
+
+    /// Spawn an object from the template with the given name. The template
+    /// determines the [ObjectType], which in turn determines the model,
+    /// components and collision setup of the new entity.
+    pub fn spawn_from_template(
+        &mut self,
+        world: &mut World,
+        template_name: &str,
+        title: &str,
+        transform: Transform,
+    ) -> Result<Entity, AssetError> {
+        let Some(object_type) = self.object_templates.get(template_name) else {
+            return Err(AssetError::Custom(
+                PathBuf::new(),
+                format!("Object template not found! ({template_name})"),
+            ));
+        };
+
+        self.spawn(world, title, template_name, object_type, transform)
     }
 
     pub fn spawn(
@@ -114,12 +138,47 @@ impl Spawner {
         }
     }
 
+    /// Spawn the object model `name` as an object that can be moved after it
+    /// was spawned. Unlike scenery, it is kept in the [DynamicBvh] so picking
+    /// and culling follow its transform.
+    pub fn spawn_movable(
+        world: &mut World,
+        title: &str,
+        name: &str,
+        object_type: ObjectType,
+        transform: Transform,
+    ) -> Result<Entity, AssetError> {
+        let model_handle = globals::models().load(ModelName::Object(name.to_string()))?;
+        let model = globals::models().get(model_handle).unwrap();
+
+        let entity = world.spawn_empty().id();
+
+        let dynamic_bvh_handle = {
+            let mut dynamic_bvh = world.resource_mut::<DynamicBvh>();
+            dynamic_bvh.insert(entity, model.bounding_box.transformed(transform.to_mat4()))
+        };
+
+        world.entity_mut(entity).insert((
+            SpawnInfo {
+                name: name.to_string(),
+                title: title.to_string(),
+                object_type,
+            },
+            transform,
+            model_handle,
+            BoundingBoxComponent(model.bounding_box),
+            dynamic_bvh_handle,
+        ));
+
+        Ok(entity)
+    }
+
     fn spawn_bipedal(
         &mut self,
         world: &mut World,
         title: &str,
-        _name: &str,
-        _object_type: ObjectType,
+        name: &str,
+        object_type: ObjectType,
         transform: Transform,
     ) -> Result<Entity, AssetError> {
         let Some(character_profile) = self.character_profiles.get(title) else {
@@ -161,15 +220,16 @@ impl Spawner {
 
         world.entity_mut(entity).insert((
             SpawnInfo {
-                _name: _name.to_string(),
-                _title: title.to_string(),
-                _object_type,
+                name: name.to_string(),
+                title: title.to_string(),
+                object_type,
             },
             transform.clone(),
             model_handle,
             BoundingBoxComponent(bounding_box),
             dynamic_bvh_handle,
             motion_controller,
+            CharacterController::default(),
             Pose::default(),
             OrdersController::default(),
         ));
@@ -194,9 +254,9 @@ impl Spawner {
                 model_handle,
                 BoundingBoxComponent(model.bounding_box),
                 SpawnInfo {
-                    _name: name.to_string(),
-                    _title: title.to_string(),
-                    _object_type: object_type,
+                    name: name.to_string(),
+                    title: title.to_string(),
+                    object_type,
                 },
                 StaticBvhHandle,
             ))
@@ -220,9 +280,9 @@ impl Spawner {
                 model_handle,
                 BoundingBoxComponent(model.bounding_box),
                 SpawnInfo {
-                    _name: name.to_string(),
-                    _title: title.to_string(),
-                    _object_type: object_type,
+                    name: name.to_string(),
+                    title: title.to_string(),
+                    object_type,
                 },
                 StaticBvhHandle,
             ))
@@ -1,7 +1,7 @@
 use bevy_ecs::prelude::*;
 use glam::Vec3;
 
-use crate::game::math::{BoundingBox, Containment, Frustum, RaySegment};
+use crate::game::math::{BoundingBox, BoundingSphere, Containment, Frustum, RaySegment};
 
 enum NodeKind {
     Leaf { start: usize, count: usize },
@@ -121,6 +121,38 @@ impl StaticBvh {
         }
     }
 
+    /// Sphere query. Writes object IDs whose bounding boxes intersect the
+    /// sphere into `out`.
+    pub fn objects_in_sphere(&self, sphere: &BoundingSphere, out: &mut Vec<Entity>) {
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack: Vec<usize> = Vec::new();
+        stack.push(0);
+
+        while let Some(node_index) = stack.pop() {
+            let node = &self.nodes[node_index];
+            if !node.bounding_box.intersects_sphere(sphere) {
+                continue;
+            }
+
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    for &item_index in self.indices[start..start + count].iter() {
+                        if self.bounding_boxes[item_index].intersects_sphere(sphere) {
+                            out.push(self.objects[item_index]);
+                        }
+                    }
+                }
+                NodeKind::Internal { left, right } => {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+    }
+
     /// Ray query. Writes object IDs whose bounding boxes intersect the ray segment into `out`.
     pub fn _objects_intersect_ray_segment(&self, ray_segment: &RaySegment, out: &mut Vec<Entity>) {
         if self.nodes.is_empty() || ray_segment.is_degenerate() {
@@ -253,3 +285,42 @@ impl StaticBvh {
         (c_min, c_max)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objects_in_sphere_tests_each_box() {
+        let mut world = World::default();
+        let items: Vec<(Entity, BoundingBox)> = (0..20)
+            .map(|i| {
+                let min = Vec3::new(i as f32 * 10.0, 0.0, 0.0);
+                let bounding_box = BoundingBox {
+                    min,
+                    max: min + Vec3::ONE,
+                };
+                (world.spawn_empty().id(), bounding_box)
+            })
+            .collect();
+
+        // Small leaves, so the sphere has to reach into several of them.
+        let mut bvh = StaticBvh::new(2);
+        bvh.rebuild(&items);
+
+        let mut found = Vec::new();
+        bvh.objects_in_sphere(
+            &BoundingSphere::new(Vec3::new(45.0, 0.5, 0.5), 15.5),
+            &mut found,
+        );
+        found.sort_by_key(|entity| entity.index());
+        assert_eq!(found, [items[3].0, items[4].0, items[5].0, items[6].0]);
+
+        found.clear();
+        bvh.objects_in_sphere(
+            &BoundingSphere::new(Vec3::new(45.0, 0.5, 0.5), 3.0),
+            &mut found,
+        );
+        assert!(found.is_empty());
+    }
+}
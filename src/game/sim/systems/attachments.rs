@@ -0,0 +1,122 @@
+use bevy_ecs::prelude::*;
+
+use crate::{
+    engine::{storage::Handle, transform::Transform},
+    game::{
+        assets::model::Model,
+        globals,
+        sim::{attachment::BoneAttachment, sequences::Pose},
+    },
+};
+
+/// Objects that can either be attached or act as the parent of an attachment.
+type AttachmentObjects<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut Transform,
+        Option<&'static Handle<Model>>,
+        Option<&'static Pose>,
+    ),
+>;
+
+/// Move every attached entity to the current world position of the bone it is
+/// attached to. Attachments whose parent no longer exists are removed.
+///
+/// Attachments to other attached entities follow their parent's transform from
+/// the previous frame.
+pub fn update_bone_attachments(
+    mut commands: Commands,
+    attachments: Query<(Entity, &BoneAttachment)>,
+    mut objects: AttachmentObjects,
+) {
+    for (entity, attachment) in attachments.iter() {
+        let Ok((parent_transform, model_handle, pose)) = objects.get(attachment.parent) else {
+            commands.entity(entity).remove::<BoneAttachment>();
+            continue;
+        };
+
+        let bone_index = attachment.bone_index as usize;
+
+        let bone = match pose.and_then(|pose| pose.bones.get(bone_index)) {
+            Some(bone) => *bone,
+            None => {
+                let Some(model) = model_handle.and_then(|handle| globals::models().get(*handle))
+                else {
+                    continue;
+                };
+                if bone_index >= model.skeleton.bones.len() {
+                    continue;
+                }
+                model.skeleton.local_transform(attachment.bone_index)
+            }
+        };
+
+        let world = parent_transform.to_mat4() * bone * attachment.local_offset.to_mat4();
+        let (_, rotation, translation) = world.to_scale_rotation_translation();
+
+        if let Ok((mut transform, _, _)) = objects.get_mut(entity) {
+            transform.translation = translation;
+            transform.rotation = rotation.normalize();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use glam::{Mat4, Quat, Vec3};
+
+    use super::*;
+
+    #[test]
+    fn attached_entities_follow_the_bone_times_the_offset() {
+        let mut world = World::new();
+        let bone = Mat4::from_rotation_translation(
+            Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            Vec3::new(0.0, 5.0, 0.0),
+        );
+        let parent = world
+            .spawn((
+                Transform::from_translation(Vec3::new(10.0, 0.0, 0.0)),
+                Pose {
+                    bones: Arc::from([Mat4::IDENTITY, bone]),
+                    local_transforms: Arc::from([]),
+                },
+            ))
+            .id();
+        let child = world
+            .spawn((
+                Transform::default(),
+                BoneAttachment {
+                    parent,
+                    bone_index: 1,
+                    local_offset: Transform::from_translation(Vec3::new(1.0, 0.0, 0.0)),
+                },
+            ))
+            .id();
+
+        let mut schedule = Schedule::default();
+        schedule.add_systems(update_bone_attachments);
+        schedule.run(&mut world);
+
+        // The offset is rotated by the bone, so it points along +Y.
+        let transform = world.get::<Transform>(child).unwrap();
+        assert!(
+            transform
+                .translation
+                .abs_diff_eq(Vec3::new(10.0, 6.0, 0.0), 1e-5)
+        );
+        assert!(
+            transform
+                .rotation
+                .abs_diff_eq(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2), 1e-5)
+        );
+
+        // Attachments are dropped with their parent.
+        world.despawn(parent);
+        schedule.run(&mut world);
+        assert!(world.get::<BoneAttachment>(child).is_none());
+    }
+}
@@ -1,10 +1,33 @@
 use bevy_ecs::prelude::*;
 
+use glam::Vec2;
+
 use crate::game::sim::{
     Camera, ComputedCamera, DayNightCycle, SimWorldState,
+    camera::ProjectionJitter,
     ecs::{ActiveCamera, Viewport},
 };
 
+/// Apply the next sub-pixel jitter to the active camera, or clear it when
+/// jittering is disabled.
+pub fn update_projection_jitter(
+    mut projection_jitter: ResMut<ProjectionJitter>,
+    viewport: Res<Viewport>,
+    mut active_cameras: Query<&mut Camera, With<ActiveCamera>>,
+) {
+    let jitter = if projection_jitter.enabled {
+        projection_jitter.next_jitter(viewport.size)
+    } else {
+        Vec2::ZERO
+    };
+
+    for mut camera in active_cameras.iter_mut() {
+        if camera.jitter != jitter {
+            camera.jitter = jitter;
+        }
+    }
+}
+
 pub fn compute_cameras(mut cameras: Query<(&Camera, &mut ComputedCamera)>) {
     for (camera, mut computed_camera) in cameras.iter_mut() {
         *computed_camera = camera.compute();
@@ -5,7 +5,7 @@ use crate::{engine::renderer::RenderContext, game::render::geometry_buffer::Geom
 pub fn clear_render_targets(
     render_context: &mut RenderContext,
     geometry_buffer: &GeometryBuffer,
-    fog_color: Vec3,
+    clear_color: Vec3,
 ) {
-    geometry_buffer.clear(&mut render_context.encoder, fog_color);
+    geometry_buffer.clear(&mut render_context.encoder, clear_color);
 }
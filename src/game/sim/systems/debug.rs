@@ -1,11 +1,225 @@
 use bevy_ecs::prelude::*;
-use glam::Vec4;
+use bitflags::bitflags;
+use glam::{Mat4, Vec3, Vec4};
 
 use crate::{
-    engine::transform::Transform,
-    game::sim::ecs::{BoundingBoxComponent, GizmoVertices},
+    engine::{
+        gizmos::{StaticGizmoHandle, create_grid},
+        storage::Handle,
+        transform::Transform,
+    },
+    game::{
+        assets::model::Model,
+        globals,
+        sim::{
+            DynamicBvh,
+            ecs::{BoundingBoxComponent, GizmoVertices},
+            sequences::Pose,
+            systems::world_interaction::WorldInteraction,
+        },
+    },
 };
 
+/// Debug visualizations for the model of the selected entity.
+#[derive(Default, Resource)]
+pub struct ModelDebugDraw {
+    /// Draw the edges of every triangle in the model.
+    pub wireframe: bool,
+    /// Draw a short line along each vertex normal.
+    pub normals: bool,
+}
+
+impl ModelDebugDraw {
+    /// Length of the normal lines in world units.
+    const NORMAL_LENGTH: f32 = 10.0;
+    /// Upper limit of normals drawn per model. Dense meshes are decimated to stay below it.
+    const MAX_NORMALS: usize = 4096;
+
+    #[inline]
+    fn is_enabled(&self) -> bool {
+        self.wireframe || self.normals
+    }
+}
+
+/// A reference grid on the ground plane through the origin.
+#[derive(Resource)]
+pub struct GroundGrid {
+    pub visible: bool,
+    /// Distance between grid lines in world units.
+    pub spacing: f32,
+    /// How far the grid reaches from the origin along each axis.
+    pub extent: f32,
+    /// The static gizmo of the grid and the spacing and extent it was built
+    /// with.
+    gizmo: Option<(f32, f32, StaticGizmoHandle)>,
+}
+
+impl Default for GroundGrid {
+    fn default() -> Self {
+        Self {
+            visible: false,
+            spacing: 100.0,
+            extent: 5_000.0,
+            gizmo: None,
+        }
+    }
+}
+
+/// Draw the [GroundGrid] while it is visible. The grid lines are kept as a
+/// static gizmo and only rebuilt when the spacing or extent change, replacing
+/// the previous gizmo.
+pub fn draw_ground_grid(mut grid: ResMut<GroundGrid>, mut gizmo_vertices: ResMut<GizmoVertices>) {
+    const GRID_COLOR: Vec4 = Vec4::new(0.8, 0.8, 0.8, 1.0);
+
+    if !grid.visible {
+        return;
+    }
+
+    let handle = match grid.gizmo {
+        Some((spacing, extent, handle)) if spacing == grid.spacing && extent == grid.extent => {
+            handle
+        }
+        _ => {
+            if let Some((_, _, old)) = grid.gizmo.take() {
+                gizmo_vertices.remove_static(old);
+            }
+            let handle = gizmo_vertices.create_static(create_grid(
+                Vec3::ZERO,
+                grid.extent,
+                grid.spacing,
+                GRID_COLOR,
+            ));
+            grid.gizmo = Some((grid.spacing, grid.extent, handle));
+            handle
+        }
+    };
+
+    gizmo_vertices.draw_static(handle);
+}
+
+bitflags! {
+    /// What [CollisionDebugDraw] draws.
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub struct CollisionDebugModes: u8 {
+        /// The bounding box of every object.
+        const BOUNDING_BOXES = 1 << 0;
+        /// The nodes of the dynamic BVH.
+        const DYNAMIC_BVH = 1 << 1;
+        /// The local axes of every object.
+        const OBJECT_AXES = 1 << 2;
+    }
+}
+
+/// Debug visualizations of the volumes used for picking and culling.
+#[derive(Resource)]
+pub struct CollisionDebugDraw {
+    pub enabled: bool,
+    pub modes: CollisionDebugModes,
+    pub bounding_box_color: Vec4,
+    pub bvh_leaf_color: Vec4,
+    pub bvh_node_color: Vec4,
+    /// Length of the object axes in world units.
+    pub axes_length: f32,
+}
+
+impl Default for CollisionDebugDraw {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            modes: CollisionDebugModes::all(),
+            bounding_box_color: Vec4::new(1.0, 0.0, 0.0, 1.0),
+            bvh_leaf_color: Vec4::new(0.0, 1.0, 0.0, 1.0),
+            bvh_node_color: Vec4::new(0.0, 0.5, 1.0, 1.0),
+            axes_length: 50.0,
+        }
+    }
+}
+
+impl CollisionDebugDraw {
+    #[cfg(feature = "egui")]
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.enabled, "Enabled");
+
+        ui.add_enabled_ui(self.enabled, |ui| {
+            for (name, mode) in [
+                ("Bounding boxes", CollisionDebugModes::BOUNDING_BOXES),
+                ("Dynamic BVH", CollisionDebugModes::DYNAMIC_BVH),
+                ("Object axes", CollisionDebugModes::OBJECT_AXES),
+            ] {
+                let mut active = self.modes.contains(mode);
+                if ui.checkbox(&mut active, name).changed() {
+                    self.modes.set(mode, active);
+                }
+            }
+
+            let edit_color = |ui: &mut egui::Ui, label: &str, color: &mut Vec4| {
+                ui.horizontal(|ui| {
+                    let mut rgba =
+                        egui::Rgba::from_rgba_unmultiplied(color.x, color.y, color.z, color.w);
+                    egui::color_picker::color_edit_button_rgba(
+                        ui,
+                        &mut rgba,
+                        egui::color_picker::Alpha::OnlyBlend,
+                    );
+                    *color = Vec4::from_array(rgba.to_rgba_unmultiplied());
+                    ui.label(label);
+                });
+            };
+            edit_color(ui, "Bounding boxes", &mut self.bounding_box_color);
+            edit_color(ui, "BVH leaves", &mut self.bvh_leaf_color);
+            edit_color(ui, "BVH nodes", &mut self.bvh_node_color);
+
+            ui.add(egui::Slider::new(&mut self.axes_length, 1.0..=500.0).text("Axes length"));
+        });
+    }
+}
+
+/// Draw the volumes selected in [CollisionDebugDraw].
+pub fn draw_collision_debug(
+    settings: Res<CollisionDebugDraw>,
+    dynamic_bvh: Res<DynamicBvh>,
+    objects: Query<(&Transform, Option<&BoundingBoxComponent>)>,
+    mut gizmo_vertices: ResMut<GizmoVertices>,
+) {
+    const AXIS_COLORS: [Vec4; 3] = [
+        Vec4::new(1.0, 0.0, 0.0, 1.0),
+        Vec4::new(0.0, 1.0, 0.0, 1.0),
+        Vec4::new(0.0, 0.0, 1.0, 1.0),
+    ];
+
+    if !settings.enabled {
+        return;
+    }
+
+    if settings.modes.contains(CollisionDebugModes::DYNAMIC_BVH) {
+        for (bounding_box, is_leaf) in dynamic_bvh.node_bounds() {
+            let color = if is_leaf {
+                settings.bvh_leaf_color
+            } else {
+                settings.bvh_node_color
+            };
+            gizmo_vertices.draw_bounding_box(bounding_box, color);
+        }
+    }
+
+    for (transform, bounding_box) in objects.iter() {
+        if settings.modes.contains(CollisionDebugModes::BOUNDING_BOXES)
+            && let Some(bounding_box) = bounding_box
+        {
+            let actual = bounding_box.0.transformed(transform.to_mat4());
+            gizmo_vertices.draw_bounding_box(&actual, settings.bounding_box_color);
+        }
+
+        if settings.modes.contains(CollisionDebugModes::OBJECT_AXES) {
+            let start = transform.translation;
+            for (axis, color) in [Vec3::X, Vec3::Y, Vec3::Z].into_iter().zip(AXIS_COLORS) {
+                let end = start + transform.rotation * axis * settings.axes_length;
+                gizmo_vertices.draw_line(start, end, color);
+            }
+        }
+    }
+}
+
 pub fn _draw_model_bounding_boxes(
     models: Query<(&Transform, &BoundingBoxComponent)>,
     mut gizmo_vertices: ResMut<GizmoVertices>,
@@ -17,3 +231,78 @@ pub fn _draw_model_bounding_boxes(
         gizmo_vertices.draw_bounding_box(&actual, color);
     }
 }
+
+/// Draw the wireframe and/or vertex normals of the selected entity's model,
+/// posed the same way it is rendered.
+pub fn draw_selected_model_debug(
+    settings: Res<ModelDebugDraw>,
+    world_interaction: Res<WorldInteraction>,
+    models: Query<(&Transform, &Handle<Model>, Option<&Pose>)>,
+    mut gizmo_vertices: ResMut<GizmoVertices>,
+) {
+    const WIREFRAME_COLOR: Vec4 = Vec4::new(1.0, 1.0, 1.0, 1.0);
+    const NORMAL_COLOR: Vec4 = Vec4::new(0.0, 1.0, 1.0, 1.0);
+
+    if !settings.is_enabled() {
+        return;
+    }
+
+    let Some((transform, model_handle, pose)) = world_interaction
+        .selected_entity
+        .and_then(|entity| models.get(entity).ok())
+    else {
+        return;
+    };
+
+    let Some(model) = globals::models().get(*model_handle) else {
+        return;
+    };
+
+    let object_to_world = transform.to_mat4();
+    let bones: Vec<Mat4> = match pose {
+        Some(pose) if pose.bones.len() == model.skeleton.bones.len() => {
+            pose.to_world_matrices(object_to_world)
+        }
+        _ => model.skeleton.to_pose().to_world_matrices(object_to_world),
+    };
+
+    let vertex_count: usize = model.meshes.iter().map(|m| m.mesh.vertices.len()).sum();
+    let normal_step = vertex_count.div_ceil(ModelDebugDraw::MAX_NORMALS).max(1);
+    let mut vertex_counter = 0_usize;
+
+    for mesh in model.meshes.iter() {
+        let vertices = &mesh.mesh.vertices;
+
+        let world_position = |index: u32| {
+            let vertex = &vertices[index as usize];
+            bones[vertex.node_index as usize].transform_point3(vertex.position)
+        };
+
+        if settings.wireframe {
+            for triangle in mesh.mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(world_position);
+                gizmo_vertices.draw_line(a, b, WIREFRAME_COLOR);
+                gizmo_vertices.draw_line(b, c, WIREFRAME_COLOR);
+                gizmo_vertices.draw_line(c, a, WIREFRAME_COLOR);
+            }
+        }
+
+        if settings.normals {
+            for vertex in vertices.iter() {
+                vertex_counter += 1;
+                if !vertex_counter.is_multiple_of(normal_step) {
+                    continue;
+                }
+
+                let bone = &bones[vertex.node_index as usize];
+                let start = bone.transform_point3(vertex.position);
+                let normal = bone.transform_vector3(vertex.normal).normalize_or_zero();
+                gizmo_vertices.draw_line(
+                    start,
+                    start + normal * ModelDebugDraw::NORMAL_LENGTH,
+                    NORMAL_COLOR,
+                );
+            }
+        }
+    }
+}
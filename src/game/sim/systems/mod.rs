@@ -7,15 +7,17 @@ use crate::{
         math::BoundingBox,
         sim::{
             DynamicBvh, DynamicBvhHandle, StaticBvh, StaticBvhHandle, ecs, extract,
-            free_camera_controller, top_down_camera_controller,
+            free_camera_controller, minimap, orders::OrderRequest, terrain,
+            top_down_camera_controller,
         },
     },
 };
 
+mod attachments;
 mod camera;
 mod changed;
 mod clear_render_targets;
-mod debug;
+pub mod debug;
 mod gizmos;
 mod orders;
 mod sequences;
@@ -23,9 +25,6 @@ pub mod world_interaction;
 
 #[derive(Resource)]
 pub struct Time {
-    /// The instant the scene started running. Constant the entire time the
-    /// scene is running.
-    pub scene_start: std::time::Instant,
     /// Time elapsed since the last frame was rendered.
     pub delta_time: f32,
     /// Time in seconds since the simulation started.
@@ -50,7 +49,6 @@ impl Default for SimulationControl {
 impl Default for Time {
     fn default() -> Self {
         Self {
-            scene_start: std::time::Instant::now(),
             delta_time: 0.0,
             sim_time: 0.0,
             frame_index: 0,
@@ -61,7 +59,8 @@ impl Default for Time {
 impl Time {
     pub fn next_frame(&mut self, delta_time: f32) {
         self.delta_time = delta_time;
-        self.sim_time = (std::time::Instant::now() - self.scene_start).as_secs_f32();
+        // Accumulate instead of reading the clock so replays see the same times.
+        self.sim_time += delta_time;
         self.frame_index = self.frame_index.wrapping_add(1);
     }
 }
@@ -77,7 +76,14 @@ pub fn build_update_schedule() -> Schedule {
     schedule.configure_sets(Update.run_if(should_run_simulation_update));
 
     // Start
-    schedule.add_systems(gizmos::clear_gizmo_vertices.in_set(Start));
+    schedule.add_systems(
+        (
+            gizmos::clear_gizmo_vertices,
+            update_messages::<OrderRequest>,
+            update_messages::<world_interaction::ObjectClicked>,
+        )
+            .in_set(Start),
+    );
 
     // Input
     schedule.add_systems(
@@ -88,6 +94,7 @@ pub fn build_update_schedule() -> Schedule {
             ),
             camera::update_far_distance.run_if(changed::time_of_day_changed),
             camera::update_active_camera_aspect_ratio.run_if(resource_changed::<ecs::Viewport>),
+            camera::update_projection_jitter,
             camera::compute_cameras,
             world_interaction::input,
         )
@@ -99,6 +106,7 @@ pub fn build_update_schedule() -> Schedule {
     schedule.add_systems(
         (
             world_interaction::update,
+            world_interaction::report_clicked_objects,
             (
                 orders::handle_order_requests,
                 orders::update_orders_controller,
@@ -106,17 +114,22 @@ pub fn build_update_schedule() -> Schedule {
                 .chain(),
             sequences::update_motion_controllers,
             sequences::update_poses,
+            attachments::update_bone_attachments,
             rebuild_static_bvh
                 .run_if(|q: Query<(), Added<ecs::BoundingBoxComponent>>| q.iter().count() > 0),
             update_dynamic_bvh,
             sequences::_debug_draw_root_motion,
+            debug::draw_selected_model_debug,
+            debug::draw_ground_grid,
+            debug::draw_collision_debug,
+            minimap::update_minimap,
         )
             .in_set(Update)
             .chain(),
     );
 
     // End
-    schedule.add_systems(reset_input_state.in_set(End));
+    schedule.add_systems((terrain::apply_terrain_changes, reset_input_state).in_set(End));
 
     schedule
 }
@@ -127,6 +140,12 @@ pub fn build_extract_schedule() -> Schedule {
     extract::create_extract_schedule()
 }
 
+/// Drop messages of type `E` that were sent before the previous tick, so
+/// unread messages don't pile up.
+fn update_messages<E: Message>(mut messages: ResMut<Messages<E>>) {
+    messages.update();
+}
+
 fn reset_input_state(mut input: ResMut<InputState>) {
     input.reset_per_frame();
 }
@@ -135,7 +154,8 @@ fn should_run_simulation_update(control: Res<SimulationControl>) -> bool {
     control.run_update
 }
 
-fn rebuild_static_bvh(
+/// Rebuild the static BVH from the bounding boxes of all static objects.
+pub fn rebuild_static_bvh(
     objects: Query<(Entity, &Transform, &ecs::BoundingBoxComponent), With<StaticBvhHandle>>,
     mut static_bvh: ResMut<StaticBvh>,
     mut bounding_box_scratch: Local<Vec<(Entity, BoundingBox)>>,
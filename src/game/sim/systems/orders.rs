@@ -1,8 +1,11 @@
 use bevy_ecs::prelude::*;
 
-use crate::game::sim::{
-    orders::{OrderRequest, OrdersController},
-    systems::Time,
+use crate::{
+    engine::transform::Transform,
+    game::sim::{
+        orders::{OrderContext, OrderRequest, OrdersController},
+        systems::Time,
+    },
 };
 
 pub fn handle_order_requests(
@@ -21,10 +24,19 @@ pub fn handle_order_requests(
 }
 
 pub fn update_orders_controller(
-    mut orders_controllers: Query<&mut OrdersController>,
+    mut orders_controllers: Query<(&mut OrdersController, &mut Transform)>,
     time: Res<Time>,
 ) {
-    for mut orders_controller in orders_controllers.iter_mut() {
-        orders_controller.execute(time.frame_index);
+    for (mut orders_controller, mut transform) in orders_controllers.iter_mut() {
+        // Borrowing the transform mutably marks it as changed.
+        if orders_controller.is_idle() {
+            continue;
+        }
+
+        let mut context = OrderContext {
+            transform: &mut transform,
+            delta_time: time.delta_time,
+        };
+        orders_controller.execute(time.frame_index, &mut context);
     }
 }
@@ -7,9 +7,12 @@ use crate::{
         assets::model::Model,
         globals,
         sim::{
+            Terrain,
+            character_controller::CharacterController,
             ecs::GizmoVertices,
             sequences::{
-                MotionController, MotionSequencer, Pose, generate_pose, generate_pose_at_key_frame,
+                MotionController, MotionSequencer, Pose, PoseCache, PoseKey, PoseSample,
+                generate_pose, generate_pose_at_key_frame,
             },
         },
     },
@@ -19,24 +22,43 @@ use super::Time;
 
 /// Advance all motion controllers for the current frame.
 pub fn update_motion_controllers(
-    mut motion_controllers: Query<(&mut MotionController, &mut Transform)>,
+    mut motion_controllers: Query<(
+        &mut MotionController,
+        &mut Transform,
+        Option<&CharacterController>,
+    )>,
+    terrain: Res<Terrain>,
     time: Res<Time>,
 ) {
-    for (mut motion_controller, mut transform) in motion_controllers.iter_mut() {
+    for (mut motion_controller, mut transform, character_controller) in
+        motion_controllers.iter_mut()
+    {
         motion_controller.update(time.delta_time);
 
         // Once the motion has been calculated, adjust the transform of the
         // entity by the `root_motion` from the [MotionController].
-        let adjust = transform.rotation * motion_controller.root_motion;
+        let mut adjust = transform.rotation * motion_controller.root_motion;
+        if let Some(character_controller) = character_controller {
+            adjust = character_controller.move_character(
+                &terrain.height_map,
+                transform.translation,
+                adjust,
+            );
+        }
         transform.translation += adjust;
     }
 }
 
 /// Build a full pose for each animated entity from the currently active motion.
+/// Entities playing the same motion at the same time share a pose from the
+/// [PoseCache].
 pub fn update_poses(
     mut poses: Query<(&MotionController, &Handle<Model>, &mut Pose)>,
     motion_sequencer: Res<MotionSequencer>,
+    mut pose_cache: Local<PoseCache>,
 ) {
+    pose_cache.clear();
+
     for (motion_controller, model_handle, mut pose) in poses.iter_mut() {
         let Some(model) = globals::models().get(*model_handle) else {
             continue;
@@ -78,24 +100,44 @@ pub fn update_poses(
             continue;
         };
 
-        *pose = if let Some(terminal_frame_index) = terminal_frame_index {
-            generate_pose_at_key_frame(
-                skeleton,
-                &motion,
-                terminal_frame_index,
-                root_translation_override,
-                Some(&pose),
-            )
-        } else {
-            generate_pose(
-                skeleton,
-                &motion,
-                sample_time,
-                motion_info.looping,
-                root_translation_override,
-                Some(&pose),
-            )
+        let sample = match terminal_frame_index {
+            Some(key_frame_index) => PoseSample::KeyFrame(key_frame_index),
+            None => PoseSample::Ticks {
+                time: current_time_ticks,
+                ticks_per_frame: scaled_ticks_per_frame,
+            },
         };
+        let key = PoseKey::new(
+            *model_handle,
+            motion_info.motion,
+            sample,
+            motion_info.looping,
+            root_translation_override,
+        );
+
+        // Poses that don't match the skeleton are replaced by the rest pose.
+        let previous = (pose.local_transforms.len() == skeleton.bones.len()).then_some(&*pose);
+        let new_pose = pose_cache.get_or_generate(key, previous, || {
+            if let Some(terminal_frame_index) = terminal_frame_index {
+                generate_pose_at_key_frame(
+                    skeleton,
+                    &motion,
+                    terminal_frame_index,
+                    root_translation_override,
+                    Some(&pose),
+                )
+            } else {
+                generate_pose(
+                    skeleton,
+                    &motion,
+                    sample_time,
+                    motion_info.looping,
+                    root_translation_override,
+                    Some(&pose),
+                )
+            }
+        });
+        *pose = new_pose;
     }
 }
 
@@ -1,12 +1,21 @@
 use bevy_ecs::prelude::*;
-use glam::{IVec2, UVec2, Vec4};
+use glam::{IVec2, UVec2, Vec3, Vec4};
 
 use crate::{
-    engine::input::InputState,
-    game::sim::{
-        ComputedCamera, DynamicBvh, SimWorldState, Terrain, UiRect,
-        ecs::{ActiveCamera, Viewport},
-        orders::{OrderRequest, RequestedOrder},
+    engine::{input::InputState, storage::Handle, transform::Transform},
+    game::{
+        assets::model::{Model, ModelRayHit},
+        globals,
+        math::RaySegment,
+        sim::{
+            ComputedCamera, DynamicBvh, SimWorldState, Terrain, UiRect,
+            ecs::{ActiveCamera, Viewport},
+            events,
+            orders::{OrderRequest, RequestedOrder},
+            pathfinding,
+            spawner::SpawnInfo,
+            walkability::WalkabilityGrid,
+        },
     },
 };
 
@@ -15,6 +24,13 @@ pub struct Clicked {
     pos: UVec2,
 }
 
+/// Sent when the user clicks on an object in the world. Consumed by
+/// [report_clicked_objects] in the next update.
+#[derive(Clone, Copy, Debug, Message)]
+pub struct ObjectClicked {
+    pub entity: Entity,
+}
+
 #[derive(Clone, Copy, Event)]
 pub struct SelectionRect {
     /// The position where the rect was dragged from.
@@ -59,6 +75,116 @@ impl SelectionRect {
 pub struct WorldInteraction {
     selection_rect: Option<SelectionRect>,
     pub selected_entity: Option<Entity>,
+    /// Objects under the last click, used to cycle through stacked objects
+    /// when clicking the same spot again.
+    pick_cycle: Option<PickCycle>,
+}
+
+/// The result of the last pick and which entry of it is selected.
+struct PickCycle {
+    pos: UVec2,
+    stack: Vec<Entity>,
+    index: usize,
+}
+
+/// Objects that can be hit by a pick ray.
+pub type PickableObjects<'w, 's> = Query<'w, 's, (&'static Transform, &'static Handle<Model>)>;
+
+/// Intersect `ray` with a single object. Objects are tested against their
+/// model's collision boxes, or the mesh triangles if the model has none.
+fn object_ray_hit(
+    ray: &RaySegment,
+    entity: Entity,
+    objects: &PickableObjects,
+) -> Option<ModelRayHit> {
+    let (transform, model_handle) = objects.get(entity).ok()?;
+    let model = globals::models().get(*model_handle)?;
+    let object_to_world = transform.to_mat4();
+
+    if model.collision_boxes.is_empty() {
+        model.intersect_ray_segment_meshes_with_transform(object_to_world, ray, true)
+    } else {
+        model.intersect_ray_segment_with_transform(object_to_world, ray)
+    }
+}
+
+/// Return all objects hit by `ray`, sorted near to far.
+pub fn pick_stack(
+    ray: &RaySegment,
+    dynamic_bvh: &DynamicBvh,
+    objects: &PickableObjects,
+) -> Vec<Entity> {
+    let mut candidates = Vec::new();
+    dynamic_bvh.query_ray_segment(ray, &mut candidates);
+
+    let mut hits: Vec<(f32, Entity)> = candidates
+        .into_iter()
+        .filter_map(|entity| {
+            let hit = object_ray_hit(ray, entity, objects)?;
+
+            Some((hit.t, entity))
+        })
+        .collect();
+
+    hits.sort_by(|(a, _), (b, _)| a.total_cmp(b));
+    hits.into_iter().map(|(_, entity)| entity).collect()
+}
+
+/// What a ray cast into the world hit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RayHitTarget {
+    Object(Entity),
+    Terrain,
+}
+
+/// The closest hit of a ray cast into the world.
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub target: RayHitTarget,
+    /// Parameter along the ray: `hit = origin + direction * t`.
+    pub t: f32,
+    /// World position of the intersection.
+    pub world_position: Vec3,
+    /// World space surface normal (unit length).
+    pub normal: Vec3,
+}
+
+/// Cast `ray` against the objects and the terrain and return the closest hit.
+pub fn cast_ray(
+    ray: &RaySegment,
+    dynamic_bvh: &DynamicBvh,
+    terrain: &Terrain,
+    objects: &PickableObjects,
+) -> Option<RayHit> {
+    let mut candidates = Vec::new();
+    dynamic_bvh.query_ray_segment(ray, &mut candidates);
+
+    let object_hit = candidates
+        .into_iter()
+        .filter_map(|entity| {
+            let hit = object_ray_hit(ray, entity, objects)?;
+
+            Some(RayHit {
+                target: RayHitTarget::Object(entity),
+                t: hit.t,
+                world_position: hit.world_position,
+                normal: hit.normal,
+            })
+        })
+        .min_by(|a, b| a.t.total_cmp(&b.t));
+
+    let terrain_hit = terrain.intersect_ray_segment(ray).map(|hit| RayHit {
+        target: RayHitTarget::Terrain,
+        t: hit.t,
+        world_position: hit.world_position,
+        normal: hit.normal.normalize_or_zero(),
+    });
+
+    match (object_hit, terrain_hit) {
+        (Some(object), Some(terrain)) if terrain.t < object.t => Some(terrain),
+        (Some(object), _) => Some(object),
+        (None, terrain) => terrain,
+    }
 }
 
 const DRAG_THRESHOLD: u32 = 2;
@@ -70,6 +196,12 @@ pub fn input(
 ) {
     use winit::event::MouseButton;
 
+    // The release of a drag that continues outside the window is never seen.
+    if !input_state.has_focus() {
+        world_interaction.selection_rect = None;
+        return;
+    }
+
     if input_state.mouse_just_pressed(MouseButton::Left) {
         // Start dragging the selection rect.
         world_interaction.selection_rect = input_state.mouse_position().map(|pos| SelectionRect {
@@ -140,6 +272,22 @@ pub fn update(world_interaction: Res<WorldInteraction>, mut state: ResMut<SimWor
     }
 }
 
+/// Drain the [ObjectClicked] events sent since the last update and report
+/// what was clicked.
+pub fn report_clicked_objects(world: &mut World) {
+    for ObjectClicked { entity } in events::drain_events::<ObjectClicked>(world) {
+        match world.get::<SpawnInfo>(entity) {
+            Some(info) => tracing::info!(
+                "Clicked {} ({}, {:?}).",
+                info.name,
+                info.title,
+                info.object_type
+            ),
+            None => tracing::info!("Clicked {entity}."),
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn on_clicked(
     clicked: On<Clicked>,
@@ -147,52 +295,80 @@ pub fn on_clicked(
     camera: Single<&ComputedCamera, With<ActiveCamera>>,
     dynamic_bvh: Res<DynamicBvh>,
     terrain: Res<Terrain>,
+    walkability: Res<WalkabilityGrid>,
+    objects: PickableObjects,
+    transforms: Query<&Transform>,
     mut world_interaction: ResMut<WorldInteraction>,
 
     mut commands: Commands,
-
-    mut entity_cache: Local<Vec<Entity>>,
-    mut terrain_hit_cache: Local<Vec<IVec2>>,
 ) {
     let ray = camera.create_ray_segment(clicked.pos, viewport.size);
 
-    entity_cache.clear();
-    dynamic_bvh.query_ray_segment(&ray, &mut entity_cache);
+    let stack = pick_stack(&ray, &dynamic_bvh, &objects);
+
+    // Clicking the same spot again on the selected object moves the selection
+    // to the next object in the stack under the cursor.
+    let cycled = match world_interaction.pick_cycle.take() {
+        Some(cycle)
+            if cycle.stack == stack
+                && cycle
+                    .pos
+                    .as_ivec2()
+                    .distance_squared(clicked.pos.as_ivec2())
+                    <= (DRAG_THRESHOLD * DRAG_THRESHOLD) as i32
+                && world_interaction.selected_entity == Some(cycle.stack[cycle.index]) =>
+        {
+            Some((cycle.index + 1) % stack.len())
+        }
+        _ => None,
+    };
+    let index = cycled.unwrap_or(0);
+
+    let clicked_entity = stack.get(index).copied();
 
-    let clicked_entity = entity_cache.first();
+    if let Some(entity) = clicked_entity {
+        commands.queue(move |world: &mut World| {
+            events::send_event(world, ObjectClicked { entity });
+        });
+    }
 
     match (clicked_entity, world_interaction.selected_entity) {
+        (Some(clicked), Some(_selected)) if cycled.is_some() => {
+            world_interaction.selected_entity = Some(clicked);
+        }
+
         (Some(_clicked), Some(_selected)) => {
             // TODO: Let the selected entity interact with the clicked entity, if possible.
         }
 
         (Some(clicked), None) => {
             // Clicked on an entity, with no current entity selected, so select it.
-            world_interaction.selected_entity = Some(*clicked);
+            world_interaction.selected_entity = Some(clicked);
         }
 
         (None, Some(selected)) => {
             // Something is selected, but we did not click on anything. Pass through to allow
             // terrain intersection checks.
-            terrain_hit_cache.clear();
-            terrain
-                .quad_tree
-                .ray_intersect_chunks(&ray, &mut terrain_hit_cache);
-
-            let terrain_hits: Vec<_> = terrain_hit_cache
-                .iter()
-                .filter_map(|&chunk| terrain.chunk_intersect_ray_segment(chunk, &ray))
-                .collect();
-
-            if let Some(terrain_hit) = terrain_hits.iter().min_by(|&a, &b| a.t.total_cmp(&b.t)) {
-                println!("hit: {terrain_hit:?}");
-                commands.write_message(OrderRequest {
-                    entity: selected,
-                    order: RequestedOrder::MoveTo {
-                        location: terrain_hit.world_position,
-                    },
-                    priority_override: None,
-                });
+            if let Some(terrain_hit) = terrain.intersect_ray_segment(&ray)
+                && let Ok(transform) = transforms.get(selected)
+            {
+                let height_map = &terrain.height_map;
+                let start = pathfinding::world_to_cell(height_map, transform.translation);
+                let goal = pathfinding::world_to_cell(height_map, terrain_hit.world_position);
+
+                match pathfinding::pathfind(&walkability, start, goal) {
+                    Some(path) => {
+                        commands.write_message(OrderRequest {
+                            entity: selected,
+                            order: RequestedOrder::MoveTo {
+                                location: terrain_hit.world_position,
+                                path: pathfinding::path_to_world(height_map, &path),
+                            },
+                            priority_override: None,
+                        });
+                    }
+                    None => tracing::info!("No path from {start} to {goal}."),
+                }
             }
         }
 
@@ -200,6 +376,14 @@ pub fn on_clicked(
             // Nothing selected and nothing clicked, we can't do anything further.
         }
     }
+
+    if !stack.is_empty() {
+        world_interaction.pick_cycle = Some(PickCycle {
+            pos: clicked.pos,
+            stack,
+            index,
+        });
+    }
 }
 
 /*
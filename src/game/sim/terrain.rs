@@ -1,15 +1,20 @@
-use bevy_ecs::resource::Resource;
-use glam::{IVec2, UVec2, Vec2, Vec3};
+use bevy_ecs::prelude::*;
+use glam::{IVec2, Quat, UVec2, Vec2, Vec3};
 
 use crate::{
-    engine::storage::Handle,
+    engine::{storage::Handle, transform::Transform},
     game::{
         assets::image::Image,
         math::{BoundingBox, RaySegment, RayTriangleHit, triangle_intersect_ray_segment},
     },
 };
 
-use super::{height_map::HeightMap, quad_tree};
+use super::{
+    height_map::{HeightMap, NodeRect},
+    minimap::Minimap,
+    quad_tree::{self, ChunkRect},
+    walkability::WalkabilityGrid,
+};
 
 // Size of each terrain:
 //
@@ -33,6 +38,9 @@ pub struct Terrain {
     pub strata_texture: Handle<Image>,
     // pub water_image: Option<Handle<Image>>,
     pub quad_tree: quad_tree::QuadTree,
+    /// Nodes changed during the last tick, which the renderer still has to
+    /// upload. Set by [apply_terrain_changes].
+    pub changed_nodes: Option<NodeRect>,
 }
 
 impl Terrain {
@@ -68,6 +76,7 @@ impl Terrain {
             terrain_texture,
             strata_texture,
             quad_tree,
+            changed_nodes: None,
         }
     }
 
@@ -172,6 +181,39 @@ impl Terrain {
         closest
     }
 
+    /// Intersect `ray_segment` with the whole terrain and return the closest
+    /// hit, if any.
+    pub fn intersect_ray_segment(&self, ray_segment: &RaySegment) -> Option<RayTriangleHit> {
+        let mut chunks = Vec::new();
+        self.quad_tree
+            .ray_intersect_chunks(ray_segment, &mut chunks);
+
+        chunks
+            .into_iter()
+            .filter_map(|chunk| self.chunk_intersect_ray_segment(chunk, ray_segment))
+            .min_by(|a, b| a.t.total_cmp(&b.t))
+    }
+
+    /// Move `transform` vertically onto the terrain surface below or above it.
+    /// With `align_to_normal` the transform is also tilted to match the slope
+    /// of the surface, keeping its heading.
+    pub fn place_on_surface(&self, transform: &mut Transform, align_to_normal: bool) {
+        let position = transform.translation.truncate();
+        transform.translation.z = self.height_map.elevation_at(position);
+
+        if align_to_normal {
+            let up = transform.rotation * Vec3::Z;
+            let normal = self.height_map.normal_at(position);
+            transform.rotation = Quat::from_rotation_arc(up, normal) * transform.rotation;
+        }
+    }
+
+    /// Build a grid of the cells that can be walked on, rejecting cells
+    /// sloped more than `max_slope` radians.
+    pub fn walkability_grid(&self, max_slope: f32) -> WalkabilityGrid {
+        WalkabilityGrid::new(&self.height_map, max_slope)
+    }
+
     pub fn calculate_lod(
         camera_position: Vec3,
         camera_forward: Vec3,
@@ -190,32 +232,182 @@ impl Terrain {
         (t.floor() as i32).clamp(0, (Self::LOD_MAX - 1) as i32) as u32
     }
 
+    /// Take the nodes changed since the last call and refresh the bounds of
+    /// the chunks containing them. Returns the changed nodes, so the renderer
+    /// only has to upload those.
+    pub fn apply_height_map_changes(&mut self) -> Option<NodeRect> {
+        let rect = self.height_map.take_dirty_rect()?;
+
+        // Nodes on the border between chunks belong to the chunks on both sides.
+        let cells_per_chunk = Self::CELLS_PER_CHUNK as i32;
+        let chunks = ChunkRect {
+            min: ((rect.min - IVec2::ONE).max(IVec2::ZERO) / cells_per_chunk).as_uvec2(),
+            max: (rect.max / cells_per_chunk + IVec2::ONE).as_uvec2(),
+        };
+
+        let height_map = &self.height_map;
+        self.quad_tree
+            .update_leaves(chunks, |chunk| Self::chunk_min_max(height_map, chunk));
+
+        Some(rect)
+    }
+
     fn build_chunk_min_max(height_map: &HeightMap, chunk_dim: UVec2) -> Vec<quad_tree::MinMax> {
         let area = chunk_dim.x as usize * chunk_dim.y as usize;
         let mut min_max = Vec::with_capacity(area);
 
         for chunk_y in 0..chunk_dim.y {
             for chunk_x in 0..chunk_dim.x {
-                let min_node = UVec2::new(chunk_x, chunk_y) * Self::CELLS_PER_CHUNK;
-                let max_node = min_node + UVec2::splat(Self::CELLS_PER_CHUNK);
-
-                let mut min_z = f32::INFINITY;
-                let mut max_z = f32::NEG_INFINITY;
-
-                for node_y in min_node.y..=max_node.y {
-                    for node_x in min_node.x..=max_node.x {
-                        let altitude = height_map
-                            .node_at(IVec2::new(node_x as i32, node_y as i32))
-                            .w;
-                        min_z = min_z.min(altitude);
-                        max_z = max_z.max(altitude);
-                    }
-                }
-
-                min_max.push(quad_tree::MinMax(min_z, max_z));
+                min_max.push(Self::chunk_min_max(
+                    height_map,
+                    UVec2::new(chunk_x, chunk_y),
+                ));
             }
         }
 
         min_max
     }
+
+    /// The lowest and highest node of a chunk.
+    fn chunk_min_max(height_map: &HeightMap, chunk: UVec2) -> quad_tree::MinMax {
+        let min_node = chunk * Self::CELLS_PER_CHUNK;
+        let max_node = min_node + UVec2::splat(Self::CELLS_PER_CHUNK);
+
+        let mut min_z = f32::INFINITY;
+        let mut max_z = f32::NEG_INFINITY;
+
+        for node_y in min_node.y..=max_node.y {
+            for node_x in min_node.x..=max_node.x {
+                let altitude = height_map
+                    .node_at(IVec2::new(node_x as i32, node_y as i32))
+                    .w;
+                min_z = min_z.min(altitude);
+                max_z = max_z.max(altitude);
+            }
+        }
+
+        quad_tree::MinMax(min_z, max_z)
+    }
+}
+
+/// Apply the height map edits made this tick to the chunk bounds, the
+/// walkability grid and the minimap, and keep the changed nodes around for
+/// the renderer.
+pub fn apply_terrain_changes(
+    mut terrain: ResMut<Terrain>,
+    mut walkability: ResMut<WalkabilityGrid>,
+    mut minimap: ResMut<Minimap>,
+) {
+    if terrain.height_map.dirty_rect().is_none() {
+        // Only touch the resource when there is something to clear.
+        if terrain.changed_nodes.is_some() {
+            terrain.changed_nodes = None;
+        }
+        return;
+    }
+
+    terrain.changed_nodes = terrain.apply_height_map_changes();
+    if let Some(rect) = terrain.changed_nodes {
+        walkability.update_nodes(&terrain.height_map, rect);
+        minimap.update_terrain(&terrain, rect);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{engine::storage::Storage, game::math::Ray};
+
+    #[test]
+    fn height_map_changes_update_the_chunk_bounds() {
+        let mut images = Storage::<Image, ()>::default();
+        let height_map =
+            HeightMap::from_iter(UVec2::splat(17), 10.0, std::iter::repeat_n(50.0, 17 * 17));
+        let mut terrain = Terrain::new(height_map, images.insert(()), images.insert(()));
+        assert_eq!(terrain.apply_height_map_changes(), None);
+
+        // A node on the border between the first two chunks.
+        terrain.height_map.set_elevation(IVec2::new(8, 3), 200.0);
+        let rect = terrain.apply_height_map_changes().unwrap();
+        assert_eq!(rect.min, IVec2::new(7, 2));
+        assert_eq!(rect.max, IVec2::new(9, 4));
+
+        for chunk in [IVec2::new(0, 0), IVec2::new(1, 0)] {
+            assert_eq!(terrain.chunk_bounding_box(chunk).unwrap().max.z, 200.0);
+        }
+        assert_eq!(
+            terrain.chunk_bounding_box(IVec2::new(1, 1)).unwrap().max.z,
+            50.0
+        );
+        assert_eq!(terrain.quad_tree._root_bounding_box().max.z, 200.0);
+    }
+
+    #[test]
+    fn terrain_changes_are_kept_for_one_tick() {
+        let mut images = Storage::<Image, ()>::default();
+        let height_map =
+            HeightMap::from_iter(UVec2::splat(17), 10.0, std::iter::repeat_n(50.0, 17 * 17));
+        let terrain = Terrain::new(height_map, images.insert(()), images.insert(()));
+
+        let mut world = World::new();
+        world.insert_resource(terrain.walkability_grid(0.5));
+        world.insert_resource(Minimap::new(&terrain));
+        world.insert_resource(terrain);
+        let mut schedule = Schedule::default();
+        schedule.add_systems(apply_terrain_changes);
+
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<Terrain>().changed_nodes, None);
+
+        world
+            .resource_mut::<Terrain>()
+            .height_map
+            .set_elevation(IVec2::new(4, 4), 500.0);
+        schedule.run(&mut world);
+        let terrain = world.resource::<Terrain>();
+        assert_eq!(
+            terrain.changed_nodes,
+            Some(NodeRect {
+                min: IVec2::new(3, 3),
+                max: IVec2::new(5, 5),
+            })
+        );
+        assert_eq!(terrain.height_map.dirty_rect(), None);
+        assert!(
+            !world
+                .resource::<WalkabilityGrid>()
+                .is_walkable(IVec2::new(3, 3))
+        );
+
+        schedule.run(&mut world);
+        assert_eq!(world.resource::<Terrain>().changed_nodes, None);
+    }
+
+    #[test]
+    fn rays_hit_the_closest_terrain_surface() {
+        let mut images = Storage::<Image, ()>::default();
+        let height_map =
+            HeightMap::from_iter(UVec2::splat(17), 10.0, std::iter::repeat_n(50.0, 17 * 17));
+        let terrain = Terrain::new(height_map, images.insert(()), images.insert(()));
+
+        let ray_segment = RaySegment {
+            ray: Ray::new(Vec3::new(35.0, 125.0, 200.0), Vec3::NEG_Z),
+            distance: 1_000.0,
+        };
+        let hit = terrain
+            .intersect_ray_segment(&ray_segment)
+            .expect("ray should hit the terrain");
+        assert!((hit.t - 150.0).abs() < 1e-3, "{}", hit.t);
+        assert!(
+            hit.world_position
+                .abs_diff_eq(Vec3::new(35.0, 125.0, 50.0), 1e-3)
+        );
+        assert!(hit.normal.normalize().abs_diff_eq(Vec3::Z, 1e-5));
+
+        let short = RaySegment {
+            distance: 100.0,
+            ..ray_segment
+        };
+        assert!(terrain.intersect_ray_segment(&short).is_none());
+    }
 }
@@ -109,6 +109,19 @@ impl TopDownCameraController {
         }
     }
 
+    /// Create a controller that starts at the current position and view
+    /// direction of `camera`.
+    pub fn from_camera(camera: &Camera, movement_speed: f32, rotation_speed: f32) -> Self {
+        let (yaw, pitch) = camera.yaw_pitch();
+        Self::new(
+            camera.position,
+            yaw.to_degrees(),
+            pitch.to_degrees(),
+            movement_speed,
+            rotation_speed,
+        )
+    }
+
     fn gather_input(&self, input_state: &InputState) -> Input {
         Input {
             move_direction: {
@@ -176,8 +189,8 @@ impl TopDownCameraController {
             }
         }
 
-        if input_state.wheel_delta() != 0.0 {
-            self.desired.position.z += input_state.wheel_delta() * move_delta * 3.0;
+        if input_state.scroll_delta() != 0.0 {
+            self.desired.position.z += input_state.scroll_delta() * move_delta * 3.0;
         }
         */
     }
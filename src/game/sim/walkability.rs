@@ -0,0 +1,160 @@
+use bevy_ecs::prelude::*;
+use bitflags::bitflags;
+use glam::{IVec2, UVec2};
+
+use super::height_map::{HeightMap, NodeRect};
+
+bitflags! {
+    /// Reasons a terrain cell can not be walked on. A cell without flags is walkable.
+    #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+    pub struct CellFlags: u8 {
+        /// One of the corner nodes is steeper than the grid's maximum slope.
+        const STEEP = 1 << 0;
+        /// Explicitly marked impassable, e.g. water or a placed obstacle.
+        const BLOCKED = 1 << 1;
+    }
+}
+
+/// Per-cell walkability of the terrain, used as the input for pathfinding.
+#[derive(Resource)]
+pub struct WalkabilityGrid {
+    /// Amount of cells in the grid. Matches the height map size.
+    size: UVec2,
+    /// Cosine of the maximum walkable slope; node normals with a smaller z are too steep.
+    min_normal_z: f32,
+    cells: Vec<CellFlags>,
+    /// Steepest slope of each cell's corners, in radians from horizontal.
+    slopes: Vec<f32>,
+}
+
+impl WalkabilityGrid {
+    /// Build the grid for `height_map`. Cells with a corner sloped more than
+    /// `max_slope` radians from horizontal are not walkable.
+    pub fn new(height_map: &HeightMap, max_slope: f32) -> Self {
+        let size = height_map.size;
+
+        let mut grid = Self {
+            size,
+            min_normal_z: max_slope.cos(),
+            cells: vec![CellFlags::empty(); size.x as usize * size.y as usize],
+            slopes: vec![0.0; size.x as usize * size.y as usize],
+        };
+
+        grid.update_region(height_map, IVec2::ZERO, size.as_ivec2() - IVec2::ONE);
+
+        grid
+    }
+
+    /// Amount of cells in the grid.
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Whether the cell at `coord` can be walked on. Coordinates outside the
+    /// grid are never walkable.
+    pub fn is_walkable(&self, coord: IVec2) -> bool {
+        self.flags(coord).is_some_and(|flags| flags.is_empty())
+    }
+
+    /// The flags of the cell at `coord`, or `None` if it is outside the grid.
+    pub fn flags(&self, coord: IVec2) -> Option<CellFlags> {
+        self.index(coord).map(|index| self.cells[index])
+    }
+
+    /// The steepest slope of the cell at `coord` in radians, or `None` if it
+    /// is outside the grid.
+    pub fn slope(&self, coord: IVec2) -> Option<f32> {
+        self.index(coord).map(|index| self.slopes[index])
+    }
+
+    /// Mark the cell at `coord` as blocked or clear a previous block. The slope
+    /// of the cell is not affected.
+    pub fn set_blocked(&mut self, coord: IVec2, blocked: bool) {
+        if let Some(index) = self.index(coord) {
+            self.cells[index].set(CellFlags::BLOCKED, blocked);
+        }
+    }
+
+    /// Recalculate the slope of the cells from `min` to `max` (inclusive)
+    /// after the height map changed there. Normals are shared with neighbors,
+    /// so callers should pass the edited region grown by one cell.
+    pub fn update_region(&mut self, height_map: &HeightMap, min: IVec2, max: IVec2) {
+        let min = min.max(IVec2::ZERO);
+        let max = max.min(self.size.as_ivec2() - IVec2::ONE);
+
+        const CORNERS: [IVec2; 4] = [IVec2::ZERO, IVec2::X, IVec2::ONE, IVec2::Y];
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                let coord = IVec2::new(x, y);
+                let min_normal_z = CORNERS
+                    .iter()
+                    .map(|&offset| height_map.node_at(coord + offset).z)
+                    .fold(1.0_f32, f32::min);
+
+                let index = y as usize * self.size.x as usize + x as usize;
+                self.cells[index].set(CellFlags::STEEP, min_normal_z < self.min_normal_z);
+                self.slopes[index] = min_normal_z.clamp(-1.0, 1.0).acos();
+            }
+        }
+    }
+
+    /// Recalculate the cells using any of the height map nodes in `rect`,
+    /// e.g. the dirty rect of a terrain edit.
+    pub fn update_nodes(&mut self, height_map: &HeightMap, rect: NodeRect) {
+        // Cells use the normals of their corners, so the cells to the bottom
+        // left of the nodes are affected too.
+        self.update_region(height_map, rect.min - IVec2::ONE, rect.max);
+    }
+
+    fn index(&self, coord: IVec2) -> Option<usize> {
+        let size = self.size.as_ivec2();
+        if coord.x < 0 || coord.y < 0 || coord.x >= size.x || coord.y >= size.y {
+            return None;
+        }
+        Some(coord.y as usize * self.size.x as usize + coord.x as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steep_and_blocked_cells_are_not_walkable() {
+        // A flat 8x8 map with a tall spike in the middle.
+        let size = UVec2::splat(8);
+        let heights = (0..64).map(|i| if i == 4 * 8 + 4 { 500.0 } else { 0.0 });
+        let height_map = HeightMap::from_iter(size, 100.0, heights);
+
+        let mut grid = WalkabilityGrid::new(&height_map, 30.0_f32.to_radians());
+
+        assert!(grid.is_walkable(IVec2::new(1, 1)));
+        assert!(!grid.is_walkable(IVec2::new(4, 4)));
+        assert_eq!(grid.flags(IVec2::new(3, 3)), Some(CellFlags::STEEP));
+        assert!(!grid.is_walkable(IVec2::new(-1, 0)));
+        assert!(!grid.is_walkable(IVec2::new(8, 0)));
+
+        grid.set_blocked(IVec2::new(1, 1), true);
+        assert!(!grid.is_walkable(IVec2::new(1, 1)));
+        grid.set_blocked(IVec2::new(1, 1), false);
+        assert!(grid.is_walkable(IVec2::new(1, 1)));
+    }
+
+    #[test]
+    fn edited_nodes_update_the_cells_around_them() {
+        let size = UVec2::splat(8);
+        let mut height_map = HeightMap::from_iter(size, 100.0, (0..64).map(|_| 0.0));
+        let max_slope = 30.0_f32.to_radians();
+        let mut grid = WalkabilityGrid::new(&height_map, max_slope);
+
+        height_map.set_elevation(IVec2::new(4, 4), 500.0);
+        let rect = height_map.take_dirty_rect().unwrap();
+        grid.update_nodes(&height_map, rect);
+
+        let rebuilt = WalkabilityGrid::new(&height_map, max_slope);
+        assert_eq!(grid.cells, rebuilt.cells);
+        assert_eq!(grid.slopes, rebuilt.slopes);
+        assert!(!grid.is_walkable(IVec2::new(3, 3)));
+    }
+}
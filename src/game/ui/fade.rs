@@ -0,0 +1,43 @@
+/// Animates an opacity value linearly from one value to another over time.
+#[derive(Clone, Copy, Debug)]
+pub struct OpacityFade {
+    from: f32,
+    to: f32,
+    /// Length of the fade in seconds.
+    duration: f32,
+    /// Seconds since the fade started.
+    elapsed: f32,
+}
+
+impl OpacityFade {
+    /// Create a fade from `from` to `to` that takes `duration` seconds.
+    pub fn new(from: f32, to: f32, duration: f32) -> Self {
+        Self {
+            from: from.clamp(0.0, 1.0),
+            to: to.clamp(0.0, 1.0),
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+        }
+    }
+
+    /// Advance the fade by `delta_time` seconds and return the new opacity.
+    pub fn update(&mut self, delta_time: f32) -> f32 {
+        self.elapsed = (self.elapsed + delta_time).min(self.duration);
+        self.value()
+    }
+
+    /// The current opacity.
+    pub fn value(&self) -> f32 {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+
+        let t = self.elapsed / self.duration;
+        self.from + (self.to - self.from) * t
+    }
+
+    /// Whether the fade reached its target.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+}
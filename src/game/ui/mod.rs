@@ -1,5 +1,7 @@
+pub mod fade;
 pub mod geometries;
 pub mod render;
+pub mod tooltip;
 pub mod widgets;
 pub mod windows;
 
@@ -1,11 +1,14 @@
 use ahash::HashMap;
-use glam::{IVec2, UVec2, Vec2, Vec4};
+use glam::{UVec2, Vec2, Vec4};
 use wgpu::util::DeviceExt;
 
 use crate::{
     engine::{
         growing_buffer::GrowingBuffer,
-        renderer::{Gpu, RenderContext, RenderTarget, SurfaceDesc},
+        renderer::{
+            ColorTargetDescriptor, FormatDependent, Gpu, RenderContext, RenderPassExt,
+            RenderTarget, SurfaceDesc, ViewportRect,
+        },
         storage::Handle,
     },
     game::{
@@ -86,7 +89,10 @@ impl UiMesh {
 
 pub struct UiMeshRenderer {
     solid_white_texture: Handle<Texture>,
-    render_pipeline: wgpu::RenderPipeline,
+    shader: wgpu::ShaderModule,
+    pipeline_layout: wgpu::PipelineLayout,
+    /// Rebuilt when the format of the render target changes.
+    render_pipeline: FormatDependent<wgpu::RenderPipeline>,
     vertices_buffer: GrowingBuffer<UiVertex>,
     indices_buffer: GrowingBuffer<u32>,
     new_size: Option<UVec2>,
@@ -183,38 +189,8 @@ impl UiMeshRenderer {
             ..Default::default()
         });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("ui_mesh_renderer_pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: None,
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                buffers: &[wgpu::VertexBufferLayout {
-                    array_stride: std::mem::size_of::<UiVertex>() as wgpu::BufferAddress,
-                    step_mode: wgpu::VertexStepMode::Vertex,
-                    attributes: &wgpu::vertex_attr_array![
-                        0 => Float32x2,
-                        1 => Float32x2,
-                        2 => Float32x4,
-                    ],
-                }],
-            },
-            primitive: wgpu::PrimitiveState::default(),
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState::default(),
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: None,
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: surface.format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-            }),
-            multiview_mask: None,
-            cache: None,
+        let render_pipeline = FormatDependent::new(surface.format, |format| {
+            Self::create_render_pipeline(&shader, &pipeline_layout, format)
         });
 
         let vertices_buffer =
@@ -235,6 +211,8 @@ impl UiMeshRenderer {
 
         Self {
             solid_white_texture,
+            shader,
+            pipeline_layout,
             render_pipeline,
             vertices_buffer,
             indices_buffer,
@@ -247,6 +225,44 @@ impl UiMeshRenderer {
         }
     }
 
+    fn create_render_pipeline(
+        shader: &wgpu::ShaderModule,
+        pipeline_layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+    ) -> wgpu::RenderPipeline {
+        globals::gpu()
+            .device
+            .create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("ui_mesh_renderer_pipeline"),
+                layout: Some(pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: shader,
+                    entry_point: None,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    buffers: &[wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<UiVertex>() as wgpu::BufferAddress,
+                        step_mode: wgpu::VertexStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![
+                            0 => Float32x2,
+                            1 => Float32x2,
+                            2 => Float32x4,
+                        ],
+                    }],
+                },
+                primitive: wgpu::PrimitiveState::default(),
+                depth_stencil: None,
+                multisample: wgpu::MultisampleState::default(),
+                fragment: Some(wgpu::FragmentState {
+                    module: shader,
+                    entry_point: None,
+                    compilation_options: wgpu::PipelineCompilationOptions::default(),
+                    targets: &[Some(ColorTargetDescriptor::alpha_blend(format).into())],
+                }),
+                multiview_mask: None,
+                cache: None,
+            })
+    }
+
     /// Returns the generated white texture used for solid-color meshes.
     pub fn solid_white_texture(&self) -> Handle<Texture> {
         self.solid_white_texture
@@ -274,6 +290,11 @@ impl UiMeshRenderer {
             );
         }
 
+        self.render_pipeline
+            .update(render_target.format(), |format| {
+                Self::create_render_pipeline(&self.shader, &self.pipeline_layout, format)
+            });
+
         let mut vertices = Vec::new();
         let mut indices = Vec::new();
         let mut draws = Vec::new();
@@ -320,7 +341,7 @@ impl UiMeshRenderer {
                     ..Default::default()
                 });
 
-        render_pass.set_pipeline(&self.render_pipeline);
+        render_pass.set_pipeline(self.render_pipeline.get());
         render_pass.set_vertex_buffer(0, self.vertices_buffer.slice(..));
         render_pass.set_index_buffer(self.indices_buffer.slice(..), wgpu::IndexFormat::Uint32);
         render_pass.set_bind_group(0, &self.viewport_bind_group, &[]);
@@ -330,25 +351,20 @@ impl UiMeshRenderer {
                 continue;
             };
 
-            if let Some(clip_rect) = draw.clip_rect {
-                let clip_min = clip_rect.position.max(IVec2::ZERO);
-                let clip_max =
-                    (clip_rect.position + clip_rect.size).min(render_target.size.as_ivec2());
-                let clip_size = clip_max - clip_min;
-
-                if clip_size.x <= 0 || clip_size.y <= 0 {
-                    continue;
+            let scissor = match draw.clip_rect {
+                Some(clip_rect) => {
+                    let Some(rect) = ViewportRect::clipped(
+                        clip_rect.position,
+                        clip_rect.size,
+                        render_target.size,
+                    ) else {
+                        continue;
+                    };
+                    rect
                 }
-
-                render_pass.set_scissor_rect(
-                    clip_min.x as u32,
-                    clip_min.y as u32,
-                    clip_size.x as u32,
-                    clip_size.y as u32,
-                );
-            } else {
-                render_pass.set_scissor_rect(0, 0, render_target.size.x, render_target.size.y);
-            }
+                None => ViewportRect::full(render_target.size),
+            };
+            render_pass.set_scissor(render_target.size, scissor);
 
             render_pass.set_bind_group(1, bind_group, &[]);
             render_pass.draw_indexed(draw.index_range.clone(), 0, 0..1);
@@ -72,6 +72,55 @@ impl WindowRenderItems {
         self.0.clear();
     }
 
+    /// Number of queued items.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if no items are queued.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Multiply the alpha of every item queued from index `first` onward by
+    /// `opacity`. Items with an opacity of zero are dropped.
+    pub fn apply_opacity(&mut self, first: usize, opacity: f32) {
+        if opacity >= 1.0 {
+            return;
+        }
+
+        if opacity <= 0.0 {
+            // Keep clip rect pushes and pops balanced for items queued before `first`.
+            let mut index = 0;
+            self.0.retain(|item| {
+                let keep = index < first
+                    || matches!(
+                        item,
+                        WindowRenderItem::PushClipRect { .. } | WindowRenderItem::ClearClipRect
+                    );
+                index += 1;
+                keep
+            });
+            return;
+        }
+
+        for item in self.0[first..].iter_mut() {
+            match item {
+                WindowRenderItem::PushClipRect { .. } | WindowRenderItem::ClearClipRect => {}
+                WindowRenderItem::TexturedRect { color, .. }
+                | WindowRenderItem::SolidRect { color, .. }
+                | WindowRenderItem::Border { color, .. }
+                | WindowRenderItem::Text { color, .. } => color.w *= opacity,
+                WindowRenderItem::Sprite { alpha, .. } => *alpha *= opacity,
+                WindowRenderItem::Mesh { mesh } => {
+                    for vertex in mesh.vertices.iter_mut() {
+                        vertex.color[3] *= opacity;
+                    }
+                }
+            }
+        }
+    }
+
     /// Appends another list of window render items, preserving their order.
     pub fn extend_from(&mut self, other: &Self) {
         self.0.extend(other.0.iter().cloned());
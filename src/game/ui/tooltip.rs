@@ -0,0 +1,136 @@
+use glam::{IVec2, Vec4};
+
+use crate::game::ui::{
+    Rect,
+    render::window_renderer::{Font, WindowRenderItems, WindowRenderer},
+    u32_to_color,
+};
+
+/// Tracks how long the cursor has been resting over a widget with a tooltip
+/// and draws the tooltip once the hover delay has passed.
+pub struct TooltipState {
+    /// Seconds the cursor has to rest over a widget before its tooltip shows.
+    pub delay: f32,
+    /// Tooltip text of the widget currently under the cursor.
+    text: Option<String>,
+    /// Seconds the cursor has been over the widget providing `text`.
+    elapsed: f32,
+}
+
+impl Default for TooltipState {
+    fn default() -> Self {
+        Self {
+            delay: Self::DEFAULT_DELAY,
+            text: None,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl TooltipState {
+    /// Default hover delay in seconds.
+    pub const DEFAULT_DELAY: f32 = 0.5;
+
+    const PADDING: IVec2 = IVec2::new(4, 3);
+    /// Offset from the cursor so the tooltip doesn't sit under the pointer.
+    const CURSOR_OFFSET: IVec2 = IVec2::new(12, 16);
+    const BACKGROUND_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 0.85);
+    const BORDER_COLOR: u32 = 0xff19ff19;
+
+    /// Advance the hover timer by `delta_time` seconds. The timer restarts
+    /// whenever the hovered tooltip changes, including when the cursor leaves.
+    pub fn update(&mut self, hovered: Option<&str>, delta_time: f32) {
+        if self.text.as_deref() == hovered {
+            if self.text.is_some() {
+                self.elapsed += delta_time;
+            }
+            return;
+        }
+
+        self.text = hovered.map(String::from);
+        self.elapsed = 0.0;
+    }
+
+    /// Hide the tooltip and restart the hover timer.
+    pub fn reset(&mut self) {
+        self.text = None;
+        self.elapsed = 0.0;
+    }
+
+    /// The tooltip text to show, if the hover delay has passed.
+    pub fn visible_text(&self) -> Option<&str> {
+        self.text.as_deref().filter(|_| self.elapsed >= self.delay)
+    }
+
+    /// Queue the tooltip panel next to `mouse_position`, kept inside the UI.
+    /// Should be called after all windows rendered so the tooltip is on top.
+    pub fn render(
+        &self,
+        mouse_position: IVec2,
+        window_renderer: &WindowRenderer,
+        render_items: &mut WindowRenderItems,
+    ) {
+        let Some(text) = self.visible_text() else {
+            return;
+        };
+
+        let font = Font::Default;
+        let text_size = IVec2::new(
+            window_renderer.measure_text_width(text.as_bytes(), font),
+            window_renderer.measure_text_height(text.as_bytes(), font),
+        );
+        let size = text_size + Self::PADDING * 2;
+
+        let ui_size = window_renderer.ui_size().as_ivec2();
+        let mut position = mouse_position + Self::CURSOR_OFFSET;
+        if position.x + size.x > ui_size.x {
+            position.x = (ui_size.x - size.x).max(0);
+        }
+        if position.y + size.y > ui_size.y {
+            // Flip above the cursor rather than covering it.
+            position.y = (mouse_position.y - size.y).max(0);
+        }
+
+        let rect = Rect::new(position, size);
+        render_items.render_solid_rect(rect, Self::BACKGROUND_COLOR);
+        render_items.render_border(rect, 1, u32_to_color(Self::BORDER_COLOR));
+        render_items.render_text(position + Self::PADDING, text.as_bytes(), font, None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shows_after_delay_and_resets_when_leaving() {
+        let mut tooltip = TooltipState::default();
+
+        tooltip.update(Some("Save"), 0.0);
+        tooltip.update(Some("Save"), TooltipState::DEFAULT_DELAY * 0.5);
+        assert_eq!(tooltip.visible_text(), None);
+
+        tooltip.update(Some("Save"), TooltipState::DEFAULT_DELAY);
+        assert_eq!(tooltip.visible_text(), Some("Save"));
+
+        tooltip.update(None, 0.1);
+        tooltip.update(Some("Save"), 0.0);
+        assert_eq!(tooltip.visible_text(), None);
+    }
+
+    #[test]
+    fn reset_hides_until_the_delay_passes_again() {
+        let mut tooltip = TooltipState::default();
+        tooltip.update(Some("Save"), 0.0);
+        tooltip.update(Some("Save"), TooltipState::DEFAULT_DELAY);
+        assert_eq!(tooltip.visible_text(), Some("Save"));
+
+        tooltip.reset();
+        assert_eq!(tooltip.visible_text(), None);
+
+        tooltip.update(Some("Save"), TooltipState::DEFAULT_DELAY);
+        assert_eq!(tooltip.visible_text(), None);
+        tooltip.update(Some("Save"), TooltipState::DEFAULT_DELAY);
+        assert_eq!(tooltip.visible_text(), Some("Save"));
+    }
+}
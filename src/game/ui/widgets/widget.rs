@@ -47,6 +47,78 @@ pub trait Widget {
         window_render_context: &mut WindowRenderContext<'_>,
         window_render_items: &mut WindowRenderItems,
     );
+
+    /// Text shown in a floating panel after the cursor rests over the widget.
+    fn tooltip(&self) -> Option<&str> {
+        None
+    }
+
+    /// Wraps the widget so it shows `text` as its tooltip.
+    fn with_tooltip(self, text: impl Into<String>) -> WithTooltip<Self>
+    where
+        Self: Sized,
+    {
+        WithTooltip {
+            widget: self,
+            tooltip: text.into(),
+        }
+    }
+}
+
+/// A widget with a tooltip attached by [Widget::with_tooltip]. Everything else
+/// is forwarded to the wrapped widget.
+pub struct WithTooltip<W: Widget> {
+    pub widget: W,
+    pub tooltip: String,
+}
+
+impl<W: Widget> Widget for WithTooltip<W> {
+    fn rect(&self) -> Rect {
+        self.widget.rect()
+    }
+
+    fn on_primary_mouse_down(
+        &mut self,
+        position: IVec2,
+        context: &mut WindowManagerContext,
+    ) -> EventResult {
+        self.widget.on_primary_mouse_down(position, context)
+    }
+
+    fn on_primary_mouse_up(
+        &mut self,
+        position: IVec2,
+        context: &mut WindowManagerContext,
+    ) -> EventResult {
+        self.widget.on_primary_mouse_up(position, context)
+    }
+
+    fn on_mouse_wheel(
+        &mut self,
+        wheel_steps: i32,
+        context: &mut WindowManagerContext,
+    ) -> EventResult {
+        self.widget.on_mouse_wheel(wheel_steps, context)
+    }
+
+    fn render(
+        &mut self,
+        origin: IVec2,
+        delta_time_ms: i32,
+        window_render_context: &mut WindowRenderContext<'_>,
+        window_render_items: &mut WindowRenderItems,
+    ) {
+        self.widget.render(
+            origin,
+            delta_time_ms,
+            window_render_context,
+            window_render_items,
+        );
+    }
+
+    fn tooltip(&self) -> Option<&str> {
+        Some(&self.tooltip)
+    }
 }
 
 #[derive(Default)]
@@ -161,6 +233,16 @@ impl Widgets {
         EventResult::Ignore
     }
 
+    /// Returns the tooltip of the topmost child widget under the cursor. A
+    /// widget without a tooltip hides the tooltips of widgets below it.
+    pub fn tooltip_at(&self, position: IVec2) -> Option<&str> {
+        self.widgets
+            .iter()
+            .rev()
+            .find(|widget| widget.rect().contains(position))
+            .and_then(|widget| widget.tooltip())
+    }
+
     /// Renders child widgets using the original engine's back-to-front widget
     /// traversal order.
     pub fn render(
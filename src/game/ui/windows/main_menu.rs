@@ -8,7 +8,10 @@ use crate::{
         globals,
         ui::{
             Rect,
-            widgets::{main_menu_button::create_main_menu_button, text_button::TextButtonWidget},
+            widgets::{
+                main_menu_button::create_main_menu_button, text_button::TextButtonWidget,
+                widget::Widget,
+            },
             windows::{
                 actions::WindowManagerAction,
                 window::{Window, WindowImpl},
@@ -37,7 +40,8 @@ pub fn new_main_menu_window(context: &WindowLayoutContext) -> Result<Window, Ass
             Rect::new(IVec2::new(10, 10), IVec2::new(100, 30)),
             "Training",
         )
-        .with_action(WindowManagerAction::StartCampaign(String::from("training"))),
+        .with_action(WindowManagerAction::StartCampaign(String::from("training")))
+        .with_tooltip("Start the training campaign"),
     ));
 
     let button_offset = IVec2::new(
@@ -9,6 +9,7 @@ use crate::{
         globals,
         ui::{
             EventResult, Rect,
+            fade::OpacityFade,
             geometries::{Geometries, GeometryBase, GeometryTiled},
             render::{
                 ui_mesh_renderer::{UiMesh, UiVertex},
@@ -40,6 +41,11 @@ pub struct WindowCommon {
     pub is_enabled: bool,
     pub is_modal: bool,
     pub is_always_on_top: bool,
+
+    /// Opacity multiplied into everything the window and its widgets render.
+    pub opacity: f32,
+    /// Animation driving `opacity`, advanced in [Window::update].
+    pub fade: Option<OpacityFade>,
 }
 
 impl WindowCommon {
@@ -53,8 +59,17 @@ impl WindowCommon {
             is_enabled: true,
             is_modal: false,
             is_always_on_top: false,
+
+            opacity: 1.0,
+            fade: None,
         }
     }
+
+    /// Fade the window from its current opacity to `opacity` over `duration`
+    /// seconds.
+    pub fn fade_to(&mut self, opacity: f32, duration: f32) {
+        self.fade = Some(OpacityFade::new(self.opacity, opacity, duration));
+    }
 }
 
 pub trait WindowImpl {
@@ -314,16 +329,30 @@ impl Window {
             .on_mouse_wheel(&mut self.common, position, wheel_steps, context)
     }
 
+    /// Returns the tooltip of the widget under the window-local `position`.
+    pub fn tooltip_at(&self, position: IVec2) -> Option<&str> {
+        self.common.widgets.tooltip_at(position)
+    }
+
     pub fn render(
         &mut self,
         context: &mut WindowRenderContext<'_>,
         render_items: &mut WindowRenderItems,
     ) {
+        let first = render_items.len();
         self.window_impl
             .render(&mut self.common, context, render_items);
+        render_items.apply_opacity(first, self.common.opacity);
     }
 
     pub fn update(&mut self, delta_time: f32) {
+        if let Some(fade) = &mut self.common.fade {
+            self.common.opacity = fade.update(delta_time);
+            if fade.is_finished() {
+                self.common.fade = None;
+            }
+        }
+
         self.window_impl.update(delta_time);
     }
 }
@@ -18,6 +18,7 @@ use crate::{
         ui::{
             EventResult,
             render::window_renderer::{UiMode, WindowRenderItems, WindowRenderer},
+            tooltip::TooltipState,
             windows::{window::Window, window_manager_context::WindowManagerContext},
         },
     },
@@ -69,6 +70,9 @@ pub struct WindowManager {
     /// Track the down state of the used mouse buttons.
     primary_button_down: bool,
     secondary_button_down: bool,
+
+    /// Hover timer and text for widget tooltips.
+    tooltip: TooltipState,
 }
 
 impl WindowManager {
@@ -88,6 +92,8 @@ impl WindowManager {
             mouse_position: None,
             primary_button_down: false,
             secondary_button_down: false,
+
+            tooltip: TooltipState::default(),
         }
     }
 
@@ -196,11 +202,16 @@ impl WindowManager {
                 self.mouse_position = None;
                 self.modal_window.is_some()
             }
-            InputEvent::MouseDown(button) => self.dispatch_mouse_down(button),
+            InputEvent::MouseDown(button) => {
+                // Clicking hides the tooltip until the cursor rests again.
+                self.tooltip.reset();
+                self.dispatch_mouse_down(button)
+            }
             InputEvent::MouseUp(button) => self.dispatch_mouse_up(button),
             InputEvent::KeyDown(_key) => self.modal_window.is_some(),
             InputEvent::KeyUp(_key) => self.modal_window.is_some(),
             InputEvent::MouseWheel(delta) => self.dispatch_mouse_wheel(delta as i32),
+            InputEvent::Focused(_) => false,
         }
     }
 
@@ -380,6 +391,17 @@ impl WindowManager {
         for window in self.windows.iter_mut() {
             window.update(delta_time);
         }
+
+        let window_index = self
+            .mouse_position
+            .and_then(|mouse| self.topmost_input_window_index(mouse));
+        let hovered = window_index
+            .zip(self.mouse_position)
+            .and_then(|(index, mouse)| {
+                let window = &self.windows[index];
+                window.tooltip_at(mouse - window.rect().position)
+            });
+        self.tooltip.update(hovered, delta_time);
     }
 
     pub fn render(
@@ -398,6 +420,11 @@ impl WindowManager {
             window.render(&mut ctx, &mut self.window_render_items_cache);
         }
 
+        if let Some(mouse) = self.mouse_position {
+            self.tooltip
+                .render(mouse, window_renderer, &mut self.window_render_items_cache);
+        }
+
         window_renderer.submit_render_items(
             render_context,
             render_target,
@@ -1,5 +1,14 @@
+#[cfg(feature = "shader-hot-reload")]
+use std::path::Path;
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
 use glam::UVec2;
 
+#[cfg(feature = "egui")]
+use crate::game::render::per_frame;
 use crate::{
     engine::{
         input::InputEvent,
@@ -8,17 +17,34 @@ use crate::{
         storage::Handle,
     },
     game::{
-        render::{compositor::Compositor, geometry_buffer::GeometryBuffer, world::WorldRenderer},
-        sim::SimWorld,
+        render::{
+            compositor::Compositor, geometry_buffer::GeometryBuffer,
+            screenshot::AccumulatedScreenshot, world::WorldRenderer,
+        },
+        sim::{ProjectionJitter, SimWorld},
     },
 };
 
 /// Native-resolution world background rendered behind the logical UI stack.
 pub struct WorldLayer {
     sim: SimWorld,
+    target_format: wgpu::TextureFormat,
+    gbuffer_layout: wgpu::BindGroupLayout,
+    shader_cache: ShaderCache,
+    /// When the shader sources were last checked for edits.
+    #[cfg(feature = "shader-hot-reload")]
+    last_shader_check: Instant,
     world_renderer: WorldRenderer,
     gbuffer: Handle<GeometryBuffer>,
     compositor: Compositor,
+    /// Size the gbuffer should be resized to and when that size was last requested.
+    pending_gbuffer_size: Option<(UVec2, Instant)>,
+    /// The screenshot being accumulated and the jitter settings to restore
+    /// once it is done.
+    screenshot: Option<(AccumulatedScreenshot, ProjectionJitter)>,
+    /// Number of frames averaged by screenshots taken from the debug panel.
+    #[cfg(feature = "egui")]
+    screenshot_frames: u32,
 }
 
 impl WorldLayer {
@@ -26,28 +52,116 @@ impl WorldLayer {
     pub fn new(size: UVec2, target_format: wgpu::TextureFormat, mut sim: SimWorld) -> Self {
         let gbuffer_layout = GeometryBuffer::create_bind_group_layout();
         let mut shader_cache = ShaderCache::default();
+        // Debug builds pick up shader edits without restarting.
+        #[cfg(feature = "shader-hot-reload")]
+        if cfg!(debug_assertions) {
+            shader_cache.watch(Path::new(env!("CARGO_MANIFEST_DIR")));
+        }
         let compositor = Compositor::new(target_format, &gbuffer_layout, &mut shader_cache);
 
-        let mut world_renderer = WorldRenderer::new(&gbuffer_layout, sim.terrain());
+        let mut world_renderer =
+            WorldRenderer::new(&gbuffer_layout, sim.terrain(), &mut shader_cache);
         let gbuffer = world_renderer.register_gbuffer(size);
         sim.resize_viewport(size);
 
         Self {
             sim,
+            target_format,
+            gbuffer_layout,
+            shader_cache,
+            #[cfg(feature = "shader-hot-reload")]
+            last_shader_check: Instant::now(),
             world_renderer,
             gbuffer,
             compositor,
+            pending_gbuffer_size: None,
+            screenshot: None,
+            #[cfg(feature = "egui")]
+            screenshot_frames: 16,
         }
     }
 
-    /// Resizes the world render targets and simulation viewport.
+    /// Render the next `frame_count` frames with a different sub-pixel jitter
+    /// each and write their average to `path`, giving an anti-aliased
+    /// screenshot at the current size.
+    pub fn capture_accumulated_screenshot(&mut self, path: PathBuf, frame_count: u32) {
+        let Some(size) = self.world_renderer.gbuffer_size(self.gbuffer) else {
+            return;
+        };
+
+        let previous_jitter = self.screenshot.take().map_or_else(
+            || self.sim.projection_jitter().clone(),
+            |(_, jitter)| jitter,
+        );
+        self.sim.set_projection_jitter(ProjectionJitter {
+            enabled: true,
+            sample_count: frame_count,
+            sample_index: 0,
+        });
+
+        let screenshot = AccumulatedScreenshot::new(path, frame_count, size, self.target_format);
+        self.screenshot = Some((screenshot, previous_jitter));
+    }
+
+    /// Read back the screenshot frame rendered last and write the screenshot
+    /// once all its frames are in.
+    fn accumulate_screenshot(&mut self) {
+        let Some((screenshot, _)) = &mut self.screenshot else {
+            return;
+        };
+
+        if !screenshot.accumulate() {
+            return;
+        }
+
+        let (screenshot, previous_jitter) = self.screenshot.take().unwrap();
+        self.sim.set_projection_jitter(previous_jitter);
+        match screenshot.save() {
+            Ok(()) => tracing::info!("Saved accumulated screenshot."),
+            Err(err) => tracing::warn!("Could not save screenshot: {err}"),
+        }
+    }
+
+    /// Time the requested size has to stay the same before the gbuffer is
+    /// reallocated. Avoids reallocating large render targets on every event
+    /// during a window drag-resize.
+    const RESIZE_DEBOUNCE: Duration = Duration::from_millis(150);
+
+    /// Resizes the simulation viewport immediately, so the camera aspect ratio
+    /// stays correct, and schedules a resize of the world render targets.
     pub fn resize(&mut self, size: UVec2) {
-        if self.world_renderer.gbuffer_size(self.gbuffer) != Some(size) {
-            tracing::info!("Resizing world layer gbuffer to {}x{}.", size.x, size.y);
-            self.world_renderer.resize_gbuffer(self.gbuffer, size);
+        // A minimized window reports a zero size. Keep the current gbuffer
+        // around until the window is restored.
+        if size.x == 0 || size.y == 0 {
+            return;
         }
 
         self.sim.resize_viewport(size);
+
+        let current_size = self.world_renderer.gbuffer_size(self.gbuffer);
+        let pending_size = self.pending_gbuffer_size.map(|(size, _)| size);
+
+        if current_size == Some(size) {
+            self.pending_gbuffer_size = None;
+        } else if pending_size != Some(size) {
+            self.pending_gbuffer_size = Some((size, Instant::now()));
+        }
+    }
+
+    /// Reallocate the gbuffer if a resize was requested and the size has been
+    /// stable for long enough.
+    fn apply_pending_resize(&mut self) {
+        let Some((size, requested_at)) = self.pending_gbuffer_size else {
+            return;
+        };
+
+        if requested_at.elapsed() < Self::RESIZE_DEBOUNCE {
+            return;
+        }
+
+        tracing::info!("Resizing world layer gbuffer to {}x{}.", size.x, size.y);
+        self.world_renderer.resize_gbuffer(self.gbuffer, size);
+        self.pending_gbuffer_size = None;
     }
 
     /// Forwards an input event to the native-resolution simulation.
@@ -60,9 +174,105 @@ impl WorldLayer {
         self.sim.update(delta_time);
     }
 
+    /// Draws debug controls for the world.
+    #[cfg(feature = "egui")]
+    pub fn debug_panel(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.world_renderer.depth_prepass, "Depth prepass");
+
+        let mut frame_count = per_frame::frame_count();
+        let changed = ui
+            .add(
+                egui::Slider::new(
+                    &mut frame_count,
+                    per_frame::MIN_FRAME_COUNT..=per_frame::MAX_FRAME_COUNT,
+                )
+                .text("Frames in flight"),
+            )
+            .changed();
+        if changed {
+            self.set_frame_count(frame_count);
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(egui::Slider::new(&mut self.screenshot_frames, 1..=64).text("Frames"));
+            if ui
+                .add_enabled(
+                    self.screenshot.is_none(),
+                    egui::Button::new("Accumulated screenshot"),
+                )
+                .clicked()
+            {
+                self.capture_accumulated_screenshot(
+                    PathBuf::from("screenshot.bmp"),
+                    self.screenshot_frames,
+                );
+            }
+        });
+
+        self.sim.debug_panel(ui);
+    }
+
+    /// Recreate the world renderer so its per frame buffers use `frame_count`
+    /// copies.
+    #[cfg(feature = "egui")]
+    fn set_frame_count(&mut self, frame_count: usize) {
+        let frame_count = per_frame::set_frame_count(frame_count);
+        tracing::info!("Recreating world renderer with {frame_count} frames in flight.");
+        self.recreate_renderer();
+    }
+
+    /// How often the shader sources are checked for edits.
+    #[cfg(feature = "shader-hot-reload")]
+    const SHADER_CHECK_INTERVAL: Duration = Duration::from_millis(500);
+
+    /// Recreate the pipelines if any of the watched shaders were edited. The
+    /// UI is not part of the world layer, so edits to its shader still need
+    /// a restart.
+    #[cfg(feature = "shader-hot-reload")]
+    fn reload_shaders(&mut self) {
+        if self.last_shader_check.elapsed() < Self::SHADER_CHECK_INTERVAL {
+            return;
+        }
+        self.last_shader_check = Instant::now();
+
+        if !self.shader_cache.reload_changed().is_empty() {
+            self.recreate_renderer();
+        }
+    }
+
+    /// Recreate the world renderer and compositor, keeping the gbuffer size
+    /// and settings.
+    fn recreate_renderer(&mut self) {
+        let size = self
+            .world_renderer
+            .gbuffer_size(self.gbuffer)
+            .unwrap_or(UVec2::ONE);
+        let depth_prepass = self.world_renderer.depth_prepass;
+
+        self.compositor = Compositor::new(
+            self.target_format,
+            &self.gbuffer_layout,
+            &mut self.shader_cache,
+        );
+        self.world_renderer = WorldRenderer::new(
+            &self.gbuffer_layout,
+            self.sim.terrain(),
+            &mut self.shader_cache,
+        );
+        self.world_renderer.depth_prepass = depth_prepass;
+        self.gbuffer = self.world_renderer.register_gbuffer(size);
+    }
+
     /// Renders the world to its gbuffer and composites it into the surface.
     pub fn render(&mut self, render_context: &mut RenderContext, render_target: &RenderTarget) {
+        #[cfg(feature = "shader-hot-reload")]
+        self.reload_shaders();
         self.resize(render_target.size);
+        self.apply_pending_resize();
+
+        // The previous frame was submitted by now, so its screenshot frame can
+        // be read back.
+        self.accumulate_screenshot();
 
         let snapshot = self.sim.extract_snapshot();
         self.world_renderer.prepare(snapshot);
@@ -72,6 +282,14 @@ impl WorldLayer {
         if let Some(bind_group) = self.world_renderer.gbuffer_bind_group(self.gbuffer) {
             self.compositor
                 .composite(render_context, render_target, &bind_group);
+
+            if let Some((screenshot, _)) = &mut self.screenshot
+                && let Some(screenshot_target) = screenshot.render_target()
+            {
+                self.compositor
+                    .composite(render_context, screenshot_target, &bind_group);
+                screenshot.frame_rendered();
+            }
         }
     }
 }
@@ -13,6 +13,7 @@ use winit::{
 
 use crate::{
     engine::{
+        frame_pacing::{FramePacing, FrameTimings, PacingStrategy},
         input,
         renderer::{RenderContext, RenderTarget, Surface, SurfaceDesc},
     },
@@ -29,6 +30,21 @@ struct Opts {
     path: PathBuf,
     /// The name of the starting campaign. Defaults to "training".
     campaign_name: Option<String>,
+    /// Downscale textures larger than this many pixels per side. Defaults to
+    /// the largest size the GPU supports.
+    #[arg(long)]
+    max_texture_size: Option<u32>,
+    /// Pixels a trackpad has to scroll to count as one mouse wheel line.
+    #[arg(long, default_value_t = input::DEFAULT_PIXELS_PER_SCROLL_LINE)]
+    pixels_per_scroll_line: f32,
+    /// Directory with files that replace the game data, like a mod. Can be
+    /// given more than once; later overlays take precedence.
+    #[arg(long)]
+    overlay: Vec<PathBuf>,
+    /// Play back a replay, recorded from the world debug panel, when the
+    /// campaign starts. Replays only reproduce a run of the same campaign.
+    #[arg(long)]
+    replay: Option<PathBuf>,
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -46,6 +62,13 @@ enum App {
         frame_index: u64,
         /// The instant that the last frame started to render.
         last_frame_time: Instant,
+        /// Rolling frame, submit and present timings.
+        frame_pacing: FramePacing,
+        /// The window has a zero sized client area, so nothing is rendered
+        /// until it is restored.
+        minimized: bool,
+        /// Pixels a trackpad has to scroll to count as one mouse wheel line.
+        pixels_per_scroll_line: f32,
         /// egui integration.
         #[cfg(feature = "egui")]
         egui_integration: engine::egui_integration::EguiIntegration,
@@ -74,7 +97,12 @@ impl ApplicationHandler for App {
                     UVec2::new(width, height)
                 };
 
-                let (surface, gpu) = engine::renderer::create(Arc::clone(&window));
+                let (surface, gpu) =
+                    engine::renderer::create(Arc::clone(&window), opts.max_texture_size);
+
+                game::render::per_frame::set_frame_count(
+                    game::render::per_frame::frame_count_for_present_mode(surface.present_mode()),
+                );
 
                 let surface_desc = SurfaceDesc {
                     size: surface.size(),
@@ -90,9 +118,9 @@ impl ApplicationHandler for App {
                     surface_desc.format,
                 );
 
-                globals::init(&opts.path, gpu);
+                globals::init(&opts.path, &opts.overlay, gpu);
 
-                let game_state = match GameState::new(&surface_desc) {
+                let game_state = match GameState::new(&surface_desc, opts.replay.clone()) {
                     Ok(game_state) => game_state,
                     Err(err) => {
                         tracing::error!("Could not initialize GameState - {err}");
@@ -111,6 +139,9 @@ impl ApplicationHandler for App {
                     egui_integration,
                     frame_index: 0,
                     last_frame_time: Instant::now(),
+                    frame_pacing: FramePacing::default(),
+                    minimized: false,
+                    pixels_per_scroll_line: opts.pixels_per_scroll_line,
                     game_state,
                 };
             }
@@ -121,6 +152,20 @@ impl ApplicationHandler for App {
         }
     }
 
+    fn new_events(&mut self, _event_loop: &ActiveEventLoop, cause: winit::event::StartCause) {
+        // A sleeping pacing strategy reached the time for the next frame.
+        if let (
+            App::Initialized {
+                window, minimized, ..
+            },
+            winit::event::StartCause::ResumeTimeReached { .. },
+        ) = (self, cause)
+            && !*minimized
+        {
+            window.request_redraw();
+        }
+    }
+
     fn window_event(
         &mut self,
         event_loop: &ActiveEventLoop,
@@ -138,6 +183,9 @@ impl ApplicationHandler for App {
                 surface_desc,
                 frame_index,
                 last_frame_time,
+                frame_pacing,
+                minimized,
+                pixels_per_scroll_line,
                 #[cfg(feature = "egui")]
                 egui_integration,
                 game_state,
@@ -151,7 +199,7 @@ impl ApplicationHandler for App {
                 let repaint = {
                     let egui_winit::EventResponse { consumed, repaint } =
                         egui_integration.window_event(window.as_ref(), &event);
-                    if consumed {
+                    if consumed && !game_state.editor_viewport_hovered() {
                         return;
                     }
                     repaint
@@ -165,6 +213,20 @@ impl ApplicationHandler for App {
                     WindowEvent::Resized(PhysicalSize { width, height }) => {
                         let size = UVec2::new(width, height);
 
+                        let was_minimized = *minimized;
+                        *minimized = width == 0 || height == 0;
+                        if *minimized {
+                            // Wait for the restore instead of spinning on
+                            // frames that can't be presented.
+                            event_loop.set_control_flow(winit::event_loop::ControlFlow::Wait);
+                            return;
+                        }
+                        if was_minimized {
+                            // Don't feed the time spent minimized into the
+                            // next update.
+                            *last_frame_time = Instant::now();
+                        }
+
                         surface.resize(&globals::gpu().device, size);
                         surface_desc.size = surface.size();
                         surface_desc.scale_factor = window.scale_factor() as f32;
@@ -180,6 +242,8 @@ impl ApplicationHandler for App {
                         window.request_redraw();
                     }
 
+                    WindowEvent::RedrawRequested if *minimized => {}
+
                     WindowEvent::RedrawRequested => {
                         let now = Instant::now();
                         let last_frame_duration = now - *last_frame_time;
@@ -213,9 +277,13 @@ impl ApplicationHandler for App {
 
                                 game_state.render(&mut render_context, &render_target);
 
-                                // Render egui if it requires a repaint.
                                 #[cfg(feature = "egui")]
-                                if repaint {
+                                game_state.sync_editor_viewport(egui_integration);
+
+                                // Render egui if it requires a repaint. The editor viewport is
+                                // drawn by egui, so it has to be rendered every frame.
+                                #[cfg(feature = "egui")]
+                                if repaint || game_state.editor_viewport_active() {
                                     egui_integration.render(
                                         window,
                                         &mut render_context.encoder,
@@ -224,29 +292,53 @@ impl ApplicationHandler for App {
                                             ctx.set_pixels_per_point(1.2);
                                             // Debug stuff from the scene.
                                             game_state.debug_panel(ctx, render_context.frame_index);
+
+                                            egui::Window::new("Frame pacing")
+                                                .show(ctx, |ui| frame_pacing.debug_ui(ui));
                                         },
                                     );
                                 }
 
+                                let submit_start = Instant::now();
                                 globals::gpu()
                                     .queue
                                     .submit(std::iter::once(render_context.encoder.finish()));
+                                let submit = submit_start.elapsed();
 
+                                let present_start = Instant::now();
                                 output.present();
+                                let present = present_start.elapsed();
+
+                                frame_pacing.record(FrameTimings {
+                                    frame: last_frame_duration,
+                                    submit,
+                                    present,
+                                });
 
                                 // Frame is done rendering.
 
                                 *frame_index += 1;
                             }
 
-                            window.request_redraw();
+                            let strategy = frame_pacing
+                                .strategy_override
+                                .unwrap_or_else(|| game_state.pacing_strategy());
+                            event_loop.set_control_flow(strategy.control_flow(now));
+                            if strategy == PacingStrategy::Yield {
+                                std::thread::yield_now();
+                            }
+                            if strategy.redraw_immediately() {
+                                window.request_redraw();
+                            }
                         }
                     }
 
                     _ => {}
                 }
 
-                if let Some(input_event) = input::translate_window_event(&event) {
+                if let Some(input_event) =
+                    input::translate_window_event(&event, *pixels_per_scroll_line)
+                {
                     game_state.input(&input_event);
                 }
             }